@@ -0,0 +1,19 @@
+//! parse_vrrp_pkt fuzz target
+//! Feed arbitrary frames to the VRRPv2 packets decoder.
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use rVRRPd::AdvertFrame;
+
+fuzz_target!(|frame: &[u8]| {
+    if let Ok(advert) = AdvertFrame::decode(frame) {
+        // a decoded frame is encoded with valid checksums,
+        // and decoded back to the same frame
+        let bytes = advert.encode();
+        let decoded = AdvertFrame::decode(&bytes).expect("cannot decode an encoded frame");
+        assert!(decoded.vrrp.verify_checksum());
+        assert_eq!(decoded.vrrp.ipaddrs, advert.vrrp.ipaddrs);
+        assert_eq!(decoded.vrrp.authdata, advert.vrrp.authdata);
+        assert_eq!(decoded.eth, advert.eth);
+        assert_eq!(decoded.encode(), bytes);
+    }
+});
@@ -0,0 +1,519 @@
+//! Client API - audit log module
+//! This module records the client API requests in a tamper-evident log file.
+//! Every record carries the HMAC of the previous one, and its own HMAC computed
+//! over its content and the previous HMAC. The chain starts with a genesis record,
+//! and is keyed with a dedicated key kept in its own file. The last record is
+//! also kept in an anchor file, so the removal of the last records is detected.
+use super::*;
+
+// std
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::time::SystemTime;
+
+// rand
+use rand::Rng;
+
+// serde
+use serde_json::{Map, Value};
+
+// hmac
+use sessions::token::gen_hmac_string;
+
+// attributes whose value is never recorded
+const REDACTED_ATTRS: [&str; 6] = [
+    "auth_secret",
+    "secret",
+    "users",
+    "key",
+    "passwd",
+    "password",
+];
+const REDACTED: &str = "<redacted>";
+
+// route of the first record of the chain
+const GENESIS_ROUTE: &str = "<genesis>";
+
+// route of the record appended when the end of the log has been removed
+const TRUNCATED_ROUTE: &str = "<truncated>";
+
+/// AuditRecord structure (Serialize-able)
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AuditRecord {
+    pub(super) seq: u64,
+    pub(super) timestamp: u64,
+    pub(super) user: String,
+    pub(super) address: Option<String>,
+    pub(super) method: String,
+    pub(super) route: String,
+    pub(super) body: Option<Value>,
+    pub(super) status: u16,
+    pub(super) diff: Option<Vec<AuditChange>>,
+    pub(super) prev: String,
+    pub(super) hmac: String,
+}
+
+/// AuditChange structure (Serialize-able)
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AuditChange {
+    pub(super) path: String,
+    pub(super) before: Value,
+    pub(super) after: Value,
+}
+
+/// ResponseAudit structure (Serialize-able)
+#[derive(Serialize)]
+pub struct ResponseAudit {
+    pub(super) verified: bool,
+    pub(super) records: Vec<AuditRecord>,
+}
+
+/// AuditAnchor structure (Serialize-able)
+/// the sequence number and HMAC of the last record, authenticated with the key
+#[derive(Serialize, Deserialize)]
+struct AuditAnchor {
+    seq: u64,
+    last: String,
+    hmac: String,
+}
+
+/// AuditEntry structure
+/// an API request to be recorded
+pub struct AuditEntry {
+    pub user: String,
+    pub address: Option<String>,
+    pub method: &'static str,
+    pub route: String,
+    pub body: Option<Value>,
+}
+
+/// AuditLog structure
+pub struct AuditLog {
+    file: Option<File>,
+    path: String,
+    key: String,
+    seq: u64,
+    last: String,
+}
+
+// AuditLog implementation
+impl AuditLog {
+    // open() method
+    // open the audit log file, and resume the chain from its last record,
+    // or start a new chain with a genesis record
+    pub fn open(path: String, key_path: String) -> AuditLog {
+        let key = match read_key(&key_path) {
+            Ok(k) => k,
+            Err(e) => {
+                // records which cannot be verified are not written
+                eprintln!(
+                    "error(api): cannot read the audit log key {}: {}",
+                    key_path, e
+                );
+                return AuditLog {
+                    file: None,
+                    path,
+                    key: String::new(),
+                    seq: 0,
+                    last: String::new(),
+                };
+            }
+        };
+        let records = read_records(&path).unwrap_or_default();
+        let file = match OpenOptions::new().create(true).append(true).open(&path) {
+            Ok(f) => Some(f),
+            Err(e) => {
+                eprintln!("error(api): cannot open the audit log {}: {}", path, e);
+                None
+            }
+        };
+        let mut log = AuditLog {
+            file,
+            path,
+            key,
+            seq: 0,
+            last: String::new(),
+        };
+        match records.last() {
+            Some(r) => {
+                log.seq = r.seq;
+                log.last = r.hmac.clone();
+                // the removal of the last records is recorded in the chain
+                if !anchor_matches(&log.path, &records, &log.key) {
+                    eprintln!(
+                        "error(api): the end of the audit log {} has been removed",
+                        log.path
+                    );
+                    let anchor = read_anchor(&log.path, &log.key);
+                    let truncated = AuditEntry {
+                        user: "rvrrpd".to_string(),
+                        address: None,
+                        method: "",
+                        route: TRUNCATED_ROUTE.to_string(),
+                        body: anchor.map(|a| serde_json::json!({ "seq": a.seq })),
+                    };
+                    log.record(truncated, 0, None);
+                }
+            }
+            None => {
+                let genesis = AuditEntry {
+                    user: "rvrrpd".to_string(),
+                    address: None,
+                    method: "",
+                    route: GENESIS_ROUTE.to_string(),
+                    body: None,
+                };
+                log.append(0, genesis, 0, None);
+            }
+        }
+        log
+    }
+    // record() method
+    // append a new record to the audit log
+    pub fn record(&mut self, entry: AuditEntry, status: u16, diff: Option<Vec<AuditChange>>) {
+        let seq = self.seq + 1;
+        self.append(seq, entry, status, diff);
+    }
+    // append() method
+    // append a record chained to the last one
+    fn append(&mut self, seq: u64, entry: AuditEntry, status: u16, diff: Option<Vec<AuditChange>>) {
+        let file = match &mut self.file {
+            Some(f) => f,
+            None => return,
+        };
+        let mut record = AuditRecord {
+            seq,
+            timestamp: SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            user: entry.user,
+            address: entry.address,
+            method: entry.method.to_string(),
+            route: entry.route,
+            body: entry.body.map(redact),
+            status,
+            diff,
+            prev: self.last.clone(),
+            hmac: String::new(),
+        };
+        record.hmac = record_hmac(&record, &self.key);
+        let line = match serde_json::to_string(&record) {
+            Ok(l) => l,
+            Err(_) => return,
+        };
+        match writeln!(file, "{}", line).and_then(|_| file.flush()) {
+            Ok(_) => {
+                self.seq = record.seq;
+                self.last = record.hmac;
+                if let Err(e) = write_anchor(&self.path, self.seq, &self.last, &self.key) {
+                    eprintln!(
+                        "error(api): cannot write the audit log anchor {}: {}",
+                        anchor_path(&self.path),
+                        e
+                    );
+                }
+            }
+            Err(e) => eprintln!(
+                "error(api): cannot write the audit log {}: {}",
+                self.path, e
+            ),
+        }
+    }
+    // query() method
+    // return the records following 'since', and verify the whole chain
+    pub fn query(&self, since: u64, limit: usize) -> Option<ResponseAudit> {
+        let records = read_records(&self.path)?;
+        let verified =
+            verify_chain(&records, &self.key) && anchor_matches(&self.path, &records, &self.key);
+        let records = records
+            .into_iter()
+            .filter(|r| r.seq > since)
+            .take(limit)
+            .collect();
+        Some(ResponseAudit { verified, records })
+    }
+}
+
+// read_records() function
+// read the records of the audit log, a malformed line is returned as an empty record
+fn read_records(path: &str) -> Option<Vec<AuditRecord>> {
+    let file = File::open(path).ok()?;
+    let records = BufReader::new(file)
+        .lines()
+        .filter_map(|l| l.ok())
+        .filter(|l| !l.is_empty())
+        .map(|l| {
+            serde_json::from_str(&l).unwrap_or_else(|_| AuditRecord {
+                seq: 0,
+                timestamp: 0,
+                user: String::new(),
+                address: None,
+                method: String::new(),
+                route: String::new(),
+                body: None,
+                status: 0,
+                diff: None,
+                prev: String::new(),
+                hmac: String::new(),
+            })
+        })
+        .collect();
+    Some(records)
+}
+
+// verify_chain() function
+// return true if the records start with the genesis record, every record is
+// authentic and chained to the previous one, and no removal has been recorded
+fn verify_chain(records: &[AuditRecord], key: &str) -> bool {
+    // the start of the log must not have been removed
+    match records.first() {
+        Some(g) if g.seq == 0 && g.route == GENESIS_ROUTE && g.prev.is_empty() => {}
+        _ => return false,
+    }
+    records.iter().enumerate().all(|(i, r)| {
+        (i == 0 || (r.prev == records[i - 1].hmac && r.seq == records[i - 1].seq + 1))
+            && record_hmac(r, key) == r.hmac
+            && r.route != TRUNCATED_ROUTE
+    })
+}
+
+// anchor_path() function
+// return the path of the anchor file of the audit log
+fn anchor_path(path: &str) -> String {
+    format!("{}.anchor", path)
+}
+
+// anchor_hmac() function
+fn anchor_hmac(seq: u64, last: &str, key: &str) -> String {
+    gen_hmac_string(&format!("{}:{}", seq, last), key.to_string())
+}
+
+// read_anchor() function
+// read the anchor of the audit log, if it is authentic
+fn read_anchor(path: &str, key: &str) -> Option<AuditAnchor> {
+    let content = std::fs::read_to_string(anchor_path(path)).ok()?;
+    let anchor: AuditAnchor = serde_json::from_str(&content).ok()?;
+    if anchor.hmac == anchor_hmac(anchor.seq, &anchor.last, key) {
+        Some(anchor)
+    } else {
+        None
+    }
+}
+
+// write_anchor() function
+// replace the anchor of the audit log with the last record
+fn write_anchor(path: &str, seq: u64, last: &str, key: &str) -> std::io::Result<()> {
+    let anchor = AuditAnchor {
+        seq,
+        last: last.to_string(),
+        hmac: anchor_hmac(seq, last, key),
+    };
+    let tmpfile = format!("{}.tmp", anchor_path(path));
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(&tmpfile)?;
+    writeln!(file, "{}", serde_json::to_string(&anchor)?)?;
+    file.sync_all()?;
+    std::fs::rename(&tmpfile, anchor_path(path))
+}
+
+// anchor_matches() function
+// return true if the last record is the one of the anchor
+fn anchor_matches(path: &str, records: &[AuditRecord], key: &str) -> bool {
+    match (read_anchor(path, key), records.last()) {
+        (Some(a), Some(r)) => a.seq == r.seq && a.last == r.hmac,
+        _ => false,
+    }
+}
+
+// read_key() function
+// read the HMAC key of the audit log, or create it only readable by its owner
+fn read_key(path: &str) -> std::io::Result<String> {
+    match std::fs::read_to_string(path) {
+        Ok(k) if !k.trim().is_empty() => return Ok(k.trim().to_string()),
+        Ok(_) => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "the key file is empty",
+            ))
+        }
+        Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+    let mut rng = rand::thread_rng();
+    let key: String = (0..4)
+        .map(|_| format!("{:016x}", rng.gen::<u64>()))
+        .collect();
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path)?;
+    writeln!(file, "{}", key)?;
+    file.sync_all()?;
+    Ok(key)
+}
+
+// record_hmac() function
+// compute the HMAC of a record, over its content and the previous HMAC
+fn record_hmac(record: &AuditRecord, key: &str) -> String {
+    let mut r = record.clone();
+    r.hmac = String::new();
+    let content = serde_json::to_string(&r).unwrap_or_default();
+    gen_hmac_string(&content, key.to_string())
+}
+
+// redact() function
+/// replace the value of the secret attributes
+pub fn redact(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(k, v)| {
+                    if REDACTED_ATTRS.contains(&k.as_str()) {
+                        (k, Value::String(REDACTED.to_string()))
+                    } else {
+                        (k, redact(v))
+                    }
+                })
+                .collect::<Map<String, Value>>(),
+        ),
+        Value::Array(list) => Value::Array(list.into_iter().map(redact).collect()),
+        v => v,
+    }
+}
+
+// diff() function
+/// return the changes between two configuration sections, with the secrets redacted
+pub fn diff(before: &Value, after: &Value) -> Vec<AuditChange> {
+    let mut changes = Vec::new();
+    diff_values(String::new(), before, after, &mut changes);
+    for c in changes.iter_mut() {
+        let attr = c.path.rsplit('.').next().unwrap_or("");
+        let attr = attr.split('[').next().unwrap_or("");
+        if REDACTED_ATTRS.contains(&attr) {
+            c.before = Value::String(REDACTED.to_string());
+            c.after = Value::String(REDACTED.to_string());
+        } else {
+            c.before = redact(c.before.take());
+            c.after = redact(c.after.take());
+        }
+    }
+    changes
+}
+
+// diff_values() function
+fn diff_values(path: String, a: &Value, b: &Value, changes: &mut Vec<AuditChange>) {
+    match (a, b) {
+        (Value::Object(ma), Value::Object(mb)) => {
+            let mut keys: Vec<&String> = ma.keys().chain(mb.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for k in keys {
+                let p = if path.is_empty() {
+                    k.clone()
+                } else {
+                    format!("{}.{}", path, k)
+                };
+                diff_values(
+                    p,
+                    ma.get(k).unwrap_or(&Value::Null),
+                    mb.get(k).unwrap_or(&Value::Null),
+                    changes,
+                );
+            }
+        }
+        (Value::Array(la), Value::Array(lb)) if la.len() == lb.len() => {
+            for (i, (va, vb)) in la.iter().zip(lb.iter()).enumerate() {
+                diff_values(format!("{}[{}]", path, i), va, vb, changes);
+            }
+        }
+        _ => {
+            if a != b {
+                changes.push(AuditChange {
+                    path,
+                    before: a.clone(),
+                    after: b.clone(),
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // entry() function
+    fn entry(route: &str) -> AuditEntry {
+        AuditEntry {
+            user: "admin".to_string(),
+            address: None,
+            method: "PUT",
+            route: route.to_string(),
+            body: None,
+        }
+    }
+
+    #[test]
+    fn chain_survives_restarts_and_detects_truncation() {
+        let dir = std::env::temp_dir().join(format!("rvrrpd-audit-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("audit.log").to_string_lossy().to_string();
+        let key = dir.join("audit.key").to_string_lossy().to_string();
+
+        let mut log = AuditLog::open(path.clone(), key.clone());
+        log.record(entry("/config/global"), 200, None);
+        log.record(entry("/config/vrouter"), 200, None);
+        assert!(log.query(0, 10).unwrap().verified);
+
+        // the key is kept across the restarts
+        let mut log = AuditLog::open(path.clone(), key.clone());
+        log.record(entry("/config/protocols"), 200, None);
+        let resp = log.query(0, 10).unwrap();
+        assert!(resp.verified);
+        assert_eq!(resp.records.len(), 4);
+
+        // removing the start of the log is detected
+        let content = std::fs::read_to_string(&path).unwrap();
+        let truncated: Vec<&str> = content.lines().skip(2).collect();
+        std::fs::write(&path, truncated.join("\n")).unwrap();
+        assert!(!log.query(0, 10).unwrap().verified);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn tail_truncation_is_detected() {
+        let dir = std::env::temp_dir().join(format!("rvrrpd-audit-tail-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("audit.log").to_string_lossy().to_string();
+        let key = dir.join("audit.key").to_string_lossy().to_string();
+
+        let mut log = AuditLog::open(path.clone(), key.clone());
+        log.record(entry("/config/global"), 200, None);
+        log.record(entry("/config/vrouter"), 200, None);
+        assert!(log.query(0, 10).unwrap().verified);
+
+        // removing the last record is detected on query
+        let content = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        std::fs::write(&path, format!("{}\n", lines[..2].join("\n"))).unwrap();
+        assert!(!log.query(0, 10).unwrap().verified);
+
+        // and on startup, where it is recorded in the chain
+        let log = AuditLog::open(path.clone(), key.clone());
+        let resp = log.query(0, 10).unwrap();
+        assert!(!resp.verified);
+        let last = resp.records.last().unwrap();
+        assert_eq!(last.route, TRUNCATED_ROUTE);
+        assert_eq!(last.body, Some(serde_json::json!({ "seq": 2 })));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
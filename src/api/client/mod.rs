@@ -1,27 +1,57 @@
 //! client API module
 // std
+use std::net::IpAddr;
 use std::sync::{Arc, Mutex, RwLock};
 
 // thread
 use std::thread;
 
 // crossbeam
-use crossbeam::{unbounded, Receiver, Sender};
+use crossbeam::{bounded, unbounded, Receiver, Sender};
 
 // virtual router
 use crate::VirtualRouter;
 
+// running virtual routers
+use crate::runtime::{config_interface, Runtime};
+
 // router
 mod router;
 
+// audit log
+mod audit;
+use audit::{AuditEntry, AuditLog, ResponseAudit};
+
 // sessions
 mod sessions;
-use sessions::auth::auth_api_client;
+use sessions::auth::{
+    auth_api_cert, auth_api_client, auth_api_key, auth_api_unix, refresh_api_client,
+};
+use sessions::guard::{GuardKey, Lockout, LoginGuard};
+use sessions::store::SessionStore;
 use sessions::token::SessionToken;
 
 // config
 use crate::config;
 
+// constants
+use crate::constants::{
+    API_ACCESS_ADMIN, API_ACCESS_CONFIG, API_ACCESS_RUN, AUTH_TYPE_SIMPLE, DEBUG_LEVEL_INFO,
+    DEBUG_LEVEL_LOW, DEBUG_SRC_API,
+};
+
+// debugging
+use crate::debug::print_debug;
+
+// finite state machine
+use crate::fsm::{Event, Reconfig};
+
+// events
+use crate::events::{self, Notification};
+
+// transitions history
+use crate::history::Transition;
+
 /// Upstream API structure
 pub struct UpstreamAPI {
     sender: Sender<FSMQueryResult>,     // channel for queries to fsm
@@ -40,23 +70,18 @@ impl<'a> UpstreamAPI {
         &self,
         down_api: &DownstreamAPI,
         cfg: config::CConfig,
-        vrs: &Vec<Arc<RwLock<VirtualRouter>>>,
+        cfg_file: String,
+        cfg_type: config::CfgType,
+        runtime: Runtime,
     ) {
         // upstream transmit and receives channels
         let (utx, urx) = self.channels();
 
-        // clone receives and transmit channels for queries/responses
+        // clone receive channel for queries (answered on their reply channel)
         let qrx = down_api.q_receiver.clone();
-        let rtx = down_api.r_sender.clone();
-
-        // duplicate virtual routers vector
-        let mut vrouters: Vec<Arc<RwLock<VirtualRouter>>> = Vec::new();
-        for vr in vrs {
-            vrouters.push(vr.clone());
-        }
 
         // spawn Client API thread
-        thread::spawn(|| capi_thread_loop(utx, urx, qrx, rtx, cfg, vrouters));
+        thread::spawn(move || capi_thread_loop(utx, urx, qrx, cfg, cfg_file, cfg_type, runtime));
     }
     // channels() method
     // channels to virtual routers finite-state-machines
@@ -101,10 +126,8 @@ pub enum FSMResponse {
 /// Downstream API structure
 #[derive(Clone, StateData)]
 pub struct DownstreamAPI {
-    q_sender: Arc<Mutex<Sender<ClientAPIQuery>>>,
-    q_receiver: Arc<Mutex<Receiver<ClientAPIQuery>>>,
-    r_sender: Arc<Mutex<Sender<ClientAPIResponse>>>,
-    r_receiver: Arc<Mutex<Receiver<ClientAPIResponse>>>,
+    q_sender: Arc<Mutex<Sender<(ClientAPIQuery, Sender<ClientAPIResponse>)>>>,
+    q_receiver: Arc<Mutex<Receiver<(ClientAPIQuery, Sender<ClientAPIResponse>)>>>,
 }
 
 /// Downstream API implementation
@@ -112,46 +135,252 @@ impl DownstreamAPI {
     // new() method
     pub fn new() -> Self {
         let (qtx, qrx) = unbounded();
-        let (rtx, rrx) = unbounded();
         Self {
             q_sender: Arc::new(Mutex::new(qtx)),
             q_receiver: Arc::new(Mutex::new(qrx)),
-            r_sender: Arc::new(Mutex::new(rtx)),
-            r_receiver: Arc::new(Mutex::new(rrx)),
         }
     }
     // query() method
-    pub fn query(&self, q: ClientAPIQuery) {
+    // send a query along with its own reply channel, so concurrent
+    // requests cannot read each other's answers
+    pub fn query(&self, q: ClientAPIQuery) -> ClientAPIReply {
+        let (rtx, rrx) = bounded(1);
         let qtx = self.q_sender.lock().unwrap();
         // panic if send fails
-        qtx.send(q).unwrap();
+        qtx.send((q, rtx)).unwrap();
+        ClientAPIReply(rrx)
     }
-    // answer() method
+}
+
+/// Client API Reply structure
+pub struct ClientAPIReply(Receiver<ClientAPIResponse>);
+
+/// Client API Reply implementation
+impl ClientAPIReply {
+    // read() method
+    // wait for the answer to the query (blocking)
     pub fn read(&self) -> ClientAPIResponse {
-        let rrx = self.r_receiver.lock().unwrap();
-        let answer = rrx.recv().unwrap();
-        answer
+        match self.0.recv() {
+            Ok(answer) => answer,
+            Err(_) => ClientAPIResponse::InternalError(
+                "error(api): the query has not been answered".to_string(),
+            ),
+        }
     }
 }
 
 /// ClientAPIQuery enumerator
 pub enum ClientAPIQuery {
-    AuthRequest(String, String),
+    AuthRequest(String, String, Option<IpAddr>),
+    AuthLogout(SessionToken),
+    AuthRefresh(SessionToken),
+    AuthRevoke(SessionToken),
+    AuthLockouts(SessionToken),
+    Audit(SessionToken, u64, usize),
     CfgGlobalAll(SessionToken),
     CfgVrrpAll(SessionToken),
     CfgProtoAll(SessionToken),
+    CfgGlobalPut(SessionToken, config::CConfig, bool),
+    CfgVrrpPut(SessionToken, Vec<config::VRConfig>, bool),
+    CfgProtoPut(SessionToken, config::Protocols, bool),
+    CfgReload(SessionToken),
     RunGlobalAll(SessionToken),
     RunVRRPAll(SessionToken),
     RunVRRPGrp(SessionToken, u8),
     RunVRRPGrpIntf(SessionToken, u8, String),
+    RunVRRPPeers(SessionToken, u8, String),
+    RunVRRPHistory(SessionToken, u8, String),
+    RunVRRPAdd(SessionToken, config::VRConfig),
+    RunVRRPDel(SessionToken, u8, String),
+    RunVRRPMod(SessionToken, u8, String, RequestVRRPAttr),
+    RunVRRPMaint(SessionToken, RequestMaintenance),
     RunProtoAll(SessionToken),
     RunProtoStatic(SessionToken),
+    Events(SessionToken),
+}
+
+// ClientAPIQuery implementation
+impl ClientAPIQuery {
+    // session() method
+    // return the session token of an authenticated query
+    fn session(&self) -> Option<&SessionToken> {
+        match self {
+            ClientAPIQuery::AuthRequest(_, _, _) => None,
+            ClientAPIQuery::AuthLogout(s)
+            | ClientAPIQuery::AuthRefresh(s)
+            | ClientAPIQuery::AuthRevoke(s)
+            | ClientAPIQuery::AuthLockouts(s)
+            | ClientAPIQuery::Audit(s, _, _)
+            | ClientAPIQuery::CfgGlobalAll(s)
+            | ClientAPIQuery::CfgVrrpAll(s)
+            | ClientAPIQuery::CfgProtoAll(s)
+            | ClientAPIQuery::CfgGlobalPut(s, _, _)
+            | ClientAPIQuery::CfgVrrpPut(s, _, _)
+            | ClientAPIQuery::CfgProtoPut(s, _, _)
+            | ClientAPIQuery::CfgReload(s)
+            | ClientAPIQuery::RunGlobalAll(s)
+            | ClientAPIQuery::RunVRRPAll(s)
+            | ClientAPIQuery::RunVRRPGrp(s, _)
+            | ClientAPIQuery::RunVRRPGrpIntf(s, _, _)
+            | ClientAPIQuery::RunVRRPPeers(s, _, _)
+            | ClientAPIQuery::RunVRRPHistory(s, _, _)
+            | ClientAPIQuery::RunVRRPAdd(s, _)
+            | ClientAPIQuery::RunVRRPDel(s, _, _)
+            | ClientAPIQuery::RunVRRPMod(s, _, _, _)
+            | ClientAPIQuery::RunVRRPMaint(s, _)
+            | ClientAPIQuery::RunProtoAll(s)
+            | ClientAPIQuery::RunProtoStatic(s)
+            | ClientAPIQuery::Events(s) => Some(s),
+        }
+    }
+    // access_level() method
+    // return the minimum access level required by the query
+    fn access_level(&self) -> u8 {
+        match self {
+            // configuration reads
+            ClientAPIQuery::CfgGlobalAll(_)
+            | ClientAPIQuery::CfgVrrpAll(_)
+            | ClientAPIQuery::CfgProtoAll(_) => API_ACCESS_CONFIG,
+            // configuration and running state modifications
+            ClientAPIQuery::CfgGlobalPut(_, _, _)
+            | ClientAPIQuery::CfgVrrpPut(_, _, _)
+            | ClientAPIQuery::CfgProtoPut(_, _, _)
+            | ClientAPIQuery::CfgReload(_)
+            | ClientAPIQuery::RunVRRPAdd(_, _)
+            | ClientAPIQuery::RunVRRPDel(_, _, _)
+            | ClientAPIQuery::RunVRRPMod(_, _, _, _)
+            | ClientAPIQuery::RunVRRPMaint(_, _)
+            | ClientAPIQuery::AuthRevoke(_)
+            | ClientAPIQuery::AuthLockouts(_)
+            | ClientAPIQuery::Audit(_, _, _) => API_ACCESS_ADMIN,
+            // running state reads
+            _ => API_ACCESS_RUN,
+        }
+    }
+    // modifies_config() method
+    // return true if the query modifies the configuration
+    fn modifies_config(&self) -> bool {
+        match self {
+            ClientAPIQuery::CfgGlobalPut(_, _, _)
+            | ClientAPIQuery::CfgVrrpPut(_, _, _)
+            | ClientAPIQuery::CfgProtoPut(_, _, _)
+            | ClientAPIQuery::CfgReload(_)
+            | ClientAPIQuery::RunVRRPAdd(_, _)
+            | ClientAPIQuery::RunVRRPDel(_, _, _) => true,
+            _ => false,
+        }
+    }
+    // audit_entry() method
+    // return the audit log entry of the query
+    fn audit_entry(&self) -> AuditEntry {
+        // the configuration route, with the 'persist' query string
+        let persist = |route: &str, p: bool| {
+            if p {
+                format!("{}?persist=true", route)
+            } else {
+                route.to_string()
+            }
+        };
+        let (method, route, body) = match self {
+            ClientAPIQuery::AuthRequest(_, _, _) => ("POST", "/auth".to_string(), None),
+            ClientAPIQuery::AuthLogout(_) => ("POST", "/auth/logout".to_string(), None),
+            ClientAPIQuery::AuthRefresh(_) => ("POST", "/auth/refresh".to_string(), None),
+            ClientAPIQuery::AuthRevoke(_) => ("POST", "/auth/revoke".to_string(), None),
+            ClientAPIQuery::AuthLockouts(_) => ("GET", "/auth/lockouts".to_string(), None),
+            ClientAPIQuery::Audit(_, _, _) => ("GET", "/audit".to_string(), None),
+            ClientAPIQuery::CfgGlobalAll(_) => ("GET", "/config/global".to_string(), None),
+            ClientAPIQuery::CfgVrrpAll(_) => ("GET", "/config/vrouter".to_string(), None),
+            ClientAPIQuery::CfgProtoAll(_) => ("GET", "/config/protocols".to_string(), None),
+            ClientAPIQuery::CfgGlobalPut(_, c, p) => (
+                "PUT",
+                persist("/config/global", *p),
+                serde_json::to_value(c).ok(),
+            ),
+            ClientAPIQuery::CfgVrrpPut(_, c, p) => (
+                "PUT",
+                persist("/config/vrouter", *p),
+                serde_json::to_value(c).ok(),
+            ),
+            ClientAPIQuery::CfgProtoPut(_, c, p) => (
+                "PUT",
+                persist("/config/protocols", *p),
+                serde_json::to_value(c).ok(),
+            ),
+            ClientAPIQuery::CfgReload(_) => ("POST", "/config/reload".to_string(), None),
+            ClientAPIQuery::RunGlobalAll(_) => ("GET", "/run/global".to_string(), None),
+            ClientAPIQuery::RunVRRPAll(_) => ("GET", "/run/vrrp".to_string(), None),
+            ClientAPIQuery::RunVRRPGrp(_, g) => ("GET", format!("/run/vrrp/{}", g), None),
+            ClientAPIQuery::RunVRRPGrpIntf(_, g, i) => {
+                ("GET", format!("/run/vrrp/{}/{}", g, i), None)
+            }
+            ClientAPIQuery::RunVRRPPeers(_, g, i) => {
+                ("GET", format!("/run/vrrp/{}/{}/peers", g, i), None)
+            }
+            ClientAPIQuery::RunVRRPHistory(_, g, i) => {
+                ("GET", format!("/run/vrrp/{}/{}/history", g, i), None)
+            }
+            ClientAPIQuery::RunVRRPAdd(_, c) => (
+                "POST",
+                format!("/run/vrrp/{}/{}", c.group(), c.interface()),
+                serde_json::to_value(c).ok(),
+            ),
+            ClientAPIQuery::RunVRRPDel(_, g, i) => {
+                ("DELETE", format!("/run/vrrp/{}/{}", g, i), None)
+            }
+            ClientAPIQuery::RunVRRPMod(_, g, i, r) => (
+                "PUT",
+                format!("/run/vrrp/{}/{}", g, i),
+                serde_json::to_value(r).ok(),
+            ),
+            ClientAPIQuery::RunVRRPMaint(_, r) => (
+                "PUT",
+                "/run/vrrp/maintenance".to_string(),
+                serde_json::to_value(r).ok(),
+            ),
+            ClientAPIQuery::RunProtoAll(_) => ("GET", "/run/protocols".to_string(), None),
+            ClientAPIQuery::RunProtoStatic(_) => ("GET", "/run/protocols/static".to_string(), None),
+            ClientAPIQuery::Events(_) => ("GET", "/events".to_string(), None),
+        };
+        // the authenticated user, API key or client certificate
+        let (user, address) = match self {
+            ClientAPIQuery::AuthRequest(u, _, a) => (u.clone(), *a),
+            _ => {
+                let sess = self.session().unwrap();
+                let user = if sess.key().is_some() {
+                    format!("key:{}", sess.user())
+                } else if let Some((uid, _)) = sess.peer() {
+                    format!("unix:{}", uid)
+                } else if let Some(id) = sess.identities().first() {
+                    format!("cert:{}", id)
+                } else {
+                    sess.user()
+                };
+                (user, sess.addr())
+            }
+        };
+        AuditEntry {
+            user,
+            address: address.map(|a| a.to_string()),
+            method,
+            route,
+            body,
+        }
+    }
 }
 
 /// ClientAPIResponse enumerator
 pub enum ClientAPIResponse {
     Unauthorized,
+    Forbidden,
+    TooManyRequests(u64),
+    BadRequest(String),
+    NotFound,
+    Conflict(String),
+    InternalError(String),
     AuthResponse(Option<SessionToken>),
+    AuthLogout,
+    AuthRevoke(ResponseRevoke),
+    AuthLockouts(Vec<ResponseLockout>),
     CfgGlobalAll(config::CConfig),
     CfgVrrpAll(Vec<config::VRConfig>),
     CfgProtoAll(config::Protocols),
@@ -159,175 +388,378 @@ pub enum ClientAPIResponse {
     RunVRRPAll(Vec<ResponseVRRPAttr>),
     RunVRRPGrp(Option<Vec<ResponseVRRPAttr>>),
     RunVRRPGrpIntf(Option<ResponseVRRPAttrExt>),
+    RunVRRPPeers(Option<Vec<ResponsePeerAttr>>),
+    RunVRRPHistory(Option<Vec<Transition>>),
+    RunVRRPAdd(ResponseVRRPAttrExt),
+    RunVRRPDel,
+    RunVRRPMod(ResponseVRRPAttrExt),
+    RunVRRPMaint(Vec<ResponseVRRPAttr>),
     RunProtoAll(Option<ResponseProtoAttr>),
     RunProtoStatic(Option<Vec<ResponseProtoStaticAttr>>),
+    Events(Option<(String, u64)>, u8),
+    Audit(ResponseAudit),
+}
+
+// ClientAPIResponse implementation
+impl ClientAPIResponse {
+    // status() method
+    // return the HTTP status code of the response
+    fn status(&self) -> u16 {
+        match self {
+            ClientAPIResponse::Unauthorized | ClientAPIResponse::AuthResponse(None) => 401,
+            ClientAPIResponse::Forbidden => 403,
+            ClientAPIResponse::TooManyRequests(_) => 429,
+            ClientAPIResponse::BadRequest(_) => 400,
+            ClientAPIResponse::NotFound
+            | ClientAPIResponse::RunVRRPGrpIntf(None)
+            | ClientAPIResponse::RunVRRPPeers(None)
+            | ClientAPIResponse::RunVRRPHistory(None) => 404,
+            ClientAPIResponse::Conflict(_) => 409,
+            ClientAPIResponse::InternalError(_) => 500,
+            ClientAPIResponse::RunVRRPAdd(_) => 201,
+            ClientAPIResponse::RunVRRPDel => 204,
+            _ => 200,
+        }
+    }
 }
 
 /// ReponseGlobalAttr structure (Serialize-able)
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 pub struct ResponseGlobalAttr {
-    debug: u8,
-    timestamp: u8,
-    timezone: u8,
-    timeformat: u8,
-    pid: String,
-    working_dir: String,
-    main_log: String,
-    error_log: String,
+    pub debug: u8,
+    pub timestamp: u8,
+    pub timezone: u8,
+    pub timeformat: u8,
+    pub pid: String,
+    pub working_dir: String,
+    pub main_log: String,
+    pub error_log: String,
+    pub crash_dump: String,
 }
 
-/// ResponseVRRPAttr structure (Serialize-able)
+/// ResponseRevoke structure (Serialize-able)
 #[derive(Serialize)]
+pub struct ResponseRevoke {
+    revoked: usize,
+}
+
+/// ResponseLockout structure (Serialize-able)
+#[derive(Serialize)]
+pub struct ResponseLockout {
+    user: Option<String>,
+    address: Option<String>,
+    failures: u32,
+    until: u64,
+}
+
+/// ResponseVRRPAttr structure (Serialize-able)
+#[derive(Serialize, Deserialize)]
 pub struct ResponseVRRPAttr {
-    virtual_ip: String,
-    group: u8,
-    interface: String,
-    priority: u8,
-    preempt: bool,
-    state: String,
+    pub virtual_ip: String,
+    pub group: u8,
+    pub interface: String,
+    pub priority: u8,
+    pub preempt: bool,
+    pub maintenance: bool,
+    pub state: String,
 }
 
 /// ResponseVRRPAttrExt structure (Serialize-able)
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 pub struct ResponseVRRPAttrExt {
-    virtual_ip: String,
-    group: u8,
-    interface: String,
-    vif: String,
-    priority: u8,
-    preempt: bool,
-    state: String,
-    auth_type: u8,
-    interface_hwaddress: String,
-    advert_interval: u8,
-    masterdown_interval: f32,
-    skew_time: f32,
+    pub virtual_ip: String,
+    pub group: u8,
+    pub interface: String,
+    pub vif: String,
+    pub priority: u8,
+    pub preempt: bool,
+    pub maintenance: bool,
+    pub state: String,
+    pub auth_type: u8,
+    pub interface_hwaddress: String,
+    pub advert_interval: u8,
+    pub masterdown_interval: f32,
+    pub skew_time: f32,
+}
+
+/// ResponsePeerAttr structure (Serialize-able)
+#[derive(Serialize, Deserialize)]
+pub struct ResponsePeerAttr {
+    pub source_ip: String,
+    pub source_mac: String,
+    pub priority: u8,
+    pub advert_interval: u8,
+    pub auth_type: u8,
+    pub addresses: Vec<String>,
+    pub first_seen: u64,
+    pub last_seen: u64,
+    pub master: bool,
+}
+
+/// RequestVRRPAttr structure (Deserialize-able)
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct RequestVRRPAttr {
+    pub priority: Option<u8>,
+    pub preempt: Option<bool>,
+    pub advert_interval: Option<u8>,
+    pub auth_type: Option<String>,
+    pub auth_secret: Option<String>,
+    pub maintenance: Option<bool>,
+}
+
+/// RequestMaintenance structure (Deserialize-able)
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct RequestMaintenance {
+    pub maintenance: bool,
 }
 
 /// RunProtoAttr structure (Serialize-able)
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 pub struct ResponseProtoAttr {
-    r#static: Option<Vec<ResponseProtoStaticAttr>>,
+    pub r#static: Option<Vec<ResponseProtoStaticAttr>>,
 }
 
 /// RunProtoStaticAttr structure (Serialize-able)
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 pub struct ResponseProtoStaticAttr {
-    destination: String,
-    mask: String,
-    next_hop: String,
-    metric: i16,
-    mtu: u64,
+    pub destination: String,
+    pub mask: String,
+    pub next_hop: String,
+    pub metric: i16,
+    pub mtu: u64,
 }
 
 // capi_thread_loop() function
 pub fn capi_thread_loop(
     _utx: Sender<FSMQueryResult>,
     _urx: Receiver<FSMQueryResult>,
-    qrx: Arc<Mutex<Receiver<ClientAPIQuery>>>,
-    rtx: Arc<Mutex<Sender<ClientAPIResponse>>>,
-    cfg: config::CConfig,
-    vrs: Vec<Arc<RwLock<VirtualRouter>>>,
+    qrx: Arc<Mutex<Receiver<(ClientAPIQuery, Sender<ClientAPIResponse>)>>>,
+    mut cfg: config::CConfig,
+    cfg_file: String,
+    cfg_type: config::CfgType,
+    runtime: Runtime,
 ) {
+    // issued session tokens
+    let mut sessions = SessionStore::new();
+
+    // failed authentication attempts
+    let mut guard = LoginGuard::new();
+
+    // audit log
+    let api = cfg.api.as_ref().unwrap();
+    let mut audit = AuditLog::open(api.audit_log(), api.audit_key());
+
     loop {
         // declare empty response
         let resp;
 
+        // get the currently running virtual routers
+        let vrs = runtime.vrouters();
+
         // acquire mutex lock
         let qrx = qrx.lock().unwrap();
 
         // listen for downstream queries (blocking)
-        let q = qrx.recv().unwrap();
-        match q {
-            ClientAPIQuery::AuthRequest(user, passwd) => {
-                let r = auth_api_client(&cfg, user, passwd);
-                resp = ClientAPIResponse::AuthResponse(r);
-            }
-            ClientAPIQuery::CfgGlobalAll(sess) => match sess.validate(&cfg) {
-                Some(_) => {
-                    let r = capi_req_cfg_global_all(&cfg);
-                    resp = ClientAPIResponse::CfgGlobalAll(r);
-                }
-                None => {
-                    resp = ClientAPIResponse::Unauthorized;
-                }
-            },
-            ClientAPIQuery::CfgVrrpAll(sess) => match sess.validate(&cfg) {
-                Some(_) => {
-                    let r = capi_req_cfg_vrrp_all(&cfg);
-                    resp = ClientAPIResponse::CfgVrrpAll(r);
-                }
-                None => {
-                    resp = ClientAPIResponse::Unauthorized;
-                }
-            },
-            ClientAPIQuery::CfgProtoAll(sess) => match sess.validate(&cfg) {
-                Some(_) => {
-                    let r = capi_req_cfg_proto_all(&cfg);
-                    resp = ClientAPIResponse::CfgProtoAll(r);
-                }
-                None => {
-                    resp = ClientAPIResponse::Unauthorized;
-                }
-            },
-            ClientAPIQuery::RunGlobalAll(sess) => match sess.validate(&cfg) {
-                Some(_) => {
-                    let r = capi_req_run_global_all(&cfg);
-                    resp = ClientAPIResponse::RunGlobalAll(r);
-                }
-                None => {
-                    resp = ClientAPIResponse::Unauthorized;
-                }
-            },
-            ClientAPIQuery::RunVRRPAll(sess) => match sess.validate(&cfg) {
-                Some(_) => {
-                    let r = capi_req_run_vrrp_all(&vrs);
-                    resp = ClientAPIResponse::RunVRRPAll(r);
-                }
-                None => {
-                    resp = ClientAPIResponse::Unauthorized;
-                }
-            },
-            ClientAPIQuery::RunVRRPGrp(sess, gid) => match sess.validate(&cfg) {
-                Some(_) => {
-                    let r = capi_req_run_vrrp_grp(&vrs, gid);
-                    resp = ClientAPIResponse::RunVRRPGrp(r);
-                }
-                None => {
-                    resp = ClientAPIResponse::Unauthorized;
-                }
-            },
-            ClientAPIQuery::RunVRRPGrpIntf(sess, gid, intf) => match sess.validate(&cfg) {
-                Some(_) => {
-                    let r = capi_req_run_vrrp_grp_intf(&vrs, gid, intf);
-                    resp = ClientAPIResponse::RunVRRPGrpIntf(r);
-                }
-                None => {
-                    resp = ClientAPIResponse::Unauthorized;
-                }
-            },
-            ClientAPIQuery::RunProtoAll(sess) => match sess.validate(&cfg) {
-                Some(_) => {
-                    let r = capi_req_run_proto_all(&vrs);
-                    resp = ClientAPIResponse::RunProtoAll(r);
-                }
-                None => {
-                    resp = ClientAPIResponse::Unauthorized;
-                }
-            },
-            ClientAPIQuery::RunProtoStatic(sess) => match sess.validate(&cfg) {
-                Some(_) => {
-                    let r = capi_req_run_proto_static(&vrs);
-                    resp = ClientAPIResponse::RunProtoStatic(r);
+        let (q, rtx) = qrx.recv().unwrap();
+
+        // the audit log entry, and the configuration before its modification
+        let entry = q.audit_entry();
+        let before = if q.modifies_config() {
+            serde_json::to_value(&cfg).ok()
+        } else {
+            None
+        };
+        // verify the session token or API key, and its access level
+        let mut granted = None;
+        if let Some(sess) = q.session() {
+            let access = if sess.key().is_some() {
+                auth_api_key(&cfg, sess)
+            } else if sess.peer().is_some() {
+                auth_api_unix(&cfg, sess)
+            } else if !sess.identities().is_empty() {
+                auth_api_cert(&cfg, sess)
+            } else {
+                let bind = cfg.api.as_ref().map_or(false, |a| a.session_bind());
+                // the access level is the one granted at authentication,
+                // not the one presented by the client
+                match sess.validate(&cfg) {
+                    Some(_) => sessions.check(sess, bind),
+                    None => None,
                 }
-                None => {
-                    resp = ClientAPIResponse::Unauthorized;
+            };
+            granted = access;
+            let denied = match access {
+                Some(a) if a >= q.access_level() => None,
+                Some(_) => Some(ClientAPIResponse::Forbidden),
+                None => Some(ClientAPIResponse::Unauthorized),
+            };
+            if let Some(resp) = denied {
+                audit.record(entry, resp.status(), None);
+                let _r = rtx.send(resp);
+                continue;
+            }
+        }
+
+        match q {
+            ClientAPIQuery::AuthRequest(user, passwd, addr) => {
+                resp = capi_req_auth(
+                    &cfg,
+                    &runtime,
+                    &mut sessions,
+                    &mut guard,
+                    user,
+                    passwd,
+                    addr,
+                );
+            }
+            ClientAPIQuery::AuthLogout(sess) => {
+                sessions.remove(&sess);
+                events::close_session(Some(&sess.token()));
+                resp = ClientAPIResponse::AuthLogout;
+            }
+            ClientAPIQuery::AuthRefresh(sess) => {
+                if sess.key().is_some() || sess.peer().is_some() || !sess.identities().is_empty() {
+                    resp = ClientAPIResponse::BadRequest(
+                        "only session tokens can be refreshed".to_string(),
+                    );
+                } else {
+                    // the refreshed token replaces the current one
+                    let r = refresh_api_client(&cfg, &sess, granted.unwrap_or(API_ACCESS_RUN));
+                    if let Some(nsess) = &r {
+                        sessions.remove(&sess);
+                        sessions.insert(nsess);
+                        events::close_session(Some(&sess.token()));
+                    }
+                    resp = ClientAPIResponse::AuthResponse(r);
                 }
-            },
+            }
+            ClientAPIQuery::AuthRevoke(_) => {
+                let revoked = sessions.clear();
+                events::close_session(None);
+                resp = ClientAPIResponse::AuthRevoke(ResponseRevoke { revoked });
+            }
+            ClientAPIQuery::AuthLockouts(_) => {
+                let r = guard.lockouts().into_iter().map(response_lockout).collect();
+                resp = ClientAPIResponse::AuthLockouts(r);
+            }
+            ClientAPIQuery::Audit(_, since, limit) => {
+                resp = match audit.query(since, limit) {
+                    Some(r) => ClientAPIResponse::Audit(r),
+                    None => ClientAPIResponse::NotFound,
+                };
+            }
+            ClientAPIQuery::CfgGlobalAll(_) => {
+                let r = capi_req_cfg_global_all(&cfg);
+                resp = ClientAPIResponse::CfgGlobalAll(r);
+            }
+            ClientAPIQuery::CfgVrrpAll(_) => {
+                let r = capi_req_cfg_vrrp_all(&cfg);
+                resp = ClientAPIResponse::CfgVrrpAll(r);
+            }
+            ClientAPIQuery::CfgProtoAll(_) => {
+                let r = capi_req_cfg_proto_all(&cfg);
+                resp = ClientAPIResponse::CfgProtoAll(r);
+            }
+            ClientAPIQuery::CfgGlobalPut(_, ncfg, persist) => {
+                resp = match capi_req_cfg_global_put(&mut cfg, ncfg, persist, &cfg_file, cfg_type) {
+                    Ok(r) => ClientAPIResponse::CfgGlobalAll(r),
+                    Err(e) => e,
+                };
+            }
+            ClientAPIQuery::CfgVrrpPut(_, nvrs, persist) => {
+                resp = match capi_req_cfg_vrrp_put(
+                    &mut cfg, &runtime, nvrs, persist, &cfg_file, cfg_type,
+                ) {
+                    Ok(r) => ClientAPIResponse::CfgVrrpAll(r),
+                    Err(e) => e,
+                };
+            }
+            ClientAPIQuery::CfgProtoPut(_, nproto, persist) => {
+                resp = match capi_req_cfg_proto_put(&mut cfg, nproto, persist, &cfg_file, cfg_type)
+                {
+                    Ok(r) => ClientAPIResponse::CfgProtoAll(r),
+                    Err(e) => e,
+                };
+            }
+            ClientAPIQuery::CfgReload(_) => {
+                resp = match capi_req_cfg_reload(&mut cfg, &runtime, &cfg_file, cfg_type) {
+                    Ok(r) => ClientAPIResponse::CfgGlobalAll(r),
+                    Err(e) => e,
+                };
+            }
+            ClientAPIQuery::RunGlobalAll(_) => {
+                let r = capi_req_run_global_all(&cfg);
+                resp = ClientAPIResponse::RunGlobalAll(r);
+            }
+            ClientAPIQuery::RunVRRPAll(_) => {
+                let r = capi_req_run_vrrp_all(&vrs);
+                resp = ClientAPIResponse::RunVRRPAll(r);
+            }
+            ClientAPIQuery::RunVRRPGrp(_, gid) => {
+                let r = capi_req_run_vrrp_grp(&vrs, gid);
+                resp = ClientAPIResponse::RunVRRPGrp(r);
+            }
+            ClientAPIQuery::RunVRRPGrpIntf(_, gid, intf) => {
+                let r = capi_req_run_vrrp_grp_intf(&vrs, gid, intf);
+                resp = ClientAPIResponse::RunVRRPGrpIntf(r);
+            }
+            ClientAPIQuery::RunVRRPPeers(_, gid, intf) => {
+                let r = capi_req_run_vrrp_peers(&vrs, gid, intf);
+                resp = ClientAPIResponse::RunVRRPPeers(r);
+            }
+            ClientAPIQuery::RunVRRPHistory(_, gid, intf) => {
+                let r = capi_req_run_vrrp_history(&vrs, gid, intf);
+                resp = ClientAPIResponse::RunVRRPHistory(r);
+            }
+            ClientAPIQuery::RunVRRPAdd(_, vrc) => {
+                resp = match capi_req_run_vrrp_add(&mut cfg, &runtime, vrc) {
+                    Ok(r) => ClientAPIResponse::RunVRRPAdd(r),
+                    Err(e) => e,
+                };
+            }
+            ClientAPIQuery::RunVRRPDel(_, gid, intf) => {
+                resp = match capi_req_run_vrrp_del(&mut cfg, &runtime, gid, intf) {
+                    Ok(_) => ClientAPIResponse::RunVRRPDel,
+                    Err(e) => e,
+                };
+            }
+            ClientAPIQuery::RunVRRPMod(_, gid, intf, req) => {
+                resp = match capi_req_run_vrrp_mod(&runtime, gid, intf, req) {
+                    Ok(r) => ClientAPIResponse::RunVRRPMod(r),
+                    Err(e) => e,
+                };
+            }
+            ClientAPIQuery::RunVRRPMaint(_, req) => {
+                resp = match capi_req_run_vrrp_maint(&runtime, &vrs, req) {
+                    Ok(r) => ClientAPIResponse::RunVRRPMaint(r),
+                    Err(e) => e,
+                };
+            }
+            ClientAPIQuery::RunProtoAll(_) => {
+                let r = capi_req_run_proto_all(&vrs);
+                resp = ClientAPIResponse::RunProtoAll(r);
+            }
+            ClientAPIQuery::RunProtoStatic(_) => {
+                let r = capi_req_run_proto_static(&vrs);
+                resp = ClientAPIResponse::RunProtoStatic(r);
+            }
+            ClientAPIQuery::Events(sess) => {
+                // the stream of a session token ends with the session
+                let session = sessions.expires(&sess).map(|e| (sess.token(), e));
+                resp = ClientAPIResponse::Events(session, granted.unwrap_or(API_ACCESS_RUN));
+            }
         }
 
-        // send queries answer back
-        let rtx = rtx.lock().unwrap();
+        // record the request, and the configuration changes
+        let diff = match (&before, resp.status()) {
+            (Some(b), 200..=299) => serde_json::to_value(&cfg).ok().map(|a| audit::diff(b, &a)),
+            _ => None,
+        };
+        audit.record(entry, resp.status(), diff);
+
+        // send the answer back on the query's reply channel
         let _r = rtx.send(resp);
     }
 }
@@ -340,15 +772,118 @@ pub fn capi_start_app(
     tls: bool,
     tls_key: String,
     tls_cert: String,
+    tls_client_ca: Option<String>,
+    rate_limit: Option<u32>,
+    tcp: bool,
+    unix: Option<config::APIUnix>,
 ) {
     // spawn the API application server in a new thread
-    thread::spawn(move || router::start(down_api, host, tls, tls_key, tls_cert));
+    thread::spawn(move || {
+        router::start(
+            down_api,
+            host,
+            tls,
+            tls_key,
+            tls_cert,
+            tls_client_ca,
+            rate_limit,
+            tcp,
+            unix,
+        )
+    });
+}
+
+// capi_req_auth() function
+/// authenticate a client, unless its user or address is locked out
+fn capi_req_auth(
+    cfg: &config::CConfig,
+    runtime: &Runtime,
+    sessions: &mut SessionStore,
+    guard: &mut LoginGuard,
+    user: String,
+    passwd: String,
+    addr: Option<IpAddr>,
+) -> ClientAPIResponse {
+    let debug = runtime.debug();
+    let source = addr.map_or("unknown".to_string(), |a| a.to_string());
+
+    // reject the attempts during backoffs and lockouts
+    if let Some(wait) = guard.check(&user, addr) {
+        print_debug(
+            debug,
+            DEBUG_LEVEL_LOW,
+            DEBUG_SRC_API,
+            format!(
+                "rejected authentication attempt for user {} from {} (retry in {}s)",
+                user, source, wait
+            ),
+        );
+        return ClientAPIResponse::TooManyRequests(wait);
+    }
+
+    match auth_api_client(cfg, user.clone(), passwd, addr) {
+        Some(sess) => {
+            guard.success(&user, addr);
+            sessions.insert(&sess);
+            ClientAPIResponse::AuthResponse(Some(sess))
+        }
+        None => {
+            print_debug(
+                debug,
+                DEBUG_LEVEL_LOW,
+                DEBUG_SRC_API,
+                format!("authentication failure for user {} from {}", user, source),
+            );
+            let api = cfg.api.as_ref().unwrap();
+            let locked = guard.failure(&user, addr, api.login_max_failures(), api.login_lockout());
+            // log and publish the new lockouts as security events
+            for l in locked {
+                let r = response_lockout(l);
+                print_debug(
+                    debug,
+                    DEBUG_LEVEL_INFO,
+                    DEBUG_SRC_API,
+                    format!(
+                        "{} locked out after {} authentication failures",
+                        match (&r.user, &r.address) {
+                            (Some(u), _) => format!("user {}", u),
+                            (_, Some(a)) => format!("address {}", a),
+                            _ => "client".to_string(),
+                        },
+                        r.failures
+                    ),
+                );
+                events::publish(Notification::LoginLockout {
+                    user: r.user,
+                    address: r.address,
+                    until: r.until,
+                });
+            }
+            ClientAPIResponse::AuthResponse(None)
+        }
+    }
+}
+
+// response_lockout() function
+fn response_lockout(l: Lockout) -> ResponseLockout {
+    let (user, address) = match l.key {
+        GuardKey::User(u) => (Some(u), None),
+        GuardKey::Address(a) => (None, Some(a.to_string())),
+    };
+    ResponseLockout {
+        user,
+        address,
+        failures: l.failures,
+        until: l.until,
+    }
 }
 
 // capi_req_cfg_global_all() function
 fn capi_req_cfg_global_all(cfg: &config::CConfig) -> config::CConfig {
-    // return the entire global configuration (cloned)
-    cfg.clone()
+    // return the entire global configuration (cloned), without the api secrets
+    let mut cfg = cfg.clone();
+    cfg.api = cfg.api.map(|a| a.redacted());
+    cfg
 }
 
 // capi_req_cfg_vrrp_all() function
@@ -363,6 +898,334 @@ fn capi_req_cfg_proto_all(cfg: &config::CConfig) -> config::Protocols {
     cfg.protocols.as_ref().unwrap().clone()
 }
 
+// capi_req_cfg_global_put() function
+fn capi_req_cfg_global_put(
+    cfg: &mut config::CConfig,
+    ncfg: config::CConfig,
+    persist: bool,
+    cfg_file: &str,
+    cfg_type: config::CfgType,
+) -> Result<config::CConfig, ClientAPIResponse> {
+    // sections have their own resources, they are accepted unchanged
+    // so the configuration read from GET can be sent back
+    if !capi_same_section(&ncfg.vrouter, &cfg.vrouter)
+        || !capi_same_section(&ncfg.protocols, &cfg.protocols)
+        || !capi_same_section(
+            &ncfg.api.as_ref().map(|a| a.redacted()),
+            &cfg.api.as_ref().map(|a| a.redacted()),
+        )
+    {
+        return Err(ClientAPIResponse::BadRequest(
+            "error(config): vrouter, protocols and api sections cannot be modified here"
+                .to_string(),
+        ));
+    }
+    let updated = capi_merge_cfg_global(cfg, ncfg)?;
+
+    capi_commit_cfg(cfg, updated, "global", persist, cfg_file, cfg_type)?;
+    Ok(capi_req_cfg_global_all(cfg))
+}
+
+// capi_same_section() function
+// return true if a configuration section is absent, or equal to the current one
+fn capi_same_section<T: serde::Serialize>(new: &Option<T>, current: &Option<T>) -> bool {
+    match new {
+        Some(_) => serde_json::to_value(new).ok() == serde_json::to_value(current).ok(),
+        None => true,
+    }
+}
+
+// capi_req_cfg_vrrp_put() function
+// replace the vrouter section, the changes are applied to the running virtual
+// routers the same way as on reload
+fn capi_req_cfg_vrrp_put(
+    cfg: &mut config::CConfig,
+    runtime: &Runtime,
+    nvrs: Vec<config::VRConfig>,
+    persist: bool,
+    cfg_file: &str,
+    cfg_type: config::CfgType,
+) -> Result<Vec<config::VRConfig>, ClientAPIResponse> {
+    let mut updated = capi_merge_cfg_vrrp(cfg, nvrs.clone())?;
+
+    // the in-memory virtual routers follow the running ones, even on failure
+    let applied = capi_apply_cfg_vrrp(cfg, runtime, &nvrs);
+    updated.vrouter = cfg.vrouter.clone();
+
+    capi_commit_cfg(cfg, updated, "vrouter", persist, cfg_file, cfg_type)?;
+    applied?;
+    Ok(capi_req_cfg_vrrp_all(cfg))
+}
+
+// capi_req_cfg_proto_put() function
+fn capi_req_cfg_proto_put(
+    cfg: &mut config::CConfig,
+    nproto: config::Protocols,
+    persist: bool,
+    cfg_file: &str,
+    cfg_type: config::CfgType,
+) -> Result<config::Protocols, ClientAPIResponse> {
+    let updated = capi_merge_cfg_proto(cfg, nproto)?;
+
+    capi_commit_cfg(cfg, updated, "protocols", persist, cfg_file, cfg_type)?;
+    Ok(capi_req_cfg_proto_all(cfg))
+}
+
+// capi_req_cfg_reload() function
+// read the configuration file again, and replace its global, vrouter and
+// protocols sections (the api section is only read at startup), the virtual
+// routers changes are applied to the running virtual routers
+fn capi_req_cfg_reload(
+    cfg: &mut config::CConfig,
+    runtime: &Runtime,
+    cfg_file: &str,
+    cfg_type: config::CfgType,
+) -> Result<config::CConfig, ClientAPIResponse> {
+    let ncfg = match config::load_config(cfg_file, cfg_type) {
+        Ok(c) => c,
+        Err(e) => return Err(ClientAPIResponse::BadRequest(e)),
+    };
+    let nvrs = ncfg.vrouter.clone().unwrap_or_default();
+    let nproto = ncfg.protocols.clone();
+
+    let updated = capi_merge_cfg_global(cfg, ncfg)?;
+    let mut updated = capi_merge_cfg_vrrp(&updated, nvrs.clone())?;
+    updated = match nproto {
+        Some(p) => capi_merge_cfg_proto(&updated, p)?,
+        None => updated,
+    };
+
+    // the in-memory virtual routers follow the running ones, even on failure
+    let applied = capi_apply_cfg_vrrp(cfg, runtime, &nvrs);
+    updated.vrouter = cfg.vrouter.clone();
+
+    capi_commit_cfg(cfg, updated, "all", false, cfg_file, cfg_type)?;
+    applied?;
+    Ok(capi_req_cfg_global_all(cfg))
+}
+
+// capi_apply_cfg_vrrp() function
+// apply the configured virtual routers to the running ones: the removed ones
+// are stopped, the added ones are started, the runtime parameters of the
+// others are reconfigured and they are restarted on any other change (their
+// maintenance mode is kept by the runtime)
+fn capi_apply_cfg_vrrp(
+    cfg: &mut config::CConfig,
+    runtime: &Runtime,
+    nvrs: &[config::VRConfig],
+) -> Result<(), ClientAPIResponse> {
+    let mut errors = Vec::new();
+
+    // compare against the running virtual routers: forget the configured ones
+    // which are not running, and stop the running ones which are not configured
+    // (the configured ones are started again below)
+    if let Some(vrs) = cfg.vrouter.as_mut() {
+        vrs.retain(|c| runtime.find(c.group(), &c.ifname()).is_some());
+    }
+    for vr in runtime.vrouters() {
+        let (gid, ifname) = {
+            let vr = vr.read().unwrap();
+            (vr.parameters.vrid(), config_interface(&vr))
+        };
+        let configured = cfg.vrouter.as_ref().map_or(false, |v| {
+            v.iter().any(|c| c.group() == gid && c.ifname() == ifname)
+        });
+        if !configured {
+            if let Err(e) = capi_req_run_vrrp_del(cfg, runtime, gid, ifname) {
+                errors.push(capi_error_message(e));
+            }
+        }
+    }
+    let vrs = cfg.vrouter.clone().unwrap_or_default();
+
+    // stop the removed virtual routers, and the ones to restart
+    for vrc in &vrs {
+        let restart = match nvrs
+            .iter()
+            .find(|n| n.group() == vrc.group() && n.ifname() == vrc.ifname())
+        {
+            Some(n) => !vrc.same_setup(n),
+            None => true,
+        };
+        if restart {
+            if let Err(e) = capi_req_run_vrrp_del(cfg, runtime, vrc.group(), vrc.ifname()) {
+                errors.push(capi_error_message(e));
+            }
+        }
+    }
+
+    for n in nvrs {
+        let current = cfg
+            .vrouter
+            .as_ref()
+            .and_then(|v| {
+                v.iter()
+                    .find(|c| c.group() == n.group() && c.ifname() == n.ifname())
+            })
+            .cloned();
+        match current {
+            // reconfigure the changed runtime parameters (unless it failed to stop)
+            Some(c) => {
+                if c != *n && c.same_setup(n) {
+                    match capi_reconfigure_vrrp(runtime, n) {
+                        Ok(_) => capi_update_cfg_vrrp(cfg, n.group(), &n.ifname(), Some(n.clone())),
+                        Err(e) => errors.push(capi_error_message(e)),
+                    }
+                }
+            }
+            // start the added virtual routers, and the ones to restart
+            None => {
+                if let Err(e) = capi_req_run_vrrp_add(cfg, runtime, n.clone()) {
+                    errors.push(capi_error_message(e));
+                }
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(ClientAPIResponse::InternalError(errors.join(", ")))
+    }
+}
+
+// capi_reconfigure_vrrp() function
+// send the runtime parameters of a virtual router configuration to its worker thread
+fn capi_reconfigure_vrrp(
+    runtime: &Runtime,
+    vrc: &config::VRConfig,
+) -> Result<(), ClientAPIResponse> {
+    let vr = match runtime.find(vrc.group(), &vrc.ifname()) {
+        Some(vr) => vr,
+        None => return Err(ClientAPIResponse::NotFound),
+    };
+    let rc = Reconfig {
+        // the address owner priority cannot be changed
+        prio: match vr.read().unwrap().parameters.prio() {
+            255 => None,
+            _ => Some(vrc.priority()),
+        },
+        preempt: Some(vrc.preemption()),
+        adverint: Some(vrc.timer_advert()),
+        auth: Some((vrc.auth_type(), vrc.auth_secret())),
+    };
+    runtime
+        .request(&vr, |ack| Event::Reconfigure(rc, ack))
+        .map_err(|e| {
+            ClientAPIResponse::InternalError(format!(
+                "error(api): the virtual router did not apply the changes: {}",
+                e
+            ))
+        })
+}
+
+// capi_error_message() function
+// return the message of an error response, for the responses gathering several errors
+fn capi_error_message(resp: ClientAPIResponse) -> String {
+    match resp {
+        ClientAPIResponse::BadRequest(e)
+        | ClientAPIResponse::Conflict(e)
+        | ClientAPIResponse::InternalError(e) => e,
+        r => format!("error(api): request failed with status {}", r.status()),
+    }
+}
+
+// capi_merge_cfg_global() function
+// return the configuration with the global directives replaced
+fn capi_merge_cfg_global(
+    cfg: &config::CConfig,
+    ncfg: config::CConfig,
+) -> Result<config::CConfig, ClientAPIResponse> {
+    if let Err(e) = ncfg.validate() {
+        return Err(ClientAPIResponse::BadRequest(e));
+    }
+
+    // replace the global directives only
+    let mut updated = cfg.clone();
+    updated.debug = ncfg.debug;
+    updated.time_zone = ncfg.time_zone;
+    updated.time_format = ncfg.time_format;
+    updated.pid = ncfg.pid;
+    updated.working_dir = ncfg.working_dir;
+    updated.main_log = ncfg.main_log;
+    updated.error_log = ncfg.error_log;
+    updated.crash_dump = ncfg.crash_dump;
+    updated.client_api = ncfg.client_api;
+    Ok(updated)
+}
+
+// capi_merge_cfg_vrrp() function
+// return the configuration with the virtual routers replaced
+fn capi_merge_cfg_vrrp(
+    cfg: &config::CConfig,
+    nvrs: Vec<config::VRConfig>,
+) -> Result<config::CConfig, ClientAPIResponse> {
+    // the daemon cannot start without any virtual router
+    if nvrs.is_empty() {
+        return Err(ClientAPIResponse::BadRequest(
+            "error(config): at least one virtual router must be configured".to_string(),
+        ));
+    }
+    for (i, vr) in nvrs.iter().enumerate() {
+        if let Err(e) = vr.validate() {
+            return Err(ClientAPIResponse::BadRequest(e));
+        }
+        // a group can only be configured once per interface
+        if nvrs[..i]
+            .iter()
+            .any(|o| o.group() == vr.group() && o.ifname() == vr.ifname())
+        {
+            return Err(ClientAPIResponse::BadRequest(format!(
+                "error(config): group {} is configured more than once on interface {}",
+                vr.group(),
+                vr.ifname()
+            )));
+        }
+    }
+
+    let mut updated = cfg.clone();
+    updated.vrouter = Some(nvrs);
+    Ok(updated)
+}
+
+// capi_merge_cfg_proto() function
+// return the configuration with the protocols replaced
+fn capi_merge_cfg_proto(
+    cfg: &config::CConfig,
+    nproto: config::Protocols,
+) -> Result<config::CConfig, ClientAPIResponse> {
+    if let Err(e) = nproto.validate() {
+        return Err(ClientAPIResponse::BadRequest(e));
+    }
+
+    let mut updated = cfg.clone();
+    updated.protocols = Some(nproto);
+    Ok(updated)
+}
+
+// capi_commit_cfg() function
+// replace the in-memory configuration, writing it to disk first if requested
+fn capi_commit_cfg(
+    cfg: &mut config::CConfig,
+    updated: config::CConfig,
+    section: &str,
+    persist: bool,
+    cfg_file: &str,
+    cfg_type: config::CfgType,
+) -> Result<(), ClientAPIResponse> {
+    if persist {
+        if let Err(e) = config::save_config(cfg_file, &updated, cfg_type) {
+            return Err(ClientAPIResponse::InternalError(e));
+        }
+    }
+    *cfg = updated;
+    // notify the events subscribers
+    events::publish(Notification::ConfigReload {
+        section: section.to_string(),
+    });
+    Ok(())
+}
+
 // capi_req_run_global_all() function
 fn capi_req_run_global_all(cfg: &config::CConfig) -> ResponseGlobalAttr {
     // build response for effective global configuration
@@ -375,6 +1238,7 @@ fn capi_req_run_global_all(cfg: &config::CConfig) -> ResponseGlobalAttr {
         working_dir: cfg.working_dir(),
         main_log: cfg.main_log(),
         error_log: cfg.error_log(),
+        crash_dump: cfg.crash_dump(),
     };
 
     attrs
@@ -400,6 +1264,7 @@ fn capi_req_run_vrrp_all(vrs: &Vec<Arc<RwLock<VirtualRouter>>>) -> Vec<ResponseV
             },
             priority: vro.parameters.prio(),
             preempt: vro.parameters.preempt(),
+            maintenance: vro.parameters.maintenance(),
             state: vro.states.states(),
         };
         // push attriburs into vector
@@ -439,6 +1304,7 @@ fn capi_req_run_vrrp_grp(
             },
             priority: vr.parameters.prio(),
             preempt: vr.parameters.preempt(),
+            maintenance: vr.parameters.maintenance(),
             state: vr.states.states(),
         };
         // push vr
@@ -487,6 +1353,7 @@ fn capi_req_run_vrrp_grp_intf(
                 },
                 priority: vr.parameters.prio(),
                 preempt: vr.parameters.preempt(),
+                maintenance: vr.parameters.maintenance(),
                 state: vr.states.states(),
                 auth_type: vr.parameters.authtype(),
                 interface_hwaddress: format!(
@@ -510,6 +1377,260 @@ fn capi_req_run_vrrp_grp_intf(
     }
 }
 
+// capi_req_run_vrrp_peers() function
+fn capi_req_run_vrrp_peers(
+    vrs: &Vec<Arc<RwLock<VirtualRouter>>>,
+    gid: u8,
+    intf: String,
+) -> Option<Vec<ResponsePeerAttr>> {
+    // find a virtual router matching the vrid (gid) and interface (intf)
+    let r = vrs.iter().find(|&vr| {
+        let vr = vr.read().unwrap();
+        if vr.current_state() == "Master" {
+            (vr.parameters.vrid() == gid) && (vr.parameters.vifname() == intf)
+        } else {
+            (vr.parameters.vrid() == gid) && (vr.parameters.interface() == intf)
+        }
+    });
+
+    // get read access
+    let vr = r?.read().unwrap();
+    let ip = |a: &[u8; 4]| format!("{}.{}.{}.{}", a[0], a[1], a[2], a[3]);
+    // the master is the last peer seen as master, unless the local router is
+    let master = vr.parameters.master();
+    let mut peers = vr.parameters.peers().lock().unwrap();
+    // build the peers attributes vector
+    let attrs = peers
+        .peers()
+        .iter()
+        .map(|p| ResponsePeerAttr {
+            source_ip: ip(&p.ipsrc),
+            source_mac: format!(
+                "{:02X}:{:02X}:{:02X}:{:02X}:{:02X}:{:02X}",
+                p.macsrc[0], p.macsrc[1], p.macsrc[2], p.macsrc[3], p.macsrc[4], p.macsrc[5],
+            ),
+            priority: p.prio,
+            advert_interval: p.adverint,
+            auth_type: p.authtype,
+            addresses: p.ipaddrs.iter().map(ip).collect(),
+            first_seen: p.first_seen,
+            last_seen: p.last_seen,
+            master: p.is_master(master),
+        })
+        .collect();
+    Some(attrs)
+}
+
+// capi_req_run_vrrp_history() function
+fn capi_req_run_vrrp_history(
+    vrs: &Vec<Arc<RwLock<VirtualRouter>>>,
+    gid: u8,
+    intf: String,
+) -> Option<Vec<Transition>> {
+    // find a virtual router matching the vrid (gid) and interface (intf)
+    let r = vrs.iter().find(|&vr| {
+        let vr = vr.read().unwrap();
+        if vr.current_state() == "Master" {
+            (vr.parameters.vrid() == gid) && (vr.parameters.vifname() == intf)
+        } else {
+            (vr.parameters.vrid() == gid) && (vr.parameters.interface() == intf)
+        }
+    });
+
+    // get read access, and return the transitions from the oldest
+    let vr = r?.read().unwrap();
+    let history = vr.parameters.history().lock().unwrap();
+    Some(history.transitions().iter().cloned().collect())
+}
+
+// capi_req_run_vrrp_add() function
+fn capi_req_run_vrrp_add(
+    cfg: &mut config::CConfig,
+    runtime: &Runtime,
+    vrc: config::VRConfig,
+) -> Result<ResponseVRRPAttrExt, ClientAPIResponse> {
+    if let Err(e) = vrc.validate() {
+        return Err(ClientAPIResponse::BadRequest(e));
+    }
+    // a group can only run once per interface
+    if runtime.find(vrc.group(), &vrc.ifname()).is_some() {
+        return Err(ClientAPIResponse::Conflict(format!(
+            "error(api): group {} is already running on interface {}",
+            vrc.group(),
+            vrc.ifname()
+        )));
+    }
+
+    match runtime.add(&vrc) {
+        Ok(vr) => {
+            // the in-memory configuration follows the running virtual routers
+            capi_update_cfg_vrrp(cfg, vrc.group(), &vrc.ifname(), Some(vrc.clone()));
+            // in dry-run mode, it may listen on the VLAN's parent interface
+            let intf = vr.read().unwrap().parameters.interface();
+            let vrs = vec![vr];
+            match capi_req_run_vrrp_grp_intf(&vrs, vrc.group(), intf) {
+                Some(attrs) => Ok(attrs),
+                None => Err(ClientAPIResponse::InternalError(
+                    "error(api): virtual router not found after creation".to_string(),
+                )),
+            }
+        }
+        Err(e) => Err(ClientAPIResponse::InternalError(format!(
+            "error(api): cannot add virtual router: {}",
+            e
+        ))),
+    }
+}
+
+// capi_req_run_vrrp_del() function
+fn capi_req_run_vrrp_del(
+    cfg: &mut config::CConfig,
+    runtime: &Runtime,
+    gid: u8,
+    intf: String,
+) -> Result<(), ClientAPIResponse> {
+    match runtime.find(gid, &intf) {
+        Some(vr) => match runtime.remove(&vr) {
+            Ok(_) => {
+                let ifname = config_interface(&vr.read().unwrap());
+                capi_update_cfg_vrrp(cfg, gid, &ifname, None);
+                Ok(())
+            }
+            Err(e) => Err(ClientAPIResponse::InternalError(format!(
+                "error(api): cannot remove virtual router: {}",
+                e
+            ))),
+        },
+        None => Err(ClientAPIResponse::NotFound),
+    }
+}
+
+// capi_update_cfg_vrrp() function
+// replace or remove a virtual router of the in-memory configuration,
+// the configuration file is left untouched
+fn capi_update_cfg_vrrp(
+    cfg: &mut config::CConfig,
+    gid: u8,
+    ifname: &str,
+    vrc: Option<config::VRConfig>,
+) {
+    let vrs = cfg.vrouter.get_or_insert_with(Vec::new);
+    vrs.retain(|c| !(c.group() == gid && c.ifname() == ifname));
+    if let Some(c) = vrc {
+        vrs.push(c);
+    }
+}
+
+// capi_req_run_vrrp_mod() function
+fn capi_req_run_vrrp_mod(
+    runtime: &Runtime,
+    gid: u8,
+    intf: String,
+    req: RequestVRRPAttr,
+) -> Result<ResponseVRRPAttrExt, ClientAPIResponse> {
+    let vr = match runtime.find(gid, &intf) {
+        Some(vr) => vr,
+        None => return Err(ClientAPIResponse::NotFound),
+    };
+
+    // validate the requested changes
+    let mut rc = Reconfig::default();
+    match req.priority {
+        Some(p) if p < 1 || p > 254 => {
+            return Err(ClientAPIResponse::BadRequest(
+                "error(api): priority must be between 1 and 254".to_string(),
+            ))
+        }
+        Some(p) => {
+            // the address owner priority cannot be changed
+            if vr.read().unwrap().parameters.prio() == 255 {
+                return Err(ClientAPIResponse::Conflict(
+                    "error(api): the priority of the address owner cannot be changed".to_string(),
+                ));
+            }
+            rc.prio = Some(p);
+        }
+        None => {}
+    }
+    match req.advert_interval {
+        Some(i) if i < 1 => {
+            return Err(ClientAPIResponse::BadRequest(
+                "error(api): advertisement interval must be at least 1s".to_string(),
+            ))
+        }
+        i => rc.adverint = i,
+    }
+    rc.preempt = req.preempt;
+    match (&req.auth_type, req.auth_secret) {
+        (Some(t), secret) => match config::parse_auth_type(t) {
+            Some(t) => {
+                let secret = match (t, secret) {
+                    // type-1 secrets are truncated to 8 bytes
+                    (AUTH_TYPE_SIMPLE, Some(mut s)) => {
+                        s.truncate(8);
+                        Some(s)
+                    }
+                    (_, s) => s,
+                };
+                rc.auth = Some((t, secret));
+            }
+            None => {
+                return Err(ClientAPIResponse::BadRequest(format!(
+                    "error(api): authentication type {} is not supported",
+                    t
+                )))
+            }
+        },
+        (None, Some(_)) => {
+            return Err(ClientAPIResponse::BadRequest(
+                "error(api): an authentication secret requires an authentication type".to_string(),
+            ))
+        }
+        (None, None) => {}
+    }
+
+    // send the changes to the virtual router's worker thread
+    if let Err(e) = runtime.request(&vr, |ack| Event::Reconfigure(rc, ack)) {
+        return Err(ClientAPIResponse::InternalError(format!(
+            "error(api): the virtual router did not apply the changes: {}",
+            e
+        )));
+    }
+    // enter or leave maintenance mode
+    if let Some(on) = req.maintenance {
+        if let Err(e) = runtime.set_maintenance(&vr, on) {
+            return Err(ClientAPIResponse::InternalError(format!(
+                "error(api): cannot change maintenance mode: {}",
+                e
+            )));
+        }
+    }
+
+    let vrs = vec![vr];
+    match capi_req_run_vrrp_grp_intf(&vrs, gid, intf) {
+        Some(attrs) => Ok(attrs),
+        None => Err(ClientAPIResponse::NotFound),
+    }
+}
+
+// capi_req_run_vrrp_maint() function
+fn capi_req_run_vrrp_maint(
+    runtime: &Runtime,
+    vrs: &Vec<Arc<RwLock<VirtualRouter>>>,
+    req: RequestMaintenance,
+) -> Result<Vec<ResponseVRRPAttr>, ClientAPIResponse> {
+    // put every running virtual router in or out of maintenance mode
+    for vr in vrs {
+        if let Err(e) = runtime.set_maintenance(vr, req.maintenance) {
+            return Err(ClientAPIResponse::InternalError(format!(
+                "error(api): cannot change maintenance mode: {}",
+                e
+            )));
+        }
+    }
+    Ok(capi_req_run_vrrp_all(vrs))
+}
+
 // capi_req_run_proto_all() function
 fn capi_req_run_proto_all(vrs: &Vec<Arc<RwLock<VirtualRouter>>>) -> Option<ResponseProtoAttr> {
     // get static attributes vector (if any)
@@ -530,7 +1651,10 @@ fn capi_req_run_proto_static(
     // create static attributes vector
     let mut pattrs: Vec<ResponseProtoStaticAttr> = Vec::new();
     // access only first virtual router
-    let vr = &vrs[0];
+    let vr = match vrs.first() {
+        Some(vr) => vr,
+        None => return None,
+    };
     // get read access
     let vro = vr.read().unwrap();
     // get access to protocols structure
@@ -575,3 +1699,33 @@ fn capi_req_run_proto_static(
         None => return None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn concurrent_queries_read_their_own_answer() {
+        let down_api = DownstreamAPI::new();
+        let query = |user: &str| ClientAPIQuery::AuthRequest(user.to_string(), String::new(), None);
+        let first = down_api.query(query("first"));
+        let second = down_api.query(query("second"));
+
+        // answer the queries, the first one with a 401 and the second with a 404
+        let qrx = down_api.q_receiver.lock().unwrap();
+        for _ in 0..2 {
+            let (q, rtx) = qrx.recv().unwrap();
+            let resp = match q {
+                ClientAPIQuery::AuthRequest(user, _, _) if user == "first" => {
+                    ClientAPIResponse::Unauthorized
+                }
+                _ => ClientAPIResponse::NotFound,
+            };
+            rtx.send(resp).unwrap();
+        }
+
+        // read in the reverse order
+        assert_eq!(second.read().status(), 404);
+        assert_eq!(first.read().status(), 401);
+    }
+}
@@ -0,0 +1,35 @@
+//! Client API - audit log handlers
+use super::*;
+
+// default and maximum number of returned records
+const AUDIT_DFLT_LIMIT: usize = 1000;
+
+/// records() handler function
+/// retrieve the audit log records, optionally following a sequence number,
+/// and the result of the chain verification
+pub fn records(state: State) -> (State, Response<Body>) {
+    // borrow references to the Downstream API
+    let down = DownstreamAPI::borrow_from(&state);
+
+    // retrieve session
+    let sess = read_session(&state);
+
+    // send a query downstream
+    let filter = AuditExtractor::borrow_from(&state);
+    let since = filter.since.unwrap_or(0);
+    let limit = filter
+        .limit
+        .unwrap_or(AUDIT_DFLT_LIMIT)
+        .min(AUDIT_DFLT_LIMIT);
+    let reply = down.query(ClientAPIQuery::Audit(sess, since, limit));
+
+    // read answer and set HTTP body (blocking)
+    let htbody = match reply.read() {
+        ClientAPIResponse::Audit(ans) => serialize_answer(&state, ans),
+        ClientAPIResponse::NotFound => create_empty_response(&state, StatusCode::NOT_FOUND),
+        ClientAPIResponse::Unauthorized => create_empty_response(&state, StatusCode::UNAUTHORIZED),
+        ClientAPIResponse::Forbidden => create_empty_response(&state, StatusCode::FORBIDDEN),
+        _ => create_empty_response(&state, StatusCode::INTERNAL_SERVER_ERROR),
+    };
+    return (state, htbody);
+}
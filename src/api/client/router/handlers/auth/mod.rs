@@ -7,6 +7,9 @@ use futures::{future, Future, Stream};
 // gotham
 use gotham::handler::{HandlerFuture, IntoHandlerError};
 
+// hyper
+use hyper::header::{HeaderValue, RETRY_AFTER};
+
 // regex
 extern crate regex;
 use regex::Regex;
@@ -27,6 +30,7 @@ pub fn client(mut state: State) -> Box<HandlerFuture> {
                     Some(c) => ClientAPIQuery::AuthRequest(
                         c.get(1).unwrap().as_str().to_string(),
                         c.get(2).unwrap().as_str().to_string(),
+                        client_addr(&state).map(|a| a.ip()),
                     ),
                     // if not, return an error
                     None => {
@@ -35,49 +39,17 @@ pub fn client(mut state: State) -> Box<HandlerFuture> {
                     }
                 };
                 // send authentication request
-                down.query(q);
+                let reply = down.query(q);
 
                 // read downstream API channel
-                match down.read() {
+                match reply.read() {
                     // read authentication response
                     ClientAPIResponse::AuthResponse(sess) => {
                         match sess {
                             // authentication succeeded
                             Some(st) => {
-                                // build cookies
-                                let mut cookie_user =
-                                    Cookie::build(COOKIE_USER, st.user()).http_only(true);
-                                let mut cookie_ts =
-                                    Cookie::build(COOKIE_TIMESTAMP, st.ts_since()).http_only(true);
-                                let mut cookie_nonce =
-                                    Cookie::build(COOKIE_NONCE, st.nonce()).http_only(true);
-                                let mut cookie_token =
-                                    Cookie::build(COOKIE_TOKEN, st.token()).http_only(true);
-                                // add 'secure' attribute if TLS is enabled
-                                if st.secure() {
-                                    cookie_user = cookie_user.secure(true);
-                                    cookie_ts = cookie_ts.secure(true);
-                                    cookie_nonce = cookie_nonce.secure(true);
-                                    cookie_token = cookie_token.secure(true);
-                                }
-                                // add 'SameSite' attribute and finish the cookies
-                                let cookie_user = cookie_user.same_site(SameSite::Strict).finish();
-                                let cookie_ts = cookie_ts.same_site(SameSite::Strict).finish();
-                                let cookie_nonce =
-                                    cookie_nonce.same_site(SameSite::Strict).finish();
-                                let cookie_token =
-                                    cookie_token.same_site(SameSite::Strict).finish();
-                                // create an empty response
-                                let mut resp = create_empty_response(&state, StatusCode::OK);
-                                // append cookies to response
-                                resp.headers_mut()
-                                    .append(SET_COOKIE, cookie_user.to_string().parse().unwrap());
-                                resp.headers_mut()
-                                    .append(SET_COOKIE, cookie_ts.to_string().parse().unwrap());
-                                resp.headers_mut()
-                                    .append(SET_COOKIE, cookie_nonce.to_string().parse().unwrap());
-                                resp.headers_mut()
-                                    .append(SET_COOKIE, cookie_token.to_string().parse().unwrap());
+                                // create the token response with the session cookies
+                                let resp = session_response(&state, &st);
                                 // return future
                                 future::ok((state, resp))
                             }
@@ -88,6 +60,13 @@ pub fn client(mut state: State) -> Box<HandlerFuture> {
                             }
                         }
                     }
+                    // the user or the client address is locked out
+                    ClientAPIResponse::TooManyRequests(wait) => {
+                        let mut resp = create_empty_response(&state, StatusCode::TOO_MANY_REQUESTS);
+                        resp.headers_mut()
+                            .insert(RETRY_AFTER, HeaderValue::from(wait));
+                        return future::ok((state, resp));
+                    }
                     // other response types are considered invalid
                     _ => {
                         let resp = create_empty_response(&state, StatusCode::INTERNAL_SERVER_ERROR);
@@ -101,6 +80,176 @@ pub fn client(mut state: State) -> Box<HandlerFuture> {
     Box::new(f)
 }
 
+/// logout() handler function
+pub fn logout(state: State) -> (State, Response<Body>) {
+    // borrow references to the Downstream API
+    let down = DownstreamAPI::borrow_from(&state);
+
+    // retrieve session
+    let sess = read_session(&state);
+
+    // send a query downstream
+    let q = ClientAPIQuery::AuthLogout(sess);
+    let reply = down.query(q);
+
+    // read answer and set HTTP body (blocking)
+    let htbody = {
+        match reply.read() {
+            // the session has been revoked, clear the session cookies
+            ClientAPIResponse::AuthLogout => {
+                let mut resp = create_empty_response(&state, StatusCode::OK);
+                clear_session_cookies(&mut resp);
+                resp
+            }
+            ClientAPIResponse::Unauthorized => {
+                create_empty_response(&state, StatusCode::UNAUTHORIZED)
+            }
+            _ => create_empty_response(&state, StatusCode::INTERNAL_SERVER_ERROR),
+        }
+    };
+    return (state, htbody);
+}
+
+/// refresh() handler function
+pub fn refresh(state: State) -> (State, Response<Body>) {
+    // borrow references to the Downstream API
+    let down = DownstreamAPI::borrow_from(&state);
+
+    // retrieve session
+    let sess = read_session(&state);
+
+    // send a query downstream
+    let q = ClientAPIQuery::AuthRefresh(sess);
+    let reply = down.query(q);
+
+    // read answer and set HTTP body (blocking)
+    let htbody = {
+        match reply.read() {
+            // a new session token has been issued
+            ClientAPIResponse::AuthResponse(Some(st)) => session_response(&state, &st),
+            ClientAPIResponse::BadRequest(e) => serialize_error(&state, StatusCode::BAD_REQUEST, e),
+            ClientAPIResponse::Unauthorized => {
+                create_empty_response(&state, StatusCode::UNAUTHORIZED)
+            }
+            _ => create_empty_response(&state, StatusCode::INTERNAL_SERVER_ERROR),
+        }
+    };
+    return (state, htbody);
+}
+
+/// revoke() handler function
+pub fn revoke(state: State) -> (State, Response<Body>) {
+    // borrow references to the Downstream API
+    let down = DownstreamAPI::borrow_from(&state);
+
+    // retrieve session
+    let sess = read_session(&state);
+
+    // send a query downstream
+    let q = ClientAPIQuery::AuthRevoke(sess);
+    let reply = down.query(q);
+
+    // read answer and set HTTP body (blocking)
+    let htbody = {
+        match reply.read() {
+            // all the sessions have been revoked, including the current one
+            ClientAPIResponse::AuthRevoke(ans) => {
+                let mut resp = serialize_answer(&state, ans);
+                clear_session_cookies(&mut resp);
+                resp
+            }
+            ClientAPIResponse::Unauthorized => {
+                create_empty_response(&state, StatusCode::UNAUTHORIZED)
+            }
+            ClientAPIResponse::Forbidden => create_empty_response(&state, StatusCode::FORBIDDEN),
+            _ => create_empty_response(&state, StatusCode::INTERNAL_SERVER_ERROR),
+        }
+    };
+    return (state, htbody);
+}
+
+/// lockouts() handler function
+pub fn lockouts(state: State) -> (State, Response<Body>) {
+    // borrow references to the Downstream API
+    let down = DownstreamAPI::borrow_from(&state);
+
+    // retrieve session
+    let sess = read_session(&state);
+
+    // send a query downstream
+    let q = ClientAPIQuery::AuthLockouts(sess);
+    let reply = down.query(q);
+
+    // read answer and set HTTP body (blocking)
+    let htbody = {
+        match reply.read() {
+            // if a response is returned
+            ClientAPIResponse::AuthLockouts(ans) => serialize_answer(&state, ans),
+            ClientAPIResponse::Unauthorized => {
+                create_empty_response(&state, StatusCode::UNAUTHORIZED)
+            }
+            ClientAPIResponse::Forbidden => create_empty_response(&state, StatusCode::FORBIDDEN),
+            _ => create_empty_response(&state, StatusCode::INTERNAL_SERVER_ERROR),
+        }
+    };
+    return (state, htbody);
+}
+
+// session_response() function
+// return the bearer token in the body, and the session cookies
+fn session_response(state: &State, st: &SessionToken) -> Response<Body> {
+    let ans = ResponseToken {
+        token: st.bearer(),
+        expires: st.ts_valid(),
+    };
+    let mut resp = serialize_answer(state, ans);
+    set_session_cookies(&mut resp, st);
+    resp
+}
+
+// set_session_cookies() function
+// append the session token cookies to the response
+fn set_session_cookies(resp: &mut Response<Body>, st: &SessionToken) {
+    let cookies = [
+        (COOKIE_USER, st.user()),
+        (COOKIE_TIMESTAMP, st.ts_since()),
+        (COOKIE_NONCE, st.nonce()),
+        (COOKIE_ACCESS, format!("{}", st.access())),
+        (COOKIE_TOKEN, st.token()),
+    ];
+    for (name, value) in cookies.iter() {
+        // build the cookie
+        let mut cookie = Cookie::build(*name, value.clone()).http_only(true);
+        // add 'secure' attribute if TLS is enabled
+        if st.secure() {
+            cookie = cookie.secure(true);
+        }
+        // add 'SameSite' attribute and finish the cookie
+        let cookie = cookie.same_site(SameSite::Strict).finish();
+        // append cookie to response
+        resp.headers_mut()
+            .append(SET_COOKIE, cookie.to_string().parse().unwrap());
+    }
+}
+
+// clear_session_cookies() function
+// instruct the client to delete the session cookies
+fn clear_session_cookies(resp: &mut Response<Body>) {
+    for name in [
+        COOKIE_USER,
+        COOKIE_TIMESTAMP,
+        COOKIE_NONCE,
+        COOKIE_ACCESS,
+        COOKIE_TOKEN,
+    ]
+    .iter()
+    {
+        let cookie = format!("{}=; Max-Age=0; HttpOnly; SameSite=Strict", name);
+        resp.headers_mut()
+            .append(SET_COOKIE, cookie.parse().unwrap());
+    }
+}
+
 /// regex_captures_authav() function
 /// creates a globally accessible and static compiled regular expression
 fn regex_captures_authav(content: &String) -> Option<regex::Captures> {
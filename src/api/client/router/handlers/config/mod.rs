@@ -1,6 +1,15 @@
 //! Client API - global router handlers
 use super::*;
 
+// futures
+use futures::{future, Future, Stream};
+
+// gotham
+use gotham::handler::{HandlerFuture, IntoHandlerError};
+
+// serde
+use serde::de::DeserializeOwned;
+
 // global configuration handlers
 pub mod global;
 
@@ -9,3 +18,89 @@ pub mod vrouter;
 
 // protocols configuration handlers
 pub mod protocols;
+
+/// reload() handler function
+/// read the configuration file again
+pub fn reload(state: State) -> (State, Response<Body>) {
+    // borrow references to the Downstream API
+    let down = DownstreamAPI::borrow_from(&state);
+
+    // retrieve session
+    let sess = read_session(&state);
+
+    // send a query downstream
+    let reply = down.query(ClientAPIQuery::CfgReload(sess));
+
+    // read answer and set HTTP body (blocking)
+    let htbody = match reply.read() {
+        ClientAPIResponse::CfgGlobalAll(ans) => serialize_answer(&state, ans),
+        ClientAPIResponse::BadRequest(e) => serialize_error(&state, StatusCode::BAD_REQUEST, e),
+        ClientAPIResponse::InternalError(e) => {
+            serialize_error(&state, StatusCode::INTERNAL_SERVER_ERROR, e)
+        }
+        ClientAPIResponse::Unauthorized => create_empty_response(&state, StatusCode::UNAUTHORIZED),
+        ClientAPIResponse::Forbidden => create_empty_response(&state, StatusCode::FORBIDDEN),
+        _ => create_empty_response(&state, StatusCode::INTERNAL_SERVER_ERROR),
+    };
+    return (state, htbody);
+}
+
+// update() function
+// common handling of the configuration PUT requests, the JSON body
+// is decoded into T and handed over to the Client API thread
+fn update<T, F>(mut state: State, query: F) -> Box<HandlerFuture>
+where
+    T: DeserializeOwned + Send + 'static,
+    F: FnOnce(SessionToken, T, bool) -> ClientAPIQuery + Send + 'static,
+{
+    let persist = PersistExtractor::take_from(&mut state)
+        .persist
+        .unwrap_or(false);
+    let f = Body::take_from(&mut state)
+        .concat2()
+        .then(move |body| match body {
+            Ok(valid_body) => {
+                // decode the new configuration
+                let obj: T = match serde_json::from_slice(&valid_body) {
+                    Ok(o) => o,
+                    Err(e) => {
+                        let resp = serialize_error(
+                            &state,
+                            StatusCode::BAD_REQUEST,
+                            format!("error(config): {}", e),
+                        );
+                        return future::ok((state, resp));
+                    }
+                };
+                let sess = read_session(&state);
+
+                // send the query downstream
+                let down = DownstreamAPI::borrow_from(&state);
+                let reply = down.query(query(sess, obj, persist));
+
+                // read answer (blocking)
+                let resp = match reply.read() {
+                    ClientAPIResponse::CfgGlobalAll(ans) => serialize_answer(&state, ans),
+                    ClientAPIResponse::CfgVrrpAll(ans) => serialize_answer(&state, ans),
+                    ClientAPIResponse::CfgProtoAll(ans) => serialize_answer(&state, ans),
+                    ClientAPIResponse::BadRequest(e) => {
+                        serialize_error(&state, StatusCode::BAD_REQUEST, e)
+                    }
+                    ClientAPIResponse::InternalError(e) => {
+                        serialize_error(&state, StatusCode::INTERNAL_SERVER_ERROR, e)
+                    }
+                    ClientAPIResponse::Unauthorized => {
+                        create_empty_response(&state, StatusCode::UNAUTHORIZED)
+                    }
+                    ClientAPIResponse::Forbidden => {
+                        create_empty_response(&state, StatusCode::FORBIDDEN)
+                    }
+                    _ => create_empty_response(&state, StatusCode::INTERNAL_SERVER_ERROR),
+                };
+                future::ok((state, resp))
+            }
+            Err(e) => future::err((state, e.into_handler_error())),
+        });
+
+    Box::new(f)
+}
@@ -0,0 +1,53 @@
+//! Client API - events stream handlers
+use super::*;
+
+// futures
+use futures::Stream;
+
+// hyper
+use hyper::header::CACHE_CONTROL;
+use hyper::Chunk;
+
+// events
+use crate::events::subscribe;
+
+/// stream() handler function
+/// stream the virtual routers events as Server-Sent Events, optionally
+/// filtered by group and/or interface
+pub fn stream(state: State) -> (State, Response<Body>) {
+    // borrow references to the Downstream API
+    let down = DownstreamAPI::borrow_from(&state);
+
+    // retrieve session
+    let sess = read_session(&state);
+
+    // send a query downstream to validate the session
+    let reply = down.query(ClientAPIQuery::Events(sess));
+
+    // read answer (blocking)
+    let htbody = match reply.read() {
+        ClientAPIResponse::Events(session, access) => {
+            // subscribe to the events matching the query string filter,
+            // until the session is revoked or expires
+            let filter = EventsFilterExtractor::borrow_from(&state);
+            let rx = subscribe(filter.group, filter.interface.clone(), session, access);
+            let events = rx
+                .map(Chunk::from)
+                .map_err(|_| failure::err_msg("events stream closed").compat());
+
+            let mut resp = create_response(
+                &state,
+                StatusCode::OK,
+                mime::TEXT_EVENT_STREAM,
+                Body::wrap_stream(events),
+            );
+            resp.headers_mut()
+                .insert(CACHE_CONTROL, "no-cache".parse().unwrap());
+            resp
+        }
+        ClientAPIResponse::Unauthorized => create_empty_response(&state, StatusCode::UNAUTHORIZED),
+        ClientAPIResponse::Forbidden => create_empty_response(&state, StatusCode::FORBIDDEN),
+        _ => create_empty_response(&state, StatusCode::INTERNAL_SERVER_ERROR),
+    };
+    return (state, htbody);
+}
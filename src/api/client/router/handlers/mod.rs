@@ -13,6 +13,12 @@ pub mod config;
 // running config scope handlers
 pub mod run;
 
+// events stream handlers
+pub mod events;
+
+// audit log handlers
+pub mod audit;
+
 // index() function
 pub fn index(state: State) -> (State, Response<Body>) {
     let body = r#"
@@ -37,3 +43,9 @@ pub fn index(state: State) -> (State, Response<Body>) {
     let htbody = { create_response(&state, StatusCode::OK, mime::TEXT_HTML, body) };
     return (state, htbody);
 }
+
+// openapi() function
+pub fn openapi(state: State) -> (State, Response<Body>) {
+    let htbody = serialize_answer(&state, openapi::document());
+    return (state, htbody);
+}
@@ -6,28 +6,22 @@ pub fn all(state: State) -> (State, Response<Body>) {
     // borrow references to the Downstream API
     let down = DownstreamAPI::borrow_from(&state);
 
-    // retrieve session cookie
-    let (user, ts_since, nonce, token) = read_session_cookies(&state);
-
-    // create SessionToken
-    let mut sess = SessionToken::new();
-    sess.set_user(user);
-    sess.set_tssince(ts_since);
-    sess.set_nonce(nonce);
-    sess.set_token(token);
+    // retrieve session
+    let sess = read_session(&state);
 
     // send a query downstream
     let q = ClientAPIQuery::RunProtoAll(sess);
-    down.query(q);
+    let reply = down.query(q);
 
     // read answer and set HTTP body (blocking)
     let htbody = {
-        match down.read() {
+        match reply.read() {
             // if a response is returned
             ClientAPIResponse::RunProtoAll(ans) => serialize_answer(&state, ans),
             ClientAPIResponse::Unauthorized => {
                 create_empty_response(&state, StatusCode::UNAUTHORIZED)
             }
+            ClientAPIResponse::Forbidden => create_empty_response(&state, StatusCode::FORBIDDEN),
             _ => create_empty_response(&state, StatusCode::INTERNAL_SERVER_ERROR),
         }
     };
@@ -39,30 +33,22 @@ pub fn pstatic(state: State) -> (State, Response<Body>) {
     // borrow references to the Downstream API
     let down = DownstreamAPI::borrow_from(&state);
 
-    // retrieve session cookie
-    let (user, ts_since, nonce, token) = read_session_cookies(&state);
-
-    // create SessionToken
-    let mut sess = SessionToken::new();
-    sess.set_user(user);
-    sess.set_tssince(ts_since);
-    sess.set_nonce(nonce);
-    sess.set_token(token);
+    // retrieve session
+    let sess = read_session(&state);
 
-    // send a query downstream
-    let sess = SessionToken::new();
     // send a query downstream
     let q = ClientAPIQuery::RunProtoStatic(sess);
-    down.query(q);
+    let reply = down.query(q);
 
     // read answer and set HTTP body (blocking)
     let htbody = {
-        match down.read() {
+        match reply.read() {
             // if a response is returned
             ClientAPIResponse::RunProtoStatic(ans) => serialize_answer(&state, ans),
             ClientAPIResponse::Unauthorized => {
                 create_empty_response(&state, StatusCode::UNAUTHORIZED)
             }
+            ClientAPIResponse::Forbidden => create_empty_response(&state, StatusCode::FORBIDDEN),
             _ => create_empty_response(&state, StatusCode::INTERNAL_SERVER_ERROR),
         }
     };
@@ -1,33 +1,33 @@
 //! Client API - VRRP running configuration handlers
 use super::*;
 
+// futures
+use futures::{future, Future, Stream};
+
+// gotham
+use gotham::handler::{HandlerFuture, IntoHandlerError};
+
 // all() handler function
 pub fn all(state: State) -> (State, Response<Body>) {
     // borrow references to the Downstream API
     let down = DownstreamAPI::borrow_from(&state);
 
-    // retrieve session cookie
-    let (user, ts_since, nonce, token) = read_session_cookies(&state);
-
-    // create SessionToken
-    let mut sess = SessionToken::new();
-    sess.set_user(user);
-    sess.set_tssince(ts_since);
-    sess.set_nonce(nonce);
-    sess.set_token(token);
+    // retrieve session
+    let sess = read_session(&state);
 
     // send a query downstream
     let q = ClientAPIQuery::RunVRRPAll(sess);
-    down.query(q);
+    let reply = down.query(q);
 
     // read answer and set HTTP body (blocking)
     let htbody = {
-        match down.read() {
+        match reply.read() {
             // if a response is returned
             ClientAPIResponse::RunVRRPAll(ans) => serialize_answer(&state, ans),
             ClientAPIResponse::Unauthorized => {
                 create_empty_response(&state, StatusCode::UNAUTHORIZED)
             }
+            ClientAPIResponse::Forbidden => create_empty_response(&state, StatusCode::FORBIDDEN),
             _ => create_empty_response(&state, StatusCode::INTERNAL_SERVER_ERROR),
         }
     };
@@ -39,15 +39,8 @@ pub fn group(state: State) -> (State, Response<Body>) {
     // borrow references to the Downstream API
     let down = DownstreamAPI::borrow_from(&state);
 
-    // retrieve session cookie
-    let (user, ts_since, nonce, token) = read_session_cookies(&state);
-
-    // create SessionToken
-    let mut sess = SessionToken::new();
-    sess.set_user(user);
-    sess.set_tssince(ts_since);
-    sess.set_nonce(nonce);
-    sess.set_token(token);
+    // retrieve session
+    let sess = read_session(&state);
 
     // extract group_id from GET path
     let path = GroupIdExtractor::borrow_from(&state);
@@ -55,15 +48,16 @@ pub fn group(state: State) -> (State, Response<Body>) {
 
     // send a query downstream
     let q = ClientAPIQuery::RunVRRPGrp(sess, gid);
-    down.query(q);
+    let reply = down.query(q);
 
     // read answer and set HTTP body (blocking)
     let htbody = {
-        match down.read() {
+        match reply.read() {
             ClientAPIResponse::RunVRRPGrp(ans) => serialize_answer(&state, ans),
             ClientAPIResponse::Unauthorized => {
                 create_empty_response(&state, StatusCode::UNAUTHORIZED)
             }
+            ClientAPIResponse::Forbidden => create_empty_response(&state, StatusCode::FORBIDDEN),
             _ => create_empty_response(&state, StatusCode::INTERNAL_SERVER_ERROR),
         }
     };
@@ -75,15 +69,8 @@ pub fn group_interface(state: State) -> (State, Response<Body>) {
     // borrow references to the Downstream API
     let down = DownstreamAPI::borrow_from(&state);
 
-    // retrieve session cookie
-    let (user, ts_since, nonce, token) = read_session_cookies(&state);
-
-    // create SessionToken
-    let mut sess = SessionToken::new();
-    sess.set_user(user);
-    sess.set_tssince(ts_since);
-    sess.set_nonce(nonce);
-    sess.set_token(token);
+    // retrieve session
+    let sess = read_session(&state);
 
     // extract group_id and interface from GET path
     let path = GroupIdInterfaceExtractor::borrow_from(&state);
@@ -92,16 +79,304 @@ pub fn group_interface(state: State) -> (State, Response<Body>) {
 
     // send a query downstream
     let q = ClientAPIQuery::RunVRRPGrpIntf(sess, gid, intf);
-    down.query(q);
+    let reply = down.query(q);
 
     // read answer and set HTTP body (blocking)
     let htbody = {
-        match down.read() {
+        match reply.read() {
             // if a response is returned
             ClientAPIResponse::RunVRRPGrpIntf(ans) => serialize_answer(&state, ans),
             ClientAPIResponse::Unauthorized => {
                 create_empty_response(&state, StatusCode::UNAUTHORIZED)
             }
+            ClientAPIResponse::Forbidden => create_empty_response(&state, StatusCode::FORBIDDEN),
+            _ => create_empty_response(&state, StatusCode::INTERNAL_SERVER_ERROR),
+        }
+    };
+    return (state, htbody);
+}
+
+/// peers() handler function
+/// retrieve the routers seen advertising for a specific virtual router
+pub fn peers(state: State) -> (State, Response<Body>) {
+    // borrow references to the Downstream API
+    let down = DownstreamAPI::borrow_from(&state);
+
+    // retrieve session
+    let sess = read_session(&state);
+
+    // extract group_id and interface from GET path
+    let path = GroupIdInterfaceExtractor::borrow_from(&state);
+    let gid = path.group_id;
+    let intf = path.interface.clone();
+
+    // send a query downstream
+    let q = ClientAPIQuery::RunVRRPPeers(sess, gid, intf);
+    let reply = down.query(q);
+
+    // read answer and set HTTP body (blocking)
+    let htbody = {
+        match reply.read() {
+            ClientAPIResponse::RunVRRPPeers(Some(ans)) => serialize_answer(&state, ans),
+            ClientAPIResponse::RunVRRPPeers(None) => {
+                create_empty_response(&state, StatusCode::NOT_FOUND)
+            }
+            ClientAPIResponse::Unauthorized => {
+                create_empty_response(&state, StatusCode::UNAUTHORIZED)
+            }
+            ClientAPIResponse::Forbidden => create_empty_response(&state, StatusCode::FORBIDDEN),
+            _ => create_empty_response(&state, StatusCode::INTERNAL_SERVER_ERROR),
+        }
+    };
+    return (state, htbody);
+}
+
+/// history() handler function
+/// retrieve the state transitions history of a specific virtual router
+pub fn history(state: State) -> (State, Response<Body>) {
+    // borrow references to the Downstream API
+    let down = DownstreamAPI::borrow_from(&state);
+
+    // retrieve session
+    let sess = read_session(&state);
+
+    // extract group_id and interface from GET path
+    let path = GroupIdInterfaceExtractor::borrow_from(&state);
+    let gid = path.group_id;
+    let intf = path.interface.clone();
+
+    // send a query downstream
+    let q = ClientAPIQuery::RunVRRPHistory(sess, gid, intf);
+    let reply = down.query(q);
+
+    // read answer and set HTTP body (blocking)
+    let htbody = {
+        match reply.read() {
+            ClientAPIResponse::RunVRRPHistory(Some(ans)) => serialize_answer(&state, ans),
+            ClientAPIResponse::RunVRRPHistory(None) => {
+                create_empty_response(&state, StatusCode::NOT_FOUND)
+            }
+            ClientAPIResponse::Unauthorized => {
+                create_empty_response(&state, StatusCode::UNAUTHORIZED)
+            }
+            ClientAPIResponse::Forbidden => create_empty_response(&state, StatusCode::FORBIDDEN),
+            _ => create_empty_response(&state, StatusCode::INTERNAL_SERVER_ERROR),
+        }
+    };
+    return (state, htbody);
+}
+
+/// add() handler function
+/// create and start a new virtual router, its attributes are read from the
+/// JSON body while the group and interface are taken from the path
+pub fn add(mut state: State) -> Box<HandlerFuture> {
+    let f = Body::take_from(&mut state)
+        .concat2()
+        .then(|body| match body {
+            Ok(valid_body) => {
+                // extract group_id and interface from POST path
+                let path = GroupIdInterfaceExtractor::borrow_from(&state);
+                let gid = path.group_id;
+                let intf = path.interface.clone();
+
+                // decode the virtual router attributes
+                let vrc =
+                    serde_json::from_slice::<serde_json::Value>(&valid_body).and_then(|mut v| {
+                        if let Some(attrs) = v.as_object_mut() {
+                            attrs.insert("group".to_string(), gid.into());
+                            attrs.insert("interface".to_string(), intf.into());
+                        }
+                        serde_json::from_value::<crate::config::VRConfig>(v)
+                    });
+                let vrc = match vrc {
+                    Ok(v) => v,
+                    Err(e) => {
+                        let resp = serialize_error(
+                            &state,
+                            StatusCode::BAD_REQUEST,
+                            format!("error(config): {}", e),
+                        );
+                        return future::ok((state, resp));
+                    }
+                };
+                let sess = read_session(&state);
+
+                // send a query downstream
+                let down = DownstreamAPI::borrow_from(&state);
+                let reply = down.query(ClientAPIQuery::RunVRRPAdd(sess, vrc));
+
+                // read answer (blocking)
+                let resp = match reply.read() {
+                    ClientAPIResponse::RunVRRPAdd(ans) => create_response(
+                        &state,
+                        StatusCode::CREATED,
+                        mime::APPLICATION_JSON,
+                        serde_json::to_vec(&ans).expect("serialized response"),
+                    ),
+                    ClientAPIResponse::BadRequest(e) => {
+                        serialize_error(&state, StatusCode::BAD_REQUEST, e)
+                    }
+                    ClientAPIResponse::Conflict(e) => {
+                        serialize_error(&state, StatusCode::CONFLICT, e)
+                    }
+                    ClientAPIResponse::InternalError(e) => {
+                        serialize_error(&state, StatusCode::INTERNAL_SERVER_ERROR, e)
+                    }
+                    ClientAPIResponse::Unauthorized => {
+                        create_empty_response(&state, StatusCode::UNAUTHORIZED)
+                    }
+                    ClientAPIResponse::Forbidden => {
+                        create_empty_response(&state, StatusCode::FORBIDDEN)
+                    }
+                    _ => create_empty_response(&state, StatusCode::INTERNAL_SERVER_ERROR),
+                };
+                future::ok((state, resp))
+            }
+            Err(e) => future::err((state, e.into_handler_error())),
+        });
+
+    Box::new(f)
+}
+
+/// modify() handler function
+/// change the priority, preemption, advertisement interval or authentication
+/// of a running virtual router, the attributes are read from the JSON body
+pub fn modify(mut state: State) -> Box<HandlerFuture> {
+    let f = Body::take_from(&mut state)
+        .concat2()
+        .then(|body| match body {
+            Ok(valid_body) => {
+                // extract group_id and interface from PUT path
+                let path = GroupIdInterfaceExtractor::borrow_from(&state);
+                let gid = path.group_id;
+                let intf = path.interface.clone();
+
+                // decode the requested changes
+                let req = match serde_json::from_slice::<RequestVRRPAttr>(&valid_body) {
+                    Ok(r) => r,
+                    Err(e) => {
+                        let resp = serialize_error(
+                            &state,
+                            StatusCode::BAD_REQUEST,
+                            format!("error(api): {}", e),
+                        );
+                        return future::ok((state, resp));
+                    }
+                };
+                let sess = read_session(&state);
+
+                // send a query downstream
+                let down = DownstreamAPI::borrow_from(&state);
+                let reply = down.query(ClientAPIQuery::RunVRRPMod(sess, gid, intf, req));
+
+                // read answer (blocking)
+                let resp = match reply.read() {
+                    ClientAPIResponse::RunVRRPMod(ans) => serialize_answer(&state, ans),
+                    ClientAPIResponse::BadRequest(e) => {
+                        serialize_error(&state, StatusCode::BAD_REQUEST, e)
+                    }
+                    ClientAPIResponse::Conflict(e) => {
+                        serialize_error(&state, StatusCode::CONFLICT, e)
+                    }
+                    ClientAPIResponse::NotFound => {
+                        create_empty_response(&state, StatusCode::NOT_FOUND)
+                    }
+                    ClientAPIResponse::InternalError(e) => {
+                        serialize_error(&state, StatusCode::INTERNAL_SERVER_ERROR, e)
+                    }
+                    ClientAPIResponse::Unauthorized => {
+                        create_empty_response(&state, StatusCode::UNAUTHORIZED)
+                    }
+                    ClientAPIResponse::Forbidden => {
+                        create_empty_response(&state, StatusCode::FORBIDDEN)
+                    }
+                    _ => create_empty_response(&state, StatusCode::INTERNAL_SERVER_ERROR),
+                };
+                future::ok((state, resp))
+            }
+            Err(e) => future::err((state, e.into_handler_error())),
+        });
+
+    Box::new(f)
+}
+
+/// maintenance() handler function
+/// put all the running virtual routers in or out of maintenance mode
+pub fn maintenance(mut state: State) -> Box<HandlerFuture> {
+    let f = Body::take_from(&mut state)
+        .concat2()
+        .then(|body| match body {
+            Ok(valid_body) => {
+                // decode the request
+                let req = match serde_json::from_slice::<RequestMaintenance>(&valid_body) {
+                    Ok(r) => r,
+                    Err(e) => {
+                        let resp = serialize_error(
+                            &state,
+                            StatusCode::BAD_REQUEST,
+                            format!("error(api): {}", e),
+                        );
+                        return future::ok((state, resp));
+                    }
+                };
+                let sess = read_session(&state);
+
+                // send a query downstream
+                let down = DownstreamAPI::borrow_from(&state);
+                let reply = down.query(ClientAPIQuery::RunVRRPMaint(sess, req));
+
+                // read answer (blocking)
+                let resp = match reply.read() {
+                    ClientAPIResponse::RunVRRPMaint(ans) => serialize_answer(&state, ans),
+                    ClientAPIResponse::InternalError(e) => {
+                        serialize_error(&state, StatusCode::INTERNAL_SERVER_ERROR, e)
+                    }
+                    ClientAPIResponse::Unauthorized => {
+                        create_empty_response(&state, StatusCode::UNAUTHORIZED)
+                    }
+                    ClientAPIResponse::Forbidden => {
+                        create_empty_response(&state, StatusCode::FORBIDDEN)
+                    }
+                    _ => create_empty_response(&state, StatusCode::INTERNAL_SERVER_ERROR),
+                };
+                future::ok((state, resp))
+            }
+            Err(e) => future::err((state, e.into_handler_error())),
+        });
+
+    Box::new(f)
+}
+
+/// remove() handler function
+/// shut down and remove a running virtual router
+pub fn remove(state: State) -> (State, Response<Body>) {
+    // borrow references to the Downstream API
+    let down = DownstreamAPI::borrow_from(&state);
+
+    // retrieve session
+    let sess = read_session(&state);
+
+    // extract group_id and interface from DELETE path
+    let path = GroupIdInterfaceExtractor::borrow_from(&state);
+    let gid = path.group_id;
+    let intf = path.interface.clone();
+
+    // send a query downstream
+    let q = ClientAPIQuery::RunVRRPDel(sess, gid, intf);
+    let reply = down.query(q);
+
+    // read answer (blocking)
+    let htbody = {
+        match reply.read() {
+            ClientAPIResponse::RunVRRPDel => create_empty_response(&state, StatusCode::NO_CONTENT),
+            ClientAPIResponse::NotFound => create_empty_response(&state, StatusCode::NOT_FOUND),
+            ClientAPIResponse::InternalError(e) => {
+                serialize_error(&state, StatusCode::INTERNAL_SERVER_ERROR, e)
+            }
+            ClientAPIResponse::Unauthorized => {
+                create_empty_response(&state, StatusCode::UNAUTHORIZED)
+            }
+            ClientAPIResponse::Forbidden => create_empty_response(&state, StatusCode::FORBIDDEN),
             _ => create_empty_response(&state, StatusCode::INTERNAL_SERVER_ERROR),
         }
     };
@@ -0,0 +1,122 @@
+//! Client API - rate limiter middleware module
+use super::*;
+
+// std
+use std::io;
+use std::time::Instant;
+
+// futures
+use futures::future;
+
+// gotham
+use gotham::handler::HandlerFuture;
+use gotham::middleware::{Middleware, NewMiddleware};
+
+// hyper
+use hyper::header::{HeaderValue, RETRY_AFTER};
+
+/// RateLimiter structure
+/// a token bucket shared by all the API routes, refilled at 'rate'
+/// requests per second
+#[derive(Clone)]
+pub struct RateLimiter {
+    rate: Option<u32>,
+    bucket: Arc<Mutex<(f64, Instant)>>,
+}
+
+// RateLimiter implementation
+impl RateLimiter {
+    // new() method
+    pub fn new(rate: Option<u32>) -> RateLimiter {
+        let tokens = rate.unwrap_or(0) as f64;
+        RateLimiter {
+            rate,
+            bucket: Arc::new(Mutex::new((tokens, Instant::now()))),
+        }
+    }
+    // allow() method
+    // take a token from the bucket, return false if it is empty
+    fn allow(&self) -> bool {
+        self.allow_at(Instant::now())
+    }
+    // allow_at() method
+    // refill the bucket up to the 'now' time, then take a token from it
+    fn allow_at(&self, now: Instant) -> bool {
+        let rate = match self.rate {
+            Some(r) => r as f64,
+            None => return true,
+        };
+        let mut bucket = self.bucket.lock().unwrap();
+        let elapsed = now.duration_since(bucket.1);
+        let elapsed = elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 / 1e9;
+        bucket.0 = (bucket.0 + elapsed * rate).min(rate);
+        bucket.1 = now;
+        if bucket.0 >= 1.0 {
+            bucket.0 -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+// RateLimiter NewMiddleware trait implementation
+impl NewMiddleware for RateLimiter {
+    type Instance = RateLimiter;
+
+    fn new_middleware(&self) -> io::Result<RateLimiter> {
+        Ok(self.clone())
+    }
+}
+
+// RateLimiter Middleware trait implementation
+impl Middleware for RateLimiter {
+    fn call<Chain>(self, state: State, chain: Chain) -> Box<HandlerFuture>
+    where
+        Chain: FnOnce(State) -> Box<HandlerFuture> + 'static,
+    {
+        if self.allow() {
+            return chain(state);
+        }
+        // too many requests, the bucket is refilled within a second
+        let mut resp = create_empty_response(&state, StatusCode::TOO_MANY_REQUESTS);
+        resp.headers_mut()
+            .insert(RETRY_AFTER, HeaderValue::from_static("1"));
+        Box::new(future::ok((state, resp)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn bucket_is_refilled_at_the_rate() {
+        let limiter = RateLimiter::new(Some(2));
+        let start = limiter.bucket.lock().unwrap().1;
+
+        // the bucket starts full
+        assert!(limiter.allow_at(start));
+        assert!(limiter.allow_at(start));
+        assert!(!limiter.allow_at(start));
+
+        // a token every half second
+        assert!(!limiter.allow_at(start + Duration::from_millis(250)));
+        assert!(limiter.allow_at(start + Duration::from_millis(500)));
+        assert!(!limiter.allow_at(start + Duration::from_millis(500)));
+
+        // the bucket never holds more than a second of requests
+        let later = start + Duration::from_secs(60);
+        assert!(limiter.allow_at(later));
+        assert!(limiter.allow_at(later));
+        assert!(!limiter.allow_at(later));
+    }
+
+    #[test]
+    fn no_rate_never_limits() {
+        let limiter = RateLimiter::new(None);
+        let start = Instant::now();
+        assert!((0..1000).all(|_| limiter.allow_at(start)));
+    }
+}
@@ -17,23 +17,18 @@ use gotham::pipeline::new_pipeline;
 use gotham::pipeline::single::single_pipeline;
 use gotham::router::builder::*;
 use gotham::router::Router;
-use gotham::state::{FromState, State};
+use gotham::state::{client_addr, FromState, State};
 
 // hyper
 extern crate hyper;
-use hyper::header::SET_COOKIE;
-use hyper::{Body, Response, StatusCode};
+use hyper::header::{AUTHORIZATION, SET_COOKIE};
+use hyper::{Body, HeaderMap, Method, Response, StatusCode};
 
 // failure
 use failure::{err_msg, Error};
 
 // openssl
 extern crate openssl;
-use openssl::{
-    pkey::PKey,
-    ssl::{SslAcceptor, SslMethod},
-    x509::X509,
-};
 
 // tokio
 use tokio::{net::TcpListener, runtime::Runtime};
@@ -55,10 +50,25 @@ use serde::Serialize;
 // handlers
 mod handlers;
 
+// tls
+mod tls;
+
+// unix domain socket
+mod unix;
+
+// rate limiter
+mod limiter;
+use limiter::RateLimiter;
+
+// OpenAPI document, and the routes table
+mod openapi;
+use openapi::{Extract, RouteHandler, OPERATIONS};
+
 // handlers constants
 const COOKIE_USER: &str = "user";
 const COOKIE_TIMESTAMP: &str = "ts";
 const COOKIE_NONCE: &str = "nonce";
+const COOKIE_ACCESS: &str = "access";
 const COOKIE_TOKEN: &str = "token";
 
 // Client API routing
@@ -66,7 +76,19 @@ const COOKIE_TOKEN: &str = "token";
 // c-api/
 //  |_ v1/
 //     |_ / GET, HEAD     index
+//     |_ openapi.json    OpenAPI 3 document of the routes below
 //     |_ auth/           client API authentication
+//     |  |_ / POST       authenticate and issue a session token
+//     |  |_ logout/
+//     |  |  |_ / POST    revoke the current session token
+//     |  |_ refresh/
+//     |  |  |_ / POST    replace the current session token
+//     |  |_ revoke/
+//     |  |  |_ / POST    revoke all the session tokens
+//     |  |_ lockouts/
+//     |     |_ / GET     retrieve the current login lockouts
+//     |_ events/ GET     stream of events (Server-Sent Events)
+//     |_ audit/ GET      retrieve the audit log records
 //     |_ config/         static configuration objects
 //     |  |_ global/      global configuration
 //     |  |  |_ / GET     retrieve all global configuration
@@ -75,8 +97,10 @@ const COOKIE_TOKEN: &str = "token";
 //     |  |  |_ / GET     retrieve all virtual router configuration
 //     |  |  |_ / PUT     modify virtual router configuration
 //     |  |_ protocols/   protocols configuration
-//     |     |_ / GET     retrieve all protocols configuration
-//     |     |_ / PUT     modify protocols configuration
+//     |  |  |_ / GET     retrieve all protocols configuration
+//     |  |  |_ / PUT     modify protocols configuration
+//     |  |_ reload/
+//     |     |_ / POST    read the configuration file again, and apply it
 //     |_ run/            running configurations objects
 //        |_ global/      running global configuration
 //        |  |_ / GET     retrieve running global configuration
@@ -85,6 +109,8 @@ const COOKIE_TOKEN: &str = "token";
 //        |  |_ / PUT      modify a specific virtual router (spec. grp/intf)
 //        |  |_ / POST     add a new VRRP virtual router (spec. grp/intf)
 //        |  |_ / DELETE   remove a specific virtual router (spec. grp/intf)
+//        |  |_ maintenance/
+//        |  |  |_ / PUT   enter or leave maintenance mode (all virtual routers)
 //        |  |_ <group-id>/
 //        |     |_ / GET       retrieve group specific information
 //        |     |_ / PUT       modify specific virtual router (spec. intf)
@@ -95,6 +121,10 @@ const COOKIE_TOKEN: &str = "token";
 //        |         |_ / PUT       modify specific virtual router
 //        |         |_ / POST      add a new VRRP virtual router
 //        |         |_ / DELETE    remove a specific virtual router
+//        |         |_ /peers/
+//        |         |   |_ / GET   retrieve the routers seen advertising
+//        |         |_ /history/
+//        |             |_ / GET   retrieve the state transitions history
 //        |_ protocols/
 //           |_ / GET          retrieve all protocols information
 //           |_ static/
@@ -104,11 +134,24 @@ const COOKIE_TOKEN: &str = "token";
 //              |_ / DELETE    remove a static route (specifc route)
 //
 
+/// Listener enumerator
+/// the listener served by a router, and the peers of its connections (a
+/// router serves a single connection of the Unix domain socket)
+#[derive(Clone, StateData)]
+enum Listener {
+    Tcp,
+    Tls(tls::PeerIdentities),
+    Unix(unix::PeerCredentials),
+}
+
 // router() function
-fn router(down_api: &DownstreamAPI) -> Router {
+fn router(down_api: &DownstreamAPI, limiter: &RateLimiter, listener: Listener) -> Router {
     // create new pipeline
     let pipeline = new_pipeline();
 
+    // add the global rate limiter middleware
+    let pipeline = pipeline.add(limiter.clone());
+
     // add CookieParser middleware
     let pipeline = pipeline.add(CookieParser);
 
@@ -118,75 +161,93 @@ fn router(down_api: &DownstreamAPI) -> Router {
     // add state middleware to existing pipeline
     let pipeline = pipeline.add(stm);
 
+    // share the listener, so the peers are only looked up on their own listener
+    let pipeline = pipeline.add(StateMiddleware::new(listener));
+
     // construct a basic chain from the pipeline
     let (chain, pipelines) = single_pipeline(pipeline.build());
 
-    // build the router with chain and pipelines
+    // build the router with chain and pipelines, from the routes table
     build_router(chain, pipelines, |route| {
-        // index
-        route.get_or_head("/").to(handlers::index);
-
-        // auth/ scope
-        route.scope("/auth", |route| {
-            // / (POST)
-            route.post("/").to(handlers::auth::client);
-        });
-
-        // config/ scope
-        route.scope("/config", |route| {
-            // global/
-            route.scope("/global", |route| {
-                route.get("/").to(handlers::config::global::all);
-            });
-            // vrouter/
-            route.scope("/vrouter", |route| {
-                route.get("/").to(handlers::config::vrouter::all);
-            });
-            // protocols/
-            route.scope("/protocols", |route| {
-                route.get("/").to(handlers::config::protocols::all);
-            })
-        });
-
-        // run/ scope
-        route.scope("/run", |route| {
-            // global/ scope
-            route.scope("/global", |route| {
-                route.get("/").to(handlers::run::global::all);
-            });
-            // vrrp/ scope
-            route.scope("/vrrp", |route| {
-                // /
-                route.get("/").to(handlers::run::vrrp::all);
-                // <group-id>/
-                route
-                    .get("/:group_id")
-                    .with_path_extractor::<GroupIdExtractor>()
-                    .to(handlers::run::vrrp::group);
-                // <group-id>/<interface>/
-                route
-                    .get("/:group_id/:interface")
-                    .with_path_extractor::<GroupIdInterfaceExtractor>()
-                    .to(handlers::run::vrrp::group_interface);
-            });
-            // protocols/ scope
-            route.scope("/protocols", |route| {
-                // /
-                route.get("/").to(handlers::run::protocols::all);
-                // static/
-                route.get("/static").to(handlers::run::protocols::pstatic);
-            });
-        });
+        for op in OPERATIONS {
+            let method = Method::from_bytes(op.method.to_uppercase().as_bytes())
+                .expect("invalid route method");
+            let single = route.request(vec![method], op.path);
+            match op.extract {
+                Extract::Nothing => to(single, op.handler),
+                Extract::Persist => to(
+                    single.with_query_string_extractor::<PersistExtractor>(),
+                    op.handler,
+                ),
+                Extract::EventsFilter => to(
+                    single.with_query_string_extractor::<EventsFilterExtractor>(),
+                    op.handler,
+                ),
+                Extract::Audit => to(
+                    single.with_query_string_extractor::<AuditExtractor>(),
+                    op.handler,
+                ),
+                Extract::GroupId => {
+                    to(single.with_path_extractor::<GroupIdExtractor>(), op.handler)
+                }
+                Extract::GroupIdInterface => to(
+                    single.with_path_extractor::<GroupIdInterfaceExtractor>(),
+                    op.handler,
+                ),
+            }
+        }
     })
 }
 
+// to() function
+// dispatch a route to its handler
+fn to<R: DefineSingleRoute>(route: R, handler: RouteHandler) {
+    match handler {
+        RouteHandler::Sync(h) => route.to(h),
+        RouteHandler::Async(h) => route.to(h),
+    }
+}
+
 // start() function
-pub fn start(down_api: DownstreamAPI, host: String, tls: bool, tls_key: String, tls_cert: String) {
+pub fn start(
+    down_api: DownstreamAPI,
+    host: String,
+    tls: bool,
+    tls_key: String,
+    tls_cert: String,
+    tls_client_ca: Option<String>,
+    rate_limit: Option<u32>,
+    tcp: bool,
+    unix: Option<config::APIUnix>,
+) {
+    // the rate limit is shared by all the connections
+    let limiter = RateLimiter::new(rate_limit);
+
+    // if the Unix domain socket is enabled
+    if let Some(u) = unix {
+        let (down_api, limiter) = (down_api.clone(), limiter.clone());
+        let server = thread::spawn(move || unix::start(down_api, limiter, u));
+        // without the TCP listener, only serve the Unix domain socket
+        if !tcp {
+            let _ = server.join();
+            return;
+        }
+    }
+
     println!("Client API Server listening on http://{}", host);
 
     // if TLS is enabled
     if tls {
-        let acceptor = build_tls_acceptor(tls_key, tls_cert).unwrap();
+        let files = tls::TlsFiles {
+            key: tls_key,
+            cert: tls_cert,
+            client_ca: tls_client_ca,
+        };
+        let acceptor = Arc::new(RwLock::new(tls::build_tls_acceptor(&files).unwrap()));
+        let peers = tls::PeerIdentities::default();
+        let listener_peers = peers.clone();
+        // reload the acceptor when the TLS files change
+        tls::watch_tls_files(Arc::clone(&acceptor), files);
         let sockaddr = host
             .to_socket_addrs()
             .unwrap()
@@ -196,10 +257,17 @@ pub fn start(down_api: DownstreamAPI, host: String, tls: bool, tls_key: String,
         let listener = TcpListener::bind(&sockaddr).unwrap();
         let server = bind_server(
             listener,
-            move || Ok(router(&down_api)),
+            move || {
+                let listener = Listener::Tls(listener_peers.clone());
+                Ok(router(&down_api, &limiter, listener))
+            },
             move |socket| {
+                let addr = socket.peer_addr().ok();
+                let acceptor = acceptor.read().unwrap().clone();
+                let peers = peers.clone();
                 acceptor
                     .accept_async(socket)
+                    .map(move |stream| tls::PeerStream::new(stream, addr, peers))
                     .map_err(|e| println!("OpenSSL error: {}", e))
             },
         );
@@ -209,26 +277,10 @@ pub fn start(down_api: DownstreamAPI, host: String, tls: bool, tls_key: String,
             .map_err(|()| err_msg("Server failed"))
             .unwrap();
     } else {
-        gotham::start(host, router(&down_api))
+        gotham::start(host, router(&down_api, &limiter, Listener::Tcp))
     }
 }
 
-// build_tls_acceptor() function
-fn build_tls_acceptor(keyfile: String, certfile: String) -> Result<SslAcceptor, Error> {
-    // openssl req -new -x509 -sha256 -newkey rsa:2048 -nodes -keyout key.pem -days 365 -out cert.pem
-    let key = std::fs::read(keyfile).expect("Cannot read RSA key file");
-    let cert = std::fs::read(certfile).expect("Cannot read X.509 certificate file");
-    let cert = X509::from_pem(&cert).expect("Malformed X.509 certificate");
-    let key = PKey::private_key_from_pem(&key).expect("Malformed PEM key");
-
-    let mut builder = SslAcceptor::mozilla_intermediate(SslMethod::tls())?;
-    builder.set_certificate(&cert)?;
-    builder.set_private_key(&key)?;
-
-    let acceptor = builder.build();
-    Ok(acceptor)
-}
-
 // serialize_answer() function
 fn serialize_answer<T: Serialize>(state: &State, ans: T) -> Response<Body> {
     create_response(
@@ -239,6 +291,52 @@ fn serialize_answer<T: Serialize>(state: &State, ans: T) -> Response<Body> {
     )
 }
 
+// serialize_error() function
+fn serialize_error(state: &State, status: StatusCode, msg: String) -> Response<Body> {
+    create_response(
+        &state,
+        status,
+        mime::APPLICATION_JSON,
+        serde_json::to_vec(&ResponseError { error: msg }).expect("serialized error"),
+    )
+}
+
+// ResponseError structure
+#[derive(Serialize)]
+struct ResponseError {
+    error: String,
+}
+
+// ResponseToken structure (Serialize-able)
+#[derive(Serialize)]
+struct ResponseToken {
+    token: String,
+    expires: u64,
+}
+
+// PersistExtractor structure
+// '?persist=true' writes the modified configuration back to disk
+#[derive(Deserialize, StateData, StaticResponseExtender)]
+struct PersistExtractor {
+    persist: Option<bool>,
+}
+
+// EventsFilterExtractor structure
+// '?group=<group-id>&interface=<interface>' filters the events stream
+#[derive(Deserialize, StateData, StaticResponseExtender)]
+struct EventsFilterExtractor {
+    group: Option<u8>,
+    interface: Option<String>,
+}
+
+// AuditExtractor structure
+// '?since=<seq>&limit=<count>' selects the audit log records
+#[derive(Deserialize, StateData, StaticResponseExtender)]
+struct AuditExtractor {
+    since: Option<u64>,
+    limit: Option<usize>,
+}
+
 // GroupIdExtractor structure
 #[derive(Deserialize, StateData, StaticResponseExtender)]
 struct GroupIdExtractor {
@@ -253,7 +351,7 @@ struct GroupIdInterfaceExtractor {
 }
 
 // read_session_cookies() function
-pub fn read_session_cookies(state: &State) -> (String, u64, u64, String) {
+pub fn read_session_cookies(state: &State) -> (String, u64, u64, u8, String) {
     // retrieve session cookies
     let c = CookieJar::borrow_from(&state);
     let user = {
@@ -271,27 +369,282 @@ pub fn read_session_cookies(state: &State) -> (String, u64, u64, String) {
             .map(|c| c.value().parse::<u64>().unwrap())
             .unwrap_or_else(|| 0)
     };
+    let access: u8 = {
+        c.get(COOKIE_ACCESS)
+            .and_then(|c| c.value().parse::<u8>().ok())
+            .unwrap_or_else(|| 0)
+    };
     let token = {
         c.get(COOKIE_TOKEN)
             .map(|c| c.value().to_owned())
             .unwrap_or_else(|| "null".to_string())
     };
-    (user, ts_since, nonce, token)
+    (user, ts_since, nonce, access, token)
+}
+
+// read_bearer() function
+// return the token of the 'Authorization: Bearer' header, if any
+pub fn read_bearer(state: &State) -> Option<String> {
+    let headers = HeaderMap::borrow_from(&state);
+    let value = headers.get(AUTHORIZATION)?.to_str().ok()?;
+    if value.len() > 7 && value[..7].eq_ignore_ascii_case("bearer ") {
+        Some(value[7..].trim().to_string())
+    } else {
+        None
+    }
+}
+
+// read_session() function
+// build a session token from the 'Authorization' header or from the session cookies
+pub fn read_session(state: &State) -> SessionToken {
+    let mut sess = match read_bearer(state) {
+        // a malformed bearer token is never valid
+        Some(bearer) => SessionToken::from_bearer(&bearer).unwrap_or_else(SessionToken::new),
+        None => {
+            let (user, ts_since, nonce, access, token) = read_session_cookies(state);
+            let mut sess = SessionToken::new();
+            // without session cookies, authenticate with the Unix socket peer
+            // credentials or the client certificate (if any) of the connection
+            if token == "null" {
+                match Listener::borrow_from(state) {
+                    Listener::Unix(cred) => sess.set_peer(Some(cred.get())),
+                    Listener::Tls(peers) => {
+                        if let Some(ids) = client_addr(state).and_then(|a| peers.get(&a)) {
+                            sess.set_identities(ids);
+                        }
+                    }
+                    Listener::Tcp => {}
+                }
+            }
+            sess.set_user(user);
+            sess.set_tssince(ts_since);
+            sess.set_nonce(nonce);
+            sess.set_access(access);
+            sess.set_token(token);
+            sess
+        }
+    };
+    sess.set_addr(client_addr(state).map(|a| a.ip()));
+    sess
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::api::client::sessions::guard::{GuardKey, Lockout};
     use gotham::test::TestServer;
     use hyper::StatusCode;
+    use serde_json::json;
 
     #[test]
     fn receive_hello_response() {
         let down_api = DownstreamAPI::new();
 
-        let server = TestServer::new(router(&down_api)).unwrap();
+        let limiter = RateLimiter::new(None);
+
+        let server = TestServer::new(router(&down_api, &limiter, Listener::Tcp)).unwrap();
         let response = server.client().get("http://localhost").perform().unwrap();
 
         assert_eq!(response.status(), StatusCode::OK);
     }
+
+    // read_session_from() function
+    // return the session token read from an 'Authorization' header and cookies
+    fn read_session_from(bearer: Option<&str>, cookies: &[(&'static str, &str)]) -> SessionToken {
+        let mut sess = None;
+        State::with_new(|state| {
+            let mut headers = HeaderMap::new();
+            if let Some(b) = bearer {
+                headers.insert(AUTHORIZATION, b.parse().unwrap());
+            }
+            state.put(headers);
+            let mut jar = CookieJar::new();
+            for (name, value) in cookies {
+                jar.add_original(Cookie::new(*name, value.to_string()));
+            }
+            state.put(jar);
+            state.put(Listener::Tcp);
+            sess = Some(read_session(state));
+        });
+        sess.unwrap()
+    }
+
+    #[test]
+    fn bearer_takes_precedence_over_cookies() {
+        let cookies = [
+            (COOKIE_USER, "operator"),
+            (COOKIE_TIMESTAMP, "1580000001"),
+            (COOKIE_NONCE, "16"),
+            (COOKIE_ACCESS, "0"),
+            (COOKIE_TOKEN, "cafe"),
+        ];
+
+        // a session token
+        let sess = read_session_from(Some("Bearer admin.1580000000.15.2.abcdef"), &cookies);
+        assert_eq!(sess.user(), "admin");
+        assert_eq!(sess.ts_since(), "1580000000");
+        assert_eq!(sess.nonce(), "15");
+        assert_eq!(sess.access(), 2);
+        assert_eq!(sess.token(), "abcdef");
+        assert_eq!(sess.key(), None);
+
+        // an API key, the scheme is case-insensitive
+        let sess = read_session_from(Some("bearer ansible:k3ysecret"), &cookies);
+        assert_eq!(sess.user(), "ansible");
+        assert_eq!(sess.key(), Some("k3ysecret"));
+
+        // a malformed bearer token is never valid, the cookies are ignored
+        let sess = read_session_from(Some("Bearer admin.1580000000"), &cookies);
+        assert_eq!(sess.user(), "null");
+        assert_eq!(sess.token(), "null");
+
+        // the session cookies, without a bearer token
+        for bearer in [None, Some("Basic YWRtaW46cGFzc3dk")].iter() {
+            let sess = read_session_from(*bearer, &cookies);
+            assert_eq!(sess.user(), "operator");
+            assert_eq!(sess.ts_since(), "1580000001");
+            assert_eq!(sess.nonce(), "16");
+            assert_eq!(sess.access(), 0);
+            assert_eq!(sess.token(), "cafe");
+        }
+    }
+
+    #[test]
+    fn routes_are_documented() {
+        let doc = openapi::document();
+        let mut routes: Vec<(&str, &str)> = Vec::new();
+        for op in OPERATIONS {
+            assert!(
+                !routes.contains(&(op.method, op.path)),
+                "duplicate route {} {}",
+                op.method,
+                op.path
+            );
+            routes.push((op.method, op.path));
+
+            // the router path parameters are the ones of its extractor
+            let path_params: Vec<&str> = op
+                .path
+                .split('/')
+                .filter(|s| s.starts_with(':'))
+                .map(|s| &s[1..])
+                .collect();
+            let extracted: Vec<&str> = op
+                .extract
+                .params()
+                .iter()
+                .filter(|p| p.location == "path")
+                .map(|p| p.name)
+                .collect();
+            assert_eq!(path_params, extracted, "{} {}", op.method, op.path);
+
+            let path = openapi::openapi_path(op.path);
+            assert!(
+                doc["paths"][&path][op.method].is_object(),
+                "undocumented route {} {}",
+                op.method,
+                op.path
+            );
+        }
+
+        // the document holds nothing but the routes
+        let documented: usize = doc["paths"]
+            .as_object()
+            .unwrap()
+            .values()
+            .map(|item| item.as_object().unwrap().len())
+            .sum();
+        assert_eq!(documented, routes.len());
+    }
+
+    #[test]
+    fn openapi_schemas_are_defined() {
+        let doc = openapi::document();
+        let text = doc.to_string();
+        let re = regex::Regex::new(r##""#/components/schemas/([A-Za-z]+)""##).unwrap();
+        for c in re.captures_iter(&text) {
+            assert!(
+                doc["components"]["schemas"].get(&c[1]).is_some(),
+                "undefined schema {}",
+                &c[1]
+            );
+        }
+        assert!(doc["paths"]["/run/vrrp/{group_id}/{interface}"]["delete"].is_object());
+    }
+
+    // example_of() function
+    // return an instance of a schema, every property being set
+    fn example_of(schema: &serde_json::Value) -> serde_json::Value {
+        match schema["type"].as_str() {
+            Some("boolean") => json!(false),
+            Some("integer") => json!(0),
+            Some("number") => json!(0.5),
+            Some("string") => json!(""),
+            Some("array") => json!([example_of(&schema["items"])]),
+            Some("object") => serde_json::Value::Object(
+                schema["properties"]
+                    .as_object()
+                    .unwrap()
+                    .iter()
+                    .map(|(k, v)| (k.clone(), example_of(v)))
+                    .collect(),
+            ),
+            _ => serde_json::Value::Null,
+        }
+    }
+
+    // fields() function
+    // return the sorted field names of a serialized structure
+    fn fields(value: &serde_json::Value) -> Vec<String> {
+        let mut names: Vec<String> = value.as_object().unwrap().keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    // round_trip() function
+    // read an instance of a schema into its structure, and serialize it back
+    fn round_trip<T>(
+        schemas: &serde_json::Value,
+        name: &str,
+    ) -> (serde_json::Value, serde_json::Value)
+    where
+        T: serde::de::DeserializeOwned + Serialize,
+    {
+        let example = example_of(&schemas[name]);
+        let value: T = serde_json::from_value(example.clone())
+            .unwrap_or_else(|e| panic!("schema {} is not its structure: {}", name, e));
+        (example, serde_json::to_value(value).unwrap())
+    }
+
+    #[test]
+    fn openapi_schemas_match_the_structures() {
+        let doc = openapi::document();
+        let schemas = &doc["components"]["schemas"];
+
+        // every field of the schema is read, and every field is written back
+        let trips = vec![
+            round_trip::<ResponseVRRPAttr>(schemas, "ResponseVRRPAttr"),
+            round_trip::<ResponseVRRPAttrExt>(schemas, "ResponseVRRPAttrExt"),
+            round_trip::<ResponsePeerAttr>(schemas, "ResponsePeerAttr"),
+            round_trip::<ResponseGlobalAttr>(schemas, "ResponseGlobalAttr"),
+            round_trip::<ResponseProtoAttr>(schemas, "ResponseProtoAttr"),
+            round_trip::<ResponseProtoStaticAttr>(schemas, "ResponseProtoStaticAttr"),
+            round_trip::<RequestVRRPAttr>(schemas, "RequestVRRPAttr"),
+            round_trip::<RequestMaintenance>(schemas, "RequestMaintenance"),
+        ];
+        for (example, value) in trips.iter() {
+            assert_eq!(fields(example), fields(value));
+        }
+
+        // a lockout, as answered by the API thread
+        let lockout = crate::api::client::response_lockout(Lockout {
+            key: GuardKey::User("admin".to_string()),
+            failures: 3,
+            until: 1_580_000_000,
+        });
+        assert_eq!(
+            fields(&example_of(&schemas["ResponseLockout"])),
+            fields(&serde_json::to_value(lockout).unwrap())
+        );
+    }
 }
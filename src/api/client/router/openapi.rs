@@ -0,0 +1,786 @@
+//! Client API - OpenAPI document module
+//! This module holds the routes of the Client API router, and generates
+//! the OpenAPI 3 document served on '/openapi.json'. The response schemas are
+//! derived from sample instances of the serialized structures.
+use super::*;
+
+// gotham
+use gotham::handler::HandlerFuture;
+
+// serde
+use serde_json::{json, Map, Value};
+
+// constants
+use crate::constants::RVRRPD_VERSION_STRING;
+
+// audit log
+use crate::api::client::audit::{AuditChange, AuditRecord, ResponseAudit};
+
+// transitions history
+use crate::history::{OpResult, Trigger};
+
+/// Param structure
+/// a path or query string parameter
+pub struct Param {
+    pub name: &'static str,
+    pub location: &'static str,
+    kind: &'static str,
+    description: &'static str,
+}
+
+/// RouteHandler enumerator
+/// the handler of a route, answering at once or with a future
+#[derive(Clone, Copy)]
+pub enum RouteHandler {
+    Sync(fn(State) -> (State, Response<Body>)),
+    Async(fn(State) -> Box<HandlerFuture>),
+}
+
+/// Extract enumerator
+/// the path or query string extractor of a route
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Extract {
+    Nothing,
+    Persist,
+    EventsFilter,
+    Audit,
+    GroupId,
+    GroupIdInterface,
+}
+
+// Extract Type Implementation
+impl Extract {
+    // params() method
+    // return the parameters read by the extractor
+    pub fn params(self) -> &'static [Param] {
+        match self {
+            Extract::Nothing => &[],
+            Extract::Persist => PERSIST,
+            Extract::EventsFilter => EVENTS_FILTER,
+            Extract::Audit => AUDIT_FILTER,
+            Extract::GroupId => GROUP_ID,
+            Extract::GroupIdInterface => GROUP_ID_INTERFACE,
+        }
+    }
+}
+
+/// Operation structure
+/// a route of the router, and its documentation
+pub struct Operation {
+    pub method: &'static str,
+    pub path: &'static str,
+    summary: &'static str,
+    pub handler: RouteHandler,
+    access: Option<u8>,
+    pub extract: Extract,
+    body: Option<(&'static str, &'static str)>,
+    status: u16,
+    response: Option<(&'static str, &'static str)>,
+}
+
+// GroupIdExtractor parameters
+const GROUP_ID: &[Param] = &[Param {
+    name: "group_id",
+    location: "path",
+    kind: "integer",
+    description: "VRRP group identifier",
+}];
+
+// GroupIdInterfaceExtractor parameters
+const GROUP_ID_INTERFACE: &[Param] = &[
+    Param {
+        name: "group_id",
+        location: "path",
+        kind: "integer",
+        description: "VRRP group identifier",
+    },
+    Param {
+        name: "interface",
+        location: "path",
+        kind: "string",
+        description: "interface name of the virtual router",
+    },
+];
+
+// PersistExtractor parameters
+const PERSIST: &[Param] = &[Param {
+    name: "persist",
+    location: "query",
+    kind: "boolean",
+    description: "write the modified configuration back to disk",
+}];
+
+// EventsFilterExtractor parameters
+const EVENTS_FILTER: &[Param] = &[
+    Param {
+        name: "group",
+        location: "query",
+        kind: "integer",
+        description: "only stream the events of this VRRP group",
+    },
+    Param {
+        name: "interface",
+        location: "query",
+        kind: "string",
+        description: "only stream the events of this interface",
+    },
+];
+
+// AuditExtractor parameters
+const AUDIT_FILTER: &[Param] = &[
+    Param {
+        name: "since",
+        location: "query",
+        kind: "integer",
+        description: "only return the records following this sequence number",
+    },
+    Param {
+        name: "limit",
+        location: "query",
+        kind: "integer",
+        description: "maximum number of returned records",
+    },
+];
+
+// media types
+const JSON: &str = "application/json";
+const TEXT: &str = "text/plain";
+const HTML: &str = "text/html";
+const SSE: &str = "text/event-stream";
+
+/// Client API routes
+/// drawn by router() and documented by document() (the router paths, with
+/// the gotham ':name' path parameters)
+pub const OPERATIONS: &[Operation] = &[
+    Operation {
+        method: "get",
+        path: "/",
+        summary: "index page",
+        handler: RouteHandler::Sync(handlers::index),
+        access: None,
+        extract: Extract::Nothing,
+        body: None,
+        status: 200,
+        response: Some((HTML, "")),
+    },
+    Operation {
+        method: "head",
+        path: "/",
+        summary: "index page",
+        handler: RouteHandler::Sync(handlers::index),
+        access: None,
+        extract: Extract::Nothing,
+        body: None,
+        status: 200,
+        response: None,
+    },
+    Operation {
+        method: "get",
+        path: "/openapi.json",
+        summary: "retrieve this OpenAPI document",
+        handler: RouteHandler::Sync(handlers::openapi),
+        access: None,
+        extract: Extract::Nothing,
+        body: None,
+        status: 200,
+        response: Some((JSON, "")),
+    },
+    Operation {
+        method: "post",
+        path: "/auth",
+        summary: "authenticate and issue a session token ('user=<user> passwd=<password>')",
+        handler: RouteHandler::Async(handlers::auth::client),
+        access: None,
+        extract: Extract::Nothing,
+        body: Some((TEXT, "")),
+        status: 200,
+        response: Some((JSON, "ResponseToken")),
+    },
+    Operation {
+        method: "post",
+        path: "/auth/logout",
+        summary: "revoke the current session token",
+        handler: RouteHandler::Sync(handlers::auth::logout),
+        access: Some(API_ACCESS_RUN),
+        extract: Extract::Nothing,
+        body: None,
+        status: 200,
+        response: None,
+    },
+    Operation {
+        method: "post",
+        path: "/auth/refresh",
+        summary: "replace the current session token",
+        handler: RouteHandler::Sync(handlers::auth::refresh),
+        access: Some(API_ACCESS_RUN),
+        extract: Extract::Nothing,
+        body: None,
+        status: 200,
+        response: Some((JSON, "ResponseToken")),
+    },
+    Operation {
+        method: "post",
+        path: "/auth/revoke",
+        summary: "revoke all the session tokens",
+        handler: RouteHandler::Sync(handlers::auth::revoke),
+        access: Some(API_ACCESS_ADMIN),
+        extract: Extract::Nothing,
+        body: None,
+        status: 200,
+        response: Some((JSON, "ResponseRevoke")),
+    },
+    Operation {
+        method: "get",
+        path: "/auth/lockouts",
+        summary: "retrieve the current login lockouts",
+        handler: RouteHandler::Sync(handlers::auth::lockouts),
+        access: Some(API_ACCESS_ADMIN),
+        extract: Extract::Nothing,
+        body: None,
+        status: 200,
+        response: Some((JSON, "ResponseLockout[]")),
+    },
+    Operation {
+        method: "get",
+        path: "/audit",
+        summary: "retrieve the audit log records",
+        handler: RouteHandler::Sync(handlers::audit::records),
+        access: Some(API_ACCESS_ADMIN),
+        extract: Extract::Audit,
+        body: None,
+        status: 200,
+        response: Some((JSON, "ResponseAudit")),
+    },
+    Operation {
+        method: "get",
+        path: "/events",
+        summary: "stream of events (Server-Sent Events)",
+        handler: RouteHandler::Sync(handlers::events::stream),
+        access: Some(API_ACCESS_RUN),
+        extract: Extract::EventsFilter,
+        body: None,
+        status: 200,
+        response: Some((SSE, "")),
+    },
+    Operation {
+        method: "get",
+        path: "/config/global",
+        summary: "retrieve all global configuration",
+        handler: RouteHandler::Sync(handlers::config::global::all),
+        access: Some(API_ACCESS_CONFIG),
+        extract: Extract::Nothing,
+        body: None,
+        status: 200,
+        response: Some((JSON, "Config")),
+    },
+    Operation {
+        method: "put",
+        path: "/config/global",
+        summary: "modify global configuration",
+        handler: RouteHandler::Async(handlers::config::global::update),
+        access: Some(API_ACCESS_ADMIN),
+        extract: Extract::Persist,
+        body: Some((JSON, "Config")),
+        status: 200,
+        response: Some((JSON, "Config")),
+    },
+    Operation {
+        method: "get",
+        path: "/config/vrouter",
+        summary: "retrieve all virtual router configuration",
+        handler: RouteHandler::Sync(handlers::config::vrouter::all),
+        access: Some(API_ACCESS_CONFIG),
+        extract: Extract::Nothing,
+        body: None,
+        status: 200,
+        response: Some((JSON, "VRConfig[]")),
+    },
+    Operation {
+        method: "put",
+        path: "/config/vrouter",
+        summary: "modify virtual router configuration",
+        handler: RouteHandler::Async(handlers::config::vrouter::update),
+        access: Some(API_ACCESS_ADMIN),
+        extract: Extract::Persist,
+        body: Some((JSON, "VRConfig[]")),
+        status: 200,
+        response: Some((JSON, "VRConfig[]")),
+    },
+    Operation {
+        method: "get",
+        path: "/config/protocols",
+        summary: "retrieve all protocols configuration",
+        handler: RouteHandler::Sync(handlers::config::protocols::all),
+        access: Some(API_ACCESS_CONFIG),
+        extract: Extract::Nothing,
+        body: None,
+        status: 200,
+        response: Some((JSON, "Protocols")),
+    },
+    Operation {
+        method: "put",
+        path: "/config/protocols",
+        summary: "modify protocols configuration",
+        handler: RouteHandler::Async(handlers::config::protocols::update),
+        access: Some(API_ACCESS_ADMIN),
+        extract: Extract::Persist,
+        body: Some((JSON, "Protocols")),
+        status: 200,
+        response: Some((JSON, "Protocols")),
+    },
+    Operation {
+        method: "post",
+        path: "/config/reload",
+        summary: "read the configuration file again, and apply it to the virtual routers",
+        handler: RouteHandler::Sync(handlers::config::reload),
+        access: Some(API_ACCESS_ADMIN),
+        extract: Extract::Nothing,
+        body: None,
+        status: 200,
+        response: Some((JSON, "Config")),
+    },
+    Operation {
+        method: "get",
+        path: "/run/global",
+        summary: "retrieve running global configuration",
+        handler: RouteHandler::Sync(handlers::run::global::all),
+        access: Some(API_ACCESS_RUN),
+        extract: Extract::Nothing,
+        body: None,
+        status: 200,
+        response: Some((JSON, "ResponseGlobalAttr")),
+    },
+    Operation {
+        method: "get",
+        path: "/run/vrrp",
+        summary: "retrieve all VRRP information",
+        handler: RouteHandler::Sync(handlers::run::vrrp::all),
+        access: Some(API_ACCESS_RUN),
+        extract: Extract::Nothing,
+        body: None,
+        status: 200,
+        response: Some((JSON, "ResponseVRRPAttr[]")),
+    },
+    Operation {
+        method: "put",
+        path: "/run/vrrp/maintenance",
+        summary: "enter or leave maintenance mode (all virtual routers)",
+        handler: RouteHandler::Async(handlers::run::vrrp::maintenance),
+        access: Some(API_ACCESS_ADMIN),
+        extract: Extract::Nothing,
+        body: Some((JSON, "RequestMaintenance")),
+        status: 200,
+        response: Some((JSON, "ResponseVRRPAttr[]")),
+    },
+    Operation {
+        method: "get",
+        path: "/run/vrrp/:group_id",
+        summary: "retrieve group specific information",
+        handler: RouteHandler::Sync(handlers::run::vrrp::group),
+        access: Some(API_ACCESS_RUN),
+        extract: Extract::GroupId,
+        body: None,
+        status: 200,
+        response: Some((JSON, "ResponseVRRPAttr[]")),
+    },
+    Operation {
+        method: "get",
+        path: "/run/vrrp/:group_id/:interface",
+        summary: "get specific virtual router information",
+        handler: RouteHandler::Sync(handlers::run::vrrp::group_interface),
+        access: Some(API_ACCESS_RUN),
+        extract: Extract::GroupIdInterface,
+        body: None,
+        status: 200,
+        response: Some((JSON, "ResponseVRRPAttrExt")),
+    },
+    Operation {
+        method: "get",
+        path: "/run/vrrp/:group_id/:interface/peers",
+        summary: "retrieve the routers seen advertising",
+        handler: RouteHandler::Sync(handlers::run::vrrp::peers),
+        access: Some(API_ACCESS_RUN),
+        extract: Extract::GroupIdInterface,
+        body: None,
+        status: 200,
+        response: Some((JSON, "ResponsePeerAttr[]")),
+    },
+    Operation {
+        method: "get",
+        path: "/run/vrrp/:group_id/:interface/history",
+        summary: "retrieve the state transitions history",
+        handler: RouteHandler::Sync(handlers::run::vrrp::history),
+        access: Some(API_ACCESS_RUN),
+        extract: Extract::GroupIdInterface,
+        body: None,
+        status: 200,
+        response: Some((JSON, "Transition[]")),
+    },
+    Operation {
+        method: "post",
+        path: "/run/vrrp/:group_id/:interface",
+        summary: "add a new VRRP virtual router",
+        handler: RouteHandler::Async(handlers::run::vrrp::add),
+        access: Some(API_ACCESS_ADMIN),
+        extract: Extract::GroupIdInterface,
+        body: Some((JSON, "VRConfig")),
+        status: 201,
+        response: Some((JSON, "ResponseVRRPAttrExt")),
+    },
+    Operation {
+        method: "put",
+        path: "/run/vrrp/:group_id/:interface",
+        summary: "modify specific virtual router",
+        handler: RouteHandler::Async(handlers::run::vrrp::modify),
+        access: Some(API_ACCESS_ADMIN),
+        extract: Extract::GroupIdInterface,
+        body: Some((JSON, "RequestVRRPAttr")),
+        status: 200,
+        response: Some((JSON, "ResponseVRRPAttrExt")),
+    },
+    Operation {
+        method: "delete",
+        path: "/run/vrrp/:group_id/:interface",
+        summary: "remove a specific virtual router",
+        handler: RouteHandler::Sync(handlers::run::vrrp::remove),
+        access: Some(API_ACCESS_ADMIN),
+        extract: Extract::GroupIdInterface,
+        body: None,
+        status: 204,
+        response: None,
+    },
+    Operation {
+        method: "get",
+        path: "/run/protocols",
+        summary: "retrieve all protocols information",
+        handler: RouteHandler::Sync(handlers::run::protocols::all),
+        access: Some(API_ACCESS_RUN),
+        extract: Extract::Nothing,
+        body: None,
+        status: 200,
+        response: Some((JSON, "ResponseProtoAttr")),
+    },
+    Operation {
+        method: "get",
+        path: "/run/protocols/static",
+        summary: "retrieve all static routes",
+        handler: RouteHandler::Sync(handlers::run::protocols::pstatic),
+        access: Some(API_ACCESS_RUN),
+        extract: Extract::Nothing,
+        body: None,
+        status: 200,
+        response: Some((JSON, "ResponseProtoStaticAttr[]")),
+    },
+];
+
+// document() function
+/// generate the OpenAPI 3 document of the Client API
+pub fn document() -> Value {
+    let mut paths = Map::new();
+    for op in OPERATIONS {
+        let path = openapi_path(op.path);
+        let item = paths
+            .entry(path)
+            .or_insert_with(|| Value::Object(Map::new()));
+        item[op.method] = operation(op);
+    }
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "rVRRPd Client API",
+            "version": RVRRPD_VERSION_STRING,
+        },
+        "paths": paths,
+        "components": {
+            "schemas": schemas(),
+            "securitySchemes": {
+                "bearer": {
+                    "type": "http",
+                    "scheme": "bearer",
+                    "description": "session token returned by '/auth', or API key \
+                                    formatted as '<name>:<secret>'",
+                },
+                "cookie": {
+                    "type": "apiKey",
+                    "in": "cookie",
+                    "name": COOKIE_TOKEN,
+                    "description": "session cookies set by '/auth'",
+                },
+            },
+        },
+    })
+}
+
+// openapi_path() function
+// convert the gotham path parameters (':name') to the OpenAPI syntax ('{name}')
+pub fn openapi_path(path: &str) -> String {
+    path.split('/')
+        .map(|s| {
+            if s.starts_with(':') {
+                format!("{{{}}}", &s[1..])
+            } else {
+                s.to_string()
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("/")
+}
+
+// operation() function
+// return the OpenAPI operation object of a route
+fn operation(op: &Operation) -> Value {
+    let mut obj = json!({
+        "summary": op.summary,
+        "parameters": op.extract.params().iter().map(|p| json!({
+            "name": p.name,
+            "in": p.location,
+            "required": p.location == "path",
+            "description": p.description,
+            "schema": { "type": p.kind },
+        })).collect::<Vec<Value>>(),
+    });
+    let mut responses = Map::new();
+    let ok = match op.response {
+        Some((mime, schema)) => json!({
+            "description": "successful operation",
+            "content": { (mime): { "schema": schema_ref(schema) } },
+        }),
+        None => json!({ "description": "successful operation" }),
+    };
+    responses.insert(op.status.to_string(), ok);
+    // the access levels are described in the configuration reference
+    if let Some(level) = op.access {
+        obj["security"] = json!([{ "bearer": [] }, { "cookie": [] }]);
+        obj["x-access-level"] = json!(level);
+        responses.insert(
+            "401".to_string(),
+            json!({ "description": "missing or invalid credentials" }),
+        );
+        responses.insert(
+            "403".to_string(),
+            json!({ "description": "insufficient access level" }),
+        );
+    }
+    responses.insert(
+        "429".to_string(),
+        json!({ "description": "too many requests" }),
+    );
+    responses.insert(
+        "default".to_string(),
+        json!({
+            "description": "error",
+            "content": { (JSON): { "schema": schema_ref("ResponseError") } },
+        }),
+    );
+    obj["responses"] = Value::Object(responses);
+    if let Some((mime, schema)) = op.body {
+        obj["requestBody"] = json!({
+            "required": true,
+            "content": { (mime): { "schema": schema_ref(schema) } },
+        });
+    }
+    obj
+}
+
+// schema_ref() function
+// return a reference to a schema, 'Name[]' is an array of 'Name'
+fn schema_ref(name: &str) -> Value {
+    if name.is_empty() {
+        json!({ "type": "string" })
+    } else if name.ends_with("[]") {
+        json!({ "type": "array", "items": schema_ref(&name[..name.len() - 2]) })
+    } else {
+        json!({ "$ref": format!("#/components/schemas/{}", name) })
+    }
+}
+
+// schemas() function
+// return the schemas of the serialized structures
+fn schemas() -> Value {
+    let mut schemas = Map::new();
+    for (name, sample) in samples() {
+        schemas.insert(name.to_string(), schema_of(&sample));
+    }
+    // the configuration sections
+    for name in &["Config", "VRConfig", "Protocols"] {
+        schemas.insert(
+            name.to_string(),
+            json!({
+                "type": "object",
+                "description": "configuration section, see the configuration reference",
+            }),
+        );
+    }
+    Value::Object(schemas)
+}
+
+// schema_of() function
+// return the schema of a serialized sample
+fn schema_of(value: &Value) -> Value {
+    match value {
+        Value::Null => json!({ "nullable": true }),
+        Value::Bool(_) => json!({ "type": "boolean" }),
+        Value::Number(n) if n.is_f64() => json!({ "type": "number" }),
+        Value::Number(_) => json!({ "type": "integer" }),
+        Value::String(_) => json!({ "type": "string" }),
+        Value::Array(a) => match a.first() {
+            Some(v) => json!({ "type": "array", "items": schema_of(v) }),
+            None => json!({ "type": "array", "items": {} }),
+        },
+        Value::Object(m) => json!({
+            "type": "object",
+            "properties": m
+                .iter()
+                .map(|(k, v)| (k.clone(), schema_of(v)))
+                .collect::<Map<String, Value>>(),
+        }),
+    }
+}
+
+// samples() function
+// return a serialized sample of every documented structure, every
+// optional field is set so its type is known
+fn samples() -> Vec<(&'static str, Value)> {
+    let vrrp = ResponseVRRPAttr {
+        virtual_ip: String::new(),
+        group: 0,
+        interface: String::new(),
+        priority: 0,
+        preempt: false,
+        maintenance: false,
+        state: String::new(),
+    };
+    let vrrp_ext = ResponseVRRPAttrExt {
+        virtual_ip: String::new(),
+        group: 0,
+        interface: String::new(),
+        vif: String::new(),
+        priority: 0,
+        preempt: false,
+        maintenance: false,
+        state: String::new(),
+        auth_type: 0,
+        interface_hwaddress: String::new(),
+        advert_interval: 0,
+        masterdown_interval: 0.5,
+        skew_time: 0.5,
+    };
+    let peer = ResponsePeerAttr {
+        source_ip: String::new(),
+        source_mac: String::new(),
+        priority: 0,
+        advert_interval: 0,
+        auth_type: 0,
+        addresses: vec![String::new()],
+        first_seen: 0,
+        last_seen: 0,
+        master: false,
+    };
+    let transition = Transition {
+        timestamp: 0,
+        from: String::new(),
+        to: String::new(),
+        trigger: Trigger::Advert {
+            source: String::new(),
+            priority: 0,
+        },
+        operations: vec![OpResult {
+            op: "",
+            action: "",
+            success: false,
+            error: Some(String::new()),
+            dry_run: true,
+            detail: Some(String::new()),
+        }],
+    };
+    let global = ResponseGlobalAttr {
+        debug: 0,
+        timestamp: 0,
+        timezone: 0,
+        timeformat: 0,
+        pid: String::new(),
+        working_dir: String::new(),
+        main_log: String::new(),
+        error_log: String::new(),
+        crash_dump: String::new(),
+    };
+    let pstatic = || ResponseProtoStaticAttr {
+        destination: String::new(),
+        mask: String::new(),
+        next_hop: String::new(),
+        metric: 0,
+        mtu: 0,
+    };
+    let proto = ResponseProtoAttr {
+        r#static: Some(vec![pstatic()]),
+    };
+    let lockout = ResponseLockout {
+        user: Some(String::new()),
+        address: Some(String::new()),
+        failures: 0,
+        until: 0,
+    };
+    let revoke = ResponseRevoke { revoked: 0 };
+    let token = ResponseToken {
+        token: String::new(),
+        expires: 0,
+    };
+    let error = ResponseError {
+        error: String::new(),
+    };
+    let vrrp_req = RequestVRRPAttr {
+        priority: Some(0),
+        preempt: Some(false),
+        advert_interval: Some(0),
+        auth_type: Some(String::new()),
+        auth_secret: Some(String::new()),
+        maintenance: Some(false),
+    };
+    let maint_req = RequestMaintenance { maintenance: false };
+    let audit = ResponseAudit {
+        verified: false,
+        records: vec![AuditRecord {
+            seq: 0,
+            timestamp: 0,
+            user: String::new(),
+            address: Some(String::new()),
+            method: String::new(),
+            route: String::new(),
+            body: Some(json!({})),
+            status: 0,
+            diff: Some(vec![AuditChange {
+                path: String::new(),
+                before: Value::Null,
+                after: Value::Null,
+            }]),
+            prev: String::new(),
+            hmac: String::new(),
+        }],
+    };
+    let to_value = |v: serde_json::Result<Value>| v.expect("serialized sample");
+    vec![
+        ("ResponseVRRPAttr", to_value(serde_json::to_value(vrrp))),
+        (
+            "ResponseVRRPAttrExt",
+            to_value(serde_json::to_value(vrrp_ext)),
+        ),
+        ("ResponsePeerAttr", to_value(serde_json::to_value(peer))),
+        ("Transition", to_value(serde_json::to_value(transition))),
+        ("ResponseGlobalAttr", to_value(serde_json::to_value(global))),
+        ("ResponseProtoAttr", to_value(serde_json::to_value(proto))),
+        (
+            "ResponseProtoStaticAttr",
+            to_value(serde_json::to_value(pstatic())),
+        ),
+        ("ResponseLockout", to_value(serde_json::to_value(lockout))),
+        ("ResponseRevoke", to_value(serde_json::to_value(revoke))),
+        ("ResponseToken", to_value(serde_json::to_value(token))),
+        ("ResponseError", to_value(serde_json::to_value(error))),
+        ("ResponseAudit", to_value(serde_json::to_value(audit))),
+        ("RequestVRRPAttr", to_value(serde_json::to_value(vrrp_req))),
+        (
+            "RequestMaintenance",
+            to_value(serde_json::to_value(maint_req)),
+        ),
+    ]
+}
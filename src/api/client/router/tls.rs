@@ -0,0 +1,205 @@
+//! Client API - TLS support module
+//! This module builds the TLS acceptor, reloads it when its files change,
+//! and keeps track of the client certificates of the established connections.
+use super::*;
+
+// std
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+// futures
+use futures::Poll;
+
+// openssl
+use openssl::{
+    nid::Nid,
+    pkey::PKey,
+    ssl::{SslAcceptor, SslMethod, SslVerifyMode},
+    x509::{X509Name, X509Ref, X509},
+};
+
+// tokio
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpStream;
+
+// tokio_openssl
+use tokio_openssl::SslStream;
+
+// TLS files polling interval (in seconds)
+const TLS_RELOAD_INTERVAL: u64 = 5;
+
+/// PeerIdentities structure
+/// client certificate identities of the established connections of a listener
+#[derive(Clone, Default)]
+pub struct PeerIdentities(Arc<Mutex<HashMap<SocketAddr, Vec<String>>>>);
+
+// PeerIdentities implementation
+impl PeerIdentities {
+    // get() method
+    /// return the identities of the client certificate presented on a connection
+    pub fn get(&self, addr: &SocketAddr) -> Option<Vec<String>> {
+        self.0.lock().unwrap().get(addr).cloned()
+    }
+}
+
+/// TlsFiles structure
+#[derive(Clone)]
+pub struct TlsFiles {
+    pub key: String,
+    pub cert: String,
+    pub client_ca: Option<String>,
+}
+
+// TlsFiles implementation
+impl TlsFiles {
+    // mtimes() method
+    // return the last modification time of every file
+    fn mtimes(&self) -> Vec<Option<SystemTime>> {
+        let mut files = vec![&self.key, &self.cert];
+        if let Some(ca) = &self.client_ca {
+            files.push(ca);
+        }
+        files
+            .iter()
+            .map(|f| std::fs::metadata(f).and_then(|m| m.modified()).ok())
+            .collect()
+    }
+}
+
+// build_tls_acceptor() function
+pub fn build_tls_acceptor(files: &TlsFiles) -> Result<SslAcceptor, Error> {
+    // openssl req -new -x509 -sha256 -newkey rsa:2048 -nodes -keyout key.pem -days 365 -out cert.pem
+    let key = std::fs::read(&files.key)
+        .map_err(|e| err_msg(format!("Cannot read RSA key file: {}", e)))?;
+    let cert = std::fs::read(&files.cert)
+        .map_err(|e| err_msg(format!("Cannot read X.509 certificate file: {}", e)))?;
+    let cert = X509::from_pem(&cert).map_err(|_| err_msg("Malformed X.509 certificate"))?;
+    let key = PKey::private_key_from_pem(&key).map_err(|_| err_msg("Malformed PEM key"))?;
+
+    let mut builder = SslAcceptor::mozilla_intermediate(SslMethod::tls())?;
+    builder.set_certificate(&cert)?;
+    builder.set_private_key(&key)?;
+
+    // require and verify the clients certificates
+    if let Some(ca) = &files.client_ca {
+        builder
+            .set_ca_file(ca)
+            .map_err(|e| err_msg(format!("Cannot read client CA file: {}", e)))?;
+        builder.set_client_ca_list(X509Name::load_client_ca_file(ca)?);
+        builder.set_verify(SslVerifyMode::PEER | SslVerifyMode::FAIL_IF_NO_PEER_CERT);
+    }
+
+    let acceptor = builder.build();
+    Ok(acceptor)
+}
+
+// watch_tls_files() function
+/// spawn a thread rebuilding the TLS acceptor when its files are modified.
+/// the current acceptor is kept if the new files are invalid.
+pub fn watch_tls_files(acceptor: Arc<RwLock<SslAcceptor>>, files: TlsFiles) {
+    thread::spawn(move || {
+        let mut mtimes = files.mtimes();
+        loop {
+            thread::sleep(Duration::from_secs(TLS_RELOAD_INTERVAL));
+            let current = files.mtimes();
+            if current == mtimes {
+                continue;
+            }
+            mtimes = current;
+            match build_tls_acceptor(&files) {
+                Ok(a) => {
+                    *acceptor.write().unwrap() = a;
+                    println!("Client API TLS certificate and key reloaded");
+                }
+                Err(e) => eprintln!("Cannot reload the Client API TLS files: {}", e),
+            }
+        }
+    });
+}
+
+// cert_identities() function
+// return the subject common name(s), and the DNS and email SANs of a certificate
+fn cert_identities(cert: &X509Ref) -> Vec<String> {
+    let mut ids = Vec::new();
+    for e in cert.subject_name().entries_by_nid(Nid::COMMONNAME) {
+        if let Ok(s) = std::str::from_utf8(e.data().as_slice()) {
+            ids.push(s.to_string());
+        }
+    }
+    if let Some(sans) = cert.subject_alt_names() {
+        for n in sans.iter() {
+            if let Some(s) = n.dnsname().or_else(|| n.email()) {
+                ids.push(s.to_string());
+            }
+        }
+    }
+    ids
+}
+
+/// PeerStream structure
+/// a TLS stream recording the client certificate identities for the
+/// lifetime of the connection
+pub struct PeerStream {
+    inner: SslStream<TcpStream>,
+    addr: Option<SocketAddr>,
+    peers: PeerIdentities,
+}
+
+// PeerStream implementation
+impl PeerStream {
+    // new() method
+    pub fn new(
+        inner: SslStream<TcpStream>,
+        addr: Option<SocketAddr>,
+        peers: PeerIdentities,
+    ) -> PeerStream {
+        if let Some(a) = addr {
+            let mut map = peers.0.lock().unwrap();
+            match inner.get_ref().ssl().peer_certificate() {
+                Some(cert) => {
+                    map.insert(a, cert_identities(&cert));
+                }
+                None => {
+                    map.remove(&a);
+                }
+            }
+        }
+        PeerStream { inner, addr, peers }
+    }
+}
+
+// PeerStream Drop trait implementation
+impl Drop for PeerStream {
+    fn drop(&mut self) {
+        if let Some(a) = &self.addr {
+            self.peers.0.lock().unwrap().remove(a);
+        }
+    }
+}
+
+impl Read for PeerStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+impl Write for PeerStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write(buf)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl AsyncRead for PeerStream {}
+
+impl AsyncWrite for PeerStream {
+    fn shutdown(&mut self) -> Poll<(), io::Error> {
+        self.inner.shutdown()
+    }
+}
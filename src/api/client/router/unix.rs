@@ -0,0 +1,123 @@
+//! Client API - Unix domain socket module
+//! This module serves the router on a Unix domain socket. Every connection is
+//! served by its own router, holding the credentials of its peer.
+use super::*;
+
+// std
+use std::fs;
+use std::io;
+use std::net::SocketAddr;
+use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+
+// futures
+use futures::Stream;
+
+// gotham
+use gotham::service::GothamService;
+
+// hyper
+use hyper::server::conn::Http;
+
+// nix
+use nix::sys::stat::{umask, Mode};
+use nix::unistd::{chown, Gid, Uid};
+
+// tokio
+use tokio::net::UnixListener;
+
+/// PeerCredentials structure
+/// uid and gid of the Unix socket peer of a connection
+#[derive(Clone, Copy)]
+pub struct PeerCredentials(u32, u32);
+
+// PeerCredentials implementation
+impl PeerCredentials {
+    // get() method
+    /// return the uid and gid of the Unix socket peer
+    pub fn get(&self) -> (u32, u32) {
+        (self.0, self.1)
+    }
+}
+
+// bind() function
+/// bind the Unix domain socket, then set its ownership and mode
+fn bind(unix: &config::APIUnix) -> io::Result<UnixListener> {
+    let path = unix.path();
+
+    // remove the socket left by a previous instance, but never another file
+    match fs::symlink_metadata(&path) {
+        Ok(m) if m.file_type().is_socket() => fs::remove_file(&path)?,
+        Ok(_) => {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} exists and is not a socket", path),
+            ))
+        }
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+
+    // only the owner can connect until the socket mode is set
+    let mask = umask(Mode::from_bits_truncate(0o077));
+    let listener = UnixListener::bind(&path);
+    umask(mask);
+    let listener = listener?;
+
+    // set the socket ownership and mode
+    if unix.owner().is_some() || unix.group().is_some() {
+        let owner = unix.owner().map(Uid::from_raw);
+        let group = unix.group().map(Gid::from_raw);
+        if let Err(e) = chown(path.as_str(), owner, group) {
+            eprintln!("error(api): cannot change the owner of {}: {}", path, e);
+        }
+    }
+    if let Err(e) = fs::set_permissions(&path, fs::Permissions::from_mode(unix.mode())) {
+        eprintln!("error(api): cannot change the mode of {}: {}", path, e);
+    }
+    Ok(listener)
+}
+
+// start() function
+/// serve the router on the Unix domain socket (blocking)
+pub fn start(down_api: DownstreamAPI, limiter: RateLimiter, unix: config::APIUnix) {
+    let path = unix.path();
+    let listener = match bind(&unix) {
+        Ok(l) => l,
+        Err(e) => {
+            eprintln!("error(api): cannot listen on unix:{}: {}", path, e);
+            return;
+        }
+    };
+    println!("Client API Server listening on unix:{}", path);
+
+    // the Unix socket peers have no address, they share the loopback one
+    let addr = SocketAddr::from(([127, 0, 0, 1], 0));
+    let protocol = Arc::new(Http::new());
+
+    let server = listener
+        .incoming()
+        .map_err(|e| eprintln!("error(api): Unix socket error: {}", e))
+        .for_each(move |stream| {
+            let cred = match stream.peer_cred() {
+                Ok(c) => PeerCredentials(c.uid as u32, c.gid as u32),
+                Err(e) => {
+                    eprintln!("error(api): cannot read the Unix socket peer: {}", e);
+                    return Ok(());
+                }
+            };
+            // the credentials are the state of this connection's router
+            let router = router(&down_api, &limiter, Listener::Unix(cred));
+            let service = GothamService::new(router).connect(addr);
+            let conn = protocol
+                .serve_connection(stream, service)
+                .with_upgrades()
+                .map_err(|e| eprintln!("error(api): Unix socket connection error: {}", e));
+            tokio::spawn(conn);
+            Ok(())
+        });
+
+    let mut runtime = Runtime::new().unwrap();
+    if runtime.block_on(server).is_err() {
+        eprintln!("error(api): Unix socket server failed");
+    }
+}
@@ -2,8 +2,9 @@
 use super::*;
 
 // std
+use std::net::IpAddr;
 use std::thread;
-use std::time;
+use std::time::{self, SystemTime};
 
 // session token
 use token::SessionToken;
@@ -30,33 +31,54 @@ pub fn auth_api_client(
     cfg: &config::CConfig,
     user: String,
     passwd: String,
+    addr: Option<IpAddr>,
 ) -> Option<SessionToken> {
     // authenticate the API user
-    let sess = match auth_user_from_cfg(cfg, user, passwd) {
-        Some(usr) => {
-            // if authentication is succesful, create a new SessionToken
-            let mut token = SessionToken::new();
-            // set authenticated user
-            token.set_user(usr);
-            // generate the token
-            match token.gen_token(cfg) {
-                // if succesfully generated
-                Ok(()) => {
-                    // set session duration
-                    let timeout = time::Duration::new(60, 0);
-                    token.set_validfor(timeout.as_secs());
-                    // return SessionToken
-                    Some(token)
-                }
-                // if token generation failed
-                _ => None,
-            }
-        }
+    match auth_user_from_cfg(cfg, user, passwd) {
+        // if authentication is succesful, issue a new SessionToken
+        Some((usr, access)) => issue_token(cfg, usr, access, addr),
         // if authentication failed
         None => None,
-    };
+    }
+}
 
-    sess
+/// refresh_api_client() function
+/// issue a new session token for an already authenticated client,
+/// with the access level granted to its current session
+pub fn refresh_api_client(
+    cfg: &config::CConfig,
+    sess: &SessionToken,
+    access: u8,
+) -> Option<SessionToken> {
+    issue_token(cfg, sess.user(), access, sess.addr())
+}
+
+// issue_token() function
+fn issue_token(
+    cfg: &config::CConfig,
+    user: String,
+    access: u8,
+    addr: Option<IpAddr>,
+) -> Option<SessionToken> {
+    // create a new SessionToken
+    let mut token = SessionToken::new();
+    // set authenticated user, its access level and address
+    token.set_user(user);
+    token.set_access(access);
+    token.set_addr(addr);
+    // generate the token
+    match token.gen_token(cfg) {
+        // if succesfully generated
+        Ok(()) => {
+            // set session duration
+            let timeout = cfg.api.as_ref().unwrap().session_timeout();
+            token.set_validfor(timeout);
+            // return SessionToken
+            Some(token)
+        }
+        // if token generation failed
+        _ => None,
+    }
 }
 
 /// auth_user_from_cfg() function
@@ -64,10 +86,10 @@ pub fn auth_api_client(
 /// and for every user, compare the hashed passwords according
 /// to the configured hash function.
 ///
-/// return the user name String if sucessfully authenticated
-fn auth_user_from_cfg(cfg: &config::CConfig, user: String, passwd: String) -> Option<String> {
+/// return the user name String and its access level if sucessfully authenticated
+fn auth_user_from_cfg(cfg: &config::CConfig, user: String, passwd: String) -> Option<(String, u8)> {
     // initialize response
-    let res: Option<String> = None;
+    let res: Option<(String, u8)> = None;
 
     // access configuation api users
     if let Some(a) = &cfg.api {
@@ -76,41 +98,20 @@ fn auth_user_from_cfg(cfg: &config::CConfig, user: String, passwd: String) -> Op
                 Some(c) => {
                     let alg = c.get(1).unwrap().as_str().to_string();
                     let username = c.get(2).unwrap().as_str().to_string();
-                    let _access = c.get(3).unwrap().as_str().to_string();
+                    let access = c.get(3).unwrap().as_str().parse::<u8>().unwrap();
                     let salt = c.get(4).unwrap().as_str().to_string();
                     let hash = c.get(5).unwrap().as_str().to_string();
                     // if the username matches
                     if username == user {
-                        // perform password hashing
-                        match &alg[..] {
-                            "SHA256" => {
-                                // create new SHA256 hasher
-                                let mut hasher = Sha256::new();
-                                // feed the hasher with the password
-                                hasher.input(&passwd);
-                                // feed the hasher with the salt
-                                hasher.input(&salt);
-                                // convert the result to an hex formatted String
-                                let h2 = format!("{:x}", hasher.result());
-                                // reduce likelihood of timing attacks by introducing a random delay
-                                // prior to the hashes comparison.
-                                let mut rng = rand::thread_rng();
-                                let rdelay = rng.gen::<u8>() as u64;
-                                let time = time::Duration::from_millis(rdelay);
-                                thread::sleep(time);
-                                // compare hashed values
-                                if hash == h2 {
-                                    return Some(username);
-                                }
-                            }
-                            "SCRYPT" => {
-                                // check if password is matching the stored hash
-                                if scrypt_check(&passwd, &hash).is_ok() {
-                                    return Some(username);
-                                }
-                            }
-                            // if alg doesn't match, continue
-                            &_ => (),
+                        // reduce likelihood of timing attacks by introducing a random delay
+                        // prior to the hashes comparison.
+                        let mut rng = rand::thread_rng();
+                        let rdelay = rng.gen::<u8>() as u64;
+                        let time = time::Duration::from_millis(rdelay);
+                        thread::sleep(time);
+                        // perform password hashing and compare hashed values
+                        if verify_hash(&alg, &passwd, &salt, &hash) {
+                            return Some((username, access));
                         }
                     }
                 }
@@ -124,6 +125,105 @@ fn auth_user_from_cfg(cfg: &config::CConfig, user: String, passwd: String) -> Op
     res
 }
 
+/// auth_api_key() function
+/// read the 'keys' vector in the api configuration section
+/// and verify the API key presented by the client.
+///
+/// return the access level of the key if it is valid, has not expired
+/// and is used from an allowed client address
+pub fn auth_api_key(cfg: &config::CConfig, sess: &SessionToken) -> Option<u8> {
+    let secret = sess.key()?;
+    let api = cfg.api.as_ref()?;
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+
+    for k in api.keys() {
+        // the API keys are formatted as the API users
+        if let Some(c) = regex_captures_apiuser(&k.key()) {
+            let alg = c.get(1).unwrap().as_str();
+            let name = c.get(2).unwrap().as_str();
+            let access = c.get(3).unwrap().as_str().parse::<u8>().unwrap();
+            let salt = c.get(4).unwrap().as_str();
+            let hash = c.get(5).unwrap().as_str();
+            if name != sess.user() {
+                continue;
+            }
+            // check the key's expiry and allowed addresses
+            if k.expires().map_or(false, |e| now >= e) || !k.allows(sess.addr()) {
+                return None;
+            }
+            if verify_hash(alg, secret, salt, hash) {
+                return Some(access);
+            }
+            return None;
+        }
+    }
+
+    None
+}
+
+/// auth_api_cert() function
+/// map the identities of the client certificate to the
+/// 'client_certs' entries in the api configuration section
+///
+/// return the access level of the first matching entry
+pub fn auth_api_cert(cfg: &config::CConfig, sess: &SessionToken) -> Option<u8> {
+    let api = cfg.api.as_ref()?;
+    // client certificates are only trusted if verified against the client CA
+    api.tls_client_ca()?;
+    api.client_certs()
+        .iter()
+        .find(|c| sess.identities().iter().any(|i| i == c.identity()))
+        .map(|c| c.access())
+}
+
+// auth_api_unix() function
+/// authenticate a client connected to the Unix domain socket, using the
+/// uid and gid of its process and the 'peers' entries of the api unix section
+///
+/// return the access level of the first matching entry
+pub fn auth_api_unix(cfg: &config::CConfig, sess: &SessionToken) -> Option<u8> {
+    let (uid, gid) = sess.peer()?;
+    cfg.api
+        .as_ref()?
+        .unix()?
+        .peers()
+        .iter()
+        .find(|p| p.matches(uid, gid))
+        .map(|p| p.access())
+}
+
+// verify_hash() function
+// hash the secret according to the hashing algorithm and compare it
+// to the stored hash
+fn verify_hash(alg: &str, secret: &str, salt: &str, hash: &str) -> bool {
+    match alg {
+        "SHA256" => {
+            // create new SHA256 hasher
+            let mut hasher = Sha256::new();
+            // feed the hasher with the secret
+            hasher.input(secret);
+            // feed the hasher with the salt
+            hasher.input(salt);
+            // convert the result to an hex formatted String
+            let h2 = format!("{:x}", hasher.result());
+            // compare hashed values in constant time
+            h2.len() == hash.len()
+                && h2
+                    .bytes()
+                    .zip(hash.bytes())
+                    .fold(0, |acc, (a, b)| acc | (a ^ b))
+                    == 0
+        }
+        // check if the secret is matching the stored hash
+        "SCRYPT" => scrypt_check(secret, hash).is_ok(),
+        // unknown hashing algorithm
+        _ => false,
+    }
+}
+
 /// regex_captures_apiuser function
 fn regex_captures_apiuser(acc: &String) -> Option<regex::Captures> {
     // the API user account information is formatted as follow:
@@ -139,3 +239,74 @@ fn regex_captures_apiuser(acc: &String) -> Option<regex::Captures> {
     // capture content using the pre-compiled regular expression
     REGEX_HTBODY_AUTH_AV.captures(acc)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // config() function
+    // return a configuration with the 'admin' user and the 'ansible' API key,
+    // whose password and secret are 'passwd' and 'k3ysecret' (salt '00ff')
+    fn config(key: &str) -> config::CConfig {
+        let cfg = format!(
+            "[api]\nsecret = \"secret\"\nsession_timeout = 300\n\
+             users = [ \"{{{{SHA256}}}}admin:1:00ff:\
+             84af3e75a16f2bdc90727a594d5ee8f8043a8f8bc7b1f8b1631915e318974388\" ]\n\
+             [[api.keys]]\nkey = \"{{{{SHA256}}}}ansible:2:00ff:\
+             080ea6bbaf1dcde054e6172958c39b4616400032ae3a8a4b02b1350cebd5aae5\"\n{}",
+            key
+        );
+        toml::from_str(&cfg).unwrap()
+    }
+
+    #[test]
+    fn refresh_keeps_the_user_access_and_address() {
+        let cfg = config("");
+        let addr = Some("10.0.0.1".parse().unwrap());
+        assert!(auth_api_client(&cfg, "admin".to_string(), "wrong".to_string(), addr).is_none());
+        let sess = auth_api_client(&cfg, "admin".to_string(), "passwd".to_string(), addr).unwrap();
+        assert_eq!(sess.access(), 1);
+        assert!(sess.validate(&cfg).is_some());
+
+        let nsess = refresh_api_client(&cfg, &sess, sess.access()).unwrap();
+        assert_eq!(nsess.user(), "admin");
+        assert_eq!(nsess.access(), 1);
+        assert_eq!(nsess.addr(), addr);
+        assert_ne!(nsess.token(), sess.token());
+        assert!(nsess.ts_valid() >= sess.ts_valid());
+        assert!(nsess.validate(&cfg).is_some());
+    }
+
+    #[test]
+    fn api_keys_are_verified_against_their_hash() {
+        let key = |bearer: &str, addr: &str| {
+            let mut sess = SessionToken::from_bearer(bearer).unwrap();
+            sess.set_addr(Some(addr.parse().unwrap()));
+            sess
+        };
+        let cfg = config("allowed = [ \"10.0.0.0/24\" ]");
+        assert_eq!(
+            auth_api_key(&cfg, &key("ansible:k3ysecret", "10.0.0.1")),
+            Some(2)
+        );
+        assert_eq!(auth_api_key(&cfg, &key("ansible:wrong", "10.0.0.1")), None);
+        assert_eq!(auth_api_key(&cfg, &key("admin:passwd", "10.0.0.1")), None);
+        // the key is only accepted from the allowed addresses
+        assert_eq!(
+            auth_api_key(&cfg, &key("ansible:k3ysecret", "10.0.1.1")),
+            None
+        );
+
+        // and until it expires
+        let cfg = config("expires = \"2020-01-01T00:00:00Z\"");
+        assert_eq!(
+            auth_api_key(&cfg, &key("ansible:k3ysecret", "10.0.0.1")),
+            None
+        );
+        let cfg = config("expires = \"2999-01-01T00:00:00Z\"");
+        assert_eq!(
+            auth_api_key(&cfg, &key("ansible:k3ysecret", "10.0.0.1")),
+            Some(2)
+        );
+    }
+}
@@ -0,0 +1,264 @@
+//! Client API - login guard module
+//! This module tracks the failed authentication attempts per client address
+//! and per user, and locks them out temporarily.
+
+// std
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::SystemTime;
+
+/// GuardKey enumerator
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum GuardKey {
+    Address(IpAddr),
+    User(String),
+}
+
+/// Failures structure
+struct Failures {
+    count: u32,
+    last: u64,
+    until: u64,
+    locked: bool,
+}
+
+/// Lockout structure
+pub struct Lockout {
+    pub key: GuardKey,
+    pub failures: u32,
+    pub until: u64,
+}
+
+/// LoginGuard structure
+pub struct LoginGuard {
+    failures: HashMap<GuardKey, Failures>,
+}
+
+/// LoginGuard implementation
+impl LoginGuard {
+    // new() method
+    pub fn new() -> LoginGuard {
+        LoginGuard {
+            failures: HashMap::new(),
+        }
+    }
+    // check() method
+    // return the number of seconds to wait if the user or the address is
+    // still backing off or locked out
+    pub fn check(&self, user: &str, addr: Option<IpAddr>) -> Option<u64> {
+        self.check_at(user, addr, now())
+    }
+    // check_at() method
+    // check the user and the address at the 'now' time
+    fn check_at(&self, user: &str, addr: Option<IpAddr>, now: u64) -> Option<u64> {
+        keys(user, addr)
+            .iter()
+            .filter_map(|k| self.failures.get(k))
+            .filter(|f| f.until > now)
+            .map(|f| f.until - now)
+            .max()
+    }
+    // failure() method
+    // record a failed attempt, and back off exponentially from one second.
+    // after 'max' consecutive failures, lock out for 'lockout' seconds.
+    // return the new lockouts
+    pub fn failure(
+        &mut self,
+        user: &str,
+        addr: Option<IpAddr>,
+        max: u32,
+        lockout: u64,
+    ) -> Vec<Lockout> {
+        self.failure_at(user, addr, max, lockout, now())
+    }
+    // failure_at() method
+    // record a failed attempt at the 'now' time
+    fn failure_at(
+        &mut self,
+        user: &str,
+        addr: Option<IpAddr>,
+        max: u32,
+        lockout: u64,
+        now: u64,
+    ) -> Vec<Lockout> {
+        let mut locked = Vec::new();
+        for k in keys(user, addr) {
+            let f = self.failures.entry(k.clone()).or_insert(Failures {
+                count: 0,
+                last: 0,
+                until: 0,
+                locked: false,
+            });
+            // the failures are forgotten after a lockout period
+            if now >= f.last.saturating_add(lockout) {
+                f.count = 0;
+                f.locked = false;
+            }
+            f.count += 1;
+            f.last = now;
+            if f.count >= max {
+                f.until = now + lockout;
+                f.locked = true;
+                locked.push(Lockout {
+                    key: k,
+                    failures: f.count,
+                    until: f.until,
+                });
+            } else {
+                let backoff = 1u64.checked_shl(f.count - 1).unwrap_or(lockout);
+                f.until = now + backoff.min(lockout);
+            }
+        }
+        self.purge(lockout, now);
+        locked
+    }
+    // success() method
+    // reset the counters after a successful authentication
+    pub fn success(&mut self, user: &str, addr: Option<IpAddr>) {
+        for k in keys(user, addr) {
+            self.failures.remove(&k);
+        }
+    }
+    // lockouts() method
+    // return the current lockouts
+    pub fn lockouts(&self) -> Vec<Lockout> {
+        self.lockouts_at(now())
+    }
+    // lockouts_at() method
+    // return the lockouts at the 'now' time
+    fn lockouts_at(&self, now: u64) -> Vec<Lockout> {
+        self.failures
+            .iter()
+            .filter(|(_, f)| f.locked && f.until > now)
+            .map(|(k, f)| Lockout {
+                key: k.clone(),
+                failures: f.count,
+                until: f.until,
+            })
+            .collect()
+    }
+    // purge() method
+    // remove the entries which have been forgotten
+    fn purge(&mut self, lockout: u64, now: u64) {
+        self.failures
+            .retain(|_, f| f.until > now || now < f.last.saturating_add(lockout));
+    }
+}
+
+// keys() function
+fn keys(user: &str, addr: Option<IpAddr>) -> Vec<GuardKey> {
+    let mut keys = vec![GuardKey::User(user.to_string())];
+    if let Some(a) = addr {
+        keys.push(GuardKey::Address(a));
+    }
+    keys
+}
+
+// now() function
+// return the current system time (in seconds since the Unix Epoch)
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAX: u32 = 3;
+    const LOCKOUT: u64 = 300;
+
+    // addr() function
+    fn addr() -> Option<IpAddr> {
+        Some("10.0.0.1".parse().unwrap())
+    }
+
+    #[test]
+    fn failures_back_off_exponentially() {
+        let mut guard = LoginGuard::new();
+        assert_eq!(guard.check_at("admin", addr(), 1000), None);
+
+        guard.failure_at("admin", addr(), 5, LOCKOUT, 1000);
+        assert_eq!(guard.check_at("admin", addr(), 1000), Some(1));
+        assert_eq!(guard.check_at("admin", addr(), 1001), None);
+        guard.failure_at("admin", addr(), 5, LOCKOUT, 1001);
+        assert_eq!(guard.check_at("admin", addr(), 1001), Some(2));
+        guard.failure_at("admin", addr(), 5, LOCKOUT, 1003);
+        assert_eq!(guard.check_at("admin", addr(), 1003), Some(4));
+
+        // the address backs off for any user
+        assert_eq!(guard.check_at("other", addr(), 1003), Some(4));
+        assert_eq!(guard.check_at("other", None, 1003), None);
+    }
+
+    #[test]
+    fn max_failures_lock_out() {
+        let mut guard = LoginGuard::new();
+        assert!(guard
+            .failure_at("admin", addr(), MAX, LOCKOUT, 1000)
+            .is_empty());
+        assert!(guard
+            .failure_at("admin", addr(), MAX, LOCKOUT, 1001)
+            .is_empty());
+        let locked = guard.failure_at("admin", addr(), MAX, LOCKOUT, 1003);
+
+        // both the user and the address are locked out
+        assert_eq!(locked.len(), 2);
+        assert!(locked
+            .iter()
+            .any(|l| l.key == GuardKey::User("admin".to_string())));
+        assert!(locked
+            .iter()
+            .any(|l| l.key == GuardKey::Address(addr().unwrap())));
+        assert!(locked
+            .iter()
+            .all(|l| l.failures == MAX && l.until == 1003 + LOCKOUT));
+        assert_eq!(
+            guard.check_at("admin", None, 1100),
+            Some(1003 + LOCKOUT - 1100)
+        );
+        assert_eq!(guard.lockouts_at(1100).len(), 2);
+
+        // until the end of the lockout
+        assert_eq!(guard.check_at("admin", addr(), 1003 + LOCKOUT), None);
+        assert!(guard.lockouts_at(1003 + LOCKOUT).is_empty());
+    }
+
+    #[test]
+    fn success_resets_the_failures() {
+        let mut guard = LoginGuard::new();
+        guard.failure_at("admin", addr(), MAX, LOCKOUT, 1000);
+        guard.failure_at("admin", addr(), MAX, LOCKOUT, 1001);
+        guard.success("admin", addr());
+        assert_eq!(guard.check_at("admin", addr(), 1003), None);
+
+        // the next failure backs off from one second again
+        assert!(guard
+            .failure_at("admin", addr(), MAX, LOCKOUT, 1003)
+            .is_empty());
+        assert_eq!(guard.check_at("admin", addr(), 1003), Some(1));
+    }
+
+    #[test]
+    fn failures_expire_and_are_purged() {
+        let mut guard = LoginGuard::new();
+        guard.failure_at("admin", addr(), MAX, LOCKOUT, 1000);
+        guard.failure_at("admin", addr(), MAX, LOCKOUT, 1001);
+
+        // the failures are forgotten after a lockout period
+        let t = 1001 + LOCKOUT;
+        assert!(guard
+            .failure_at("admin", addr(), MAX, LOCKOUT, t)
+            .is_empty());
+        assert_eq!(guard.check_at("admin", addr(), t), Some(1));
+
+        // and the forgotten entries are removed
+        guard.failure_at("other", None, MAX, LOCKOUT, t + LOCKOUT + 1);
+        assert_eq!(guard.failures.len(), 1);
+        assert!(guard
+            .failures
+            .contains_key(&GuardKey::User("other".to_string())));
+    }
+}
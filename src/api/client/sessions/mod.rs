@@ -5,3 +5,9 @@ pub mod auth;
 
 // session token
 pub mod token;
+
+// server-side session store
+pub mod store;
+
+// login guard
+pub mod guard;
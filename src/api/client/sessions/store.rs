@@ -0,0 +1,180 @@
+//! Client API - server-side session store module
+
+// std
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::SystemTime;
+
+// session token
+use super::token::SessionToken;
+
+/// StoredSession structure
+struct StoredSession {
+    user: String,
+    access: u8,
+    expires: u64,
+    addr: Option<IpAddr>,
+}
+
+/// SessionStore structure
+/// keep track of the issued session tokens, so they can be revoked
+pub struct SessionStore {
+    sessions: HashMap<String, StoredSession>,
+}
+
+/// SessionStore implementation
+impl SessionStore {
+    // new() method
+    pub fn new() -> SessionStore {
+        SessionStore {
+            sessions: HashMap::new(),
+        }
+    }
+    // insert() method
+    // register a newly issued session token
+    pub fn insert(&mut self, sess: &SessionToken) {
+        // drop the expired sessions first
+        self.purge(now());
+        self.sessions.insert(
+            sess.token(),
+            StoredSession {
+                user: sess.user(),
+                access: sess.access(),
+                expires: sess.ts_valid(),
+                addr: sess.addr(),
+            },
+        );
+    }
+    // check() method
+    // return the access level granted to the session token if it has been
+    // issued and not revoked, and if 'bind' is set, is presented from the
+    // same client address
+    pub fn check(&self, sess: &SessionToken, bind: bool) -> Option<u8> {
+        self.check_at(sess, bind, now())
+    }
+    // check_at() method
+    // check the session token at the 'now' time
+    fn check_at(&self, sess: &SessionToken, bind: bool, now: u64) -> Option<u8> {
+        self.sessions
+            .get(&sess.token())
+            .filter(|s| {
+                s.user == sess.user() && s.expires > now && (!bind || s.addr == sess.addr())
+            })
+            .map(|s| s.access)
+    }
+    // expires() method
+    // return the expiry of an issued session token
+    pub fn expires(&self, sess: &SessionToken) -> Option<u64> {
+        self.sessions.get(&sess.token()).map(|s| s.expires)
+    }
+    // remove() method
+    // revoke a single session token
+    pub fn remove(&mut self, sess: &SessionToken) -> bool {
+        self.sessions.remove(&sess.token()).is_some()
+    }
+    // clear() method
+    // revoke all the session tokens, return the number of active sessions revoked
+    pub fn clear(&mut self) -> usize {
+        self.purge(now());
+        let count = self.sessions.len();
+        self.sessions.clear();
+        count
+    }
+    // purge() method
+    // remove the sessions expired at the 'now' time
+    fn purge(&mut self, now: u64) {
+        self.sessions.retain(|_, s| s.expires > now);
+    }
+}
+
+// now() function
+// return the current system time (in seconds since the Unix Epoch)
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // session() function
+    // return a session token of 'user', valid until 'expires'
+    fn session(user: &str, token: &str, expires: u64) -> SessionToken {
+        let mut sess = SessionToken::new();
+        sess.set_user(user.to_string());
+        sess.set_access(2);
+        sess.set_tssince(expires - 300);
+        sess.set_validfor(300);
+        sess.set_token(token.to_string());
+        sess.set_addr(Some("10.0.0.1".parse().unwrap()));
+        sess
+    }
+
+    #[test]
+    fn sessions_expire() {
+        let mut store = SessionStore::new();
+        let sess = session("admin", "a1", 1000);
+        store.insert(&sess);
+        assert_eq!(store.check_at(&sess, false, 999), Some(2));
+        assert_eq!(store.check_at(&sess, false, 1000), None);
+        assert_eq!(store.expires(&sess), Some(1000));
+
+        // a token is only valid for its user
+        let mut other = session("admin", "a1", 1000);
+        other.set_user("operator".to_string());
+        assert_eq!(store.check_at(&other, false, 999), None);
+    }
+
+    #[test]
+    fn sessions_are_bound_to_the_client_address() {
+        let mut store = SessionStore::new();
+        let sess = session("admin", "a1", 1000);
+        store.insert(&sess);
+        let mut moved = session("admin", "a1", 1000);
+        moved.set_addr(Some("10.0.0.2".parse().unwrap()));
+        assert_eq!(store.check_at(&moved, false, 999), Some(2));
+        assert_eq!(store.check_at(&moved, true, 999), None);
+        assert_eq!(store.check_at(&sess, true, 999), Some(2));
+    }
+
+    #[test]
+    fn logout_and_revoke_remove_the_sessions() {
+        let later = now() + 300;
+        let mut store = SessionStore::new();
+        let (a, b, c) = (
+            session("admin", "a1", later),
+            session("admin", "a2", later),
+            session("operator", "o1", later),
+        );
+        store.insert(&a);
+        store.insert(&b);
+        store.insert(&c);
+
+        // logout
+        assert!(store.remove(&a));
+        assert!(!store.remove(&a));
+        assert_eq!(store.check(&a, false), None);
+        assert_eq!(store.check(&b, false), Some(2));
+
+        // revoke all
+        assert_eq!(store.clear(), 2);
+        assert_eq!(store.check(&b, false), None);
+        assert_eq!(store.check(&c, false), None);
+    }
+
+    #[test]
+    fn expired_sessions_are_purged() {
+        let mut store = SessionStore::new();
+        store.insert(&session("admin", "a1", 1000));
+        store.insert(&session("admin", "a2", now() + 300));
+        // the first session expired before the second was inserted
+        assert_eq!(store.sessions.len(), 1);
+        assert!(store.sessions.contains_key("a2"));
+
+        store.purge(now() + 300);
+        assert!(store.sessions.is_empty());
+    }
+}
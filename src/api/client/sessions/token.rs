@@ -1,6 +1,7 @@
 //! Client API - session token module
 
 // std
+use std::net::IpAddr;
 use std::thread;
 use std::time::SystemTime;
 
@@ -25,8 +26,13 @@ pub struct SessionToken {
     ts_since: u64,
     ts_valid: u64,
     nonce: u64,
+    access: u8,
     token: String,
     secure: bool,
+    addr: Option<IpAddr>,
+    key: Option<String>,
+    identities: Vec<String>,
+    peer: Option<(u32, u32)>,
 }
 
 /// SessionToken implementation
@@ -38,10 +44,37 @@ impl SessionToken {
             ts_since: 0,
             ts_valid: 0,
             nonce: 0,
+            access: 0,
             token: "null".to_string(),
             secure: false,
+            addr: None,
+            key: None,
+            identities: Vec::new(),
+            peer: None,
         }
     }
+    // from_bearer() method
+    // build a session token from an HTTP bearer token, which is either
+    // a session token '<user>.<ts>.<nonce>.<access>.<token>',
+    // or an API key '<name>:<secret>'
+    pub fn from_bearer(bearer: &str) -> Option<SessionToken> {
+        let mut sess = SessionToken::new();
+        if let Some(i) = bearer.find(':') {
+            sess.user = bearer[..i].to_string();
+            sess.key = Some(bearer[i + 1..].to_string());
+            return Some(sess);
+        }
+        let fields: Vec<&str> = bearer.split('.').collect();
+        if fields.len() != 5 {
+            return None;
+        }
+        sess.user = fields[0].to_string();
+        sess.ts_since = fields[1].parse::<u64>().ok()?;
+        sess.nonce = fields[2].parse::<u64>().ok()?;
+        sess.access = fields[3].parse::<u8>().ok()?;
+        sess.token = fields[4].to_string();
+        Some(sess)
+    }
     // set_user() setter
     pub fn set_user(&mut self, user: String) {
         self.user = user;
@@ -59,10 +92,26 @@ impl SessionToken {
     pub fn set_nonce(&mut self, nonce: u64) {
         self.nonce = nonce;
     }
+    // set_access() setter
+    pub fn set_access(&mut self, access: u8) {
+        self.access = access;
+    }
     // set_token() setter
     pub fn set_token(&mut self, token: String) {
         self.token = token;
     }
+    // set_addr() setter
+    pub fn set_addr(&mut self, addr: Option<IpAddr>) {
+        self.addr = addr;
+    }
+    // set_identities() setter
+    pub fn set_identities(&mut self, identities: Vec<String>) {
+        self.identities = identities;
+    }
+    // set_peer() setter
+    pub fn set_peer(&mut self, peer: Option<(u32, u32)>) {
+        self.peer = peer;
+    }
     // gen_token() method
     pub fn gen_token(&mut self, cfg: &config::CConfig) -> std::io::Result<()> {
         // get current system time (in seconds since the Unix Epoch)
@@ -78,11 +127,9 @@ impl SessionToken {
         let nonce: u64 = rng.gen();
         // set the nonce
         self.nonce = nonce;
-        // concatenate the user and time with the nonce
-        let utn = format!("{}{}{}", self.user, self.ts_since, self.nonce);
-        // hash the above elements
+        // hash the user and time with the nonce and access level
         let secret = cfg.api.as_ref().unwrap().secret();
-        let token = gen_hmac_string(&utn, secret);
+        let token = gen_hmac_string(&self.hmac_input(), secret);
         // set the hashed token
         self.token = token;
         // set the token's 'secure' flag if tls is enabled
@@ -100,10 +147,18 @@ impl SessionToken {
     pub fn ts_since(&self) -> String {
         format!("{}", self.ts_since)
     }
+    // ts_valid() method
+    pub fn ts_valid(&self) -> u64 {
+        self.ts_valid
+    }
     // nonce() method
     pub fn nonce(&self) -> String {
         format!("{}", self.nonce)
     }
+    // access() method
+    pub fn access(&self) -> u8 {
+        self.access
+    }
     // token() method
     pub fn token(&self) -> String {
         self.token.clone()
@@ -112,14 +167,57 @@ impl SessionToken {
     pub fn secure(&self) -> bool {
         self.secure
     }
+    // addr() method
+    pub fn addr(&self) -> Option<IpAddr> {
+        self.addr
+    }
+    // key() method
+    // return the API key secret, if the client authenticates with an API key
+    pub fn key(&self) -> Option<&str> {
+        self.key.as_ref().map(|k| k.as_str())
+    }
+    // identities() method
+    // return the identities of the client certificate, if the client authenticates with it
+    pub fn identities(&self) -> &[String] {
+        &self.identities
+    }
+    // peer() method
+    // return the uid and gid of the Unix socket peer, if the client connects through it
+    pub fn peer(&self) -> Option<(u32, u32)> {
+        self.peer
+    }
+    // bearer() method
+    // return the session token in its HTTP bearer form
+    pub fn bearer(&self) -> String {
+        format!(
+            "{}.{}.{}.{}.{}",
+            self.user, self.ts_since, self.nonce, self.access, self.token
+        )
+    }
+    // hmac_input() method
+    // return the authenticated fields separated by dots, the last three fields
+    // being numbers, a field cannot be shifted into its neighbour
+    fn hmac_input(&self) -> String {
+        format!(
+            "{}.{}.{}.{}",
+            self.user, self.ts_since, self.nonce, self.access
+        )
+    }
     // validate() method
-    // check the integrity of the token
+    // check the integrity and the lifetime of the token
     pub fn validate(&self, cfg: &config::CConfig) -> Option<String> {
-        // concatenate the user and time with the nonce
-        let utn = format!("{}{}{}", self.user, self.ts_since, self.nonce);
-        // hash the above elements
+        // the token expires 'session_timeout' seconds after its issuance
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let timeout = cfg.api.as_ref().unwrap().session_timeout();
+        if now >= self.ts_since.saturating_add(timeout) {
+            return None;
+        }
+        // hash the user and time with the nonce and access level
         let secret = cfg.api.as_ref().unwrap().secret();
-        let token = gen_hmac_string(&utn, secret);
+        let token = gen_hmac_string(&self.hmac_input(), secret);
         // compare the stored (or passed) hash with the recomputed hash/token above
         // make sure the comparison time is randomized or constant to avoid timing attacks
         let mut rng = rand::thread_rng();
@@ -139,7 +237,7 @@ impl SessionToken {
 type HmacSha3_256 = Hmac<Sha3_256>;
 
 // gen_hmac_string() function
-fn gen_hmac_string(input: &String, secret: String) -> String {
+pub fn gen_hmac_string(input: &String, secret: String) -> String {
     let mut mac = HmacSha3_256::new_varkey(secret.as_bytes()).expect("invalid key length");
     mac.input(input.as_bytes());
     let res = mac.result();
@@ -147,3 +245,53 @@ fn gen_hmac_string(input: &String, secret: String) -> String {
 
     format!("{:x}", out)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hmac_input_fields_cannot_shift() {
+        let mut a = SessionToken::new();
+        a.set_user("admin".to_string());
+        a.set_tssince(1580000000);
+        a.set_nonce(15);
+        a.set_access(0);
+        // the last digit of the nonce moved in front of the access level
+        let mut b = SessionToken::new();
+        b.set_user("admin".to_string());
+        b.set_tssince(1580000000);
+        b.set_nonce(1);
+        b.set_access(50);
+        assert_ne!(a.hmac_input(), b.hmac_input());
+        assert_ne!(
+            gen_hmac_string(&a.hmac_input(), "secret".to_string()),
+            gen_hmac_string(&b.hmac_input(), "secret".to_string())
+        );
+    }
+
+    #[test]
+    fn tokens_expire_after_the_session_timeout() {
+        let cfg: config::CConfig =
+            toml::from_str("[api]\nsecret = \"secret\"\nsession_timeout = 300").unwrap();
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let token = |since: u64| {
+            let mut sess = SessionToken::new();
+            sess.set_user("admin".to_string());
+            sess.set_tssince(since);
+            sess.set_access(2);
+            sess.set_token(gen_hmac_string(&sess.hmac_input(), "secret".to_string()));
+            sess
+        };
+        assert!(token(now - 10).validate(&cfg).is_some());
+        assert!(token(now - 300).validate(&cfg).is_none());
+
+        // the token is authenticated with the secret
+        let mut forged = token(now);
+        forged.set_access(3);
+        assert!(forged.validate(&cfg).is_none());
+    }
+}
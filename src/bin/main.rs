@@ -32,10 +32,11 @@ fn print_usage(program: &str, opts: Options) {
     Modes:
     0 = VRRPv2 Sniffer
     1 = VRRPv2 Virtual Router (foreground)
-    2 = VRRPv2 Virtual Router (daemon)\
+    2 = VRRPv2 Virtual Router (daemon)
+    3 = VRRPv2 Virtual Router (observer, foreground)\
     "
     );
-    let usage = format!("Usage: {} -m0|1|2 [options]\n\n{}", program, modes);
+    let usage = format!("Usage: {} -m0|1|2|3 [options]\n\n{}", program, modes);
     print!("{}", opts.usage(&usage));
 }
 
@@ -54,7 +55,7 @@ fn parse_cli_opts(args: &[String]) -> Result<Config, Box<dyn Error>> {
     opts.optopt(
         "m",
         "mode",
-        "operation modes (see Modes):\n 0(sniffer), 1(foreground), 2(daemon)",
+        "operation modes (see Modes):\n 0(sniffer), 1(foreground), 2(daemon), 3(observer)",
         "MODE",
     );
     opts.optopt(
@@ -75,6 +76,12 @@ fn parse_cli_opts(args: &[String]) -> Result<Config, Box<dyn Error>> {
         "configuration format: toml(default), json",
         "FORMAT",
     );
+    opts.optmulti(
+        "M",
+        "maintenance",
+        "start virtual router(s) in maintenance mode:\n all, or <group>/<interface>",
+        "VR",
+    );
 
     let matches = match opts.parse(&args[1..]) {
         Ok(m) => m,
@@ -129,7 +136,17 @@ fn parse_cli_opts(args: &[String]) -> Result<Config, Box<dyn Error>> {
         None => Option::None,
     };
 
-    Ok(Config::new(iface, mode, conf, debug, cfg_format))
+    // maintenance command-line option(s)
+    let maintenance = matches.opt_strs("maintenance");
+
+    Ok(Config::new(
+        iface,
+        mode,
+        conf,
+        debug,
+        cfg_format,
+        maintenance,
+    ))
 }
 
 // run() function
@@ -0,0 +1,1256 @@
+//! # rvrrpctl
+//!
+//! `rvrrpctl` is the command-line client of the rVRRPd Client API.
+extern crate rVRRPd;
+use rVRRPd::{
+    encode_config, CConfig, CfgType, RequestMaintenance, RequestVRRPAttr, ResponsePeerAttr,
+    ResponseProtoStaticAttr, ResponseVRRPAttr, ResponseVRRPAttrExt,
+};
+
+// getopts
+use getopts::Options;
+
+// std
+use std::env;
+use std::error::Error;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, TcpStream};
+use std::os::unix::io::AsRawFd;
+use std::os::unix::net::UnixStream;
+
+// nix
+use nix::sys::termios::{tcgetattr, tcsetattr, LocalFlags, SetArg};
+
+// openssl
+use openssl::ssl::{SslConnector, SslFiletype, SslMethod, SslStream, SslVerifyMode};
+
+// serde
+use serde::de::DeserializeOwned;
+use serde_derive::Deserialize;
+use serde_json::Value;
+
+// chrono
+use chrono::{Local, TimeZone};
+
+// default Client API URL
+const RVRRPCTL_DFLT_URL: &str = "http://127.0.0.1:7080";
+
+/// MyError Type
+#[derive(Debug)]
+struct MyError(String);
+
+impl std::fmt::Display for MyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+impl Error for MyError {}
+
+// error() function
+fn error<T>(msg: String) -> Result<T, Box<dyn Error>> {
+    Err(Box::new(MyError(msg)))
+}
+
+// read_password() function
+// prompt for a password on the terminal, without echoing it
+fn read_password(user: &str) -> io::Result<String> {
+    let stdin = io::stdin();
+    let fd = stdin.as_raw_fd();
+    eprint!("Password for {}: ", user);
+    io::stderr().flush()?;
+
+    // disable the echo while the password is typed (if stdin is a terminal)
+    let saved = tcgetattr(fd).ok();
+    if let Some(t) = &saved {
+        let mut noecho = t.clone();
+        noecho.local_flags.remove(LocalFlags::ECHO);
+        tcsetattr(fd, SetArg::TCSANOW, &noecho)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+    }
+    let mut line = String::new();
+    let read = stdin.lock().read_line(&mut line);
+    if let Some(t) = &saved {
+        tcsetattr(fd, SetArg::TCSANOW, t).unwrap_or(());
+        eprintln!();
+    }
+    read?;
+
+    Ok(line.trim_end_matches(&['\r', '\n'][..]).to_string())
+}
+
+/// Conn enumerator
+/// a connection to the Client API
+enum Conn {
+    Tcp(TcpStream),
+    Tls(SslStream<TcpStream>),
+    Unix(UnixStream),
+}
+
+impl Read for Conn {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Conn::Tcp(s) => s.read(buf),
+            Conn::Tls(s) => s.read(buf),
+            Conn::Unix(s) => s.read(buf),
+        }
+    }
+}
+
+impl Write for Conn {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Conn::Tcp(s) => s.write(buf),
+            Conn::Tls(s) => s.write(buf),
+            Conn::Unix(s) => s.write(buf),
+        }
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Conn::Tcp(s) => s.flush(),
+            Conn::Tls(s) => s.flush(),
+            Conn::Unix(s) => s.flush(),
+        }
+    }
+}
+
+/// Response structure
+/// an HTTP response, with its body left on the connection
+struct Response {
+    status: u16,
+    chunked: bool,
+    length: Option<usize>,
+    reader: BufReader<Conn>,
+}
+
+// Response implementation
+impl Response {
+    // body() method
+    // read the whole body
+    fn body(mut self) -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut body = Vec::new();
+        if self.chunked {
+            while let Some(chunk) = self.chunk()? {
+                body.extend(chunk);
+            }
+        } else if let Some(len) = self.length {
+            body.resize(len, 0);
+            self.reader.read_exact(&mut body)?;
+        } else {
+            self.reader.read_to_end(&mut body)?;
+        }
+        Ok(body)
+    }
+    // chunk() method
+    // read the next chunk of a chunked body, None after the last one
+    fn chunk(&mut self) -> Result<Option<Vec<u8>>, Box<dyn Error>> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let size = line.trim().split(';').next().unwrap_or("");
+        let size = match usize::from_str_radix(size, 16) {
+            Ok(s) => s,
+            Err(_) => return error(format!("malformed chunk size '{}'", line.trim())),
+        };
+        if size == 0 {
+            return Ok(None);
+        }
+        let mut chunk = vec![0; size + 2];
+        self.reader.read_exact(&mut chunk)?;
+        chunk.truncate(size);
+        Ok(Some(chunk))
+    }
+    // json() method
+    // decode the JSON body, or return the API error
+    fn json<T: DeserializeOwned>(self) -> Result<T, Box<dyn Error>> {
+        let status = self.status;
+        let body = self.body()?;
+        if status >= 300 {
+            return error(status_error(status, &body));
+        }
+        Ok(serde_json::from_slice(&body)?)
+    }
+}
+
+// status_error() function
+// return the error message of an API response
+fn status_error(status: u16, body: &[u8]) -> String {
+    #[derive(Deserialize)]
+    struct ResponseError {
+        error: String,
+    }
+    match status {
+        401 => "authentication failed or required".to_string(),
+        403 => "insufficient access level".to_string(),
+        404 => "not found".to_string(),
+        429 => "too many requests, try again later".to_string(),
+        _ => match serde_json::from_slice::<ResponseError>(body) {
+            Ok(e) => e.error,
+            Err(_) => format!("the API returned HTTP status {}", status),
+        },
+    }
+}
+
+// parse_url() function
+// return whether TLS is used, and the host and port of a Client API URL
+fn parse_url(url: &str) -> Result<(bool, String, u16), Box<dyn Error>> {
+    let (tls, rest) = if url.starts_with("https://") {
+        (true, &url[8..])
+    } else if url.starts_with("http://") {
+        (false, &url[7..])
+    } else {
+        return error(format!("unsupported URL '{}'", url));
+    };
+    let rest = rest.trim_end_matches('/');
+    let (host, port) = match rest.rfind(':') {
+        Some(i) if !rest.ends_with(']') => match rest[i + 1..].parse::<u16>() {
+            Ok(p) => (rest[..i].to_string(), p),
+            Err(_) => return error(format!("invalid port in URL '{}'", url)),
+        },
+        _ => (rest.to_string(), if tls { 443 } else { 80 }),
+    };
+    Ok((tls, host, port))
+}
+
+// request_head() function
+// return the request line and headers of an HTTP request
+fn request_head(
+    method: &str,
+    path: &str,
+    host: &str,
+    token: Option<&str>,
+    body: Option<(&str, usize)>,
+) -> String {
+    let mut req = format!(
+        "{} {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n",
+        method, path, host
+    );
+    if let Some(t) = token {
+        req.push_str(&format!("Authorization: Bearer {}\r\n", t));
+    }
+    let len = match body {
+        Some((mime, len)) => {
+            req.push_str(&format!("Content-Type: {}\r\n", mime));
+            len
+        }
+        None => 0,
+    };
+    req.push_str(&format!("Content-Length: {}\r\n\r\n", len));
+    req
+}
+
+/// Client structure
+struct Client {
+    tls: bool,
+    host: String,
+    port: u16,
+    socket: Option<String>,
+    connector: Option<SslConnector>,
+    insecure: bool,
+    token: Option<String>,
+}
+
+// Client implementation
+impl Client {
+    // new() method
+    fn new(
+        url: &str,
+        socket: Option<String>,
+        insecure: bool,
+        cert: Option<String>,
+        key: Option<String>,
+    ) -> Result<Client, Box<dyn Error>> {
+        let (tls, host, port) = parse_url(url)?;
+        let connector = if tls {
+            let mut builder = SslConnector::builder(SslMethod::tls())?;
+            if insecure {
+                builder.set_verify(SslVerifyMode::NONE);
+            }
+            if let Some(c) = cert {
+                builder.set_certificate_chain_file(c)?;
+            }
+            if let Some(k) = key {
+                builder.set_private_key_file(k, SslFiletype::PEM)?;
+            }
+            Some(builder.build())
+        } else {
+            None
+        };
+        Ok(Client {
+            tls,
+            host,
+            port,
+            socket,
+            connector,
+            insecure,
+            token: None,
+        })
+    }
+    // connect() method
+    fn connect(&self) -> Result<Conn, Box<dyn Error>> {
+        if let Some(path) = &self.socket {
+            return Ok(Conn::Unix(UnixStream::connect(path)?));
+        }
+        let host = self.host.trim_start_matches('[').trim_end_matches(']');
+        let tcp = TcpStream::connect((host, self.port))?;
+        match &self.connector {
+            Some(c) if self.tls => {
+                let stream = c
+                    .configure()?
+                    .verify_hostname(!self.insecure)
+                    .connect(host, tcp)
+                    .map_err(|e| MyError(format!("TLS error: {}", e)))?;
+                Ok(Conn::Tls(stream))
+            }
+            _ => Ok(Conn::Tcp(tcp)),
+        }
+    }
+    // request() method
+    // send an HTTP request, and read the response headers
+    fn request(
+        &self,
+        method: &str,
+        path: &str,
+        body: Option<(&str, Vec<u8>)>,
+    ) -> Result<Response, Box<dyn Error>> {
+        let mut conn = self.connect()?;
+        let req = request_head(
+            method,
+            path,
+            &self.host,
+            self.token.as_deref(),
+            body.as_ref().map(|(mime, b)| (*mime, b.len())),
+        );
+        let body = body.map(|(_, b)| b).unwrap_or_default();
+        conn.write_all(req.as_bytes())?;
+        conn.write_all(&body)?;
+        conn.flush()?;
+
+        // status line and headers
+        let mut reader = BufReader::new(conn);
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        let status = match line.split_whitespace().nth(1).map(|s| s.parse::<u16>()) {
+            Some(Ok(s)) => s,
+            _ => return error(format!("malformed HTTP response '{}'", line.trim())),
+        };
+        let mut chunked = false;
+        let mut length = None;
+        loop {
+            line.clear();
+            if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+                break;
+            }
+            let mut it = line.splitn(2, ':');
+            let name = it.next().unwrap_or("").trim().to_ascii_lowercase();
+            let value = it.next().unwrap_or("").trim().to_ascii_lowercase();
+            match name.as_str() {
+                "transfer-encoding" => chunked = value.contains("chunked"),
+                "content-length" => length = value.parse::<usize>().ok(),
+                _ => {}
+            }
+        }
+        Ok(Response {
+            status,
+            chunked,
+            length,
+            reader,
+        })
+    }
+    // get() method
+    fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, Box<dyn Error>> {
+        self.request("GET", path, None)?.json()
+    }
+    // send() method
+    // send a JSON body
+    fn send<B: serde::Serialize, T: DeserializeOwned>(
+        &self,
+        method: &str,
+        path: &str,
+        body: &B,
+    ) -> Result<T, Box<dyn Error>> {
+        let body = serde_json::to_vec(body)?;
+        self.request(method, path, Some(("application/json", body)))?
+            .json()
+    }
+    // login() method
+    // authenticate with a user and password, and keep the session token
+    fn login(&mut self, user: &str, passwd: &str) -> Result<(), Box<dyn Error>> {
+        #[derive(Deserialize)]
+        struct ResponseToken {
+            token: String,
+        }
+        let body = format!("user={} passwd={}", user, passwd).into_bytes();
+        let resp: ResponseToken = self
+            .request("POST", "/auth", Some(("text/plain", body)))?
+            .json()?;
+        self.token = Some(resp.token);
+        Ok(())
+    }
+}
+
+// print_usage() function
+fn print_usage(program: &str, opts: Options) {
+    let commands = format!(
+        "\
+    Commands:
+    show vrrp [brief|detail]                   show the virtual routers
+    show vrrp peers [<group> <interface>]      show the routers seen advertising
+    show vrrp history [<group> <interface>]    show the state transitions history
+    show config                                show the configuration
+    show routes                                show the static routes
+    set priority <group> <interface> <prio>    change the priority of a virtual router
+    maintenance on|off [<group> <interface>]   enter or leave maintenance mode
+    reload                                     read the configuration file again and apply it
+    events [--follow] [<group> [<interface>]]  show the next event (or all of them)\
+    "
+    );
+    let usage = format!("Usage: {} [options] <command>\n\n{}", program, commands);
+    print!("{}", opts.usage(&usage));
+}
+
+// run() function
+fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let program = args[0].clone();
+    let mut opts = Options::new();
+
+    opts.optflag("h", "help", "display help information");
+    opts.optopt(
+        "u",
+        "url",
+        "Client API URL:\n (default to http://127.0.0.1:7080)",
+        "URL",
+    );
+    opts.optopt("s", "socket", "Client API Unix domain socket", "PATH");
+    opts.optopt(
+        "U",
+        "user",
+        "authenticate as USER, with the password of\n $RVRRPCTL_PASSWORD (or prompted for)",
+        "USER",
+    );
+    opts.optopt(
+        "t",
+        "token",
+        "session token or API key ('<name>:<secret>'):\n (default to $RVRRPCTL_TOKEN)",
+        "TOKEN",
+    );
+    opts.optflag("k", "insecure", "do not verify the server certificate");
+    opts.optopt("", "cert", "client certificate (PEM)", "FILE");
+    opts.optopt("", "key", "client certificate key (PEM)", "FILE");
+    opts.optflag("j", "json", "print the API responses in JSON");
+    opts.optflag("f", "follow", "keep printing the events");
+
+    let matches = match opts.parse(&args[1..]) {
+        Ok(m) => m,
+        Err(f) => return error(f.to_string()),
+    };
+
+    // help command-line option
+    if matches.opt_present("help") || matches.free.is_empty() {
+        print_usage(&program, opts);
+        std::process::exit(1);
+    }
+
+    // check the command before connecting
+    let free: Vec<&str> = matches.free.iter().map(|s| s.as_str()).collect();
+    let command = parse_command(&free)?;
+
+    let url = matches
+        .opt_str("url")
+        .unwrap_or_else(|| RVRRPCTL_DFLT_URL.to_string());
+    let mut client = Client::new(
+        &url,
+        matches.opt_str("socket"),
+        matches.opt_present("insecure"),
+        matches.opt_str("cert"),
+        matches.opt_str("key"),
+    )?;
+
+    // authentication
+    if let Some(user) = matches.opt_str("user") {
+        // the password is never read from the command line, where it is
+        // visible to the other users of the host
+        let passwd = match env::var("RVRRPCTL_PASSWORD") {
+            Ok(p) => p,
+            Err(_) => read_password(&user)?,
+        };
+        client.login(&user, &passwd)?;
+    } else {
+        client.token = matches
+            .opt_str("token")
+            .or_else(|| env::var("RVRRPCTL_TOKEN").ok());
+    }
+
+    let json = matches.opt_present("json");
+    match command {
+        Command::ShowVrrp(true) => show_vrrp_detail(&client, json),
+        Command::ShowVrrp(false) => {
+            let vrs: Vec<ResponseVRRPAttr> = client.get("/run/vrrp")?;
+            print_vrrp_brief(&vrs, json)
+        }
+        Command::ShowPeers(vr) => show_vrrp_peers(&client, vr, json),
+        Command::ShowHistory(vr) => show_vrrp_history(&client, vr, json),
+        Command::ShowConfig => show_config(&client, json),
+        Command::ShowRoutes => {
+            let routes: Option<Vec<ResponseProtoStaticAttr>> =
+                client.get("/run/protocols/static")?;
+            print_routes(&routes.unwrap_or_default(), json)
+        }
+        Command::SetPriority(group, intf, prio) => {
+            let req = RequestVRRPAttr {
+                priority: Some(prio),
+                preempt: None,
+                advert_interval: None,
+                auth_type: None,
+                auth_secret: None,
+                maintenance: None,
+            };
+            let vr: ResponseVRRPAttrExt = client.send("PUT", &vrrp_path(group, &intf), &req)?;
+            print_vrrp_detail(&[vr], json)
+        }
+        Command::Maintenance(state, None) => {
+            let req = RequestMaintenance { maintenance: state };
+            let vrs: Vec<ResponseVRRPAttr> = client.send("PUT", "/run/vrrp/maintenance", &req)?;
+            print_vrrp_brief(&vrs, json)
+        }
+        Command::Maintenance(state, Some((group, intf))) => {
+            let req = RequestVRRPAttr {
+                priority: None,
+                preempt: None,
+                advert_interval: None,
+                auth_type: None,
+                auth_secret: None,
+                maintenance: Some(state),
+            };
+            let vr: ResponseVRRPAttrExt = client.send("PUT", &vrrp_path(group, &intf), &req)?;
+            print_vrrp_detail(&[vr], json)
+        }
+        Command::Reload => {
+            let resp = client.request("POST", "/config/reload", None)?;
+            let cfg: Value = resp.json()?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&cfg)?);
+            } else {
+                println!("Configuration reloaded and applied to the virtual routers");
+            }
+            Ok(())
+        }
+        Command::Events(path) => follow_events(&client, &path, matches.opt_present("follow"), json),
+    }
+}
+
+/// Command enumerator
+/// a command of the command line, with its checked arguments
+#[derive(Debug, PartialEq)]
+enum Command {
+    ShowVrrp(bool), // in detail
+    ShowPeers(Option<(u8, String)>),
+    ShowHistory(Option<(u8, String)>),
+    ShowConfig,
+    ShowRoutes,
+    SetPriority(u8, String, u8),
+    Maintenance(bool, Option<(u8, String)>),
+    Reload,
+    Events(String), // path of the events stream
+}
+
+// parse_command() function
+// return the command of the free command-line arguments
+fn parse_command(free: &[&str]) -> Result<Command, Box<dyn Error>> {
+    let vr = |group: &str, intf: &str| -> Result<(u8, String), Box<dyn Error>> {
+        Ok((parse_arg::<u8>(group, "group")?, intf.to_string()))
+    };
+    match free {
+        ["show", "vrrp"] | ["show", "vrrp", "detail"] => Ok(Command::ShowVrrp(true)),
+        ["show", "vrrp", "brief"] => Ok(Command::ShowVrrp(false)),
+        ["show", "vrrp", "peers"] => Ok(Command::ShowPeers(None)),
+        ["show", "vrrp", "peers", group, intf] => Ok(Command::ShowPeers(Some(vr(group, intf)?))),
+        ["show", "vrrp", "history"] => Ok(Command::ShowHistory(None)),
+        ["show", "vrrp", "history", group, intf] => {
+            Ok(Command::ShowHistory(Some(vr(group, intf)?)))
+        }
+        ["show", "config"] => Ok(Command::ShowConfig),
+        ["show", "routes"] => Ok(Command::ShowRoutes),
+        ["set", "priority", group, intf, prio] => {
+            let (group, intf) = vr(group, intf)?;
+            Ok(Command::SetPriority(
+                group,
+                intf,
+                parse_arg(prio, "priority")?,
+            ))
+        }
+        ["maintenance", state] => Ok(Command::Maintenance(parse_state(state)?, None)),
+        ["maintenance", state, group, intf] => Ok(Command::Maintenance(
+            parse_state(state)?,
+            Some(vr(group, intf)?),
+        )),
+        ["reload"] => Ok(Command::Reload),
+        ["events", filter @ ..] if filter.len() <= 2 => {
+            let group = match filter.get(0) {
+                Some(g) => Some(parse_arg::<u8>(g, "group")?),
+                None => None,
+            };
+            Ok(Command::Events(events_path(group, filter.get(1).copied())))
+        }
+        _ => error(format!("Unknown command '{}'", free.join(" "))),
+    }
+}
+
+// vrrp_path() function
+// return the path of a virtual router
+fn vrrp_path(group: u8, intf: &str) -> String {
+    format!("/run/vrrp/{}/{}", group, intf)
+}
+
+// events_path() function
+// return the path of the events stream, filtered by group and interface
+fn events_path(group: Option<u8>, intf: Option<&str>) -> String {
+    let mut path = "/events".to_string();
+    if let Some(g) = group {
+        path.push_str(&format!("?group={}", g));
+        if let Some(i) = intf {
+            path.push_str(&format!("&interface={}", i));
+        }
+    }
+    path
+}
+
+// parse_arg() function
+fn parse_arg<T: std::str::FromStr>(s: &str, name: &str) -> Result<T, Box<dyn Error>> {
+    match s.parse::<T>() {
+        Ok(v) => Ok(v),
+        Err(_) => error(format!("Invalid {} '{}'", name, s)),
+    }
+}
+
+// parse_state() function
+fn parse_state(s: &str) -> Result<bool, Box<dyn Error>> {
+    match s {
+        "on" => Ok(true),
+        "off" => Ok(false),
+        _ => error(format!("Invalid maintenance state '{}' (on|off)", s)),
+    }
+}
+
+// print_json() function
+fn print_json<T: serde::Serialize>(v: &T) -> Result<(), Box<dyn Error>> {
+    println!("{}", serde_json::to_string_pretty(v)?);
+    Ok(())
+}
+
+// print_vrrp_brief() function
+fn print_vrrp_brief(vrs: &[ResponseVRRPAttr], json: bool) -> Result<(), Box<dyn Error>> {
+    if json {
+        return print_json(&vrs);
+    }
+    print!("{}", format_vrrp_brief(vrs));
+    Ok(())
+}
+
+// format_vrrp_brief() function
+// format the virtual routers as Cisco's 'show vrrp brief'
+fn format_vrrp_brief(vrs: &[ResponseVRRPAttr]) -> String {
+    let mut out = format!(
+        "{:<18} {:<3} {:<3} {:<3} {:<3} {:<7} {}\n",
+        "Interface", "Grp", "Pri", "Pre", "Mnt", "State", "Group addr"
+    );
+    for vr in vrs {
+        out.push_str(&format!(
+            "{:<18} {:<3} {:<3} {:<3} {:<3} {:<7} {}\n",
+            vr.interface,
+            vr.group,
+            vr.priority,
+            yes_no(vr.preempt),
+            yes_no(vr.maintenance),
+            vr.state,
+            vr.virtual_ip
+        ));
+    }
+    out
+}
+
+// show_vrrp_detail() function
+fn show_vrrp_detail(client: &Client, json: bool) -> Result<(), Box<dyn Error>> {
+    let vrs: Vec<ResponseVRRPAttr> = client.get("/run/vrrp")?;
+    let mut details = Vec::new();
+    for vr in vrs {
+        let path = vrrp_path(vr.group, &vr.interface);
+        details.push(client.get::<ResponseVRRPAttrExt>(&path)?);
+    }
+    print_vrrp_detail(&details, json)
+}
+
+// print_vrrp_detail() function
+fn print_vrrp_detail(vrs: &[ResponseVRRPAttrExt], json: bool) -> Result<(), Box<dyn Error>> {
+    if json {
+        return print_json(&vrs);
+    }
+    print!("{}", format_vrrp_detail(vrs));
+    Ok(())
+}
+
+// format_vrrp_detail() function
+// format the virtual routers as Cisco's 'show vrrp'
+fn format_vrrp_detail(vrs: &[ResponseVRRPAttrExt]) -> String {
+    let mut out = String::new();
+    for vr in vrs {
+        out.push_str(&format!("{} - Group {}\n", vr.interface, vr.group));
+        out.push_str(&format!("  State is {}\n", vr.state));
+        out.push_str(&format!("  Virtual IP address is {}\n", vr.virtual_ip));
+        out.push_str(&format!("  Virtual interface is {}\n", vr.vif));
+        out.push_str(&format!(
+            "  Interface MAC address is {}\n",
+            vr.interface_hwaddress
+        ));
+        out.push_str(&format!(
+            "  Advertisement interval is {:.3} sec\n",
+            vr.advert_interval as f32
+        ));
+        if vr.preempt {
+            out.push_str("  Preemption enabled\n");
+        } else {
+            out.push_str("  Preemption disabled\n");
+        }
+        out.push_str(&format!("  Priority is {}\n", vr.priority));
+        if vr.maintenance {
+            out.push_str("  Maintenance mode enabled\n");
+        }
+        match vr.auth_type {
+            0 => out.push_str("  Authentication disabled\n"),
+            1 => out.push_str("  Authentication text\n"),
+            t => out.push_str(&format!("  Authentication type {}\n", t)),
+        }
+        out.push_str(&format!(
+            "  Master Down interval is {:.3} sec\n",
+            vr.masterdown_interval
+        ));
+        out.push_str(&format!("  Skew time is {:.3} sec\n\n", vr.skew_time));
+    }
+    out
+}
+
+// select_vrs() function
+// return the given virtual router, or all the running ones
+fn select_vrs(
+    client: &Client,
+    vr: Option<(u8, String)>,
+) -> Result<Vec<(u8, String)>, Box<dyn Error>> {
+    match vr {
+        Some(v) => Ok(vec![v]),
+        None => Ok(client
+            .get::<Vec<ResponseVRRPAttr>>("/run/vrrp")?
+            .into_iter()
+            .map(|v| (v.group, v.interface))
+            .collect()),
+    }
+}
+
+// show_vrrp_peers() function
+// print the routers seen advertising, for one or all the virtual routers
+fn show_vrrp_peers(
+    client: &Client,
+    vr: Option<(u8, String)>,
+    json: bool,
+) -> Result<(), Box<dyn Error>> {
+    let mut peers = Vec::new();
+    for (group, intf) in select_vrs(client, vr)? {
+        let path = format!("{}/peers", vrrp_path(group, &intf));
+        let p: Vec<ResponsePeerAttr> = client.get(&path)?;
+        peers.push((group, intf, p));
+    }
+    if json {
+        let v: Vec<Value> = peers
+            .iter()
+            .map(|(g, i, p)| serde_json::json!({"group": g, "interface": i, "peers": p}))
+            .collect();
+        return print_json(&v);
+    }
+    for (group, intf, p) in peers {
+        print!("{}", format_peers(group, &intf, &p));
+    }
+    Ok(())
+}
+
+// format_peers() function
+// format the routers seen advertising for a virtual router
+fn format_peers(group: u8, intf: &str, peers: &[ResponsePeerAttr]) -> String {
+    let mut out = format!("{} - Group {}\n", intf, group);
+    out.push_str(&format!(
+        "  {:<15} {:<17} {:<3} {:<3} {:<4} {:<6} {}\n",
+        "Peer address", "MAC address", "Pri", "Adv", "Auth", "Master", "Last seen"
+    ));
+    for peer in peers {
+        out.push_str(&format!(
+            "  {:<15} {:<17} {:<3} {:<3} {:<4} {:<6} {}\n",
+            peer.source_ip,
+            peer.source_mac,
+            peer.priority,
+            peer.advert_interval,
+            peer.auth_type,
+            yes_no(peer.master),
+            format_time(peer.last_seen as i64)
+        ));
+        if !peer.addresses.is_empty() {
+            out.push_str(&format!("    Addresses {}\n", peer.addresses.join(", ")));
+        }
+    }
+    out.push('\n');
+    out
+}
+
+// show_vrrp_history() function
+// print the state transitions history, for one or all the virtual routers
+fn show_vrrp_history(
+    client: &Client,
+    vr: Option<(u8, String)>,
+    json: bool,
+) -> Result<(), Box<dyn Error>> {
+    let mut histories = Vec::new();
+    for (group, intf) in select_vrs(client, vr)? {
+        let path = format!("{}/history", vrrp_path(group, &intf));
+        let h: Vec<Value> = client.get(&path)?;
+        histories.push((group, intf, h));
+    }
+    if json {
+        let v: Vec<Value> = histories
+            .iter()
+            .map(|(g, i, h)| serde_json::json!({"group": g, "interface": i, "transitions": h}))
+            .collect();
+        return print_json(&v);
+    }
+    for (group, intf, h) in histories {
+        print!("{}", format_history(group, &intf, &h));
+    }
+    Ok(())
+}
+
+// format_history() function
+// format the state transitions history of a virtual router
+fn format_history(group: u8, intf: &str, transitions: &[Value]) -> String {
+    let text = |v: &Value, k: &str| match v.get(k) {
+        Some(Value::String(s)) => s.clone(),
+        Some(v) => v.to_string(),
+        None => "-".to_string(),
+    };
+    let mut out = format!("{} - Group {}\n", intf, group);
+    for t in transitions {
+        let ts = t.get("timestamp").and_then(|t| t.as_i64()).unwrap_or(0);
+        let trigger = t.get("trigger").cloned().unwrap_or(Value::Null);
+        let mut reason = text(&trigger, "event");
+        if reason == "advert" {
+            reason = format!(
+                "advert from {} priority {}",
+                text(&trigger, "source"),
+                text(&trigger, "priority")
+            );
+        }
+        out.push_str(&format!(
+            "  {}  {} -> {} ({})\n",
+            format_time(ts),
+            text(t, "from"),
+            text(t, "to"),
+            reason
+        ));
+        let empty = Vec::new();
+        let ops = t
+            .get("operations")
+            .and_then(|o| o.as_array())
+            .unwrap_or(&empty);
+        for op in ops {
+            let dry_run = op.get("dry_run").and_then(|d| d.as_bool());
+            let result = match op.get("success").and_then(|s| s.as_bool()) {
+                Some(true) if dry_run == Some(true) => {
+                    format!("skipped (dry-run): {}", text(op, "detail"))
+                }
+                Some(true) => "ok".to_string(),
+                _ => format!("failed: {}", text(op, "error")),
+            };
+            out.push_str(&format!(
+                "      {} {} {}\n",
+                text(op, "op"),
+                text(op, "action"),
+                result
+            ));
+        }
+    }
+    out.push('\n');
+    out
+}
+
+// show_config() function
+// print the configuration in the TOML format
+fn show_config(client: &Client, json: bool) -> Result<(), Box<dyn Error>> {
+    let cfg: CConfig = client.get("/config/global")?;
+    if json {
+        return print_json(&cfg);
+    }
+    match encode_config(&cfg, CfgType::Toml) {
+        Ok(s) => print!("{}", s),
+        Err(e) => return error(e),
+    }
+    Ok(())
+}
+
+// print_routes() function
+fn print_routes(routes: &[ResponseProtoStaticAttr], json: bool) -> Result<(), Box<dyn Error>> {
+    if json {
+        return print_json(&routes);
+    }
+    print!("{}", format_routes(routes));
+    Ok(())
+}
+
+// format_routes() function
+// format the static routes as Cisco's 'show ip route static'
+fn format_routes(routes: &[ResponseProtoStaticAttr]) -> String {
+    let mut out = "Codes: S - static\n\n".to_string();
+    for r in routes {
+        let dest = match r.mask.parse::<Ipv4Addr>() {
+            Ok(m) => format!("{}/{}", r.destination, u32::from(m).count_ones()),
+            Err(_) => format!("{} {}", r.destination, r.mask),
+        };
+        out.push_str(&format!(
+            "S     {} [1/{}] via {}, mtu {}\n",
+            dest, r.metric, r.next_hop, r.mtu
+        ));
+    }
+    out
+}
+
+// follow_events() function
+// print the next event of the stream, or all of them
+fn follow_events(
+    client: &Client,
+    path: &str,
+    follow: bool,
+    json: bool,
+) -> Result<(), Box<dyn Error>> {
+    let mut resp = client.request("GET", path, None)?;
+    if resp.status >= 300 {
+        let status = resp.status;
+        return error(status_error(status, &resp.body()?));
+    }
+    if !resp.chunked {
+        return error("the events stream is not chunked".to_string());
+    }
+    let mut pending = String::new();
+    while let Some(chunk) = resp.chunk()? {
+        pending.push_str(&String::from_utf8_lossy(&chunk));
+        // every event ends with an empty line
+        while let Some(i) = pending.find("\n\n") {
+            let event: String = pending.drain(..i + 2).collect();
+            // the keepalives are comment lines, without data
+            let mut printed = false;
+            for data in event.lines().filter(|l| l.starts_with("data:")) {
+                let data = data[5..].trim();
+                if json {
+                    println!("{}", data);
+                } else {
+                    println!("{}", format_event(&serde_json::from_str(data)?));
+                }
+                printed = true;
+            }
+            io::stdout().flush()?;
+            if printed && !follow {
+                return Ok(());
+            }
+        }
+    }
+    Ok(())
+}
+
+// format_event() function
+// format an event as a Cisco's log message
+fn format_event(event: &Value) -> String {
+    let obj = match event.as_object() {
+        Some(o) => o,
+        None => return event.to_string(),
+    };
+    let ts = obj.get("timestamp").and_then(|t| t.as_i64()).unwrap_or(0);
+    let date = format_time(ts);
+    let name = obj
+        .get("event")
+        .and_then(|e| e.as_str())
+        .unwrap_or("unknown");
+    let tag = name.replace('_', "").to_uppercase();
+    let text = |k: &str| match obj.get(k) {
+        Some(Value::String(s)) => s.clone(),
+        Some(v) => v.to_string(),
+        None => "-".to_string(),
+    };
+    let msg = match name {
+        "state_change" => format!(
+            "{} Grp {} state {} -> {}",
+            text("interface"),
+            text("group"),
+            text("from"),
+            text("to")
+        ),
+        _ => obj
+            .iter()
+            .filter(|(k, _)| *k != "timestamp" && *k != "event")
+            .map(|(k, _)| format!("{}={}", k, text(k)))
+            .collect::<Vec<String>>()
+            .join(" "),
+    };
+    format!("{}: %VRRP-6-{}: {}", date, tag, msg)
+}
+
+// format_time() function
+// format a timestamp in the local time zone
+fn format_time(ts: i64) -> String {
+    match Local.timestamp_opt(ts, 0).single() {
+        Some(t) => t.format("%b %e %Y %T").to_string(),
+        None => ts.to_string(),
+    }
+}
+
+// yes_no() function
+fn yes_no(b: bool) -> &'static str {
+    if b {
+        "Y"
+    } else {
+        "N"
+    }
+}
+
+// main() function
+fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    if let Err(e) = run(&args) {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn urls_are_parsed() {
+        let url = |u: &str| parse_url(u).map_err(|e| e.to_string());
+        assert_eq!(
+            url("http://127.0.0.1:7080"),
+            Ok((false, "127.0.0.1".to_string(), 7080))
+        );
+        assert_eq!(
+            url("https://vrrp.example/"),
+            Ok((true, "vrrp.example".to_string(), 443))
+        );
+        assert_eq!(
+            url("http://vrrp.example"),
+            Ok((false, "vrrp.example".to_string(), 80))
+        );
+        assert_eq!(
+            url("https://[::1]:7443"),
+            Ok((true, "[::1]".to_string(), 7443))
+        );
+        assert_eq!(url("https://[::1]"), Ok((true, "[::1]".to_string(), 443)));
+        assert!(url("ftp://127.0.0.1").is_err());
+        assert!(url("http://127.0.0.1:http").is_err());
+    }
+
+    #[test]
+    fn requests_carry_the_token_and_body() {
+        assert_eq!(
+            request_head("GET", "/run/vrrp", "127.0.0.1", None, None),
+            "GET /run/vrrp HTTP/1.1\r\nHost: 127.0.0.1\r\nConnection: close\r\n\
+             Content-Length: 0\r\n\r\n"
+        );
+        assert_eq!(
+            request_head(
+                "PUT",
+                "/run/vrrp/1/eth0",
+                "127.0.0.1",
+                Some("ansible:k3ysecret"),
+                Some(("application/json", 16))
+            ),
+            "PUT /run/vrrp/1/eth0 HTTP/1.1\r\nHost: 127.0.0.1\r\nConnection: close\r\n\
+             Authorization: Bearer ansible:k3ysecret\r\n\
+             Content-Type: application/json\r\nContent-Length: 16\r\n\r\n"
+        );
+    }
+
+    #[test]
+    fn commands_are_parsed() {
+        let command = |c: &str| {
+            let free: Vec<&str> = c.split_whitespace().collect();
+            parse_command(&free).map_err(|e| e.to_string())
+        };
+        assert_eq!(command("show vrrp"), Ok(Command::ShowVrrp(true)));
+        assert_eq!(command("show vrrp brief"), Ok(Command::ShowVrrp(false)));
+        assert_eq!(command("show vrrp peers"), Ok(Command::ShowPeers(None)));
+        assert_eq!(
+            command("show vrrp history 1 eth0"),
+            Ok(Command::ShowHistory(Some((1, "eth0".to_string()))))
+        );
+        assert_eq!(
+            command("set priority 1 eth0 254"),
+            Ok(Command::SetPriority(1, "eth0".to_string(), 254))
+        );
+        assert_eq!(
+            command("set priority 1 eth0 300"),
+            Err("Invalid priority '300'".to_string())
+        );
+        assert_eq!(
+            command("maintenance on 2 eth1"),
+            Ok(Command::Maintenance(true, Some((2, "eth1".to_string()))))
+        );
+        assert_eq!(
+            command("maintenance off"),
+            Ok(Command::Maintenance(false, None))
+        );
+        assert!(command("maintenance yes").is_err());
+        assert_eq!(
+            command("events"),
+            Ok(Command::Events("/events".to_string()))
+        );
+        assert_eq!(
+            command("events 1 eth0"),
+            Ok(Command::Events(
+                "/events?group=1&interface=eth0".to_string()
+            ))
+        );
+        assert_eq!(command("events x"), Err("Invalid group 'x'".to_string()));
+        assert!(command("events 1 eth0 eth1").is_err());
+        assert_eq!(
+            command("show ip"),
+            Err("Unknown command 'show ip'".to_string())
+        );
+    }
+
+    #[test]
+    fn paths_are_built() {
+        assert_eq!(vrrp_path(1, "eth0"), "/run/vrrp/1/eth0");
+        assert_eq!(events_path(None, None), "/events");
+        assert_eq!(events_path(Some(7), None), "/events?group=7");
+        assert_eq!(
+            events_path(Some(7), Some("eth0")),
+            "/events?group=7&interface=eth0"
+        );
+    }
+
+    #[test]
+    fn errors_are_explained() {
+        assert_eq!(status_error(401, b""), "authentication failed or required");
+        assert_eq!(
+            status_error(400, br#"{"error":"invalid priority"}"#),
+            "invalid priority"
+        );
+        assert_eq!(
+            status_error(500, b"oops"),
+            "the API returned HTTP status 500"
+        );
+    }
+
+    #[test]
+    fn virtual_routers_are_formatted() {
+        let brief = format_vrrp_brief(&[ResponseVRRPAttr {
+            virtual_ip: "10.0.0.1".to_string(),
+            group: 1,
+            interface: "eth0".to_string(),
+            priority: 254,
+            preempt: true,
+            maintenance: false,
+            state: "Master".to_string(),
+        }]);
+        let lines: Vec<&str> = brief.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                "Interface          Grp Pri Pre Mnt State   Group addr",
+                "eth0               1   254 Y   N   Master  10.0.0.1",
+            ]
+        );
+
+        let detail = format_vrrp_detail(&[ResponseVRRPAttrExt {
+            virtual_ip: "10.0.0.1".to_string(),
+            group: 1,
+            interface: "eth0".to_string(),
+            vif: "standby1".to_string(),
+            priority: 100,
+            preempt: false,
+            maintenance: true,
+            state: "Backup".to_string(),
+            auth_type: 1,
+            interface_hwaddress: "00:11:22:33:44:55".to_string(),
+            advert_interval: 1,
+            masterdown_interval: 3.609,
+            skew_time: 0.609,
+        }]);
+        assert!(detail.starts_with("eth0 - Group 1\n  State is Backup\n"));
+        assert!(detail.contains("  Advertisement interval is 1.000 sec\n"));
+        assert!(detail.contains("  Preemption disabled\n"));
+        assert!(detail.contains("  Maintenance mode enabled\n"));
+        assert!(detail.contains("  Authentication text\n"));
+        assert!(
+            detail.ends_with("  Master Down interval is 3.609 sec\n  Skew time is 0.609 sec\n\n")
+        );
+    }
+
+    #[test]
+    fn routes_are_formatted() {
+        let route = |mask: &str| ResponseProtoStaticAttr {
+            destination: "10.1.0.0".to_string(),
+            mask: mask.to_string(),
+            next_hop: "10.0.0.254".to_string(),
+            metric: 10,
+            mtu: 1500,
+        };
+        assert_eq!(
+            format_routes(&[route("255.255.255.0"), route("bogus")]),
+            "Codes: S - static\n\n\
+             S     10.1.0.0/24 [1/10] via 10.0.0.254, mtu 1500\n\
+             S     10.1.0.0 bogus [1/10] via 10.0.0.254, mtu 1500\n"
+        );
+    }
+
+    #[test]
+    fn history_and_events_are_formatted() {
+        let ts = 1_580_000_000;
+        let transition = serde_json::json!({
+            "timestamp": ts,
+            "from": "Backup",
+            "to": "Master",
+            "trigger": { "event": "advert", "source": "10.0.0.2", "priority": 90 },
+            "operations": [
+                { "op": "ip", "action": "add", "success": true, "dry_run": false },
+                { "op": "route", "action": "add", "success": true, "dry_run": true,
+                  "detail": "10.1.0.0/24" },
+                { "op": "arp", "action": "send", "success": false, "error": "EPERM" },
+            ],
+        });
+        assert_eq!(
+            format_history(1, "eth0", &[transition]),
+            format!(
+                "eth0 - Group 1\n  {}  Backup -> Master (advert from 10.0.0.2 priority 90)\n\
+                 \x20     ip add ok\n\
+                 \x20     route add skipped (dry-run): 10.1.0.0/24\n\
+                 \x20     arp send failed: EPERM\n\n",
+                format_time(ts)
+            )
+        );
+
+        let event = serde_json::json!({
+            "timestamp": ts,
+            "event": "state_change",
+            "interface": "eth0",
+            "group": 1,
+            "from": "Backup",
+            "to": "Master",
+        });
+        assert_eq!(
+            format_event(&event),
+            format!(
+                "{}: %VRRP-6-STATECHANGE: eth0 Grp 1 state Backup -> Master",
+                format_time(ts)
+            )
+        );
+        assert_eq!(
+            format_event(&serde_json::json!("keepalive")),
+            "\"keepalive\""
+        );
+    }
+}
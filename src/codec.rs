@@ -0,0 +1,657 @@
+//! VRRPv2 packets codec module
+//! This module encodes and decodes the Ethernet frames carrying VRRPv2 ADVERTISEMENTs.
+//! The decoders are bounds-checked, and the encoders compute the lengths and checksums.
+
+// constants
+use crate::constants::*;
+
+// authentication
+use crate::auth::gen_auth_data;
+
+// checksums
+use crate::checksums;
+
+// std
+use std::error::Error;
+use std::fmt;
+
+/// PacketError Enumerator
+/// the reasons a received frame cannot be decoded as a VRRPv2 packet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PacketError {
+    Truncated,       // the frame is shorter than its headers announce
+    NotIPv4,         // the frame does not carry an IPv4 packet
+    BadIPHeader,     // invalid IP version, header length or total length
+    Fragmented,      // the IP packet is a fragment
+    NotVRRP,         // the IP protocol is not VRRP
+    TooManyVlanTags, // more than two VLAN tags
+}
+
+// PacketError Display Implementation
+impl fmt::Display for PacketError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            PacketError::Truncated => "truncated packet",
+            PacketError::NotIPv4 => "not an IPv4 packet",
+            PacketError::BadIPHeader => "invalid IPv4 header",
+            PacketError::Fragmented => "fragmented IPv4 packet",
+            PacketError::NotVRRP => "not a VRRP packet",
+            PacketError::TooManyVlanTags => "too many VLAN tags",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+// PacketError Error Implementation
+impl Error for PacketError {}
+
+// read_u16() function
+// read a big-endian u16, the caller checks the bounds
+fn read_u16(buf: &[u8], pos: usize) -> u16 {
+    u16::from_be_bytes([buf[pos], buf[pos + 1]])
+}
+
+// read_array4() function
+// copy an IPv4 address, the caller checks the bounds
+fn read_array4(buf: &[u8], pos: usize) -> [u8; 4] {
+    [buf[pos], buf[pos + 1], buf[pos + 2], buf[pos + 3]]
+}
+
+// read_array6() function
+// copy a MAC address, the caller checks the bounds
+fn read_array6(buf: &[u8], pos: usize) -> [u8; 6] {
+    [
+        buf[pos],
+        buf[pos + 1],
+        buf[pos + 2],
+        buf[pos + 3],
+        buf[pos + 4],
+        buf[pos + 5],
+    ]
+}
+
+/// VlanTag Structure
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VlanTag {
+    pub tpid: u16, // tag protocol identifier (802.1Q or 802.1ad)
+    pub tci: u16,  // priority, drop eligible indicator and VLAN identifier
+}
+
+/// EthernetHeader Structure
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EthernetHeader {
+    pub dst_mac: [u8; 6],    // destination MAC address
+    pub src_mac: [u8; 6],    // source MAC address
+    pub vlans: Vec<VlanTag>, // VLAN tags, from the outer one
+    pub ethertype: u16,      // ether type
+}
+
+// EthernetHeader Implementation
+impl EthernetHeader {
+    // decode() method
+    /// decode the Ethernet header and up to two VLAN tags,
+    /// and return it with the frame's payload
+    pub fn decode(frame: &[u8]) -> Result<(EthernetHeader, &[u8]), PacketError> {
+        if frame.len() < ETHER_FRAME_SIZE {
+            return Err(PacketError::Truncated);
+        }
+        let dst_mac = read_array6(frame, 0);
+        let src_mac = read_array6(frame, 6);
+        let mut pos = 12;
+        let mut ethertype = read_u16(frame, pos);
+        let mut vlans = Vec::new();
+        while ethertype == ETHER_P_8021Q || ethertype == ETHER_P_8021AD {
+            if vlans.len() == ETHER_VLAN_MAX_TAGS {
+                return Err(PacketError::TooManyVlanTags);
+            }
+            if frame.len() < pos + 2 + ETHER_VLAN_TAG_SIZE {
+                return Err(PacketError::Truncated);
+            }
+            vlans.push(VlanTag {
+                tpid: ethertype,
+                tci: read_u16(frame, pos + 2),
+            });
+            pos += ETHER_VLAN_TAG_SIZE;
+            ethertype = read_u16(frame, pos);
+        }
+        let hdr = EthernetHeader {
+            dst_mac,
+            src_mac,
+            vlans,
+            ethertype,
+        };
+        Ok((hdr, &frame[pos + 2..]))
+    }
+    // encode() method
+    pub fn encode(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.dst_mac);
+        buf.extend_from_slice(&self.src_mac);
+        for tag in &self.vlans {
+            buf.extend_from_slice(&tag.tpid.to_be_bytes());
+            buf.extend_from_slice(&tag.tci.to_be_bytes());
+        }
+        buf.extend_from_slice(&self.ethertype.to_be_bytes());
+    }
+    // vlan() getter
+    // the outer VLAN identifier, if the frame is tagged
+    pub fn vlan(&self) -> Option<u16> {
+        self.vlans.first().map(|t| t.tci & 0x0fff)
+    }
+}
+
+/// Ipv4Header Structure
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ipv4Header {
+    pub tos: u8,          // DSCP and ECN
+    pub ident: u16,       // identifier
+    pub flags: u16,       // flags and fragment offset
+    pub ttl: u8,          // TTL
+    pub proto: u8,        // IP Protocol
+    pub checksum: u16,    // header checksum (as decoded, computed when encoding)
+    pub src: [u8; 4],     // source IP address
+    pub dst: [u8; 4],     // destination IP address
+    pub options: Vec<u8>, // IP options, padded to 4 bytes when encoding
+}
+
+// Ipv4Header Implementation
+impl Ipv4Header {
+    // decode() method
+    /// decode the IPv4 header, and return it with the packet's payload
+    /// (bounded by the total length, the Ethernet padding is left out)
+    pub fn decode(pkt: &[u8]) -> Result<(Ipv4Header, &[u8]), PacketError> {
+        if pkt.len() < IP_HEADER_MIN_SIZE {
+            return Err(PacketError::Truncated);
+        }
+        if pkt[0] >> 4 != 4 {
+            return Err(PacketError::BadIPHeader);
+        }
+        let ihl = (pkt[0] & 0x0f) as usize * 4;
+        let total_len = read_u16(pkt, 2) as usize;
+        if ihl < IP_HEADER_MIN_SIZE || total_len < ihl {
+            return Err(PacketError::BadIPHeader);
+        }
+        if pkt.len() < total_len {
+            return Err(PacketError::Truncated);
+        }
+        let hdr = Ipv4Header {
+            tos: pkt[1],
+            ident: read_u16(pkt, 4),
+            flags: read_u16(pkt, 6),
+            ttl: pkt[8],
+            proto: pkt[9],
+            checksum: read_u16(pkt, 10),
+            src: read_array4(pkt, 12),
+            dst: read_array4(pkt, 16),
+            options: pkt[IP_HEADER_MIN_SIZE..ihl].to_vec(),
+        };
+        Ok((hdr, &pkt[ihl..total_len]))
+    }
+    // encode() method
+    /// encode the IPv4 header of a packet carrying payload_len bytes,
+    /// the total length and the header checksum are computed
+    pub fn encode(&self, payload_len: usize, buf: &mut Vec<u8>) {
+        let start = buf.len();
+        // the header length is at most 60 bytes
+        let opt_len = self
+            .options
+            .len()
+            .min(IP_HEADER_MAX_SIZE - IP_HEADER_MIN_SIZE);
+        let options = &self.options[..opt_len];
+        let hdr_len = IP_HEADER_MIN_SIZE + (options.len() + 3) / 4 * 4;
+        buf.push(0x40 | (hdr_len / 4) as u8);
+        buf.push(self.tos);
+        buf.extend_from_slice(&((hdr_len + payload_len) as u16).to_be_bytes());
+        buf.extend_from_slice(&self.ident.to_be_bytes());
+        buf.extend_from_slice(&self.flags.to_be_bytes());
+        buf.push(self.ttl);
+        buf.push(self.proto);
+        buf.extend_from_slice(&[0, 0]);
+        buf.extend_from_slice(&self.src);
+        buf.extend_from_slice(&self.dst);
+        buf.extend_from_slice(options);
+        buf.resize(start + hdr_len, 0);
+        // the header checksum only covers the header
+        let checksum = checksums::one_complement_sum(&buf[start..], None);
+        buf[start + 10..start + 12].copy_from_slice(&checksum.to_be_bytes());
+    }
+    // is_fragment() method
+    // true if the more fragments flag or the fragment offset is set
+    pub fn is_fragment(&self) -> bool {
+        self.flags & 0x3fff != 0
+    }
+}
+
+/// VrrpAdvert Structure
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VrrpAdvert {
+    pub version: u8,           // version/type - 4/4 bits
+    pub vrid: u8,              // virtual router id
+    pub prio: u8,              // priority
+    pub authtype: u8,          // auth type
+    pub adverint: u8,          // advertisement interval
+    pub checksum: u16,         // checksum (as decoded, computed when encoding)
+    pub ipaddrs: Vec<[u8; 4]>, // IP addresses, the count is taken from this vector
+    pub authdata: [u8; 8],     // authentication data
+}
+
+// VrrpAdvert Implementation
+impl VrrpAdvert {
+    // decode() method
+    /// decode a VRRPv2 PDU, the bytes after the authentication data are ignored
+    pub fn decode(pdu: &[u8]) -> Result<VrrpAdvert, PacketError> {
+        if pdu.len() < VRRP_V2_HEADER_SIZE {
+            return Err(PacketError::Truncated);
+        }
+        let count = pdu[3] as usize;
+        let auth_pos = VRRP_V2_HEADER_SIZE + count * 4;
+        if pdu.len() < auth_pos + VRRP_V2_AUTH_DATA_SIZE {
+            return Err(PacketError::Truncated);
+        }
+        let mut authdata = [0u8; 8];
+        authdata.copy_from_slice(&pdu[auth_pos..auth_pos + VRRP_V2_AUTH_DATA_SIZE]);
+        Ok(VrrpAdvert {
+            version: pdu[0],
+            vrid: pdu[1],
+            prio: pdu[2],
+            authtype: pdu[4],
+            adverint: pdu[5],
+            checksum: read_u16(pdu, VRRP_V2_CHECKSUM_POS),
+            ipaddrs: (0..count)
+                .map(|i| read_array4(pdu, VRRP_V2_HEADER_SIZE + i * 4))
+                .collect(),
+            authdata,
+        })
+    }
+    // encode_unsigned() method
+    // encode the header and IP addresses, with the checksum zeroed out,
+    // as covered by the proprietary authentication types
+    fn encode_unsigned(&self, buf: &mut Vec<u8>) {
+        buf.push(self.version);
+        buf.push(self.vrid);
+        buf.push(self.prio);
+        buf.push(self.ipaddrs.len() as u8);
+        buf.push(self.authtype);
+        buf.push(self.adverint);
+        buf.extend_from_slice(&[0, 0]);
+        for addr in &self.ipaddrs {
+            buf.extend_from_slice(addr);
+        }
+    }
+    // encode() method
+    /// encode the VRRPv2 PDU, the checksum is computed
+    pub fn encode(&self, buf: &mut Vec<u8>) {
+        let start = buf.len();
+        self.encode_unsigned(buf);
+        buf.extend_from_slice(&self.authdata);
+        let checksum = checksums::one_complement_sum(&buf[start..], None);
+        let pos = start + VRRP_V2_CHECKSUM_POS;
+        buf[pos..pos + 2].copy_from_slice(&checksum.to_be_bytes());
+    }
+    // compute_checksum() method
+    pub fn compute_checksum(&self) -> u16 {
+        let mut buf = Vec::new();
+        self.encode(&mut buf);
+        read_u16(&buf, VRRP_V2_CHECKSUM_POS)
+    }
+    // verify_checksum() method
+    pub fn verify_checksum(&self) -> bool {
+        let mut buf = Vec::new();
+        self.encode_unsigned(&mut buf);
+        buf.extend_from_slice(&self.authdata);
+        let pos = VRRP_V2_CHECKSUM_POS;
+        buf[pos..pos + 2].copy_from_slice(&self.checksum.to_be_bytes());
+        // all-ones if the carried checksum is valid
+        checksums::rfc1071(&buf) == 0xFFFF
+    }
+    // auth_data() method
+    /// compute the authentication data of the ADVERTISEMENT for the given secret
+    pub fn auth_data(&self, secret: &Option<String>) -> [u8; 8] {
+        let data = match self.authtype {
+            // AUTH_TYPE_P0 (PROPRIETARY-TRUNCATED-8B-SHA256)
+            // AUTH_TYPE_P1 (PROPRIETARY-XOF-8B-SHAKE256)
+            // computed on the PDU minus the authentication data, and
+            // with the checksum field zeroed out (HMAC-then-checksum)
+            AUTH_TYPE_P0 | AUTH_TYPE_P1 => {
+                let mut msg = Vec::new();
+                self.encode_unsigned(&mut msg);
+                gen_auth_data(self.authtype, secret, Option::Some(&msg))
+            }
+            // all remaining types
+            _ => gen_auth_data(self.authtype, secret, Option::None),
+        };
+        // the authentication data field is 8 bytes long
+        let mut authdata = [0u8; 8];
+        let len = data.len().min(VRRP_V2_AUTH_DATA_SIZE);
+        authdata[..len].copy_from_slice(&data[..len]);
+        authdata
+    }
+    // sign() method
+    /// set the authentication data for the given secret
+    pub fn sign(&mut self, secret: &Option<String>) {
+        self.authdata = self.auth_data(secret);
+    }
+}
+
+/// AdvertFrame Structure
+/// an Ethernet frame carrying a VRRPv2 ADVERTISEMENT
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdvertFrame {
+    pub eth: EthernetHeader,
+    pub ip: Ipv4Header,
+    pub vrrp: VrrpAdvert,
+}
+
+// AdvertFrame Implementation
+impl AdvertFrame {
+    // decode() method
+    /// decode an Ethernet frame carrying a VRRPv2 packet, with up to two VLAN
+    /// tags and IPv4 options. The Ethernet padding after the IP packet and
+    /// the bytes after the authentication data are ignored.
+    pub fn decode(frame: &[u8]) -> Result<AdvertFrame, PacketError> {
+        let (eth, payload) = EthernetHeader::decode(frame)?;
+        if eth.ethertype != ETHER_P_IP {
+            return Err(PacketError::NotIPv4);
+        }
+        let (ip, payload) = Ipv4Header::decode(payload)?;
+        if ip.is_fragment() {
+            return Err(PacketError::Fragmented);
+        }
+        if ip.proto != IP_UPPER_PROTO_VRRP {
+            return Err(PacketError::NotVRRP);
+        }
+        let vrrp = VrrpAdvert::decode(payload)?;
+        Ok(AdvertFrame { eth, ip, vrrp })
+    }
+    // encode() method
+    /// encode the frame, the lengths and checksums are computed
+    pub fn encode(&self) -> Vec<u8> {
+        let mut pdu = Vec::new();
+        self.vrrp.encode(&mut pdu);
+        let mut frame = Vec::new();
+        self.eth.encode(&mut frame);
+        self.ip.encode(pdu.len(), &mut frame);
+        frame.extend_from_slice(&pdu);
+        frame
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // hand-built VRRPv2 ADVERTISEMENTs (not captured from a router), using the
+    // header fields set by Cisco IOS: ToS 0xc0, a non-zero IP identifier and
+    // the addresses of the group only.
+    // the golden tests against captured Cisco IOS ADVERTISEMENTs are still
+    // missing: no capture is available yet, they must be added along with
+    // their source (IOS release, platform and capture file) once one is.
+    // group 1, priority 100, no authentication, 192.168.1.1
+    const ADVERT_NOAUTH: [u8; 54] = [
+        0x01, 0x00, 0x5e, 0x00, 0x00, 0x12, 0x00, 0x00, 0x5e, 0x00, 0x01, 0x01, //
+        0x08, 0x00, 0x45, 0xc0, 0x00, 0x28, 0x0a, 0x3c, 0x00, 0x00, 0xff, 0x70, //
+        0x0e, 0xad, 0xc0, 0xa8, 0x01, 0x02, 0xe0, 0x00, 0x00, 0x12, 0x21, 0x01, //
+        0x64, 0x01, 0x00, 0x01, 0xb9, 0x52, 0xc0, 0xa8, 0x01, 0x01, 0x00, 0x00, //
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+    // group 10, priority 110, text authentication "cisco", 10.0.0.254
+    const ADVERT_TEXT_AUTH: [u8; 54] = [
+        0x01, 0x00, 0x5e, 0x00, 0x00, 0x12, 0x00, 0x00, 0x5e, 0x00, 0x01, 0x0a, //
+        0x08, 0x00, 0x45, 0xc0, 0x00, 0x28, 0x1f, 0x07, 0x00, 0x00, 0xff, 0x70, //
+        0xb1, 0x8a, 0x0a, 0x00, 0x00, 0x02, 0xe0, 0x00, 0x00, 0x12, 0x21, 0x0a, //
+        0x6e, 0x01, 0x01, 0x01, 0x1f, 0x28, 0x0a, 0x00, 0x00, 0xfe, 0x63, 0x69, //
+        0x73, 0x63, 0x6f, 0x00, 0x00, 0x00,
+    ];
+    // group 20 on VLAN 900, priority 120, advertisement interval 3s,
+    // text authentication "c1sc0!", 172.16.9.1 and 172.16.9.254 (secondary)
+    const ADVERT_VLAN_SECONDARY: [u8; 62] = [
+        0x01, 0x00, 0x5e, 0x00, 0x00, 0x12, 0x00, 0x00, 0x5e, 0x00, 0x01, 0x14, //
+        0x81, 0x00, 0x03, 0x84, 0x08, 0x00, 0x45, 0xc0, 0x00, 0x2c, 0x00, 0x02, //
+        0x00, 0x00, 0xff, 0x70, 0x25, 0x7a, 0xac, 0x10, 0x09, 0x03, 0xe0, 0x00, //
+        0x00, 0x12, 0x21, 0x14, 0x78, 0x02, 0x01, 0x03, 0xf4, 0x0f, 0xac, 0x10, //
+        0x09, 0x01, 0xac, 0x10, 0x09, 0xfe, 0x63, 0x31, 0x73, 0x63, 0x30, 0x21, //
+        0x00, 0x00,
+    ];
+
+    // the ADVERTISEMENT of ADVERT_NOAUTH
+    fn advert() -> AdvertFrame {
+        AdvertFrame {
+            eth: EthernetHeader {
+                dst_mac: ETHER_VRRP_V2_DST_MAC,
+                src_mac: [0x00, 0x00, 0x5e, 0x00, 0x01, 0x01],
+                vlans: Vec::new(),
+                ethertype: ETHER_P_IP,
+            },
+            ip: Ipv4Header {
+                tos: IP_DSCP_CS6,
+                ident: 0x0a3c,
+                flags: 0,
+                ttl: IP_TTL_VRRP_MINTTL,
+                proto: IP_UPPER_PROTO_VRRP,
+                checksum: 0,
+                src: [192, 168, 1, 2],
+                dst: VRRP_V2_IP_MCAST_DST,
+                options: Vec::new(),
+            },
+            vrrp: VrrpAdvert {
+                version: VRRP_V2_ADVERT_VERSION_TYPE,
+                vrid: 1,
+                prio: 100,
+                authtype: 0,
+                adverint: 1,
+                checksum: 0,
+                ipaddrs: vec![[192, 168, 1, 1]],
+                authdata: [0; 8],
+            },
+        }
+    }
+
+    #[test]
+    fn golden_noauth() {
+        let frame = AdvertFrame::decode(&ADVERT_NOAUTH).unwrap();
+        assert_eq!(frame.eth.vlan(), None);
+        assert_eq!(frame.ip.src, [192, 168, 1, 2]);
+        assert_eq!(frame.ip.checksum, 0x0ead);
+        assert_eq!(frame.vrrp.vrid, 1);
+        assert_eq!(frame.vrrp.prio, 100);
+        assert_eq!(frame.vrrp.ipaddrs, vec![[192, 168, 1, 1]]);
+        assert!(frame.vrrp.verify_checksum());
+        assert_eq!(frame.encode(), &ADVERT_NOAUTH[..]);
+
+        // the built ADVERTISEMENT is encoded to the same bytes
+        assert_eq!(advert().encode(), &ADVERT_NOAUTH[..]);
+    }
+
+    #[test]
+    fn golden_text_auth() {
+        let frame = AdvertFrame::decode(&ADVERT_TEXT_AUTH).unwrap();
+        assert_eq!(frame.vrrp.authtype, AUTH_TYPE_SIMPLE);
+        assert_eq!(&frame.vrrp.authdata, b"cisco\0\0\0");
+        assert!(frame.vrrp.verify_checksum());
+        assert_eq!(
+            frame.vrrp.auth_data(&Some("cisco".to_string())),
+            frame.vrrp.authdata
+        );
+        assert_ne!(
+            frame.vrrp.auth_data(&Some("Cisco".to_string())),
+            frame.vrrp.authdata
+        );
+        assert_eq!(frame.encode(), &ADVERT_TEXT_AUTH[..]);
+    }
+
+    #[test]
+    fn golden_vlan_secondary() {
+        let frame = AdvertFrame::decode(&ADVERT_VLAN_SECONDARY).unwrap();
+        assert_eq!(frame.eth.vlan(), Some(900));
+        assert_eq!(frame.vrrp.adverint, 3);
+        assert_eq!(frame.vrrp.ipaddrs, vec![[172, 16, 9, 1], [172, 16, 9, 254]]);
+        assert!(frame.vrrp.verify_checksum());
+        assert_eq!(frame.encode(), &ADVERT_VLAN_SECONDARY[..]);
+    }
+
+    #[test]
+    fn round_trip_address_counts() {
+        for count in 0..=255usize {
+            let mut frame = advert();
+            frame.vrrp.ipaddrs = (0..count)
+                .map(|i| [10, 0, (i >> 8) as u8, i as u8])
+                .collect();
+            let bytes = frame.encode();
+            let decoded = AdvertFrame::decode(&bytes).unwrap();
+            assert_eq!(decoded.vrrp.ipaddrs, frame.vrrp.ipaddrs);
+            assert!(decoded.vrrp.verify_checksum());
+            assert_eq!(decoded.encode(), bytes);
+        }
+    }
+
+    #[test]
+    fn round_trip_auth_types() {
+        let secret = Some("s3cr3t".to_string());
+        for authtype in &[0, AUTH_TYPE_SIMPLE, AUTH_TYPE_P0, AUTH_TYPE_P1] {
+            let mut frame = advert();
+            frame.vrrp.authtype = *authtype;
+            frame.vrrp.sign(&secret);
+            let decoded = AdvertFrame::decode(&frame.encode()).unwrap();
+            assert!(decoded.vrrp.verify_checksum());
+            assert_eq!(decoded.vrrp.auth_data(&secret), decoded.vrrp.authdata);
+            if *authtype != 0 {
+                let other = Some("other".to_string());
+                assert_ne!(decoded.vrrp.auth_data(&other), decoded.vrrp.authdata);
+            }
+        }
+        // the proprietary types cover the PDU
+        let mut frame = advert();
+        frame.vrrp.authtype = AUTH_TYPE_P0;
+        frame.vrrp.sign(&secret);
+        let authdata = frame.vrrp.authdata;
+        frame.vrrp.prio = 101;
+        assert_ne!(frame.vrrp.auth_data(&secret), authdata);
+    }
+
+    #[test]
+    fn decode_ip_options() {
+        // router alert option
+        let mut frame = advert();
+        frame.ip.options = vec![0x94, 0x04, 0x00, 0x00];
+        let bytes = frame.encode();
+        assert_eq!(bytes[14], 0x46);
+        let decoded = AdvertFrame::decode(&bytes).unwrap();
+        assert_eq!(decoded.ip.options, frame.ip.options);
+        assert_eq!(decoded.vrrp.ipaddrs, vec![[192, 168, 1, 1]]);
+        assert_eq!(decoded.encode(), bytes);
+    }
+
+    #[test]
+    fn decode_vlan_tags() {
+        let mut frame = ADVERT_NOAUTH.to_vec();
+        // 802.1Q tag, VLAN 900 with priority 6
+        let tag = [0x81, 0x00, 0xc3, 0x84];
+        frame.splice(12..12, tag.iter().cloned());
+        let decoded = AdvertFrame::decode(&frame).unwrap();
+        assert_eq!(decoded.eth.vlan(), Some(900));
+        assert_eq!(decoded.encode(), frame);
+
+        // 802.1ad outer tag, VLAN 10
+        let tag = [0x88, 0xa8, 0x00, 0x0a];
+        frame.splice(12..12, tag.iter().cloned());
+        let decoded = AdvertFrame::decode(&frame).unwrap();
+        assert_eq!(decoded.eth.vlan(), Some(10));
+        assert_eq!(decoded.encode(), frame);
+
+        // a third tag is refused
+        frame.splice(12..12, tag.iter().cloned());
+        assert_eq!(
+            AdvertFrame::decode(&frame).unwrap_err(),
+            PacketError::TooManyVlanTags
+        );
+    }
+
+    #[test]
+    fn decode_ethernet_padding() {
+        let mut frame = ADVERT_NOAUTH.to_vec();
+        frame.extend_from_slice(&[0; 6]);
+        let decoded = AdvertFrame::decode(&frame).unwrap();
+        assert_eq!(decoded.encode(), &ADVERT_NOAUTH[..]);
+    }
+
+    #[test]
+    fn decode_truncated() {
+        let mut frame = advert();
+        frame.ip.options = vec![0x94, 0x04, 0x00, 0x00];
+        let bytes = frame.encode();
+        for len in 0..bytes.len() {
+            assert_eq!(
+                AdvertFrame::decode(&bytes[..len]).unwrap_err(),
+                PacketError::Truncated,
+                "length {}",
+                len
+            );
+        }
+    }
+
+    #[test]
+    fn decode_bad_addrcount() {
+        let mut frame = ADVERT_NOAUTH.to_vec();
+        // more addresses than the IP packet holds
+        frame[34 + 3] = 2;
+        assert_eq!(
+            AdvertFrame::decode(&frame).unwrap_err(),
+            PacketError::Truncated
+        );
+        frame[34 + 3] = 255;
+        assert_eq!(
+            AdvertFrame::decode(&frame).unwrap_err(),
+            PacketError::Truncated
+        );
+    }
+
+    #[test]
+    fn decode_bad_headers() {
+        // ARP
+        let mut frame = ADVERT_NOAUTH.to_vec();
+        frame[12..14].copy_from_slice(&ETHER_P_ARP.to_be_bytes());
+        assert_eq!(
+            AdvertFrame::decode(&frame).unwrap_err(),
+            PacketError::NotIPv4
+        );
+
+        // IPv6 version
+        let mut frame = ADVERT_NOAUTH.to_vec();
+        frame[14] = 0x65;
+        assert_eq!(
+            AdvertFrame::decode(&frame).unwrap_err(),
+            PacketError::BadIPHeader
+        );
+
+        // header length below 20 bytes
+        let mut frame = ADVERT_NOAUTH.to_vec();
+        frame[14] = 0x44;
+        assert_eq!(
+            AdvertFrame::decode(&frame).unwrap_err(),
+            PacketError::BadIPHeader
+        );
+
+        // total length below the header length
+        let mut frame = ADVERT_NOAUTH.to_vec();
+        frame[16..18].copy_from_slice(&16u16.to_be_bytes());
+        assert_eq!(
+            AdvertFrame::decode(&frame).unwrap_err(),
+            PacketError::BadIPHeader
+        );
+
+        // first fragment (more fragments flag)
+        let mut frame = ADVERT_NOAUTH.to_vec();
+        frame[20] = 0x20;
+        assert_eq!(
+            AdvertFrame::decode(&frame).unwrap_err(),
+            PacketError::Fragmented
+        );
+
+        // UDP
+        let mut frame = ADVERT_NOAUTH.to_vec();
+        frame[23] = 17;
+        assert_eq!(
+            AdvertFrame::decode(&frame).unwrap_err(),
+            PacketError::NotVRRP
+        );
+    }
+}
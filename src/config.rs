@@ -3,12 +3,21 @@
 use super::*;
 
 // std
+use std::io::Write;
 use std::net::IpAddr;
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+// chrono
+use chrono::DateTime;
 
 // rand
 use rand::Rng;
 
+// operating system drivers
+use crate::os::drivers::{MacvlanMode, VmacModes};
+
 /// CfgType Enumerator
+#[derive(Debug, Clone, Copy)]
 pub enum CfgType {
     Toml, // TOML
     Json, // JSON
@@ -24,6 +33,7 @@ pub struct CConfig {
     pub working_dir: Option<String>,
     pub main_log: Option<String>,
     pub error_log: Option<String>,
+    pub crash_dump: Option<String>,
     pub vrouter: Option<Vec<VRConfig>>,
     pub protocols: Option<Protocols>,
     pub client_api: Option<String>,
@@ -89,6 +99,13 @@ impl CConfig {
             None => RVRRPD_DFLT_ELOGFILE.to_string(),
         }
     }
+    // crash_dump() getter
+    pub fn crash_dump(&self) -> String {
+        match &self.crash_dump {
+            Some(v) => v.clone(),
+            None => RVRRPD_DFLT_CRASHDUMP.to_string(),
+        }
+    }
     // client_api() method
     pub fn client_api(&self) -> bool {
         match &self.client_api {
@@ -99,13 +116,70 @@ impl CConfig {
             None => false,
         }
     }
+    // validate() method
+    // verify the global directives without panicking
+    pub fn validate(&self) -> Result<(), String> {
+        match self.debug {
+            Some(0) | Some(1) | Some(2) | Some(3) | Some(5) | None => {}
+            Some(v) => return Err(format!("error(config): invalid debug level {}", v)),
+        }
+        match &self.time_zone {
+            Some(s) => match &s[..] {
+                "local" | "utc" => {}
+                _ => return Err(format!("error(config): invalid time zone '{}'", s)),
+            },
+            None => {}
+        }
+        match &self.time_format {
+            Some(s) => match &s[..] {
+                "disabled" | "short" | "rfc2822" => {}
+                _ => return Err(format!("error(config): invalid time format '{}'", s)),
+            },
+            None => {}
+        }
+        match &self.client_api {
+            Some(s) => match &s[..] {
+                "http" => {}
+                _ => return Err(format!("error(config): invalid client API type '{}'", s)),
+            },
+            None => {}
+        }
+        if let Some(api) = &self.api {
+            if api.session_timeout == Some(0) {
+                return Err("error(config): the API session timeout cannot be zero".to_string());
+            }
+            if api.login_max_failures == Some(0) || api.login_lockout == Some(0) {
+                return Err(
+                    "error(config): the API login failures and lockout cannot be zero".to_string(),
+                );
+            }
+            if api.rate_limit == Some(0) {
+                return Err("error(config): the API rate limit cannot be zero".to_string());
+            }
+            for k in api.keys() {
+                k.validate()?;
+            }
+            match &api.unix {
+                Some(u) => u.validate()?,
+                None if !api.tcp() => {
+                    return Err(
+                        "error(config): the API needs a TCP listener or a Unix socket".to_string(),
+                    );
+                }
+                None => {}
+            }
+        }
+        Ok(())
+    }
 }
 
 /// Virtual-Routers Configuration Structure
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct VRConfig {
     group: u8,
     interface: String,
+    vlan: Option<u16>,
+    vlan_address: Option<String>,
     vip: Option<String>,
     priority: Option<u8>,
     preemption: Option<bool>,
@@ -115,6 +189,9 @@ pub struct VRConfig {
     rfc3768: Option<bool>,
     netdrv: Option<String>,
     iftype: Option<String>,
+    macvlan_mode: Option<String>,
+    use_vmac: Option<bool>,
+    secondary_vmac: Option<bool>,
     vifname: Option<String>,
     socket_filter: Option<bool>,
 }
@@ -130,6 +207,30 @@ impl VRConfig {
     pub fn interface(&self) -> &String {
         &self.interface
     }
+    // vlan() getter
+    pub fn vlan(&self) -> Option<u16> {
+        self.vlan
+    }
+    // ifname() method
+    // return the interface the virtual router runs on,
+    // its VLAN sub-interface when a VLAN is configured
+    pub fn ifname(&self) -> String {
+        match self.vlan {
+            Some(vid) => format!("{}.{}", self.interface, vid),
+            None => self.interface.clone(),
+        }
+    }
+    // vlan_address() method
+    // return the primary IP address and netmask of the VLAN sub-interface
+    pub fn vlan_address(&self) -> Option<([u8; 4], [u8; 4])> {
+        match self.vlan_address.as_ref().and_then(|s| parse_prefix(s)) {
+            Some((IpAddr::V4(ip), len)) => {
+                let mask = u32::max_value().checked_shl(32 - len as u32).unwrap_or(0);
+                Some((ip.octets(), mask.to_be_bytes()))
+            }
+            _ => None,
+        }
+    }
     // vip() getter
     pub fn vip(&self) -> [u8; 4] {
         match &self.vip {
@@ -169,11 +270,9 @@ impl VRConfig {
     // auth_type() method
     pub fn auth_type(&self) -> u8 {
         match &self.auth_type {
-            Some(s) => match &s[..] {
-                "rfc2338-simple" => AUTH_TYPE_SIMPLE,
-                "p0-t8-sha256" => AUTH_TYPE_P0,
-                "p1-b8-shake256" => AUTH_TYPE_P1,
-                _ => panic!("error(config): authentication type {} is not supported", s),
+            Some(s) => match parse_auth_type(s) {
+                Some(t) => t,
+                None => panic!("error(config): authentication type {} is not supported", s),
             },
             None => 0,
         }
@@ -197,17 +296,16 @@ impl VRConfig {
         }
     }
     // rfc3768() getter
+    // return the configured compatibility flag, the virtual router forces it
+    // while the authentication type is 'p0-t8-sha256', or 'p1-b8-shake256'
     pub fn rfc3768(&self) -> bool {
-        // if auth_type is 'p0-t8-sha256', or 'p1-b8-shake256',
-        // overwrite rfc3768 compatibility flag
         match &self.auth_type {
             Some(t) => match &t[..] {
-                "p0-t8-sha256" | "p1-b8-shake256" => {
+                "p0-t8-sha256" | "p1-b8-shake256" if self.rfc3768 == Some(false) => {
                     println!(
                         "warning(config): authentication type {} is enabled, forcing rfc3768 compatibility.",
                         t
                     );
-                    return true;
                 }
                 _ => {}
             },
@@ -222,14 +320,15 @@ impl VRConfig {
     pub fn netdrv(&self) -> NetDrivers {
         // if os is Linux
         if cfg!(target_os = "linux") {
-            // if macvlan is configured, return libnl
-            match self.iftype() {
-                IfTypes::macvlan => return NetDrivers::libnl,
-                _ => {}
-            }
             match &self.netdrv {
                 Some(s) => match &s[..] {
+                    // macvlan and ipvlan interfaces require a netlink driver
+                    "ioctl" if self.iftype() != IfTypes::ether => NetDrivers::libnl,
+                    // as well as secondary MAC addresses and VLAN interfaces
+                    "ioctl" if self.vmac_mode() == VmacModes::secondary => NetDrivers::libnl,
+                    "ioctl" if self.vlan.is_some() => NetDrivers::libnl,
                     "ioctl" => NetDrivers::ioctl,
+                    "netlink" => NetDrivers::netlink,
                     _ => NetDrivers::libnl,
                 },
                 None => NetDrivers::libnl,
@@ -247,6 +346,7 @@ impl VRConfig {
             match &self.iftype {
                 Some(s) => match &s[..] {
                     "macvlan" => IfTypes::macvlan,
+                    "ipvlan" => IfTypes::ipvlan,
                     _ => IfTypes::ether,
                 },
                 None => IfTypes::ether,
@@ -255,6 +355,26 @@ impl VRConfig {
             IfTypes::ether
         }
     }
+    // macvlan_mode() method
+    pub fn macvlan_mode(&self) -> MacvlanMode {
+        match &self.macvlan_mode {
+            Some(s) => match &s[..] {
+                "private" => MacvlanMode::private,
+                "vepa" => MacvlanMode::vepa,
+                "passthru" => MacvlanMode::passthru,
+                _ => MacvlanMode::bridge,
+            },
+            None => MacvlanMode::bridge,
+        }
+    }
+    // vmac_mode() method
+    pub fn vmac_mode(&self) -> VmacModes {
+        match (self.use_vmac, self.secondary_vmac) {
+            (Some(false), _) => VmacModes::none,
+            (_, Some(true)) => VmacModes::secondary,
+            _ => VmacModes::primary,
+        }
+    }
     // vifname() method
     pub fn vifname(&self) -> String {
         match &self.vifname {
@@ -269,10 +389,136 @@ impl VRConfig {
             None => true,
         }
     }
+    // same_setup() method
+    // return true if another configuration of this virtual router only differs
+    // in the parameters which can be changed while it is running
+    pub fn same_setup(&self, other: &VRConfig) -> bool {
+        let other = VRConfig {
+            priority: self.priority,
+            preemption: self.preemption,
+            auth_type: self.auth_type.clone(),
+            auth_secret: self.auth_secret.clone(),
+            timers: self.timers.clone(),
+            ..other.clone()
+        };
+        *self == other
+    }
+    // validate() method
+    // verify the virtual router directives without panicking
+    pub fn validate(&self) -> Result<(), String> {
+        if self.group < 1 {
+            return Err(format!(
+                "error(config): group id {} is out of range (1-255)",
+                self.group
+            ));
+        }
+        if self.interface.is_empty() {
+            return Err("error(config): no interface specified".to_string());
+        }
+        match &self.vip {
+            Some(ip) => match ip.parse::<IpAddr>() {
+                Ok(IpAddr::V4(_)) => {}
+                Ok(IpAddr::V6(_)) => {
+                    return Err("error(config): Only IPv4 addresses are supported".to_string())
+                }
+                Err(_) => return Err(format!("error(config): invalid virtual IP '{}'", ip)),
+            },
+            None => return Err("error(config): No virtual IP specified".to_string()),
+        }
+        match self.priority {
+            Some(v) if v < 1 || v > 254 => {
+                return Err(format!(
+                    "error(config): priority {} is out of range (1-254)",
+                    v
+                ))
+            }
+            _ => {}
+        }
+        match &self.timers {
+            Some(t) if t.advert < 1 => {
+                return Err("error(config): advertisement interval must be at least 1s".to_string())
+            }
+            _ => {}
+        }
+        match &self.auth_type {
+            Some(s) => match &s[..] {
+                "rfc2338-simple" | "p0-t8-sha256" | "p1-b8-shake256" => {}
+                _ => {
+                    return Err(format!(
+                        "error(config): authentication type {} is not supported",
+                        s
+                    ))
+                }
+            },
+            None => {}
+        }
+        match &self.netdrv {
+            Some(s) => match &s[..] {
+                "ioctl" | "libnl" | "netlink" => {}
+                _ => return Err(format!("error(config): invalid network driver '{}'", s)),
+            },
+            None => {}
+        }
+        match &self.iftype {
+            Some(s) => match &s[..] {
+                "ether" | "macvlan" | "ipvlan" => {}
+                _ => return Err(format!("error(config): invalid interface type '{}'", s)),
+            },
+            None => {}
+        }
+        match &self.macvlan_mode {
+            Some(s) => match &s[..] {
+                "private" | "vepa" | "bridge" | "passthru" => {}
+                _ => return Err(format!("error(config): invalid macvlan mode '{}'", s)),
+            },
+            None => {}
+        }
+        if self.use_vmac == Some(false) && self.secondary_vmac == Some(true) {
+            return Err(
+                "error(config): secondary_vmac cannot be enabled when use_vmac is false"
+                    .to_string(),
+            );
+        }
+        match self.vlan {
+            Some(v) if v < 1 || v > 4094 => {
+                return Err(format!(
+                    "error(config): VLAN id {} is out of range (1-4094)",
+                    v
+                ))
+            }
+            _ => {}
+        }
+        match &self.vlan_address {
+            Some(_) if self.vlan.is_none() => {
+                return Err("error(config): vlan_address requires a VLAN id".to_string())
+            }
+            Some(s) if !s.contains('/') || self.vlan_address().is_none() => {
+                return Err(format!(
+                    "error(config): invalid VLAN interface address '{}'",
+                    s
+                ))
+            }
+            _ => {}
+        }
+        // interface names are limited to IFNAMSIZ - 1 characters
+        if self.vifname().len() > 15 {
+            return Err(format!(
+                "error(config): virtual interface name '{}' is too long",
+                self.vifname()
+            ));
+        }
+        if self.ifname().len() > 15 {
+            return Err(format!(
+                "error(config): VLAN interface name '{}' is too long",
+                self.ifname()
+            ));
+        }
+        Ok(())
+    }
 }
 
 /// Timers Option Type
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 struct Timers {
     advert: u8,
 }
@@ -288,6 +534,22 @@ pub struct Protocols {
     pub r#static: Option<Vec<Static>>,
 }
 
+// Protocols Option Implementation
+impl Protocols {
+    // validate() method
+    pub fn validate(&self) -> Result<(), String> {
+        match &self.r#static {
+            Some(stv) => {
+                for st in stv {
+                    st.validate()?;
+                }
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+}
+
 /// Static Option Type
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Static {
@@ -336,43 +598,164 @@ impl Static {
             None => 0,
         }
     }
+    // validate() method
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, value) in [
+            ("route", &self.route),
+            ("mask", &self.mask),
+            ("nh", &self.nh),
+        ]
+        .iter()
+        {
+            match value.parse::<IpAddr>() {
+                Ok(IpAddr::V4(_)) => {}
+                _ => {
+                    return Err(format!(
+                        "error(config-static): invalid IPv4 {} '{}'",
+                        name, value
+                    ))
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 // decode_config() function
-/// read and decode configuration file
+/// read, decode and verify the configuration file
 pub fn decode_config(filename: String, cfgtype: CfgType) -> CConfig {
-    let file: std::string::String = match std::fs::read_to_string(filename) {
+    match load_config(&filename, cfgtype).and_then(|c| validate_config(&c).map(|_| c)) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+// validate_config() function
+/// verify the global directives, and the virtual routers and protocols sections
+pub fn validate_config(config: &CConfig) -> Result<(), String> {
+    config.validate()?;
+    for vr in config.vrouter.iter().flatten() {
+        vr.validate()?;
+    }
+    if let Some(p) = &config.protocols {
+        p.validate()?;
+    }
+    Ok(())
+}
+
+// load_config() function
+/// read and decode the configuration file, without exiting on errors
+pub fn load_config(filename: &str, cfgtype: CfgType) -> Result<CConfig, String> {
+    let file = match std::fs::read_to_string(filename) {
         Ok(s) => s,
         Err(e) => {
-            eprintln!(
+            return Err(format!(
                 "error(config): Cannot read rVRRPd configuration file: {}",
                 e
-            );
-            std::process::exit(1);
+            ))
         }
     };
+    match cfgtype {
+        // TOML
+        CfgType::Toml => toml::from_str(&file)
+            .map_err(|e| format!("error(config): Cannot parse TOML configuration file: {}", e)),
+        // JSON
+        CfgType::Json => serde_json::from_str(&file)
+            .map_err(|e| format!("error(config): Cannot parse JSON configuration file: {}", e)),
+    }
+}
+
+// encode_config() function
+/// encode configuration in the given format
+pub fn encode_config(config: &CConfig, cfgtype: CfgType) -> Result<String, String> {
     match cfgtype {
         // TOML
         CfgType::Toml => {
-            let config: CConfig = match toml::from_str(&file) {
-                Ok(c) => c,
+            // go through a toml value so tables are emitted after plain values
+            let value = match toml::Value::try_from(config) {
+                Ok(v) => v,
                 Err(e) => {
-                    eprintln!("error(config): Cannot parse TOML configuration file: {}", e);
-                    std::process::exit(1);
+                    return Err(format!(
+                        "error(config): Cannot encode TOML configuration: {}",
+                        e
+                    ))
                 }
             };
-            return config;
+            match toml::to_string(&value) {
+                Ok(s) => Ok(s),
+                Err(e) => Err(format!(
+                    "error(config): Cannot encode TOML configuration: {}",
+                    e
+                )),
+            }
         }
         // JSON
-        CfgType::Json => {
-            let config: CConfig = match serde_json::from_str(&file) {
-                Ok(c) => c,
-                Err(e) => {
-                    eprintln!("error(config): Cannot parse JSON configuration file: {}", e);
-                    std::process::exit(1);
-                }
-            };
-            return config;
+        CfgType::Json => match serde_json::to_string_pretty(config) {
+            Ok(s) => Ok(s),
+            Err(e) => Err(format!(
+                "error(config): Cannot encode JSON configuration: {}",
+                e
+            )),
+        },
+    }
+}
+
+// save_config() function
+/// atomically replace the configuration file, keeping a backup copy
+/// of the previous one as '<filename>.bak'
+pub fn save_config(filename: &str, config: &CConfig, cfgtype: CfgType) -> Result<(), String> {
+    let content = encode_config(config, cfgtype)?;
+    let mut rng = rand::thread_rng();
+    let tmpfile = format!("{}.{:016x}.tmp", filename, rng.gen::<u64>());
+    let bakfile = format!("{}.bak", filename);
+
+    // the configuration holds secrets, keep the original permissions
+    // (or only allow the owner to access a new file)
+    let perms = std::fs::metadata(filename).map(|m| m.permissions()).ok();
+    let mode = perms.as_ref().map_or(0o600, |p| p.mode() & 0o777);
+
+    // write the new configuration to a temporary file next to the original,
+    // never opening a file which already exists
+    {
+        let mut file = match std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(mode)
+            .open(&tmpfile)
+        {
+            Ok(f) => f,
+            Err(e) => return Err(format!("error(config): Cannot create {}: {}", tmpfile, e)),
+        };
+        // the mode of the new file is restricted by the umask
+        if let Some(p) = perms {
+            let _ = file.set_permissions(p);
+        }
+        if let Err(e) = file
+            .write_all(content.as_bytes())
+            .and_then(|_| file.sync_all())
+        {
+            let _ = std::fs::remove_file(&tmpfile);
+            return Err(format!("error(config): Cannot write {}: {}", tmpfile, e));
+        }
+    }
+
+    // backup the current configuration file (if any)
+    if std::path::Path::new(filename).exists() {
+        if let Err(e) = std::fs::copy(filename, &bakfile) {
+            let _ = std::fs::remove_file(&tmpfile);
+            return Err(format!("error(config): Cannot backup {}: {}", filename, e));
+        }
+    }
+
+    // replace the configuration file
+    match std::fs::rename(&tmpfile, filename) {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            let _ = std::fs::remove_file(&tmpfile);
+            Err(format!("error(config): Cannot replace {}: {}", filename, e))
         }
     }
 }
@@ -380,12 +763,25 @@ pub fn decode_config(filename: String, cfgtype: CfgType) -> CConfig {
 /// API structure
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct API {
+    #[serde(default)]
     users: Vec<String>,
+    keys: Option<Vec<APIKey>>,
     secret: Option<String>,
     host: Option<String>,
     tls: Option<bool>,
     tls_key: Option<String>,
     tls_cert: Option<String>,
+    tls_client_ca: Option<String>,
+    client_certs: Option<Vec<APIClientCert>>,
+    session_timeout: Option<u64>,
+    session_bind: Option<bool>,
+    login_max_failures: Option<u32>,
+    login_lockout: Option<u64>,
+    rate_limit: Option<u32>,
+    audit_log: Option<String>,
+    audit_key: Option<String>,
+    tcp: Option<bool>,
+    unix: Option<APIUnix>,
 }
 
 // API structure implementation
@@ -394,6 +790,13 @@ impl API {
     pub fn users(&self) -> Vec<String> {
         self.users.clone()
     }
+    // keys() method
+    pub fn keys(&self) -> Vec<APIKey> {
+        match &self.keys {
+            Some(k) => k.clone(),
+            None => Vec::new(),
+        }
+    }
     // secret() method
     pub fn secret(&self) -> String {
         let secret = match &self.secret {
@@ -431,6 +834,284 @@ impl API {
             None => RVRRPD_CFG_DFLT_TLSCERT.to_string(),
         }
     }
+    // tls_client_ca() method
+    pub fn tls_client_ca(&self) -> Option<String> {
+        self.tls_client_ca.clone()
+    }
+    // client_certs() method
+    pub fn client_certs(&self) -> Vec<APIClientCert> {
+        match &self.client_certs {
+            Some(c) => c.clone(),
+            None => Vec::new(),
+        }
+    }
+    // session_timeout() method
+    pub fn session_timeout(&self) -> u64 {
+        match self.session_timeout {
+            Some(t) => t,
+            None => RVRRPD_CFG_DFLT_SESSION_TIMEOUT,
+        }
+    }
+    // session_bind() method
+    pub fn session_bind(&self) -> bool {
+        match self.session_bind {
+            Some(b) => b,
+            None => false,
+        }
+    }
+    // login_max_failures() method
+    pub fn login_max_failures(&self) -> u32 {
+        match self.login_max_failures {
+            Some(n) => n,
+            None => RVRRPD_CFG_DFLT_LOGIN_MAX_FAILURES,
+        }
+    }
+    // login_lockout() method
+    pub fn login_lockout(&self) -> u64 {
+        match self.login_lockout {
+            Some(t) => t,
+            None => RVRRPD_CFG_DFLT_LOGIN_LOCKOUT,
+        }
+    }
+    // rate_limit() method
+    pub fn rate_limit(&self) -> Option<u32> {
+        self.rate_limit
+    }
+    // audit_log() method
+    pub fn audit_log(&self) -> String {
+        match &self.audit_log {
+            Some(s) => s.clone(),
+            None => RVRRPD_DFLT_AUDITLOG.to_string(),
+        }
+    }
+    // audit_key() method
+    // return the file holding the HMAC key of the audit log
+    pub fn audit_key(&self) -> String {
+        match &self.audit_key {
+            Some(s) => s.clone(),
+            None => format!("{}.key", self.audit_log()),
+        }
+    }
+    // tcp() method
+    // return false if the API only listens on its Unix domain socket
+    pub fn tcp(&self) -> bool {
+        match self.tcp {
+            Some(b) => b,
+            None => true,
+        }
+    }
+    // unix() method
+    pub fn unix(&self) -> Option<APIUnix> {
+        self.unix.clone()
+    }
+    // redacted() method
+    // return the section without the secrets, the credentials hashes and the
+    // paths of the keys, as returned to the API clients
+    pub fn redacted(&self) -> API {
+        API {
+            users: Vec::new(),
+            keys: None,
+            secret: None,
+            tls_key: None,
+            audit_key: None,
+            ..self.clone()
+        }
+    }
+}
+
+/// API Unix Domain Socket structure
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct APIUnix {
+    path: Option<String>,
+    owner: Option<u32>,
+    group: Option<u32>,
+    mode: Option<u32>,
+    peers: Option<Vec<APIUnixPeer>>,
+}
+
+// API Unix Domain Socket structure implementation
+impl APIUnix {
+    // path() method
+    pub fn path(&self) -> String {
+        match &self.path {
+            Some(s) => s.clone(),
+            None => RVRRPD_CFG_DFLT_UNIX_SOCKET.to_string(),
+        }
+    }
+    // owner() method
+    pub fn owner(&self) -> Option<u32> {
+        self.owner
+    }
+    // group() method
+    pub fn group(&self) -> Option<u32> {
+        self.group
+    }
+    // mode() method
+    pub fn mode(&self) -> u32 {
+        match self.mode {
+            Some(m) => m,
+            None => RVRRPD_CFG_DFLT_UNIX_MODE,
+        }
+    }
+    // peers() method
+    pub fn peers(&self) -> Vec<APIUnixPeer> {
+        match &self.peers {
+            Some(p) => p.clone(),
+            None => Vec::new(),
+        }
+    }
+    // validate() method
+    pub fn validate(&self) -> Result<(), String> {
+        if self.mode() > 0o777 {
+            return Err(format!(
+                "error(config): invalid API Unix socket mode {:o}",
+                self.mode()
+            ));
+        }
+        for p in self.peers() {
+            if p.uid.is_none() && p.gid.is_none() {
+                return Err(
+                    "error(config): an API Unix socket peer needs a uid or a gid".to_string(),
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// API Unix Domain Socket Peer structure
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct APIUnixPeer {
+    uid: Option<u32>,
+    gid: Option<u32>,
+    access: u8,
+}
+
+// API Unix Domain Socket Peer structure implementation
+impl APIUnixPeer {
+    // matches() method
+    // return true if the peer credentials match the uid and gid (if set)
+    pub fn matches(&self, uid: u32, gid: u32) -> bool {
+        self.uid.map_or(true, |u| u == uid) && self.gid.map_or(true, |g| g == gid)
+    }
+    // access() method
+    pub fn access(&self) -> u8 {
+        self.access
+    }
+}
+
+/// API Client Certificate structure
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct APIClientCert {
+    identity: String,
+    access: u8,
+}
+
+// API Client Certificate structure implementation
+impl APIClientCert {
+    // identity() method
+    pub fn identity(&self) -> &str {
+        &self.identity
+    }
+    // access() method
+    pub fn access(&self) -> u8 {
+        self.access
+    }
+}
+
+/// API Key structure
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct APIKey {
+    key: String,
+    expires: Option<String>,
+    allowed: Option<Vec<String>>,
+}
+
+// API Key structure implementation
+impl APIKey {
+    // key() method
+    pub fn key(&self) -> String {
+        self.key.clone()
+    }
+    // expires() method
+    // return the expiry time of the key (in seconds since the Unix Epoch)
+    pub fn expires(&self) -> Option<i64> {
+        match &self.expires {
+            Some(s) => DateTime::parse_from_rfc3339(s).ok().map(|d| d.timestamp()),
+            None => None,
+        }
+    }
+    // allows() method
+    // return true if the key can be used from the given client address
+    pub fn allows(&self, addr: Option<IpAddr>) -> bool {
+        match &self.allowed {
+            Some(list) => match addr {
+                Some(a) => list
+                    .iter()
+                    .filter_map(|p| parse_prefix(p))
+                    .any(|p| prefix_contains(p, a)),
+                None => false,
+            },
+            None => true,
+        }
+    }
+    // validate() method
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(s) = &self.expires {
+            if DateTime::parse_from_rfc3339(s).is_err() {
+                return Err(format!(
+                    "error(config): invalid API key expiry '{}' (RFC 3339 expected)",
+                    s
+                ));
+            }
+        }
+        if let Some(list) = &self.allowed {
+            for p in list {
+                if parse_prefix(p).is_none() {
+                    return Err(format!(
+                        "error(config): invalid API key allowed address '{}'",
+                        p
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// parse_prefix() function
+// parse an IP address, optionally followed by a prefix length
+fn parse_prefix(s: &str) -> Option<(IpAddr, u8)> {
+    let mut it = s.splitn(2, '/');
+    let addr = it.next()?.parse::<IpAddr>().ok()?;
+    let max = if addr.is_ipv4() { 32 } else { 128 };
+    let len = match it.next() {
+        Some(l) => l.parse::<u8>().ok()?,
+        None => max,
+    };
+    if len > max {
+        return None;
+    }
+    Some((addr, len))
+}
+
+// prefix_contains() function
+fn prefix_contains(prefix: (IpAddr, u8), addr: IpAddr) -> bool {
+    match (prefix.0, addr) {
+        (IpAddr::V4(n), IpAddr::V4(a)) => {
+            let mask = u32::max_value()
+                .checked_shl(32 - prefix.1 as u32)
+                .unwrap_or(0);
+            u32::from(n) & mask == u32::from(a) & mask
+        }
+        (IpAddr::V6(n), IpAddr::V6(a)) => {
+            let mask = u128::max_value()
+                .checked_shl(128 - prefix.1 as u32)
+                .unwrap_or(0);
+            u128::from(n) & mask == u128::from(a) & mask
+        }
+        _ => false,
+    }
 }
 
 // gen_runtime_secret() function
@@ -446,3 +1127,81 @@ fn gen_runtime_secret() -> String {
     }
     SECRET.to_string()
 }
+
+// parse_auth_type() function
+/// return the authentication type of a configuration string, if supported
+pub fn parse_auth_type(s: &str) -> Option<u8> {
+    match s {
+        "rfc2338-simple" => Some(AUTH_TYPE_SIMPLE),
+        "p0-t8-sha256" => Some(AUTH_TYPE_P0),
+        "p1-b8-shake256" => Some(AUTH_TYPE_P1),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // vrouter_config() function
+    // return a configuration with a single virtual router and the given directives
+    fn vrouter_config(directives: &str) -> CConfig {
+        let cfg = format!(
+            "[[vrouter]]\ngroup = 1\ninterface = \"eth0\"\nvip = \"10.0.0.1\"\n{}\n",
+            directives
+        );
+        toml::from_str(&cfg).unwrap()
+    }
+
+    #[test]
+    fn macvlan_modes_are_verified_on_load() {
+        for mode in ["private", "vepa", "bridge", "passthru"].iter() {
+            let cfg = vrouter_config(&format!(
+                "iftype = \"macvlan\"\nmacvlan_mode = \"{}\"",
+                mode
+            ));
+            assert!(validate_config(&cfg).is_ok(), "mode {}", mode);
+        }
+        // a typo must not silently select the bridge mode
+        let cfg = vrouter_config("iftype = \"macvlan\"\nmacvlan_mode = \"brigde\"");
+        assert!(validate_config(&cfg).is_err());
+    }
+
+    #[test]
+    fn interface_types_are_verified_on_load() {
+        assert!(validate_config(&vrouter_config("iftype = \"ipvlan\"")).is_ok());
+        assert!(validate_config(&vrouter_config("iftype = \"ipvlna\"")).is_err());
+    }
+
+    #[test]
+    fn reload_reconfigures_the_runtime_parameters_only() {
+        let vrc = |directives: &str| vrouter_config(directives).vrouter.unwrap().remove(0);
+        let current = vrc("priority = 100");
+
+        // these changes are applied without restarting the virtual router
+        assert!(current.same_setup(&vrc("priority = 200\npreemption = true")));
+        assert!(current.same_setup(&vrc(
+            "priority = 100\nauth_type = \"rfc2338-simple\"\nauth_secret = \"secret\""
+        )));
+        assert!(current.same_setup(&vrc("priority = 100\n[vrouter.timers]\nadvert = 3")));
+        // any other change restarts it
+        assert!(!current.same_setup(&vrc("priority = 100\nvlan = 10")));
+        assert!(!current.same_setup(&vrc("priority = 100\nrfc3768 = false")));
+    }
+
+    #[test]
+    fn api_secrets_are_redacted() {
+        let cfg = vrouter_config(
+            "[api]\nusers = [ \"{{SHA256}}admin:2:0011:2233\" ]\nsecret = \"secret\"\n\
+             tls_key = \"/etc/rvrrpd/ssl/key.pem\"\naudit_key = \"/etc/rvrrpd/audit.key\"\n\
+             rate_limit = 10\n[[api.keys]]\nkey = \"{{SHA256}}ansible:2:4455:6677\"",
+        );
+        let api = serde_json::to_string(&cfg.api.unwrap().redacted()).unwrap();
+
+        for s in ["0011", "secret", "key.pem", "audit.key", "4455"].iter() {
+            assert!(!api.contains(s), "{} in {}", s, api);
+        }
+        // the other directives are kept
+        assert!(api.contains("\"rate_limit\":10"));
+    }
+}
@@ -7,6 +7,8 @@ pub const RVRRPD_DFLT_PIDFILE: &str = "/var/run/rvrrpd.pid";
 pub const RVRRPD_DFLT_WORKDIR: &str = "/tmp";
 pub const RVRRPD_DFLT_LOGFILE: &str = "/var/log/rvrrpd.log";
 pub const RVRRPD_DFLT_ELOGFILE: &str = "/var/log/rvrrpd-error.log";
+pub const RVRRPD_DFLT_AUDITLOG: &str = "/var/log/rvrrpd-audit.log";
+pub const RVRRPD_DFLT_CRASHDUMP: &str = "/var/log/rvrrpd-crash.json";
 pub const RVRRPD_DFLT_DATE_FORMAT: &str = "%b %e %Y %T";
 pub const RVRRPD_DFLT_MACVLAN_NAME: &str = "standby";
 pub const RVRRPD_DFLT_CLIENT_API: &str = "disabled";
@@ -15,6 +17,14 @@ pub const RVRRPD_VERSION_STRING: &str = "0.1.3";
 // Config Constants
 pub const RVRRPD_CFG_DFLT_TLSKEY: &str = "/etc/rvrrpd/ssl/key.pem";
 pub const RVRRPD_CFG_DFLT_TLSCERT: &str = "/etc/rvrrpd/ssl/cert.pem";
+pub const RVRRPD_CFG_DFLT_SESSION_TIMEOUT: u64 = 3600;
+pub const RVRRPD_CFG_DFLT_LOGIN_MAX_FAILURES: u32 = 5;
+pub const RVRRPD_CFG_DFLT_LOGIN_LOCKOUT: u64 = 300;
+pub const RVRRPD_CFG_DFLT_UNIX_SOCKET: &str = "/var/run/rvrrpd.sock";
+pub const RVRRPD_CFG_DFLT_UNIX_MODE: u32 = 0o660;
+
+// Client API Events Constants
+pub const EVENTS_KEEPALIVE_INTERVAL: u64 = 15;
 
 // Debug Constants
 pub const DEBUG_LEVEL_INFO: u8 = 0;
@@ -40,11 +50,18 @@ pub const DEBUG_SRC_IP: &str = "ip";
 pub const DEBUG_SRC_AUTH: &str = "auth";
 pub const DEBUG_SRC_MACVLAN: &str = "macvlan";
 pub const DEBUG_SRC_BPF: &str = "bpf";
+pub const DEBUG_SRC_API: &str = "api";
+pub const DEBUG_SRC_DRYRUN: &str = "dry-run";
+pub const DEBUG_SRC_MONITOR: &str = "monitor";
 
 // Ethernet Constants
+pub const ETHER_P_ALL: u16 = 0x0003; // every protocol
 pub const ETHER_P_IP: u16 = 0x0800; // IPv4 (/usr/include/linux/if_ether.h)
 pub const ETHER_P_ARP: u16 = 0x0806;
-pub const ETHER_VRRP_IPADDR_POS: usize = 42; // Position of the IP addresses variable-length field
+pub const ETHER_P_8021Q: u16 = 0x8100; // 802.1Q VLAN tag
+pub const ETHER_P_8021AD: u16 = 0x88A8; // 802.1ad service VLAN tag (QinQ)
+pub const ETHER_VLAN_TAG_SIZE: usize = 4;
+pub const ETHER_VLAN_MAX_TAGS: usize = 2;
 pub const ETHER_VRRP_V2_SRC_MAC: [u8; 6] = [0x00, 0x00, 0x5e, 0x00, 0x01, 0x00];
 pub const ETHER_VRRP_V2_DST_MAC: [u8; 6] = [0x01, 0x00, 0x5e, 0x00, 0x00, 0x12];
 pub const ETHER_ARP_DST_MAC: [u8; 6] = [0xff, 0xff, 0xff, 0xff, 0xff, 0xff];
@@ -57,6 +74,8 @@ pub const ARP_OP_REQUEST: u16 = 1; // request
 // IP Constants
 pub const IP_FRAME_OFFSET: usize = 14;
 pub const IP_V4_VERSION: u8 = 0x45;
+pub const IP_HEADER_MIN_SIZE: usize = 20;
+pub const IP_HEADER_MAX_SIZE: usize = 60;
 pub const IP_UPPER_PROTO_VRRP: u8 = 112;
 pub const IP_TTL_VRRP_MINTTL: u8 = 255;
 pub const IP_DSCP_CS6: u8 = 0xc0;
@@ -64,6 +83,8 @@ pub const IP_DSCP_CS6: u8 = 0xc0;
 // VRRP Constants
 pub const VRRP_V2_FRAME_OFFSET: usize = 34;
 pub const VRRP_V2_CHECKSUM_POS: usize = 6;
+pub const VRRP_V2_HEADER_SIZE: usize = 8;
+pub const VRRP_V2_AUTH_DATA_SIZE: usize = 8;
 pub const VRRP_V2_VER_TYPE_AUTHMSG: u8 = 0x21;
 pub const VRRP_V2_IP_MCAST_DST: [u8; 4] = [224, 0, 0, 18];
 pub const VRRP_V2_ADVERT_VERSION_TYPE: u8 = 0x21;
@@ -73,3 +94,8 @@ pub const VRRP_V2_DEFAULT_PRIORITY: u8 = 100;
 pub const AUTH_TYPE_SIMPLE: u8 = 1;
 pub const AUTH_TYPE_P0: u8 = 250;
 pub const AUTH_TYPE_P1: u8 = 251;
+
+// Client API Access Levels
+pub const API_ACCESS_RUN: u8 = 0; // read-only access to running information
+pub const API_ACCESS_CONFIG: u8 = 1; // read access to the configuration
+pub const API_ACCESS_ADMIN: u8 = 2; // configuration and running state modifications
@@ -0,0 +1,270 @@
+//! events module
+//! This module publishes the virtual routers events to the client API subscribers.
+use super::*;
+
+// futures
+use futures::sync::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+
+// std
+use std::sync::Once;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Notification Enumerator (Serialize-able)
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Notification {
+    // virtual router state transition
+    StateChange {
+        group: u8,
+        interface: String,
+        from: String,
+        to: String,
+    },
+    // an ADVERTISEMENT has been received from a new master
+    NewMaster {
+        group: u8,
+        interface: String,
+        source: String,
+        priority: u8,
+    },
+    // an ADVERTISEMENT failed authentication
+    AuthFailure {
+        group: u8,
+        interface: String,
+        source: String,
+    },
+    // the priority of a virtual router has been changed
+    PriorityChange {
+        group: u8,
+        interface: String,
+        from: u8,
+        to: u8,
+    },
+    // a configuration section has been modified
+    ConfigReload {
+        section: String,
+    },
+    // a user or a client address has been locked out of the client API
+    LoginLockout {
+        user: Option<String>,
+        address: Option<String>,
+        until: u64,
+    },
+}
+
+// Notification Implementation
+impl Notification {
+    // matches() method
+    // return true if the notification passes the subscriber's filter and access level
+    fn matches(&self, group: Option<u8>, intf: &Option<String>, access: u8) -> bool {
+        let (g, i) = match self {
+            Notification::StateChange {
+                group, interface, ..
+            }
+            | Notification::NewMaster {
+                group, interface, ..
+            }
+            | Notification::AuthFailure {
+                group, interface, ..
+            }
+            | Notification::PriorityChange {
+                group, interface, ..
+            } => (group, interface),
+            // configuration changes are sent to every subscriber
+            Notification::ConfigReload { .. } => return true,
+            // security events are only sent to the administrators
+            Notification::LoginLockout { .. } => return access >= API_ACCESS_ADMIN,
+        };
+        group.map_or(true, |f| f == *g) && intf.as_ref().map_or(true, |f| f == i)
+    }
+}
+
+/// EventMessage Structure (Serialize-able)
+#[derive(Serialize)]
+struct EventMessage<'a> {
+    timestamp: u64,
+    #[serde(flatten)]
+    notification: &'a Notification,
+}
+
+/// Subscriber Structure
+struct Subscriber {
+    group: Option<u8>,
+    interface: Option<String>,
+    session: Option<(String, u64)>, // session token and its expiry
+    access: u8,                     // access level of the session
+    tx: UnboundedSender<String>,
+}
+
+// Subscriber Implementation
+impl Subscriber {
+    // expired() method
+    // return true if the session of the subscriber has expired
+    fn expired(&self, now: u64) -> bool {
+        self.session.as_ref().map_or(false, |(_, e)| now >= *e)
+    }
+}
+
+// subscribers list
+lazy_static! {
+    static ref SUBSCRIBERS: Mutex<Vec<Subscriber>> = Mutex::new(Vec::new());
+}
+
+// keepalive thread
+static KEEPALIVE: Once = Once::new();
+
+// subscribe() function
+/// register a new subscriber, optionally filtering on a group and/or an interface.
+/// every event is received as a Server-Sent Events message. a subscriber
+/// authenticated with a session token is given the token and its expiry,
+/// so its stream is closed when the session ends. the security events are
+/// only sent to the subscribers with the administrator access level.
+pub fn subscribe(
+    group: Option<u8>,
+    interface: Option<String>,
+    session: Option<(String, u64)>,
+    access: u8,
+) -> UnboundedReceiver<String> {
+    // the sessions expiry is checked on every keepalive
+    KEEPALIVE.call_once(|| {
+        thread::spawn(keepalive);
+    });
+
+    let (tx, rx) = unbounded();
+    SUBSCRIBERS.lock().unwrap().push(Subscriber {
+        group,
+        interface,
+        session,
+        access,
+        tx,
+    });
+    rx
+}
+
+// close_session() function
+/// close the streams of the subscribers authenticated with a session token,
+/// or with any session token if none is given
+pub fn close_session(token: Option<&str>) {
+    SUBSCRIBERS
+        .lock()
+        .unwrap()
+        .retain(|s| match (&s.session, token) {
+            (Some((t, _)), Some(token)) => t != token,
+            (Some(_), None) => false,
+            (None, _) => true,
+        });
+}
+
+// keepalive() function
+// periodically send a comment line to the subscribers, so the streams
+// of expired sessions and of the clients which have gone away are closed
+fn keepalive() {
+    loop {
+        thread::sleep(Duration::from_secs(EVENTS_KEEPALIVE_INTERVAL));
+        let now = now();
+        SUBSCRIBERS.lock().unwrap().retain(|s| {
+            !s.expired(now) && s.tx.unbounded_send(": keepalive\n\n".to_string()).is_ok()
+        });
+    }
+}
+
+// now() function
+// return the current system time (in seconds since the Unix Epoch)
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+// publish() function
+/// send a notification to the matching subscribers
+pub fn publish(notification: Notification) {
+    let mut subscribers = SUBSCRIBERS.lock().unwrap();
+    // nothing to do if nobody is listening
+    if subscribers.is_empty() {
+        return;
+    }
+
+    let timestamp = now();
+    let msg = EventMessage {
+        timestamp,
+        notification: &notification,
+    };
+    let data = match serde_json::to_string(&msg) {
+        Ok(s) => format!("data: {}\n\n", s),
+        Err(_) => return,
+    };
+
+    // drop the subscribers which have gone away, or whose session has expired
+    subscribers.retain(|s| {
+        if s.expired(timestamp) {
+            false
+        } else if notification.matches(s.group, &s.interface, s.access) {
+            s.tx.unbounded_send(data.clone()).is_ok()
+        } else {
+            !s.tx.is_closed()
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // state_change() function
+    fn state_change(group: u8, interface: &str) -> Notification {
+        Notification::StateChange {
+            group,
+            interface: interface.to_string(),
+            from: "Backup".to_string(),
+            to: "Master".to_string(),
+        }
+    }
+
+    #[test]
+    fn notifications_match_the_filter() {
+        let n = state_change(1, "eth0");
+        let eth0 = Some("eth0".to_string());
+        assert!(n.matches(None, &None, API_ACCESS_RUN));
+        assert!(n.matches(Some(1), &None, API_ACCESS_RUN));
+        assert!(n.matches(Some(1), &eth0, API_ACCESS_RUN));
+        assert!(!n.matches(Some(2), &None, API_ACCESS_RUN));
+        assert!(!n.matches(Some(1), &Some("eth1".to_string()), API_ACCESS_RUN));
+
+        // configuration changes ignore the filter
+        let n = Notification::ConfigReload {
+            section: "vrouter".to_string(),
+        };
+        assert!(n.matches(Some(2), &eth0, API_ACCESS_RUN));
+    }
+
+    #[test]
+    fn lockouts_are_only_sent_to_administrators() {
+        let n = Notification::LoginLockout {
+            user: Some("admin".to_string()),
+            address: None,
+            until: 1000,
+        };
+        assert!(n.matches(Some(2), &None, API_ACCESS_ADMIN));
+        assert!(!n.matches(None, &None, API_ACCESS_CONFIG));
+        assert!(!n.matches(None, &None, API_ACCESS_RUN));
+    }
+
+    #[test]
+    fn subscribers_expire_with_their_session() {
+        let (tx, _rx) = unbounded();
+        let subscriber = |session| Subscriber {
+            group: None,
+            interface: None,
+            session,
+            access: API_ACCESS_RUN,
+            tx: tx.clone(),
+        };
+        assert!(!subscriber(None).expired(u64::max_value()));
+        let s = subscriber(Some(("token".to_string(), 1000)));
+        assert!(!s.expired(999));
+        assert!(s.expired(1000));
+    }
+}
@@ -14,9 +14,11 @@ use crate::debug::Verbose;
 // operating system drivers
 use crate::os::drivers::Operation;
 
-// address resolution protocol
-#[cfg(target_os = "linux")]
-use os::linux::arp::open_raw_socket_arp;
+// client API events
+use crate::events::{self, Notification};
+
+// transitions history
+use crate::history::Trigger;
 
 /// Internal Protocol States "Enumerator"
 #[derive(Debug)]
@@ -44,6 +46,7 @@ impl States {
 pub struct Timers {
     master_down: f32, // Timer that fires when ADVERTISEMENT has not been heared for 'master_down'
     advert: u8,       // Timer that fires sending of ADVERTISEMENT every 'advertint' interval
+    epoch: u32,       // Timers generation, running timer threads exit when it changes
 }
 
 // Timers Type Implementation
@@ -53,6 +56,7 @@ impl Timers {
         Timers {
             master_down,
             advert,
+            epoch: 0,
         }
     }
     // master_down() getter
@@ -65,20 +69,37 @@ impl Timers {
         //assert!(self.advert > 0);
         self.advert
     }
+    // epoch() getter
+    pub fn epoch(&self) -> u32 {
+        self.epoch
+    }
+    // restart() method
+    // invalidate the running timer threads, so new ones can be started
+    // with updated intervals
+    pub fn restart(&mut self) {
+        self.epoch = self.epoch.wrapping_add(1);
+    }
 }
 
 /// Flags Structure
 #[derive(Debug)]
 pub struct Flags {
-    down: u8,    // down flag
-    rtset: bool, // routes set
+    down: u8,        // down flag
+    rtset: bool,     // routes set
+    link_down: bool, // the interface is down
+    addr_lost: bool, // the interface lost its primary address
 }
 
 // Flags Type Implementation
 impl Flags {
     // new() method
     pub fn new(down: u8) -> Flags {
-        Flags { down, rtset: false }
+        Flags {
+            down,
+            rtset: false,
+            link_down: false,
+            addr_lost: false,
+        }
     }
     // get_down_flag() method
     pub fn get_down_flag(&self) -> u8 {
@@ -107,6 +128,33 @@ impl Flags {
     pub fn clear_rtset(&mut self) {
         self.rtset = false;
     }
+    // link_down() getter
+    pub fn link_down(&self) -> bool {
+        self.link_down
+    }
+    // set_link_down() setter
+    pub fn set_link_down(&mut self, down: bool) {
+        self.link_down = down;
+    }
+    // set_addr_lost() setter
+    pub fn set_addr_lost(&mut self, lost: bool) {
+        self.addr_lost = lost;
+    }
+    // faulty() method
+    // return true if the interface cannot be used by the virtual router
+    pub fn faulty(&self) -> bool {
+        self.link_down || self.addr_lost
+    }
+}
+
+/// Reconfig Structure
+/// Parameters changed at runtime on a running virtual router
+#[derive(Debug, Clone, Default)]
+pub struct Reconfig {
+    pub prio: Option<u8>,
+    pub preempt: Option<bool>,
+    pub adverint: Option<u8>,
+    pub auth: Option<(u8, Option<String>)>,
 }
 
 /// Event Enumerator
@@ -115,10 +163,16 @@ pub enum Event {
     Startup,
     Shutdown, // Internal Shutdown Event
     Terminate,
-    MasterDown,          // internal master down notification
-    MasterDownExpiry,    // internal master_down timer expiry notification
-    Advert([u8; 4], u8), // got ADVERTISEMENT message (carrying priority)
-    GenAdvert,           // generate an ADVERTISEMENT message
+    MasterDown,                              // internal master down notification
+    MasterDownExpiry,                        // internal master_down timer expiry notification
+    Advert([u8; 4], u8),                     // got ADVERTISEMENT message (carrying priority)
+    GenAdvert,                               // generate an ADVERTISEMENT message
+    Reconfigure(Reconfig, mpsc::Sender<()>), // runtime parameters change (acknowledged)
+    Maintenance(bool, mpsc::Sender<()>),     // enter or leave maintenance mode (acknowledged)
+    LinkState(bool),                         // the interface went down or came back up
+    AddrAdded([u8; 4]),                      // an IPv4 address has been added on the interface
+    AddrRemoved([u8; 4]),                    // an IPv4 address has been removed from the interface
+    LinkChange(i32, String),                 // the interface has been renamed or re-created
 }
 
 // fsm_run() function
@@ -131,17 +185,6 @@ pub fn fsm_run(
     fd: i32,
     debug: &Verbose,
 ) {
-    // print debugging information
-    print_debug(
-        debug,
-        DEBUG_LEVEL_HIGH,
-        DEBUG_SRC_FSM,
-        format!("registering notification sender channel for thread {}", id),
-    );
-
-    // register notification sender channel
-    register_tx(&vr, &tx, id, &debug);
-
     // start thread loop
     loop {
         // // receive on client API receive channel (non-blocking)
@@ -224,6 +267,70 @@ pub fn fsm_run(
             format!("worker thread {} write lock acquired", id),
         );
 
+        // the event triggering a possible transition, the operations performed
+        // while handling a previous event are forgotten
+        let trigger = Trigger::from(&event);
+        vr.parameters.history().lock().unwrap().clear_ops();
+
+        // apply runtime parameters changes, regardless of the current state
+        if let Event::Reconfigure(rc, ack) = event {
+            // print debugging information
+            print_debug(
+                debug,
+                DEBUG_LEVEL_HIGH,
+                DEBUG_SRC_WORKER,
+                format!("worker thread {} applying {:?}", id, rc),
+            );
+            reconfigure(&mut vr, &rc, fd, timer_tx, timer_vr, debug);
+            // acknowledge the change, the requester may have given up waiting
+            ack.send(()).unwrap_or(());
+            continue;
+        }
+
+        // enter or leave maintenance mode, regardless of the current state
+        if let Event::Maintenance(on, ack) = event {
+            vr.parameters.set_maintenance(on);
+            // print information
+            let vip = vr.parameters.vip();
+            print_debug(
+                debug,
+                DEBUG_LEVEL_INFO,
+                DEBUG_SRC_INFO,
+                format!(
+                    "VR {}.{}.{}.{} for group {} on interface {} - Maintenance mode {}",
+                    vip[0],
+                    vip[1],
+                    vip[2],
+                    vip[3],
+                    vr.parameters.vrid(),
+                    vr.parameters.interface(),
+                    if on { "enabled" } else { "disabled" }
+                ),
+            );
+            // a master releases its mastership right away
+            if on && vr.current_state() == "Master" {
+                release_mastership(&mut vr, fd, timer_tx, timer_vr, debug);
+                change_state(&mut vr, States::Backup, trigger);
+            }
+            ack.send(()).unwrap_or(());
+            continue;
+        }
+
+        // interface changes reported by the netlink monitor, regardless of the current state
+        let event = match event {
+            Event::LinkState(_)
+            | Event::AddrAdded(_)
+            | Event::AddrRemoved(_)
+            | Event::LinkChange(..) => {
+                match link_change(&mut vr, event, fd, trigger.clone(), debug) {
+                    // the interface has recovered, start the virtual router again
+                    Some(e) => e,
+                    None => continue,
+                }
+            }
+            e => e,
+        };
+
         // evaluate virtual router's current state
         let st = match &vr.get_states() {
             States::Down => {
@@ -265,7 +372,10 @@ pub fn fsm_run(
 
                         // if the virtual router is the owner of the virtual ip address
                         // OR the priority has been configured at 255
-                        if vr.is_owner_vip(&vr.parameters.vip()) || vr.parameters.prio() == 255 {
+                        // (unless it is started in maintenance mode)
+                        if (vr.is_owner_vip(&vr.parameters.vip()) || vr.parameters.prio() == 255)
+                            && !vr.parameters.maintenance()
+                        {
                             // force the priority to 255
                             vr.parameters.set_prio(255);
                             // set VRRP virtual mac address
@@ -277,10 +387,10 @@ pub fn fsm_run(
                             {
                                 // setup MAC address or virtual interface
                                 match vr.parameters.iftype() {
-                                    // if vr's interface is of type macvlan
-                                    IfTypes::macvlan => {
-                                        // create macvlan interface
-                                        match vr.setup_macvlan_link(vmac, Operation::Add, debug) {
+                                    // if vr's interface is of type macvlan or ipvlan
+                                    IfTypes::macvlan | IfTypes::ipvlan => {
+                                        // create macvlan or ipvlan interface
+                                        match vr.setup_virtual_link(vmac, Operation::Add, debug) {
                                             Some((vif_idx, vif_name)) => {
                                                 // store the virtual interface's index
                                                 vr.parameters.set_vifidx(vif_idx);
@@ -294,7 +404,11 @@ pub fn fsm_run(
                                                 vr.parameters.set_ifmac(ifmac);
                                             }
                                             // if it failed for some reasons, do not change vr's interface
-                                            None => (),
+                                            None => {
+                                                // save vr's interface mac
+                                                let ifmac = vr.get_mac_addresses(fd, debug);
+                                                vr.parameters.set_ifmac(ifmac);
+                                            }
                                         };
                                     }
                                     _ => {
@@ -302,7 +416,7 @@ pub fn fsm_run(
                                         let ifmac = vr.get_mac_addresses(fd, debug);
                                         vr.parameters.set_ifmac(ifmac);
                                         // set virtual router's MAC address
-                                        vr.set_mac_addresses(fd, vmac, debug);
+                                        vr.set_vmac(fd, Operation::Add, debug);
                                     }
                                 }
                             }
@@ -318,22 +432,14 @@ pub fn fsm_run(
                                 ),
                             }
 
-                            // --- Linux specific ARP handling
-                            #[cfg(target_os = "linux")]
-                            {
-                                // send gratuitious ARP requests
-                                let arp_sockfd = open_raw_socket_arp().unwrap();
-                                vr.broadcast_gratuitious_arp(arp_sockfd, debug).unwrap();
-                            }
-                            // END Linux specific ARP handling
-
-                            // --- FreeBSD specific ARP handling
-                            #[cfg(target_os = "freebsd")]
-                            {
-                                // reuse BPF file descriptor
-                                vr.broadcast_gratuitious_arp(fd, &debug).unwrap();
+                            // send gratuitious ARP requests
+                            if let Err(e) = vr.broadcast_gratuitious_arp(fd, debug) {
+                                eprintln!(
+                                    "error(fsm): error while sending gratuitous ARP on interface {}: {}",
+                                    vr.parameters.interface(),
+                                    e
+                                );
                             }
-                            // END FreeBSD specific ARP handling
 
                             // set advertisement interval
                             vr.timers.advert = vr.parameters.adverint();
@@ -393,12 +499,14 @@ pub fn fsm_run(
             States::Backup => {
                 match event {
                     // event: If ADVERTISEMENT message is received
-                    Event::Advert(_ipsrc, prio) => {
+                    Event::Advert(ipsrc, prio) => {
                         // if the priority is zero then set the master_down timer to skew_time
                         if prio == 0 {
                             // set master_down interval to skew_time
                             vr.timers.master_down = vr.parameters.skewtime();
                         } else {
+                            // keep track of the master router
+                            seen_master(&mut vr, ipsrc, prio);
                             // if priority is greater than or equal to the local priority OR preempt is false
                             if vr.parameters.preempt() == false || prio >= vr.parameters.prio() {
                                 // clear down flag (signal master is alive)
@@ -429,6 +537,17 @@ pub fn fsm_run(
                     }
                     // event: If the Timers::master_down reached zero
                     Event::MasterDown => {
+                        // a virtual router in maintenance must not become master
+                        if vr.parameters.maintenance() {
+                            // print debugging information
+                            print_debug(
+                                debug,
+                                DEBUG_LEVEL_HIGH,
+                                DEBUG_SRC_FSM,
+                                format!("ignoring MasterDown event in maintenance mode"),
+                            );
+                            continue;
+                        }
                         // print information
                         let vip = vr.parameters.vip();
                         print_debug(
@@ -453,10 +572,10 @@ pub fn fsm_run(
                         #[cfg(target_os = "linux")]
                         // setup MAC address or virtual interface
                         match vr.parameters.iftype() {
-                            // if vr's interface is of type macvlan
-                            IfTypes::macvlan => {
-                                // create macvlan interface
-                                match vr.setup_macvlan_link(vmac, Operation::Add, debug) {
+                            // if vr's interface is of type macvlan or ipvlan
+                            IfTypes::macvlan | IfTypes::ipvlan => {
+                                // create macvlan or ipvlan interface
+                                match vr.setup_virtual_link(vmac, Operation::Add, debug) {
                                     Some((vif_idx, vif_name)) => {
                                         // store the virtual interface's index
                                         vr.parameters.set_vifidx(vif_idx);
@@ -470,7 +589,11 @@ pub fn fsm_run(
                                         vr.parameters.set_ifmac(ifmac);
                                     }
                                     // if it failed for some reasons, do not change vr's interface
-                                    None => (),
+                                    None => {
+                                        // save vr's interface mac
+                                        let ifmac = vr.get_mac_addresses(fd, debug);
+                                        vr.parameters.set_ifmac(ifmac);
+                                    }
                                 };
                             }
                             _ => {
@@ -478,48 +601,27 @@ pub fn fsm_run(
                                 let ifmac = vr.get_mac_addresses(fd, debug);
                                 vr.parameters.set_ifmac(ifmac);
                                 // set virtual router's MAC address
-                                vr.set_mac_addresses(fd, vmac, debug);
+                                vr.set_vmac(fd, Operation::Add, debug);
                             }
                         }
                         // END Linux specific interface type handling
 
-                        // --- Linux specific interface type handling
-                        #[cfg(target_os = "linux")]
-                        // set VIP according to network driver in use
-                        match vr.parameters.netdrv() {
-                            NetDrivers::ioctl => {
-                                // set IP addresses (including VIP) on the vr's interface
-                                vr.set_ip_addresses(fd, Operation::Add, debug);
-                                // set routes
-                                vr.set_ip_routes(fd, Operation::Add, debug);
-                            }
-                            NetDrivers::libnl => {
-                                // add vip on vr's interface
-                                vr.set_ip_addresses(fd, Operation::Add, debug);
-                                // set routes
-                                vr.set_ip_routes(fd, Operation::Add, debug);
-                            }
-                        }
-                        // END Linux specific interface type handling
+                        // set IP addresses (including VIP) on the vr's interface
+                        vr.set_ip_addresses(fd, Operation::Add, debug);
 
-                        // --- Linux specific ARP handling
+                        // --- Linux specific routes handling
                         #[cfg(target_os = "linux")]
-                        {
-                            // send gratuitious ARP requests
-                            let arp_sockfd = open_raw_socket_arp().unwrap();
-                            vr.broadcast_gratuitious_arp(arp_sockfd, debug).unwrap();
-                        }
-                        // END Linux specific ARP handling
+                        vr.set_ip_routes(fd, Operation::Add, debug);
+                        // END Linux specific routes handling
 
-                        // --- FreeBSD specific interface tyoe handling
-                        #[cfg(target_os = "freebsd")]
-                        {
-                            // set VIP
-                            vr.set_ip_addresses(fd, Operation::Add, debug);
-                            // reuse BPF file descriptor
-                            vr.broadcast_gratuitious_arp(fd, &debug).unwrap();
+                        // send gratuitious ARP requests
+                        if let Err(e) = vr.broadcast_gratuitious_arp(fd, debug) {
+                            eprintln!(
+                                "error(fsm): error while sending gratuitous ARP on interface {}: {}",
+                                vr.parameters.interface(),
+                                e
+                            );
                         }
-                        // END FreeBSD specific interface tyoe handling
 
                         // if the master_down and advert timers have been canceled, restart them.
                         if (vr.timers.master_down <= 0.0) && (vr.timers.advert <= 0) {
@@ -642,54 +744,11 @@ pub fn fsm_run(
                                     DEBUG_SRC_FSM,
                                     format!("down flag cleared in Master state"),
                                 );
+                                // keep track of the new master router
+                                seen_master(&mut vr, ipsrc, prio);
 
-                                // --- Linux specific interface tyoe handling
-                                #[cfg(target_os = "linux")]
-                                // restore primary or delete vip on vr's interface
-                                match vr.parameters.iftype() {
-                                    IfTypes::macvlan => {
-                                        // removes macvlan interface
-                                        vr.setup_macvlan_link(
-                                            vr.parameters.ifmac(),
-                                            Operation::Rem,
-                                            debug,
-                                        );
-                                        // restore back vif and physical interfaces
-                                        let vif = vr.parameters.interface();
-                                        let phys = vr.parameters.vifname();
-                                        vr.parameters.set_vifname(vif);
-                                        vr.parameters.set_interface(phys);
-                                        // remove added routes
-                                        vr.set_ip_routes(fd, Operation::Rem, debug);
-                                    }
-                                    _ => {
-                                        // restore interface's MAC address
-                                        vr.set_mac_addresses(fd, vr.parameters.ifmac(), debug);
-                                        match vr.parameters.netdrv() {
-                                            NetDrivers::ioctl => {
-                                                // restore primary IP
-                                                #[cfg(target_os = "linux")]
-                                                vr.set_ip_addresses(fd, Operation::Rem, debug);
-                                                // re-set routes
-                                                vr.set_ip_routes(fd, Operation::Add, debug);
-                                            }
-                                            NetDrivers::libnl => {
-                                                // delete vip
-                                                vr.delete_ip_addresses(fd, debug);
-                                            }
-                                        }
-                                    }
-                                }
-                                // END Linux specific interface type handling
-
-                                // -- FreeBSD specific interface type handling
-                                #[cfg(target_os = "freebsd")]
-                                {
-                                    // we don't have to re-set the mac address here
-                                    // delete the VIP
-                                    vr.delete_ip_addresses(fd, debug);
-                                }
-                                // END FreeBSD specific interface type handling
+                                // release the virtual IP address, virtual interface and routes
+                                release_vip(&mut vr, fd, debug);
 
                                 // print information
                                 let vip = vr.parameters.vip();
@@ -741,20 +800,23 @@ pub fn fsm_run(
                         // -- Linux specific interface tyoe handling
                         #[cfg(target_os = "linux")]
                         match vr.parameters.iftype() {
-                            IfTypes::macvlan => {
-                                // removes macvlan interface
-                                vr.setup_macvlan_link(vr.parameters.ifmac(), Operation::Rem, debug);
+                            IfTypes::macvlan | IfTypes::ipvlan => {
+                                // removes macvlan or ipvlan interface
+                                vr.setup_virtual_link(vr.parameters.ifmac(), Operation::Rem, debug);
                                 // restore back vif and physical interfaces
-                                let vif = vr.parameters.interface();
-                                let phys = vr.parameters.vifname();
-                                vr.parameters.set_vifname(vif);
-                                vr.parameters.set_interface(phys);
+                                // (they are never swapped in dry-run mode)
+                                if !vr.parameters.dry_run() {
+                                    let vif = vr.parameters.interface();
+                                    let phys = vr.parameters.vifname();
+                                    vr.parameters.set_vifname(vif);
+                                    vr.parameters.set_interface(phys);
+                                }
                                 // remove routes
                                 vr.set_ip_routes(fd, Operation::Rem, debug);
                             }
                             _ => {
                                 // restore interface's MAC address
-                                vr.set_mac_addresses(fd, vr.parameters.ifmac(), debug);
+                                vr.set_vmac(fd, Operation::Rem, debug);
                                 // restore primary or delete vip on vr's interface
                                 match vr.parameters.netdrv() {
                                     NetDrivers::ioctl => {
@@ -763,7 +825,7 @@ pub fn fsm_run(
                                         // remove routes
                                         vr.set_ip_routes(fd, Operation::Rem, debug);
                                     }
-                                    NetDrivers::libnl => {
+                                    NetDrivers::libnl | NetDrivers::netlink => {
                                         // delete vip
                                         vr.delete_ip_addresses(fd, debug);
                                         // remove added routes
@@ -793,7 +855,7 @@ pub fn fsm_run(
             }
         };
         // set end-of-loop state
-        vr.set_states(st);
+        change_state(&mut vr, st, trigger);
         // print debugging information
         print_debug(
             debug,
@@ -804,9 +866,410 @@ pub fn fsm_run(
     }
 }
 
+// change_state() function
+/// set the virtual router's state, record the transition and notify the
+/// client API subscribers
+fn change_state(vr: &mut VirtualRouter, st: States, trigger: Trigger) {
+    let from = vr.get_states().states();
+    let to = st.states();
+    // the master is now the local router
+    if let States::Master = st {
+        vr.parameters.set_master(None);
+    }
+    vr.set_states(st);
+    if from != to {
+        vr.parameters
+            .history()
+            .lock()
+            .unwrap()
+            .transition(from.clone(), to.clone(), trigger);
+        events::publish(Notification::StateChange {
+            group: vr.parameters.vrid(),
+            interface: vr.parameters.interface(),
+            from,
+            to,
+        });
+    }
+}
+
+// seen_master() function
+/// record the master router's address from an ADVERTISEMENT, and notify
+/// the client API subscribers when it changes
+fn seen_master(vr: &mut VirtualRouter, ipsrc: [u8; 4], prio: u8) {
+    if vr.parameters.master() != Some(ipsrc) {
+        vr.parameters.set_master(Some(ipsrc));
+        events::publish(Notification::NewMaster {
+            group: vr.parameters.vrid(),
+            interface: vr.parameters.interface(),
+            source: format!("{}.{}.{}.{}", ipsrc[0], ipsrc[1], ipsrc[2], ipsrc[3]),
+            priority: prio,
+        });
+    }
+}
+
+// release_vip() function
+/// restore the interface and delete the virtual IP address when
+/// leaving the Master state
+fn release_vip(vr: &mut VirtualRouter, fd: i32, debug: &Verbose) {
+    // --- Linux specific interface tyoe handling
+    #[cfg(target_os = "linux")]
+    // restore primary or delete vip on vr's interface
+    match vr.parameters.iftype() {
+        IfTypes::macvlan | IfTypes::ipvlan => {
+            // removes macvlan or ipvlan interface
+            vr.setup_virtual_link(vr.parameters.ifmac(), Operation::Rem, debug);
+            // restore back vif and physical interfaces
+            // (they are never swapped in dry-run mode)
+            if !vr.parameters.dry_run() {
+                let vif = vr.parameters.interface();
+                let phys = vr.parameters.vifname();
+                vr.parameters.set_vifname(vif);
+                vr.parameters.set_interface(phys);
+            }
+            // remove added routes
+            vr.set_ip_routes(fd, Operation::Rem, debug);
+        }
+        _ => {
+            // restore interface's MAC address
+            vr.set_vmac(fd, Operation::Rem, debug);
+            match vr.parameters.netdrv() {
+                NetDrivers::ioctl => {
+                    // restore primary IP
+                    #[cfg(target_os = "linux")]
+                    vr.set_ip_addresses(fd, Operation::Rem, debug);
+                    // re-set routes
+                    vr.set_ip_routes(fd, Operation::Add, debug);
+                }
+                NetDrivers::libnl | NetDrivers::netlink => {
+                    // delete vip
+                    vr.delete_ip_addresses(fd, debug);
+                }
+            }
+        }
+    }
+    // END Linux specific interface type handling
+
+    // -- FreeBSD specific interface type handling
+    #[cfg(target_os = "freebsd")]
+    {
+        // we don't have to re-set the mac address here
+        // delete the VIP
+        vr.delete_ip_addresses(fd, debug);
+    }
+    // END FreeBSD specific interface type handling
+}
+
+// release_mastership() function
+/// send an ADVERTISEMENT with priority 0, release the virtual IP address
+/// and restart the timers so the virtual router can run as backup
+fn release_mastership(
+    vr: &mut VirtualRouter,
+    fd: i32,
+    timer_tx: Arc<Mutex<mpsc::Sender<Event>>>,
+    timer_vr: Arc<RwLock<VirtualRouter>>,
+    debug: &Verbose,
+) {
+    // send ADVERTISEMENT with priority equal 0
+    let prio = vr.parameters.prio();
+    vr.parameters.set_prio(0);
+    match vr.send_advertisement(fd, &debug) {
+        Ok(_) => (),
+        Err(e) => eprintln!(
+            "error(fsm): error while sending VRRP advertisement on interface {}: {}",
+            vr.parameters.interface(),
+            e
+        ),
+    }
+    vr.parameters.set_prio(prio);
+
+    // release the virtual IP address, virtual interface and routes
+    release_vip(vr, fd, debug);
+
+    // cancel advertisement timer
+    vr.timers.advert = 255;
+    // re-init master_down timer and restart timer thread(s)
+    vr.timers.master_down = vr.parameters.master_down();
+    vr.timers.restart();
+    vr.flags.clear_down_flag();
+    let d = debug.clone();
+    let _timer_thread = thread::spawn(move || {
+        timers::start_timers(timer_tx, timer_vr, &d);
+    });
+
+    // print information
+    let vip = vr.parameters.vip();
+    print_debug(
+        &debug,
+        DEBUG_LEVEL_INFO,
+        DEBUG_SRC_INFO,
+        format!(
+            "VR {}.{}.{}.{} for group {} on interface {} - Changed from Master to Backup",
+            vip[0],
+            vip[1],
+            vip[2],
+            vip[3],
+            vr.parameters.vrid(),
+            vr.parameters.interface()
+        ),
+    );
+}
+
+// link_change() function
+/// handle the interface changes reported by the netlink monitor, a virtual
+/// router whose interface goes down or loses its primary address waits in
+/// Init state, a virtual IP address removed while master is set back.
+/// Return a Startup event once the interface has recovered.
+fn link_change(
+    vr: &mut VirtualRouter,
+    event: Event,
+    fd: i32,
+    trigger: Trigger,
+    debug: &Verbose,
+) -> Option<Event> {
+    // a virtual router being shut down is left alone
+    if let States::Down = vr.get_states() {
+        return None;
+    }
+    let faulty = vr.flags.faulty();
+    let vip = vr.parameters.vip();
+
+    match event {
+        Event::LinkState(up) => vr.flags.set_link_down(!up),
+        Event::AddrAdded(ip) if ip == vr.parameters.primary_ip() => vr.flags.set_addr_lost(false),
+        Event::AddrRemoved(ip) if ip == vr.parameters.primary_ip() => {
+            // ioctls replace the primary address with the virtual IP address
+            let replaced = vr.parameters.netdrv() == NetDrivers::ioctl
+                && vr.parameters.iftype() == IfTypes::ether
+                && vr.current_state() == "Master"
+                && !vr.is_owner_vip(&vip);
+            if !replaced {
+                vr.flags.set_addr_lost(true);
+            }
+        }
+        Event::AddrRemoved(ip) if ip == vip => {
+            if vr.current_state() == "Master" {
+                // print information
+                print_debug(
+                    debug,
+                    DEBUG_LEVEL_INFO,
+                    DEBUG_SRC_INFO,
+                    format!(
+                        "VR {} for group {} on interface {} - Virtual IP address removed, setting it back",
+                        std::net::Ipv4Addr::from(vip),
+                        vr.parameters.vrid(),
+                        vr.parameters.interface()
+                    ),
+                );
+                vr.set_ip_addresses(fd, Operation::Add, debug);
+                if let Err(e) = vr.broadcast_gratuitious_arp(fd, debug) {
+                    eprintln!(
+                        "error(fsm): error while sending gratuitous ARP on interface {}: {}",
+                        vr.parameters.interface(),
+                        e
+                    );
+                }
+            }
+            return None;
+        }
+        #[cfg(target_os = "linux")]
+        Event::LinkChange(ifindex, ifname) => {
+            // print information
+            print_debug(
+                debug,
+                DEBUG_LEVEL_INFO,
+                DEBUG_SRC_INFO,
+                format!(
+                    "VR {} for group {} - Interface {} (ifindex {}) is now {} (ifindex {})",
+                    std::net::Ipv4Addr::from(vip),
+                    vr.parameters.vrid(),
+                    runtime::phys_interface(vr),
+                    vr.parameters.ifindex(),
+                    ifname,
+                    ifindex
+                ),
+            );
+            // the physical interface name is saved as the virtual
+            // interface name while the macvlan or ipvlan interface is in use
+            if runtime::phys_interface(vr) != vr.parameters.interface() {
+                vr.parameters.set_vifname(ifname.clone());
+            } else {
+                vr.parameters.set_interface(ifname.clone());
+            }
+            if ifindex != vr.parameters.ifindex() {
+                vr.parameters.set_ifindex(ifindex);
+                // a re-created interface is not in promiscuous mode
+                if let Err(e) = vr.set_promiscuous(fd, &ifname, true, debug) {
+                    eprintln!("error(fsm): {}", e);
+                }
+            }
+            return None;
+        }
+        _ => return None,
+    }
+
+    match (faulty, vr.flags.faulty()) {
+        // the interface cannot be used anymore
+        (false, true) => {
+            fault(vr, fd, trigger, debug);
+            None
+        }
+        // the interface is usable again
+        (true, false) => {
+            // print information
+            print_debug(
+                debug,
+                DEBUG_LEVEL_INFO,
+                DEBUG_SRC_INFO,
+                format!(
+                    "VR {} for group {} on interface {} - Interface has recovered",
+                    std::net::Ipv4Addr::from(vip),
+                    vr.parameters.vrid(),
+                    vr.parameters.interface()
+                ),
+            );
+            match vr.get_states() {
+                States::Init => Some(Event::Startup),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+// fault() function
+/// release the mastership and stop the timers of a virtual router whose
+/// interface cannot be used anymore, it then waits in Init state
+fn fault(vr: &mut VirtualRouter, fd: i32, trigger: Trigger, debug: &Verbose) {
+    // print information
+    print_debug(
+        debug,
+        DEBUG_LEVEL_INFO,
+        DEBUG_SRC_INFO,
+        format!(
+            "VR {} for group {} on interface {} - Interface {}",
+            std::net::Ipv4Addr::from(vr.parameters.vip()),
+            vr.parameters.vrid(),
+            vr.parameters.interface(),
+            if vr.flags.link_down() {
+                "is down"
+            } else {
+                "lost its primary address"
+            }
+        ),
+    );
+
+    if vr.current_state() == "Master" {
+        // send ADVERTISEMENT with priority equal 0, if the link is still up
+        if !vr.flags.link_down() {
+            let prio = vr.parameters.prio();
+            vr.parameters.set_prio(0);
+            match vr.send_advertisement(fd, &debug) {
+                Ok(_) => (),
+                Err(e) => eprintln!(
+                    "error(fsm): error while sending VRRP advertisement on interface {}: {}",
+                    vr.parameters.interface(),
+                    e
+                ),
+            }
+            vr.parameters.set_prio(prio);
+        }
+        // release the virtual IP address, virtual interface and routes
+        release_vip(vr, fd, debug);
+    }
+
+    // stop the timer thread(s), they are started again on recovery
+    vr.timers.advert = 255;
+    vr.timers.master_down = -1.0;
+    vr.timers.restart();
+    vr.flags.set_down_flag();
+
+    change_state(vr, States::Init, trigger);
+}
+
+// reconfigure() function
+/// apply runtime parameters changes to a virtual router, recompute its
+/// timers and send an ADVERTISEMENT right away when it is master
+fn reconfigure(
+    vr: &mut VirtualRouter,
+    rc: &Reconfig,
+    fd: i32,
+    timer_tx: Arc<Mutex<mpsc::Sender<Event>>>,
+    timer_vr: Arc<RwLock<VirtualRouter>>,
+    debug: &Verbose,
+) {
+    let adverint = vr.parameters.adverint();
+    let prio = vr.parameters.prio();
+
+    // update parameters
+    if let Some(p) = rc.prio {
+        vr.parameters.set_prio(p);
+    }
+    if let Some(p) = rc.preempt {
+        vr.parameters.set_preempt(p);
+    }
+    if let Some(i) = rc.adverint {
+        vr.parameters.set_adverint(i);
+    }
+    if let Some((t, secret)) = &rc.auth {
+        vr.parameters.set_auth(*t, secret.clone());
+    }
+
+    // notify the client API subscribers of a priority change
+    if vr.parameters.prio() != prio {
+        events::publish(Notification::PriorityChange {
+            group: vr.parameters.vrid(),
+            interface: vr.parameters.interface(),
+            from: prio,
+            to: vr.parameters.prio(),
+        });
+    }
+
+    // recompute skew_time and master_down interval
+    vr.parameters.update_master_down();
+    let timers_changed = vr.parameters.adverint() != adverint || vr.parameters.prio() != prio;
+
+    match vr.get_states() {
+        States::Master => {
+            // advertise the new parameters immediately
+            match vr.send_advertisement(fd, &debug) {
+                Ok(_) => (),
+                Err(e) => eprintln!(
+                    "error(fsm): error while sending VRRP advertisement on interface {}: {}",
+                    vr.parameters.interface(),
+                    e
+                ),
+            }
+            vr.timers.advert = vr.parameters.adverint();
+        }
+        States::Backup => {
+            // reset a running master_down timer
+            if vr.timers.master_down > 0.0 {
+                vr.timers.master_down = vr.parameters.master_down();
+            }
+        }
+        _ => return,
+    }
+
+    // restart timer thread(s) with the new intervals
+    if timers_changed {
+        vr.timers.restart();
+        // print debugging information
+        print_debug(
+            debug,
+            DEBUG_LEVEL_EXTENSIVE,
+            DEBUG_SRC_WORKER,
+            format!("restarting timer thread for group {}", vr.parameters.vrid()),
+        );
+        let d = debug.clone();
+        let _timer_thread = thread::spawn(move || {
+            timers::start_timers(timer_tx, timer_vr, &d);
+        });
+    }
+}
+
 // register_tx() function
 /// registers the virtual router sending channel
-fn register_tx(
+pub fn register_tx(
     vr: &Arc<RwLock<VirtualRouter>>,
     tx: &Arc<Mutex<mpsc::Sender<Event>>>,
     id: usize,
@@ -852,3 +1315,602 @@ fn is_primary_higher(primary: &[u8; 4], local: &[u8; 4]) -> bool {
     }
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::os::drivers::{MacvlanMode, NetDriver, VmacModes};
+    use crate::os::mock::{Call, MockDriver};
+    use crate::protocols::Static;
+    use crate::vrouter::Parameters;
+
+    const LOCAL: [u8; 4] = [10, 0, 0, 2];
+    const MASK: [u8; 4] = [255, 255, 255, 0];
+
+    // vrouter() function
+    // build a virtual router on the mock driver, without a real interface
+    fn vrouter(
+        vrid: u8,
+        vip: [u8; 4],
+        driver: &Arc<MockDriver>,
+        iftype: IfTypes,
+        routes: Option<Vec<Static>>,
+    ) -> Arc<RwLock<VirtualRouter>> {
+        vrouter_compat(vrid, vip, driver, iftype, routes, true)
+    }
+
+    // vrouter_compat() function
+    // build a virtual router with the given rfc3768 compatibility flag
+    fn vrouter_compat(
+        vrid: u8,
+        vip: [u8; 4],
+        driver: &Arc<MockDriver>,
+        iftype: IfTypes,
+        routes: Option<Vec<Static>>,
+        rfc3768: bool,
+    ) -> Arc<RwLock<VirtualRouter>> {
+        let driver: Arc<dyn NetDriver> = driver.clone();
+        let parameters = Parameters::new(
+            vrid,
+            "eth0".to_string(),
+            2,
+            100,
+            vip,
+            vec![LOCAL],
+            vec![MASK],
+            10,
+            0.6,
+            30.6,
+            true,
+            rfc3768,
+            0,
+            [0; 8],
+            None,
+            Arc::new(Mutex::new(Protocols { r#static: routes })),
+            driver,
+            iftype,
+            format!("vrrp.{}", vrid),
+            0,
+            3,
+            false,
+            None,
+            None,
+        );
+        Arc::new(RwLock::new(VirtualRouter {
+            parameters,
+            states: States::Init,
+            timers: Timers::new(5.0, 1),
+            flags: Flags::new(0x1),
+        }))
+    }
+
+    // run() function
+    // handle the events on the finite-state machine, until terminated
+    fn run(vr: &Arc<RwLock<VirtualRouter>>, events: Vec<Event>) {
+        let (tx, rx) = mpsc::channel();
+        for event in events {
+            tx.send(event).unwrap();
+        }
+        tx.send(Event::Terminate).unwrap();
+        let tx = Arc::new(Mutex::new(tx));
+        let rx = Arc::new(Mutex::new(rx));
+        fsm_run(0, &tx, &rx, vr, 3, &Verbose::new(0, 0, 0));
+    }
+
+    // vmac() function
+    fn vmac(vrid: u8) -> [u8; 6] {
+        let mut vmac = ETHER_VRRP_V2_SRC_MAC;
+        vmac[5] = vrid;
+        vmac
+    }
+
+    // advert_prio() function
+    // return the priority advertised in a sent frame
+    fn advert_prio(call: &Call) -> u8 {
+        match call {
+            Call::SendFrame { frame, .. } => AdvertFrame::decode(frame).unwrap().vrrp.prio,
+            c => panic!("not an advertisement: {:?}", c),
+        }
+    }
+
+    #[test]
+    fn owner_startup_and_shutdown() {
+        let driver = Arc::new(MockDriver::new(NetDrivers::libnl));
+        let vr = vrouter(61, LOCAL, &driver, IfTypes::ether, None);
+
+        run(&vr, vec![Event::Startup]);
+        assert_eq!(vr.read().unwrap().current_state(), "Master");
+        let calls = driver.calls();
+        assert_eq!(calls.len(), 4);
+        assert_eq!(
+            calls[0],
+            Call::GetMacAddress {
+                ifname: "eth0".to_string()
+            }
+        );
+        assert_eq!(
+            calls[1],
+            Call::SetMacAddress {
+                ifname: "eth0".to_string(),
+                mac: vmac(61)
+            }
+        );
+        assert_eq!(advert_prio(&calls[2]), 255);
+        match &calls[3] {
+            Call::SendArp { ifindex, frame } => {
+                assert_eq!(*ifindex, 2);
+                assert_eq!(frame.len(), 42);
+                assert_eq!(frame[12..14], ETHER_P_ARP.to_be_bytes());
+                assert_eq!(frame[22..28], vmac(61));
+                assert_eq!(frame[28..32], LOCAL);
+            }
+            c => panic!("unexpected call {:?}", c),
+        }
+
+        driver.clear();
+        run(&vr, vec![Event::Shutdown]);
+        assert_eq!(vr.read().unwrap().current_state(), "Down");
+        let calls = driver.calls();
+        assert_eq!(calls.len(), 3);
+        assert_eq!(advert_prio(&calls[0]), 0);
+        assert_eq!(
+            calls[1],
+            Call::SetMacAddress {
+                ifname: "eth0".to_string(),
+                mac: driver.mac()
+            }
+        );
+        assert_eq!(
+            calls[2],
+            Call::SetIpAddress {
+                ifindex: 2,
+                ifname: "eth0".to_string(),
+                ip: LOCAL,
+                netmask: MASK,
+                op: Operation::Rem
+            }
+        );
+    }
+
+    #[test]
+    fn backup_takeover_and_release() {
+        let driver = Arc::new(MockDriver::new(NetDrivers::ioctl));
+        let vip = [10, 0, 0, 1];
+        let routes = vec![Static::new(
+            [0, 0, 0, 0],
+            [0, 0, 0, 0],
+            [10, 0, 0, 254],
+            0,
+            0,
+        )];
+        let vr = vrouter(62, vip, &driver, IfTypes::ether, Some(routes));
+
+        // a backup router leaves the host untouched
+        run(&vr, vec![Event::Startup]);
+        assert_eq!(vr.read().unwrap().current_state(), "Backup");
+        assert!(driver.calls().is_empty());
+
+        run(&vr, vec![Event::MasterDown]);
+        assert_eq!(vr.read().unwrap().current_state(), "Master");
+        let calls = driver.calls();
+        assert_eq!(calls.len(), 6);
+        assert_eq!(
+            calls[1],
+            Call::SetMacAddress {
+                ifname: "eth0".to_string(),
+                mac: vmac(62)
+            }
+        );
+        assert_eq!(
+            calls[2],
+            Call::SetIpAddress {
+                ifindex: 2,
+                ifname: "eth0".to_string(),
+                ip: vip,
+                netmask: MASK,
+                op: Operation::Add
+            }
+        );
+        assert_eq!(
+            calls[3],
+            Call::SetIpRoute {
+                ifname: "eth0".to_string(),
+                route: [0, 0, 0, 0],
+                nh: [10, 0, 0, 254],
+                op: Operation::Add
+            }
+        );
+        assert!(matches!(calls[4], Call::SendArp { ifindex: 2, .. }));
+        assert_eq!(advert_prio(&calls[5]), 100);
+
+        // a higher priority master takes over, ioctls set the primary address back
+        driver.clear();
+        run(&vr, vec![Event::Advert([10, 0, 0, 3], 200)]);
+        assert_eq!(vr.read().unwrap().current_state(), "Backup");
+        let calls = driver.calls();
+        assert_eq!(
+            calls[0],
+            Call::SetMacAddress {
+                ifname: "eth0".to_string(),
+                mac: driver.mac()
+            }
+        );
+        assert_eq!(
+            calls[1],
+            Call::SetIpAddress {
+                ifindex: 2,
+                ifname: "eth0".to_string(),
+                ip: LOCAL,
+                netmask: MASK,
+                op: Operation::Add
+            }
+        );
+    }
+
+    #[test]
+    fn macvlan_takeover_and_shutdown() {
+        let driver = Arc::new(MockDriver::new(NetDrivers::libnl));
+        let vr = vrouter(63, [10, 0, 0, 1], &driver, IfTypes::macvlan, None);
+
+        run(&vr, vec![Event::Startup, Event::MasterDown]);
+        assert_eq!(vr.read().unwrap().current_state(), "Master");
+        assert_eq!(vr.read().unwrap().parameters.interface(), "vrrp.63");
+        let calls = driver.calls();
+        assert_eq!(calls.len(), 6);
+        assert_eq!(
+            calls[0],
+            Call::SetupMacvlanLink {
+                master: 2,
+                vifname: "vrrp.63".to_string(),
+                mac: vmac(63),
+                mode: MacvlanMode::bridge,
+                op: Operation::Add
+            }
+        );
+        assert_eq!(
+            calls[1],
+            Call::LinkIndex {
+                ifname: "vrrp.63".to_string()
+            }
+        );
+        assert_eq!(
+            calls[2],
+            Call::GetMacAddress {
+                ifname: "vrrp.63".to_string()
+            }
+        );
+        // the virtual IP address is set on the macvlan interface
+        assert_eq!(
+            calls[3],
+            Call::SetIpAddress {
+                ifindex: driver.ifindex(),
+                ifname: "vrrp.63".to_string(),
+                ip: [10, 0, 0, 1],
+                netmask: MASK,
+                op: Operation::Add
+            }
+        );
+        assert!(matches!(calls[4], Call::SendArp { ifindex: 2, .. }));
+        assert_eq!(advert_prio(&calls[5]), 100);
+
+        driver.clear();
+        run(&vr, vec![Event::Shutdown]);
+        assert_eq!(vr.read().unwrap().current_state(), "Down");
+        assert_eq!(vr.read().unwrap().parameters.interface(), "eth0");
+        let calls = driver.calls();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(advert_prio(&calls[0]), 0);
+        assert!(matches!(
+            calls[1],
+            Call::SetupMacvlanLink {
+                master: 2,
+                op: Operation::Rem,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn interface_mac_kept_or_secondary() {
+        // the interface's MAC address is never changed
+        let driver = Arc::new(MockDriver::new(NetDrivers::netlink));
+        let vr = vrouter(64, [10, 0, 0, 1], &driver, IfTypes::ether, None);
+        vr.write()
+            .unwrap()
+            .parameters
+            .set_vmac_mode(VmacModes::none);
+
+        run(
+            &vr,
+            vec![Event::Startup, Event::MasterDown, Event::Shutdown],
+        );
+        let calls = driver.calls();
+        assert!(!calls.iter().any(|c| match c {
+            Call::SetMacAddress { .. } | Call::SetSecondaryMac { .. } => true,
+            _ => false,
+        }));
+        // the frames are sourced from the interface's MAC address
+        for call in &calls {
+            match call {
+                Call::SendArp { frame, .. } => {
+                    assert_eq!(frame[6..12], driver.mac());
+                    assert_eq!(frame[22..28], driver.mac());
+                }
+                Call::SendFrame { frame, .. } => {
+                    assert_eq!(
+                        AdvertFrame::decode(frame).unwrap().eth.src_mac,
+                        driver.mac()
+                    )
+                }
+                _ => {}
+            }
+        }
+
+        // the virtual MAC address is added to the unicast filter
+        let driver = Arc::new(MockDriver::new(NetDrivers::netlink));
+        let vr = vrouter(65, [10, 0, 0, 1], &driver, IfTypes::ether, None);
+        vr.write()
+            .unwrap()
+            .parameters
+            .set_vmac_mode(VmacModes::secondary);
+
+        run(&vr, vec![Event::Startup, Event::MasterDown]);
+        let calls = driver.calls();
+        assert_eq!(
+            calls[1],
+            Call::SetSecondaryMac {
+                ifindex: 2,
+                mac: vmac(65),
+                op: Operation::Add
+            }
+        );
+        assert!(!calls.iter().any(|c| match c {
+            Call::SetMacAddress { .. } => true,
+            _ => false,
+        }));
+        // advertisements are sent from the virtual MAC address,
+        // while the interface's MAC address is announced by ARP
+        for call in &calls {
+            match call {
+                Call::SendArp { frame, .. } => {
+                    assert_eq!(frame[6..12], driver.mac());
+                    assert_eq!(frame[22..28], driver.mac());
+                }
+                Call::SendFrame { frame, .. } => {
+                    assert_eq!(AdvertFrame::decode(frame).unwrap().eth.src_mac, vmac(65))
+                }
+                _ => {}
+            }
+        }
+
+        driver.clear();
+        run(&vr, vec![Event::Shutdown]);
+        assert_eq!(
+            driver.calls()[1],
+            Call::SetSecondaryMac {
+                ifindex: 2,
+                mac: vmac(65),
+                op: Operation::Rem
+            }
+        );
+    }
+
+    #[test]
+    fn ipvlan_takeover_keeps_the_interface_mac() {
+        let driver = Arc::new(MockDriver::new(NetDrivers::netlink));
+        let vr = vrouter(68, [10, 0, 0, 1], &driver, IfTypes::ipvlan, None);
+
+        run(&vr, vec![Event::Startup, Event::MasterDown]);
+        assert_eq!(vr.read().unwrap().parameters.interface(), "vrrp.68");
+        let calls = driver.calls();
+        assert_eq!(
+            calls[0],
+            Call::SetupIpvlanLink {
+                master: 2,
+                vifname: "vrrp.68".to_string(),
+                op: Operation::Add
+            }
+        );
+        assert!(matches!(
+            calls[3],
+            Call::SetIpAddress { ifindex, .. } if ifindex == driver.ifindex()
+        ));
+        // the frames are sourced from the interface's MAC address
+        match &calls[4] {
+            Call::SendArp { frame, .. } => {
+                assert_eq!(frame[6..12], driver.mac());
+                assert_eq!(frame[22..28], driver.mac());
+            }
+            c => panic!("unexpected call {:?}", c),
+        }
+        match &calls[5] {
+            Call::SendFrame { frame, .. } => {
+                assert_eq!(
+                    AdvertFrame::decode(frame).unwrap().eth.src_mac,
+                    driver.mac()
+                )
+            }
+            c => panic!("unexpected call {:?}", c),
+        }
+
+        driver.clear();
+        run(&vr, vec![Event::Shutdown]);
+        assert_eq!(vr.read().unwrap().parameters.interface(), "eth0");
+        assert!(matches!(
+            driver.calls()[1],
+            Call::SetupIpvlanLink {
+                op: Operation::Rem,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn dry_run_leaves_the_host_untouched() {
+        let driver = Arc::new(MockDriver::new(NetDrivers::libnl));
+        let routes = vec![Static::new(
+            [0, 0, 0, 0],
+            [0, 0, 0, 0],
+            [10, 0, 0, 254],
+            0,
+            0,
+        )];
+        let vr = vrouter(64, [10, 0, 0, 1], &driver, IfTypes::ether, Some(routes));
+        vr.write().unwrap().parameters.set_dry_run(true);
+
+        run(
+            &vr,
+            vec![
+                Event::Startup,
+                Event::MasterDown,
+                Event::LinkChange(7, "lan0".to_string()),
+                Event::Shutdown,
+            ],
+        );
+        assert_eq!(vr.read().unwrap().current_state(), "Down");
+        // the interface's MAC address is read, nothing is changed or sent
+        for call in driver.calls() {
+            assert!(matches!(call, Call::GetMacAddress { .. }), "{:?}", call);
+        }
+    }
+
+    #[test]
+    fn auth_change_restores_the_configured_compatibility() {
+        let driver = Arc::new(MockDriver::new(NetDrivers::libnl));
+        let vr = vrouter_compat(65, [10, 0, 0, 1], &driver, IfTypes::ether, None, false);
+        let mut vr = vr.write().unwrap();
+        assert!(!vr.parameters.rfc3768());
+
+        // the proprietary authentication types force the compatibility
+        vr.parameters
+            .set_auth(AUTH_TYPE_P0, Some("secret".to_string()));
+        assert!(vr.parameters.rfc3768());
+        vr.parameters
+            .set_auth(AUTH_TYPE_SIMPLE, Some("secret".to_string()));
+        assert!(!vr.parameters.rfc3768());
+        vr.parameters
+            .set_auth(AUTH_TYPE_P1, Some("secret".to_string()));
+        assert!(vr.parameters.rfc3768());
+        vr.parameters.set_auth(0, None);
+        assert!(!vr.parameters.rfc3768());
+    }
+
+    #[test]
+    fn maintenance_survives_reload() {
+        let driver = Arc::new(MockDriver::new(NetDrivers::libnl));
+        let vr = vrouter(66, [10, 0, 0, 1], &driver, IfTypes::ether, None);
+        let (ack, _) = mpsc::channel();
+        run(&vr, vec![Event::Maintenance(true, ack), Event::Startup]);
+        assert_eq!(vr.read().unwrap().current_state(), "Backup");
+
+        // a reload raising the priority reconfigures the running virtual router
+        let (ack, _) = mpsc::channel();
+        let rc = Reconfig {
+            prio: Some(254),
+            preempt: Some(true),
+            adverint: Some(1),
+            auth: Some((0, None)),
+        };
+        run(&vr, vec![Event::Reconfigure(rc, ack), Event::MasterDown]);
+
+        // it stays in maintenance mode, out of master
+        let vro = vr.read().unwrap();
+        assert_eq!(vro.parameters.prio(), 254);
+        assert!(vro.parameters.maintenance());
+        assert_eq!(vro.current_state(), "Backup");
+        assert!(driver.calls().is_empty());
+    }
+
+    #[test]
+    fn link_down_and_recovery() {
+        let driver = Arc::new(MockDriver::new(NetDrivers::libnl));
+        let vip = [10, 0, 0, 1];
+        let vr = vrouter(65, vip, &driver, IfTypes::ether, None);
+        run(&vr, vec![Event::Startup, Event::MasterDown]);
+        assert_eq!(vr.read().unwrap().current_state(), "Master");
+
+        // the mastership is released without advertising on the down link
+        driver.clear();
+        run(&vr, vec![Event::LinkState(false)]);
+        assert_eq!(vr.read().unwrap().current_state(), "Init");
+        let calls = driver.calls();
+        assert_eq!(calls.len(), 2);
+        assert!(matches!(calls[0], Call::SetMacAddress { .. }));
+        assert!(matches!(
+            calls[1],
+            Call::SetIpAddress {
+                ip,
+                op: Operation::Rem,
+                ..
+            } if ip == vip
+        ));
+
+        // the virtual router is started again once the link is back up
+        run(
+            &vr,
+            vec![Event::LinkState(false), Event::Advert(LOCAL, 200)],
+        );
+        assert_eq!(vr.read().unwrap().current_state(), "Init");
+        run(&vr, vec![Event::LinkState(true)]);
+        assert_eq!(vr.read().unwrap().current_state(), "Backup");
+    }
+
+    #[test]
+    fn primary_address_lost_and_vip_removed() {
+        let driver = Arc::new(MockDriver::new(NetDrivers::netlink));
+        let vip = [10, 0, 0, 1];
+        let vr = vrouter(66, vip, &driver, IfTypes::ether, None);
+        run(&vr, vec![Event::Startup, Event::MasterDown]);
+
+        // a virtual IP address removed while master is set back
+        driver.clear();
+        run(&vr, vec![Event::AddrRemoved(vip)]);
+        assert_eq!(vr.read().unwrap().current_state(), "Master");
+        let calls = driver.calls();
+        assert_eq!(calls.len(), 2);
+        assert!(matches!(
+            calls[0],
+            Call::SetIpAddress {
+                ip,
+                op: Operation::Add,
+                ..
+            } if ip == vip
+        ));
+        assert!(matches!(calls[1], Call::SendArp { .. }));
+
+        // losing the primary address releases the mastership, priority 0 is advertised
+        driver.clear();
+        run(&vr, vec![Event::AddrRemoved(LOCAL)]);
+        assert_eq!(vr.read().unwrap().current_state(), "Init");
+        assert_eq!(advert_prio(&driver.calls()[0]), 0);
+
+        // other addresses are ignored
+        run(&vr, vec![Event::AddrAdded([10, 0, 0, 9])]);
+        assert_eq!(vr.read().unwrap().current_state(), "Init");
+        run(&vr, vec![Event::AddrAdded(LOCAL)]);
+        assert_eq!(vr.read().unwrap().current_state(), "Backup");
+    }
+
+    #[test]
+    fn interface_renamed_and_recreated() {
+        let driver = Arc::new(MockDriver::new(NetDrivers::libnl));
+        let vr = vrouter(67, [10, 0, 0, 1], &driver, IfTypes::ether, None);
+        run(&vr, vec![Event::Startup]);
+
+        run(&vr, vec![Event::LinkChange(2, "lan0".to_string())]);
+        assert_eq!(vr.read().unwrap().parameters.interface(), "lan0");
+        assert!(driver.calls().is_empty());
+
+        // a new interface must be set in promiscuous mode again
+        run(&vr, vec![Event::LinkChange(7, "lan0".to_string())]);
+        assert_eq!(vr.read().unwrap().parameters.ifindex(), 7);
+        assert_eq!(
+            driver.calls(),
+            vec![Call::SetPromiscuous {
+                ifname: "lan0".to_string(),
+                on: true
+            }]
+        );
+        assert_eq!(vr.read().unwrap().current_state(), "Backup");
+    }
+}
@@ -0,0 +1,333 @@
+//! history module
+//! This module records the state transitions of the virtual routers, and
+//! writes them to the crash dump file when the daemon panics.
+use super::*;
+
+// std
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::Write;
+use std::panic;
+use std::sync::{MutexGuard, TryLockError, Weak};
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// fsm
+use crate::fsm::Event;
+
+// number of transitions kept per virtual router
+const HISTORY_MAX_TRANSITIONS: usize = 32;
+
+/// Trigger Enumerator (Serialize-able)
+/// the event which triggered a state transition
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Trigger {
+    Startup,
+    Shutdown,
+    MasterDown,
+    Advert { source: String, priority: u8 },
+    Maintenance { enabled: bool },
+    Link { up: bool },
+    Address { address: String, removed: bool },
+    Other,
+}
+
+// Trigger Implementation
+impl Trigger {
+    // from() method
+    pub fn from(event: &Event) -> Trigger {
+        match event {
+            Event::Startup => Trigger::Startup,
+            Event::Shutdown => Trigger::Shutdown,
+            Event::MasterDown => Trigger::MasterDown,
+            Event::Advert(ipsrc, prio) => Trigger::Advert {
+                source: format!("{}.{}.{}.{}", ipsrc[0], ipsrc[1], ipsrc[2], ipsrc[3]),
+                priority: *prio,
+            },
+            Event::Maintenance(on, _) => Trigger::Maintenance { enabled: *on },
+            Event::LinkState(up) => Trigger::Link { up: *up },
+            Event::AddrAdded(ip) | Event::AddrRemoved(ip) => Trigger::Address {
+                address: format!("{}.{}.{}.{}", ip[0], ip[1], ip[2], ip[3]),
+                removed: matches!(event, Event::AddrRemoved(_)),
+            },
+            _ => Trigger::Other,
+        }
+    }
+}
+
+/// OpResult Structure (Serialize-able)
+/// the outcome of a VIP, MAC or routes operation
+#[derive(Debug, Clone, Serialize)]
+pub struct OpResult {
+    pub op: &'static str,
+    pub action: &'static str,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub dry_run: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+/// Transition Structure (Serialize-able)
+#[derive(Debug, Clone, Serialize)]
+pub struct Transition {
+    pub timestamp: u64,
+    pub from: String,
+    pub to: String,
+    pub trigger: Trigger,
+    pub operations: Vec<OpResult>,
+}
+
+/// History Structure
+#[derive(Debug, Default)]
+pub struct History {
+    transitions: VecDeque<Transition>,
+    pending: Vec<OpResult>, // operations performed while handling the current event
+}
+
+// History Implementation
+impl History {
+    // new() method
+    pub fn new() -> History {
+        History {
+            transitions: VecDeque::with_capacity(HISTORY_MAX_TRANSITIONS),
+            pending: Vec::new(),
+        }
+    }
+    // op() method
+    // record the outcome of an operation, it is attached to the next transition
+    pub fn op(&mut self, op: &'static str, action: &'static str, result: Result<(), String>) {
+        self.pending.push(OpResult {
+            op,
+            action,
+            success: result.is_ok(),
+            error: result.err(),
+            dry_run: false,
+            detail: None,
+        });
+    }
+    // skipped() method
+    // record an operation which has not been performed in dry-run mode
+    pub fn skipped(&mut self, op: &'static str, action: &'static str, detail: String) {
+        self.pending.push(OpResult {
+            op,
+            action,
+            success: true,
+            error: None,
+            dry_run: true,
+            detail: Some(detail),
+        });
+    }
+    // clear_ops() method
+    // forget the operations which did not lead to a transition
+    pub fn clear_ops(&mut self) {
+        self.pending.clear();
+    }
+    // transition() method
+    // record a state transition, the oldest one is dropped when the history is full
+    pub fn transition(&mut self, from: String, to: String, trigger: Trigger) {
+        if self.transitions.len() >= HISTORY_MAX_TRANSITIONS {
+            self.transitions.pop_front();
+        }
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        self.transitions.push_back(Transition {
+            timestamp,
+            from,
+            to,
+            trigger,
+            operations: self.pending.drain(..).collect(),
+        });
+    }
+    // transitions() getter
+    pub fn transitions(&self) -> &VecDeque<Transition> {
+        &self.transitions
+    }
+}
+
+// virtual routers histories, for the crash dump
+lazy_static! {
+    static ref HISTORIES: Mutex<Vec<(u8, String, Weak<Mutex<History>>)>> = Mutex::new(Vec::new());
+}
+
+// register() function
+/// create the history of a virtual router
+pub fn register(vrid: u8, interface: &str) -> Arc<Mutex<History>> {
+    let history = Arc::new(Mutex::new(History::new()));
+    let mut histories = HISTORIES.lock().unwrap();
+    // forget the removed virtual routers
+    histories.retain(|(_, _, h)| h.upgrade().is_some());
+    histories.push((vrid, interface.to_string(), Arc::downgrade(&history)));
+    history
+}
+
+/// VRDump Structure (Serialize-able)
+#[derive(Serialize)]
+struct VRDump {
+    group: u8,
+    interface: String,
+    transitions: Vec<Transition>,
+}
+
+/// CrashDump Structure (Serialize-able)
+#[derive(Serialize)]
+struct CrashDump {
+    timestamp: u64,
+    version: &'static str,
+    thread: Option<String>,
+    panic: String,
+    vrouters: Vec<VRDump>,
+}
+
+// try_lock() function
+// lock a mutex without blocking, even if it has been poisoned by the panic
+fn try_lock<T>(m: &Mutex<T>) -> Option<MutexGuard<'_, T>> {
+    match m.try_lock() {
+        Ok(g) => Some(g),
+        Err(TryLockError::Poisoned(e)) => Some(e.into_inner()),
+        Err(TryLockError::WouldBlock) => None,
+    }
+}
+
+// dump() function
+/// write the virtual routers histories to the crash dump file
+fn dump(path: &str, info: &dyn std::fmt::Display) -> io::Result<()> {
+    // the histories locked by the panicking thread are skipped
+    let mut vrouters = Vec::new();
+    if let Some(histories) = try_lock(&HISTORIES) {
+        for (group, interface, h) in histories.iter() {
+            if let Some(h) = h.upgrade() {
+                if let Some(h) = try_lock(&h) {
+                    vrouters.push(VRDump {
+                        group: *group,
+                        interface: interface.clone(),
+                        transitions: h.transitions().iter().cloned().collect(),
+                    });
+                }
+            }
+        }
+    }
+    let dump = CrashDump {
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        version: RVRRPD_VERSION_STRING,
+        thread: thread::current().name().map(|n| n.to_string()),
+        panic: info.to_string(),
+        vrouters,
+    };
+    let mut file = File::create(path)?;
+    serde_json::to_writer_pretty(&mut file, &dump)?;
+    file.write_all(b"\n")
+}
+
+// set_crash_dump() function
+/// write the crash dump file on panic, before the default panic handling
+pub fn set_crash_dump(path: String) {
+    let default = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        match dump(&path, info) {
+            Ok(_) => eprintln!("error(main): crash dump written to {}", path),
+            Err(e) => eprintln!("error(main): cannot write crash dump to {}: {}", path, e),
+        }
+        default(info);
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn oldest_transitions_are_evicted() {
+        let mut h = History::new();
+        for i in 0..HISTORY_MAX_TRANSITIONS + 2 {
+            h.transition(format!("{}", i), format!("{}", i + 1), Trigger::Other);
+        }
+        let t = h.transitions();
+        assert_eq!(t.len(), HISTORY_MAX_TRANSITIONS);
+        assert_eq!(t.front().unwrap().from, "2");
+        assert_eq!(
+            t.back().unwrap().to,
+            format!("{}", HISTORY_MAX_TRANSITIONS + 2)
+        );
+    }
+
+    #[test]
+    fn operations_are_attached_to_the_next_transition() {
+        let mut h = History::new();
+        h.op("vip", "add", Ok(()));
+        h.op("routes", "add", Err("no route to host".to_string()));
+        h.skipped("mac", "add", "add 00:00:5e:00:01:01 on eth0".to_string());
+        h.transition(
+            "Backup".to_string(),
+            "Master".to_string(),
+            Trigger::MasterDown,
+        );
+
+        let ops = &h.transitions()[0].operations;
+        assert_eq!(ops.len(), 3);
+        assert!(ops[0].success && !ops[0].dry_run);
+        assert_eq!(ops[1].error.as_ref().unwrap(), "no route to host");
+        assert!(ops[2].dry_run);
+
+        // the operations which did not lead to a transition are dropped
+        h.op("vip", "delete", Ok(()));
+        h.clear_ops();
+        h.transition("Master".to_string(), "Backup".to_string(), Trigger::Other);
+        assert!(h.transitions()[1].operations.is_empty());
+    }
+
+    #[test]
+    fn crash_dump_holds_the_histories() {
+        let history = register(200, "dump0");
+        {
+            let mut h = history.lock().unwrap();
+            h.op("vip", "add", Ok(()));
+            h.transition(
+                "Backup".to_string(),
+                "Master".to_string(),
+                Trigger::MasterDown,
+            );
+        }
+        let path = std::env::temp_dir()
+            .join(format!("rvrrpd-crash-{}.json", std::process::id()))
+            .to_string_lossy()
+            .to_string();
+
+        // the histories registered by the other tests may be locked, retry
+        let mut vr = None;
+        for _ in 0..100 {
+            dump(&path, &"test panic").unwrap();
+            let content = std::fs::read_to_string(&path).unwrap();
+            let dump: serde_json::Value = serde_json::from_str(&content).unwrap();
+            assert_eq!(dump["panic"], "test panic");
+            assert_eq!(dump["version"], RVRRPD_VERSION_STRING);
+            vr = dump["vrouters"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|v| v["interface"] == "dump0")
+                .cloned();
+            if vr.is_some() {
+                break;
+            }
+            thread::sleep(std::time::Duration::from_millis(10));
+        }
+        std::fs::remove_file(&path).unwrap();
+
+        let vr = vr.unwrap();
+        assert_eq!(vr["group"], 200);
+        let t = &vr["transitions"][0];
+        assert_eq!(t["from"], "Backup");
+        assert_eq!(t["to"], "Master");
+        assert_eq!(t["trigger"]["event"], "master_down");
+        assert_eq!(t["operations"][0]["op"], "vip");
+    }
+}
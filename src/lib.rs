@@ -10,10 +10,6 @@ use libc::{c_void, recvfrom, sockaddr, sockaddr_ll, socklen_t};
 #[macro_use]
 extern crate foreign_types;
 
-// itertools
-extern crate itertools;
-use itertools::Itertools;
-
 // serde
 extern crate serde;
 #[macro_use]
@@ -50,9 +46,9 @@ extern crate lazy_static;
 mod constants;
 use constants::*;
 
-// VRRP data structure
-mod packets;
-use packets::VRRPpkt;
+// VRRP packets codec
+mod codec;
+pub use codec::{AdvertFrame, EthernetHeader, Ipv4Header, PacketError, VlanTag, VrrpAdvert};
 
 // operating systems support
 mod os;
@@ -60,6 +56,7 @@ mod os;
 use os::drivers::{IfTypes, NetDrivers};
 #[cfg(target_os = "linux")]
 use os::drivers::{IfTypes, NetDrivers, PflagOp};
+use os::drivers::{NetDriver, Operation};
 
 // operating system specific support
 #[cfg(target_os = "freebsd")]
@@ -71,7 +68,10 @@ use os::freebsd::libc::read_bpf_buf;
 #[cfg(target_os = "linux")]
 use os::linux::filter::{SockFilter, SockFprog};
 #[cfg(target_os = "linux")]
-use os::linux::libc::{open_raw_socket_fd, recv_ip_pkts, set_sock_filter};
+use os::linux::libc::{
+    close_fd, open_raw_socket_all, open_raw_socket_fd, recv_ip_pkts, set_sock_filter,
+    set_sock_rcvtimeo,
+};
 
 // finite state machine
 mod fsm;
@@ -79,6 +79,10 @@ mod fsm;
 // application programming interface
 mod api;
 use api::client::{capi_start_app, DownstreamAPI, FSMQueryResult, UpstreamAPI};
+pub use api::client::{
+    RequestMaintenance, RequestVRRPAttr, ResponseGlobalAttr, ResponsePeerAttr,
+    ResponseProtoStaticAttr, ResponseVRRPAttr, ResponseVRRPAttrExt,
+};
 
 // checksums
 mod checksums;
@@ -86,6 +90,16 @@ mod checksums;
 // timers
 mod timers;
 
+// client API events
+mod events;
+use events::Notification;
+
+// virtual routers peers
+mod peers;
+
+// state transitions history
+mod history;
+
 // channels and threads
 use std::sync::mpsc;
 use std::sync::RwLock;
@@ -95,9 +109,18 @@ use std::sync::{Arc, Mutex};
 mod threads;
 use threads::ThreadPool;
 
+// running virtual routers
+mod runtime;
+use runtime::Runtime;
+
+// interfaces monitoring
+#[cfg(target_os = "linux")]
+mod monitor;
+
 // config
 mod config;
 use config::decode_config;
+pub use config::{encode_config, CConfig, CfgType};
 
 // virtual routers
 mod vrouter;
@@ -110,7 +133,6 @@ use protocols::{Protocols, Static};
 
 // authentication
 mod auth;
-use auth::gen_auth_data;
 
 // debug
 mod debug;
@@ -122,10 +144,12 @@ use std::convert::TryInto;
 use std::ffi::CString;
 use std::fs::File;
 use std::io;
+#[cfg(target_os = "linux")]
 use std::mem;
+#[cfg(target_os = "freebsd")]
 use std::ptr;
-use std::slice;
 use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(target_os = "freebsd")]
 use std::thread;
 
 /// Library Config Structure
@@ -137,6 +161,7 @@ pub struct Config {
     conf: Option<String>,
     debug: Option<u8>,
     cfg_format: Option<String>,
+    maintenance: Vec<String>,
 }
 
 // Config Implementation
@@ -148,6 +173,7 @@ impl Config {
         conf: Option<String>,
         debug: Option<u8>,
         cfg_format: Option<String>,
+        maintenance: Vec<String>,
     ) -> Config {
         Config {
             iface,
@@ -155,6 +181,7 @@ impl Config {
             conf,
             debug,
             cfg_format,
+            maintenance,
         }
     }
     // iface() getter
@@ -180,6 +207,13 @@ impl Config {
     pub fn debug(&self) -> Option<u8> {
         self.debug
     }
+    // maintenance() method
+    // return true if the virtual router must be started in maintenance mode
+    pub fn maintenance(&self, group: u8, interface: &str) -> bool {
+        self.maintenance
+            .iter()
+            .any(|m| m == "all" || *m == format!("{}/{}", group, interface))
+    }
     // cfg_format() method
     pub fn cfg_format(&self) -> config::CfgType {
         match &self.cfg_format {
@@ -373,7 +407,7 @@ pub fn listen_ip_pkts(cfg: &Config) -> io::Result<()> {
             // END FreeBSD specific handling
         }
         // virtual router modes
-        1 | 2 => {
+        1 | 2 | 3 => {
             // read configuration file
             let config = decode_config(cfg.conf(), cfg.cfg_format());
 
@@ -414,6 +448,15 @@ pub fn listen_ip_pkts(cfg: &Config) -> io::Result<()> {
             // setup signal handler for the possibly forked process
             let shutdown = setup_signal_handler();
 
+            // in observer mode, the host is never modified
+            let dry_run = cfg.mode == 3;
+            if dry_run {
+                println!("Running in observer (dry-run) mode, no change will be made to the host");
+            }
+
+            // write the transitions history of the virtual routers on panic
+            history::set_crash_dump(config.crash_dump());
+
             // initialize the virtual router vector
             let mut vrouters: Vec<Arc<RwLock<VirtualRouter>>> = Vec::new();
 
@@ -469,34 +512,12 @@ pub fn listen_ip_pkts(cfg: &Config) -> io::Result<()> {
 
             // create a new virtual router and push it into the 'vrouters' vector
             for vr in vcvr {
-                // clone protocols
-                let protocols = Arc::clone(&protocols);
-
-                // create new virtual router structure
-                match VirtualRouter::new(
-                    vr.group(),
-                    vr.interface().to_string(),
-                    vr.priority(),
-                    vr.vip(),
-                    vr.timer_advert(),
-                    vr.preemption(),
-                    vr.rfc3768(),
-                    vr.auth_type(),
-                    vr.auth_secret().clone(),
-                    protocols,
-                    &debug,
-                    vr.netdrv(),
-                    vr.iftype(),
-                    vr.vifname(),
-                    -1,
-                    vr.socket_filter(),
-                ) {
-                    Ok(vr) => {
-                        let vr = RwLock::new(vr);
-                        vrouters.push(Arc::new(vr));
-                    }
-                    Err(e) => return Err(e),
+                let mut vro = new_vrouter(vr, &protocols, &debug, dry_run)?;
+                // start in maintenance mode if requested on the command line
+                if cfg.maintenance(vr.group(), &vr.ifname()) {
+                    vro.parameters.set_maintenance(true);
                 }
+                vrouters.push(Arc::new(RwLock::new(vro)));
             }
 
             // Initialize the Downstream Client API, spawn its thread and set its reference.
@@ -504,59 +525,56 @@ pub fn listen_ip_pkts(cfg: &Config) -> io::Result<()> {
             let up_api = UpstreamAPI::new();
             let down_api = DownstreamAPI::new();
             let capi: Option<&UpstreamAPI> = match isClientAPIEnabled {
-                true => {
-                    let host = config.api.as_ref().unwrap().host();
-                    let tls = config.api.as_ref().unwrap().tls();
-                    let tls_key = config.api.as_ref().unwrap().tls_key();
-                    let tls_cert = config.api.as_ref().unwrap().tls_cert();
-                    up_api.spawn_thread(&down_api, config, &vrouters);
-                    capi_start_app(down_api, host, tls, tls_key, tls_cert);
-                    Some(&up_api)
-                }
+                true => Some(&up_api),
                 false => None,
             };
 
+            // keep track of the running virtual routers
+            let runtime = Runtime::new(
+                &vrouters,
+                protocols,
+                capi.map(|c| c.channels()),
+                dry_run,
+                &debug,
+            );
+
+            if isClientAPIEnabled {
+                let host = config.api.as_ref().unwrap().host();
+                let tls = config.api.as_ref().unwrap().tls();
+                let tls_key = config.api.as_ref().unwrap().tls_key();
+                let tls_cert = config.api.as_ref().unwrap().tls_cert();
+                let tls_client_ca = config.api.as_ref().unwrap().tls_client_ca();
+                let rate_limit = config.api.as_ref().unwrap().rate_limit();
+                let tcp = config.api.as_ref().unwrap().tcp();
+                let unix = config.api.as_ref().unwrap().unix();
+                up_api.spawn_thread(
+                    &down_api,
+                    config,
+                    cfg.conf(),
+                    cfg.cfg_format(),
+                    runtime.clone(),
+                );
+                capi_start_app(
+                    down_api,
+                    host,
+                    tls,
+                    tls_key,
+                    tls_cert,
+                    tls_client_ca,
+                    rate_limit,
+                    tcp,
+                    unix,
+                );
+            }
+
             // --- Linux specific handling
             #[cfg(target_os = "linux")]
             {
-                // set vr's interface(s) in promiscuous mode
+                // open vr's raw socket and set its interface in promiscuous mode
                 for vr in &vrouters {
                     // acquire write lock
                     let mut vr = vr.write().unwrap();
-
-                    // open vr's raw socket
-                    let sock_fd = open_raw_socket_fd()?;
-
-                    // set BPF socket filter if enabled
-                    if vr.parameters.socket_filter() {
-                        let filter: [SockFilter; 10] =
-                            SockFilter::new_vrrpv2_gid(vr.parameters.vrid());
-                        let bpf_fprog = SockFprog::build_fprog_vrrpv2_gid(&filter);
-                        set_sock_filter(sock_fd, &bpf_fprog)?;
-                    }
-
-                    // convert interface string
-                    let iface =
-                        CString::new(vr.parameters.interface().as_bytes() as &[u8]).unwrap();
-
-                    // set interface is promiscuous mode
-                    match os::linux::netdev::set_if_promiscuous(sock_fd, &iface, PflagOp::Set) {
-                        Err(e) => return Err(e),
-                        _ => {}
-                    }
-
-                    // store raw socket file descriptor
-                    vr.parameters.set_fd(sock_fd);
-
-                    // if enabled, set downstream client API sender and receiver channels
-                    match capi {
-                        Some(c) => {
-                            let (s, r) = c.channels();
-                            vr.parameters.set_capi_tx(s);
-                            vr.parameters.set_capi_rx(r);
-                        }
-                        None => (),
-                    }
+                    setup_vr_socket(&mut vr, &capi.map(|c| c.channels()), &debug)?;
                 }
 
                 // print debugging information
@@ -567,63 +585,9 @@ pub fn listen_ip_pkts(cfg: &Config) -> io::Result<()> {
                     format!("created virtual-router vector - {:?}", vrouters),
                 );
 
-                // create a pool of threads
-                let mut threads = ThreadPool::new(&vrouters, &debug);
-
-                // send Startup event to worker threads
-                std::thread::sleep(std::time::Duration::from_secs(1));
-                threads.startup(&vrouters, &debug);
-
-                // spawn a listener thread per virtual router
-                for vr in &vrouters {
-                    // get vr's socket descriptor
-                    let vro = &vr.read().unwrap();
-                    let sock_fd = vro.parameters.fd();
-
-                    // create single item vrouters vector
-                    let mut vrouters: Vec<Arc<RwLock<VirtualRouter>>> = Vec::new();
-                    vrouters.push(vr.clone());
-
-                    thread::spawn(move || {
-                        // initialize packet buffer
-                        let mut pkt_buf: [u8; 1024] = [0; 1024];
-
-                        // initialize sockaddr and packet buffer
-                        let mut sockaddr: sockaddr_ll = unsafe { mem::zeroed() };
-
-                        loop {
-                            // Block on receiving IP packets
-                            match recv_ip_pkts(sock_fd, &mut sockaddr, &mut pkt_buf) {
-                                Ok(len) => {
-                                    // create and initialize pkg_hdr
-                                    let mut pkt_hdr = PktHdr::new();
-                                    // set inbound interface's ifindex)
-                                    pkt_hdr.in_ifidx = sockaddr.sll_ifindex;
-                                    match verify_vrrp_pkt(
-                                        sock_fd,
-                                        &pkt_hdr,
-                                        &pkt_buf[0..len],
-                                        &vrouters,
-                                        &debug,
-                                    ) {
-                                        Some((ifindex, vrid, ipsrc, advert_prio)) => {
-                                            handle_vrrp_advert(
-                                                &vrouters,
-                                                ifindex,
-                                                vrid,
-                                                ipsrc,
-                                                advert_prio,
-                                                &debug,
-                                            );
-                                        }
-                                        _ => (),
-                                    }
-                                }
-                                Err(_e) => (),
-                            }
-                        }
-                    });
-                }
+                // create a pool of threads, start the workers
+                // and spawn a listener thread per virtual router
+                runtime.start();
 
                 // main thread loop
                 loop {
@@ -631,30 +595,9 @@ pub fn listen_ip_pkts(cfg: &Config) -> io::Result<()> {
                     std::thread::sleep(std::time::Duration::from_secs(5));
 
                     // check if global shutdown variable is set
-                    // if set, then call set_if_promiscuous() to remove promisc mode on interface
+                    // if set, then shut down the virtual routers and remove promisc mode on interfaces
                     if shutdown.load(Ordering::Relaxed) {
-                        // manually calling the threads pool destructor
-                        threads.drop(&vrouters, &debug);
-
-                        for vr in &vrouters {
-                            // acquire read lock
-                            let vr = vr.read().unwrap();
-
-                            // get vr's socket file descriptor
-                            let sock_fd = vr.parameters.fd();
-
-                            // remove promiscuous mode off interface
-                            let iface = CString::new(vr.parameters.interface().as_bytes() as &[u8])
-                                .unwrap();
-                            match os::linux::netdev::set_if_promiscuous(
-                                sock_fd,
-                                &iface,
-                                PflagOp::Unset,
-                            ) {
-                                Err(e) => return Err(e),
-                                _ => {}
-                            }
-                        }
+                        runtime.shutdown()?;
 
                         println!("Exiting...");
                         std::process::exit(0);
@@ -683,8 +626,10 @@ pub fn listen_ip_pkts(cfg: &Config) -> io::Result<()> {
                     bpf_setup_buf(bpf_fd, &mut bpf_buf, &debug)?;
                     // bind interface to BPF device
                     bpf_bind_device(bpf_fd, &iface, &debug)?;
-                    // set interface in promiscuous mode
-                    bpf_set_promisc(bpf_fd, &debug)?;
+                    // set interface in promiscuous mode (except in dry-run mode)
+                    if !vr.parameters.dry_run() {
+                        bpf_set_promisc(bpf_fd, &debug)?;
+                    }
 
                     // store BPF file descriptor
                     vr.parameters.set_fd(bpf_fd);
@@ -835,6 +780,259 @@ pub fn listen_ip_pkts(cfg: &Config) -> io::Result<()> {
     }
 }
 
+// new_vrouter() function
+/// create a virtual router from its configuration
+fn new_vrouter(
+    vr: &config::VRConfig,
+    protocols: &Arc<Mutex<Protocols>>,
+    debug: &Verbose,
+    dry_run: bool,
+) -> io::Result<VirtualRouter> {
+    let driver = vr.netdrv().driver();
+
+    // run the virtual router on its VLAN sub-interface
+    let (ifname, vlan_owned) = match vr.vlan() {
+        Some(vid) => setup_vlan_link(vr, &driver, vid, dry_run, debug)?,
+        None => (vr.interface().to_string(), false),
+    };
+
+    let mut vro = match VirtualRouter::new(
+        vr.group(),
+        ifname.clone(),
+        vr.priority(),
+        vr.vip(),
+        vr.timer_advert(),
+        vr.preemption(),
+        vr.rfc3768(),
+        vr.auth_type(),
+        vr.auth_secret().clone(),
+        Arc::clone(protocols),
+        debug,
+        Arc::clone(&driver),
+        vr.iftype(),
+        vr.vifname(),
+        -1,
+        vr.socket_filter(),
+    ) {
+        Ok(vro) => vro,
+        Err(e) => {
+            // do not leave a created VLAN sub-interface behind
+            if vlan_owned {
+                let vlanidx = driver.link_index(&ifname).unwrap_or(0);
+                let _ = driver.setup_vlan_link(0, vlanidx, &ifname, 0, &Operation::Rem, debug);
+            }
+            return Err(e);
+        }
+    };
+    vro.parameters.set_macvlan_mode(vr.macvlan_mode());
+    vro.parameters.set_vmac_mode(vr.vmac_mode());
+    vro.parameters.set_dry_run(dry_run);
+    // listening on the parent interface, only the frames of the VLAN are received
+    if ifname != vr.ifname() {
+        vro.parameters.set_vlan_tag(vr.vlan());
+    }
+    vro.parameters.set_vlan_owned(vlan_owned);
+    Ok(vro)
+}
+
+// setup_vlan_link() function
+/// create the VLAN sub-interface of a virtual router unless it already exists,
+/// and return the interface the virtual router must run on, and whether the
+/// sub-interface has been created (and must then be deleted on shutdown)
+fn setup_vlan_link(
+    vr: &config::VRConfig,
+    driver: &Arc<dyn NetDriver>,
+    vid: u16,
+    dry_run: bool,
+    debug: &Verbose,
+) -> io::Result<(String, bool)> {
+    let ifname = vr.ifname();
+
+    // the sub-interface has been created beforehand
+    if driver.link_index(&ifname).is_ok() {
+        return Ok((ifname, false));
+    }
+
+    // in dry-run mode, the virtual router listens on the parent interface
+    if dry_run {
+        print_debug(
+            debug,
+            DEBUG_LEVEL_INFO,
+            DEBUG_SRC_DRYRUN,
+            format!(
+                "vrid {} on interface {}: skipping vlan add ({} does not exist)",
+                vr.group(),
+                vr.interface(),
+                ifname
+            ),
+        );
+        return Ok((vr.interface().to_string(), false));
+    }
+
+    // a primary IP address is required on the new sub-interface
+    let (ip, netmask) = match vr.vlan_address() {
+        Some(a) => a,
+        None => {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!("no vlan_address configured to create interface {}", ifname),
+            ))
+        }
+    };
+
+    // print debugging information
+    print_debug(
+        debug,
+        DEBUG_LEVEL_MEDIUM,
+        DEBUG_SRC_VR,
+        format!(
+            "creating VLAN {} interface {} on {}",
+            vid,
+            ifname,
+            vr.interface()
+        ),
+    );
+
+    let master = driver.link_index(vr.interface())?;
+    driver.setup_vlan_link(master, 0, &ifname, vid, &Operation::Add, debug)?;
+    let vlanidx = driver.link_index(&ifname)?;
+    if let Err(e) = driver.set_ip_address(-1, vlanidx, &ifname, ip, netmask, &Operation::Add, debug)
+    {
+        let _ = driver.setup_vlan_link(master, vlanidx, &ifname, vid, &Operation::Rem, debug);
+        return Err(e);
+    }
+
+    Ok((ifname, true))
+}
+
+// release_vlan_link() function
+/// delete the VLAN sub-interface created by the virtual router
+#[cfg(target_os = "linux")]
+fn release_vlan_link(vr: &VirtualRouter, debug: &Verbose) -> io::Result<()> {
+    if !vr.parameters.vlan_owned() {
+        return Ok(());
+    }
+
+    // print debugging information
+    print_debug(
+        debug,
+        DEBUG_LEVEL_MEDIUM,
+        DEBUG_SRC_VR,
+        format!("deleting VLAN interface {}", vr.parameters.interface()),
+    );
+
+    vr.parameters.driver().setup_vlan_link(
+        0,
+        vr.parameters.ifindex(),
+        &vr.parameters.interface(),
+        0,
+        &Operation::Rem,
+        debug,
+    )
+}
+
+// setup_vr_socket() function
+/// open the virtual router's raw socket and set its interface in promiscuous mode
+#[cfg(target_os = "linux")]
+fn setup_vr_socket(
+    vr: &mut VirtualRouter,
+    capi: &Option<(Sender<FSMQueryResult>, Receiver<FSMQueryResult>)>,
+    debug: &Verbose,
+) -> io::Result<()> {
+    // open vr's raw socket, the VLAN tags of the frames received
+    // on a parent interface are only visible to a socket receiving all the frames
+    let sock_fd = match vr.parameters.vlan_tag() {
+        Some(_) => open_raw_socket_all()?,
+        None => open_raw_socket_fd()?,
+    };
+
+    let setup = || -> io::Result<()> {
+        // set BPF socket filter if enabled
+        if vr.parameters.socket_filter() {
+            let filter = SockFilter::new_vrrpv2_gid(vr.parameters.vrid(), vr.parameters.vlan_tag());
+            let bpf_fprog = SockFprog::build_fprog_vrrpv2_gid(&filter);
+            set_sock_filter(sock_fd, &bpf_fprog)?;
+        }
+
+        // wake up the listener thread every second, so it can be stopped
+        set_sock_rcvtimeo(sock_fd, 1)?;
+
+        // set interface is promiscuous mode (except in dry-run mode)
+        vr.set_promiscuous(sock_fd, &vr.parameters.interface(), true, debug)
+    };
+    // do not leak the socket if it cannot be set up
+    if let Err(e) = setup() {
+        let _ = close_fd(sock_fd);
+        return Err(e);
+    }
+
+    // store raw socket file descriptor
+    vr.parameters.set_fd(sock_fd);
+
+    // if enabled, set downstream client API sender and receiver channels
+    match capi {
+        Some((s, r)) => {
+            vr.parameters.set_capi_tx(s.clone());
+            vr.parameters.set_capi_rx(r.clone());
+        }
+        None => (),
+    }
+
+    Ok(())
+}
+
+// release_vr_socket() function
+/// remove promiscuous mode off the virtual router's interface (if requested)
+/// and close its raw socket
+#[cfg(target_os = "linux")]
+fn release_vr_socket(vr: &VirtualRouter, promisc: bool, debug: &Verbose) -> io::Result<()> {
+    // get vr's socket file descriptor
+    let sock_fd = vr.parameters.fd();
+
+    if promisc {
+        vr.set_promiscuous(sock_fd, &vr.parameters.interface(), false, debug)?;
+    }
+
+    close_fd(sock_fd)
+}
+
+// recv_vrrp_pkts() function
+/// receive and handle VRRP packets of a virtual router until stopped
+#[cfg(target_os = "linux")]
+fn recv_vrrp_pkts(vr: Arc<RwLock<VirtualRouter>>, stop: Arc<AtomicBool>, debug: &Verbose) {
+    // get vr's socket descriptor
+    let sock_fd = vr.read().unwrap().parameters.fd();
+
+    // create single item vrouters vector
+    let mut vrouters: Vec<Arc<RwLock<VirtualRouter>>> = Vec::new();
+    vrouters.push(vr);
+
+    // initialize packet buffer
+    let mut pkt_buf: [u8; 1024] = [0; 1024];
+
+    // initialize sockaddr and packet buffer
+    let mut sockaddr: sockaddr_ll = unsafe { mem::zeroed() };
+
+    while !stop.load(Ordering::Relaxed) {
+        // Block on receiving IP packets (until the socket's timeout)
+        match recv_ip_pkts(sock_fd, &mut sockaddr, &mut pkt_buf) {
+            Ok(len) => {
+                // create and initialize pkg_hdr
+                let mut pkt_hdr = PktHdr::new();
+                // set inbound interface's ifindex)
+                pkt_hdr.in_ifidx = sockaddr.sll_ifindex;
+                match verify_vrrp_pkt(sock_fd, &pkt_hdr, &pkt_buf[0..len], &vrouters, &debug) {
+                    Some((ifindex, vrid, ipsrc, advert_prio)) => {
+                        handle_vrrp_advert(&vrouters, ifindex, vrid, ipsrc, advert_prio, &debug);
+                    }
+                    _ => (),
+                }
+            }
+            Err(_e) => (),
+        }
+    }
+}
+
 // verify_vrrp_pkt() function
 /// Verify VRRPv2 ADVERTISEMENT packets (as per RFC3768 7.1)
 fn verify_vrrp_pkt(
@@ -844,65 +1042,35 @@ fn verify_vrrp_pkt(
     vrouters: &Vec<Arc<RwLock<VirtualRouter>>>,
     debug: &Verbose,
 ) -> Option<(i32, u8, [u8; 4], u8)> {
-    // ignore packets that are too short (plus one IP address and auth. data. field)
-    if packet.len() < (mem::size_of::<VRRPpkt>() + 4 + 8) {
-        return None;
-    }
-
-    // read the *possibly* VRRP packet
-    let vrrp_pkt: VRRPpkt = unsafe { ptr::read(packet.as_ptr() as *const _) };
+    // parse the *possibly* VRRP packet, silently ignore the other IP packets
+    let frame = match AdvertFrame::decode(packet) {
+        Ok(p) => p,
+        Err(PacketError::NotVRRP) | Err(PacketError::NotIPv4) => return None,
+        Err(e) => {
+            print_debug(
+                debug,
+                DEBUG_LEVEL_HIGH,
+                DEBUG_SRC_PACKET,
+                format!("dropping malformed VRRP packet: {}", e),
+            );
+            return None;
+        }
+    };
 
-    // filter out all IP packets with IP protocol not matching VRRPv2
-    if *vrrp_pkt.ipproto() != IP_UPPER_PROTO_VRRP {
-        return None;
-    }
+    let vrrp = &frame.vrrp;
 
     // verify the IP ttl is 255
-    if *vrrp_pkt.ipttl() != IP_TTL_VRRP_MINTTL {
+    if frame.ip.ttl != IP_TTL_VRRP_MINTTL {
         return None;
     }
 
     // verify the VRRP version is 0x2 and the message type is 0x1 (ADVERTISEMENT)
-    if *vrrp_pkt.version() != VRRP_V2_VER_TYPE_AUTHMSG {
+    if vrrp.version != VRRP_V2_VER_TYPE_AUTHMSG {
         return None;
     }
 
-    // compute the number of bytes to read for the IP addresses
-    let ip_bcnt = (vrrp_pkt.s_addrcount(packet.len()) * 4) as usize;
-
-    // construct a variable-size vector of u8 for the VRRP PDU
-    let mut vrrp_pdu = Vec::new();
-    vrrp_pdu.push(*vrrp_pkt.version());
-    vrrp_pdu.push(*vrrp_pkt.vrid());
-    vrrp_pdu.push(*vrrp_pkt.prio());
-    vrrp_pdu.push(*vrrp_pkt.addrcount());
-    vrrp_pdu.push(*vrrp_pkt.authtype());
-    vrrp_pdu.push(*vrrp_pkt.adverint());
-    vrrp_pdu.push(*vrrp_pkt.checksum() as u8);
-    vrrp_pdu.push((*vrrp_pkt.checksum()).to_be() as u8);
-
-    // read IP addresses from packet buffer
-    // and extend vrrp_pdu vector to ip addresses
-    let ipaddrs = unsafe {
-        slice::from_raw_parts(
-            packet[ETHER_VRRP_IPADDR_POS..].as_ptr() as *const _,
-            ip_bcnt,
-        )
-    };
-    vrrp_pdu.extend_from_slice(&ipaddrs);
-
-    // read authentication data from packet buffer
-    // and extend vrrp_pdu vector to auth. data
-    let authdata = unsafe {
-        slice::from_raw_parts(
-            packet[ETHER_VRRP_IPADDR_POS + ip_bcnt..].as_ptr() as *const _,
-            8,
-        )
-    };
-    vrrp_pdu.extend_from_slice(&authdata);
-
     // verify the VRRP checksum (RFC1071)
-    if checksums::rfc1071(&vrrp_pdu) != 0xFFFF {
+    if !vrrp.verify_checksum() {
         return None;
     }
 
@@ -910,7 +1078,7 @@ fn verify_vrrp_pkt(
     // and the local router is not the owner of the destination IP address.
     let ifb_vr = vrouters.iter().find(|&v| {
         let v = v.read().unwrap();
-        (v.parameters.ifindex() == pkt_hdr.in_ifidx) && (v.parameters.vrid() == *vrrp_pkt.vrid())
+        (v.parameters.ifindex() == pkt_hdr.in_ifidx) && (v.parameters.vrid() == vrrp.vrid)
     });
     match ifb_vr {
         // if a virtual router exists for this interface / VRID pair:
@@ -918,8 +1086,13 @@ fn verify_vrrp_pkt(
             // first get read lock on vr's RwLock guard
             let vr = vr.read().unwrap();
 
+            // the frames of another VLAN may be received on a parent interface
+            if frame.eth.vlan().is_some() && frame.eth.vlan() != vr.parameters.vlan_tag() {
+                return None;
+            }
+
             // verify the destination address is not owned by the virtual router
-            if vr.parameters.ipaddrs().contains(vrrp_pkt.ipdst()) {
+            if vr.parameters.ipaddrs().contains(&frame.ip.dst) {
                 print_debug(
                     debug,
                     DEBUG_LEVEL_MEDIUM,
@@ -931,7 +1104,7 @@ fn verify_vrrp_pkt(
 
             // verify the authentication type matches the configured method
             // for this virtual router
-            if *vrrp_pkt.authtype() != vr.parameters.authtype() {
+            if vrrp.authtype != vr.parameters.authtype() {
                 print_debug(
                     debug,
                     DEBUG_LEVEL_MEDIUM,
@@ -944,67 +1117,35 @@ fn verify_vrrp_pkt(
             // perform message authentication
             match vr.parameters.authtype() {
                 // AUTH_TYPE_SIMPLE (RFC2338 Type-1 Plain)
-                AUTH_TYPE_SIMPLE => {
-                    print_debug(
-                        debug,
-                        DEBUG_LEVEL_EXTENSIVE,
-                        DEBUG_SRC_AUTH,
-                        format!("performing VRRP simple (type-1) authentication"),
-                    );
-                    let d = gen_auth_data(
-                        AUTH_TYPE_SIMPLE,
-                        vr.parameters.authsecret(),
-                        Option::Some(&vrrp_pdu[..vrrp_pdu.len() - 8]),
-                    );
-                    if d != authdata {
-                        print_debug(
-                            debug,
-                            DEBUG_LEVEL_MEDIUM,
-                            DEBUG_SRC_AUTH,
-                            format!("VRRP message authentication failed"),
-                        );
-                        return None;
-                    }
-                }
                 // AUTH_TYPE_P0 (PROPRIETARY-TRUNCATED-8B-SHA256)
                 // AUTH_TYPE_P1 (PROPRIETARY-XOF-8B-SHAKE256)
-                AUTH_TYPE_P0 | AUTH_TYPE_P1 => {
+                AUTH_TYPE_SIMPLE | AUTH_TYPE_P0 | AUTH_TYPE_P1 => {
                     print_debug(
                         debug,
                         DEBUG_LEVEL_EXTENSIVE,
                         DEBUG_SRC_AUTH,
                         format!(
-                            "performing VRRP proprietary ({}) authentication",
+                            "performing VRRP type-{} authentication",
                             vr.parameters.authtype()
                         ),
                     );
-                    // get the verification code on the VRRP PDU minus the authentication header
-                    // and the checksum field zero-ed out (HMAC-then-checksum)
-                    let zchecksum = [0u8, 0u8];
-                    vrrp_pdu.splice(
-                        VRRP_V2_CHECKSUM_POS..VRRP_V2_CHECKSUM_POS + 2,
-                        zchecksum.iter().cloned(),
-                    );
-                    let hmac = gen_auth_data(
-                        vr.parameters.authtype(),
-                        vr.parameters.authsecret(),
-                        Option::Some(&vrrp_pdu[..vrrp_pdu.len() - 8]),
-                    );
+                    let authdata = vrrp.auth_data(vr.parameters.authsecret());
                     // print debugging information
                     print_debug(
                         debug,
                         DEBUG_LEVEL_EXTENSIVE,
                         DEBUG_SRC_AUTH,
-                        format!("VRRP message authentication data {:02x?}", &hmac[..]),
+                        format!("VRRP message authentication data {:02x?}", &authdata[..]),
                     );
                     // check if authentication data matches
-                    if hmac != authdata {
+                    if authdata != vrrp.authdata {
                         print_debug(
                             debug,
                             DEBUG_LEVEL_MEDIUM,
                             DEBUG_SRC_AUTH,
                             format!("VRRP message authentication failed"),
                         );
+                        publish_auth_failure(&vr, &frame.ip.src);
                         return None;
                     }
                 }
@@ -1012,9 +1153,20 @@ fn verify_vrrp_pkt(
                 _ => {}
             }
 
+            // record the peer, an authenticated ADVERTISEMENT with a non-matching
+            // advertisement interval is still reported
+            vr.parameters.peers().lock().unwrap().seen(
+                frame.ip.src,
+                frame.eth.src_mac,
+                vrrp.prio,
+                vrrp.adverint,
+                vrrp.authtype,
+                vrrp.ipaddrs.clone(),
+            );
+
             // verify the message's 'avertint' field matches the locally
             // configured vr's advertisement interval
-            if *vrrp_pkt.adverint() != vr.parameters.adverint() {
+            if vrrp.adverint != vr.parameters.adverint() {
                 print_debug(
                     debug,
                     DEBUG_LEVEL_MEDIUM,
@@ -1028,8 +1180,8 @@ fn verify_vrrp_pkt(
             Some((
                 vr.parameters.ifindex(),
                 vr.parameters.vrid(),
-                *vrrp_pkt.ipsrc(),
-                *vrrp_pkt.prio(),
+                frame.ip.src,
+                vrrp.prio,
             ))
         }
         // if no matching virtual router exists, simply drop the VRRP message
@@ -1045,6 +1197,16 @@ fn verify_vrrp_pkt(
     }
 }
 
+// publish_auth_failure() function
+/// notify the client API subscribers of an authentication failure
+fn publish_auth_failure(vr: &VirtualRouter, ipsrc: &[u8; 4]) {
+    events::publish(Notification::AuthFailure {
+        group: vr.parameters.vrid(),
+        interface: vr.parameters.interface(),
+        source: format!("{}.{}.{}.{}", ipsrc[0], ipsrc[1], ipsrc[2], ipsrc[3]),
+    });
+}
+
 // handle_vrrp_advert() function
 /// Handle VRRPv2 ADVERTISEMENT message
 fn handle_vrrp_advert(
@@ -1088,10 +1250,11 @@ fn handle_vrrp_advert(
                         format!("sending Advert event notification"),
                     );
                     // acquiring lock on sender channel
+                    // the worker may have exited already
                     tx.lock()
                         .unwrap()
                         .send(fsm::Event::Advert(ipsrc, advert_prio))
-                        .unwrap();
+                        .unwrap_or(());
                     // print debugging information
                     print_debug(
                         debug,
@@ -1108,102 +1271,76 @@ fn handle_vrrp_advert(
                 ),
             }
         }
-        None => {
-            panic!("error(main): incorrect virtual router reference, possible race condition, panicking");
-        }
+        // the virtual router may have been removed since the packet was read
+        None => print_debug(
+            debug,
+            DEBUG_LEVEL_LOW,
+            DEBUG_SRC_MAIN,
+            format!(
+                "no virtual router for VRID {} on if {}, dropping ADVERTISEMENT",
+                vrid, ifindex
+            ),
+        ),
     }
 }
 
 // filter_vrrp_pkt() function
 /// Filter VRRPv2 packets for sniffing mode
 fn filter_vrrp_pkt(fd: i32, _pkt_hdr: &PktHdr, packet: &[u8]) {
-    // ignore packets that are way too short (plus auth. data. field)
-    if packet.len() < (mem::size_of::<VRRPpkt>() + 8) {
-        return;
-    }
-
-    // read packet
-    let vrrp_pkt: VRRPpkt = unsafe { ptr::read(packet.as_ptr() as *const _) };
-
-    // filter VRRP packets (IP Proto 112)
-    if *vrrp_pkt.ipproto() != IP_UPPER_PROTO_VRRP {
-        return;
-    }
+    // parse packet, only VRRP packets (IP Proto 112) are shown
+    let frame = match AdvertFrame::decode(packet) {
+        Ok(p) => p,
+        Err(PacketError::NotVRRP) | Err(PacketError::NotIPv4) => return,
+        Err(e) => {
+            println!("Malformed VRRP message received: {}.", e);
+            return;
+        }
+    };
 
     // verify the IP TTL is 255 (per RFC3768 7.1)
-    if *vrrp_pkt.ipttl() != IP_TTL_VRRP_MINTTL {
+    if frame.ip.ttl != IP_TTL_VRRP_MINTTL {
         println!(
             "VRRP message received with invalid TTL {:#X}.",
-            vrrp_pkt.ipttl()
+            frame.ip.ttl
         );
     }
 
     // perform VRRP sanity checks
     // if VRRP version is not 2 and type is not advertisement (p/x 0b00100001)
-    if *vrrp_pkt.version() != VRRP_V2_VER_TYPE_AUTHMSG {
+    if frame.vrrp.version != VRRP_V2_VER_TYPE_AUTHMSG {
         return;
     }
 
-    // compute the number of bytes to read for the IP addresses
-    let ip_bcnt = (vrrp_pkt.s_addrcount(packet.len()) * 4) as usize;
-
-    // constructing a variable-size vector of u8 for the VRRP PDU
-    let mut vrrp_pdu = Vec::new();
-    vrrp_pdu.push(*vrrp_pkt.version());
-    vrrp_pdu.push(*vrrp_pkt.vrid());
-    vrrp_pdu.push(*vrrp_pkt.prio());
-    vrrp_pdu.push(*vrrp_pkt.addrcount());
-    vrrp_pdu.push(*vrrp_pkt.authtype());
-    vrrp_pdu.push(*vrrp_pkt.adverint());
-    vrrp_pdu.push(*vrrp_pkt.checksum() as u8);
-    vrrp_pdu.push((*vrrp_pkt.checksum()).to_be() as u8);
-
-    // read IP addresses from packet buffer
-    // and extend vrrp_pdu vector to ip addresses
-    let ipaddrs = unsafe {
-        slice::from_raw_parts(
-            packet[ETHER_VRRP_IPADDR_POS..].as_ptr() as *const _,
-            ip_bcnt,
-        )
-    };
-    vrrp_pdu.extend_from_slice(&ipaddrs);
-
-    // read authentication data from packet buffer
-    // and extend vrrp_pdu vector to auth. data
-    let authdata = unsafe {
-        slice::from_raw_parts(
-            packet[ETHER_VRRP_IPADDR_POS + ip_bcnt..].as_ptr() as *const _,
-            8,
-        )
-    };
-    vrrp_pdu.extend_from_slice(&authdata);
-
-    // verify result of the RFC1071 checksum
-    if checksums::rfc1071(&vrrp_pdu) != 0xFFFF {
+    // verify the VRRP checksum
+    if !frame.vrrp.verify_checksum() {
         println!(
             "VRRP message with invalid checksum {:#X} detected",
-            checksums::rfc1071(&vrrp_pdu)
+            frame.vrrp.checksum
         );
     }
 
     // call show_vrrp_pkt() to handle VRRPv2 packets
-    show_vrrp_pkt(fd, &vrrp_pkt, ipaddrs, authdata);
+    show_vrrp_pkt(fd, &frame);
 }
 
 // show_vrrp_pkt() function
 /// Display VRRPv2 packets
-fn show_vrrp_pkt(_fd: i32, vrrp_pkt: &VRRPpkt, ipaddrs: &[u8], _authdata: &[u8]) {
+fn show_vrrp_pkt(_fd: i32, frame: &AdvertFrame) {
+    let vrrp = &frame.vrrp;
     // prints some fields
     println!("VRRPv2 Packet:");
-    println!(" Version/Type: {:#2X}", vrrp_pkt.version());
-    println!(" Virtual Router ID: {}", vrrp_pkt.vrid());
-    println!(" Priority: {}", vrrp_pkt.prio());
-    println!(" IP Address Count: {}", vrrp_pkt.addrcount());
-    println!(" Authentication Type: {:#2X}", vrrp_pkt.authtype());
-    println!(" Advertisement Interval: {}s", vrrp_pkt.adverint());
-    println!(" VRRP Checksum: {:#X}", vrrp_pkt.checksum());
+    if let Some(vlan) = frame.eth.vlan() {
+        println!(" VLAN: {}", vlan);
+    }
+    println!(" Version/Type: {:#2X}", vrrp.version);
+    println!(" Virtual Router ID: {}", vrrp.vrid);
+    println!(" Priority: {}", vrrp.prio);
+    println!(" IP Address Count: {}", vrrp.ipaddrs.len());
+    println!(" Authentication Type: {:#2X}", vrrp.authtype);
+    println!(" Advertisement Interval: {}s", vrrp.adverint);
+    println!(" VRRP Checksum: {:#X}", vrrp.checksum);
     println!(" IP Address(es):");
-    for (a, b, c, d) in ipaddrs.into_iter().tuple_windows() {
-        println!("  - {}.{}.{}.{}\n", a, b, c, d)
+    for a in &vrrp.ipaddrs {
+        println!("  - {}.{}.{}.{}\n", a[0], a[1], a[2], a[3])
     }
 }
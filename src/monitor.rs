@@ -0,0 +1,145 @@
+//! interfaces monitoring module
+//! This module watches the links and IPv4 addresses changes of the host, and
+//! notifies the virtual routers running on the affected interfaces.
+use super::*;
+
+// finite state machine
+use fsm::Event;
+
+// rtnetlink
+use os::linux::rtnetlink::{self, LinkEvent, Monitor};
+
+// std
+use std::thread;
+use std::time::Duration;
+
+// first and longest delays before receiving again after errors
+const MONITOR_BACKOFF_MIN_MS: u64 = 100;
+const MONITOR_BACKOFF_MAX_MS: u64 = 5000;
+
+// start() function
+/// subscribe to the links and addresses notifications and spawn the
+/// monitor thread
+pub fn start(runtime: Runtime) -> io::Result<()> {
+    let monitor = Monitor::open()?;
+
+    thread::spawn(move || {
+        let mut errors = 0;
+        loop {
+            match monitor.recv() {
+                Ok(events) => {
+                    errors = 0;
+                    for event in events {
+                        dispatch(&runtime, &event);
+                    }
+                }
+                // notifications were lost when the socket buffer overran,
+                // the current state replaces them
+                Err(ref e) if e.raw_os_error() == Some(libc::ENOBUFS) => {
+                    eprintln!("warning(monitor): netlink notifications lost, resynchronizing");
+                    match rtnetlink::dump() {
+                        Ok(events) => {
+                            errors = 0;
+                            for event in events {
+                                dispatch(&runtime, &event);
+                            }
+                        }
+                        Err(e) => {
+                            eprintln!("error(monitor): cannot dump the links and addresses: {}", e);
+                            errors += 1;
+                            thread::sleep(backoff(errors));
+                        }
+                    }
+                }
+                Err(e) => {
+                    eprintln!(
+                        "error(monitor): cannot receive netlink notifications: {}",
+                        e
+                    );
+                    errors += 1;
+                    thread::sleep(backoff(errors));
+                }
+            }
+        }
+    });
+
+    Ok(())
+}
+
+// backoff() function
+/// return the delay after consecutive errors, doubled on each of them
+fn backoff(errors: u32) -> Duration {
+    let ms = MONITOR_BACKOFF_MIN_MS << errors.saturating_sub(1).min(16);
+    Duration::from_millis(ms.min(MONITOR_BACKOFF_MAX_MS))
+}
+
+// dispatch() function
+/// send the events of a link or address change to the virtual routers
+fn dispatch(runtime: &Runtime, event: &LinkEvent) {
+    // print debugging information
+    print_debug(
+        runtime.debug(),
+        DEBUG_LEVEL_EXTENSIVE,
+        DEBUG_SRC_MONITOR,
+        format!("got {:?}", event),
+    );
+
+    for vr in runtime.vrouters() {
+        let vr = vr.read().unwrap();
+        if let Some(tx) = vr.parameters.notification() {
+            for e in events(&vr, event) {
+                // the worker may have exited already
+                tx.lock().unwrap().send(e).unwrap_or(());
+            }
+        }
+    }
+}
+
+// events() function
+/// return the events a link or address change means for a virtual router
+fn events(vr: &VirtualRouter, event: &LinkEvent) -> Vec<Event> {
+    let ifindex = vr.parameters.ifindex();
+    let phys = runtime::phys_interface(vr);
+
+    match event {
+        LinkEvent::NewLink {
+            ifindex: idx,
+            ifname,
+            up,
+        } if *idx == ifindex || *ifname == phys => {
+            let mut events = Vec::new();
+            // renamed, or deleted and created again
+            if *idx != ifindex || *ifname != phys {
+                events.push(Event::LinkChange(*idx, ifname.clone()));
+            }
+            events.push(Event::LinkState(*up));
+            events
+        }
+        LinkEvent::DelLink { ifindex: idx, .. } if *idx == ifindex => {
+            vec![Event::LinkState(false)]
+        }
+        LinkEvent::NewAddr { ifindex: idx, ip } if *idx == ifindex => vec![Event::AddrAdded(*ip)],
+        // the virtual IP address of a master is set on its virtual interface
+        LinkEvent::DelAddr { ifindex: idx, ip }
+            if *idx == ifindex
+                || (vr.parameters.iftype() != IfTypes::ether && *idx == vr.parameters.vifidx()) =>
+        {
+            vec![Event::AddrRemoved(*ip)]
+        }
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_back_off() {
+        assert_eq!(backoff(1), Duration::from_millis(100));
+        assert_eq!(backoff(2), Duration::from_millis(200));
+        assert_eq!(backoff(6), Duration::from_millis(3200));
+        assert_eq!(backoff(7), Duration::from_millis(5000));
+        assert_eq!(backoff(100), Duration::from_millis(5000));
+    }
+}
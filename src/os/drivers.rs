@@ -1,19 +1,221 @@
 //! generic drivers module
+use crate::*;
+
+// debugging
+use crate::debug::Verbose;
+
+// protocols
+use crate::protocols::Static;
+
+// std
+use std::fmt;
 
 // network drivers enumerator
 #[allow(non_camel_case_types)]
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum NetDrivers {
-    ioctl, // ioctl
-    libnl, // netlink (libnl-3)
+    ioctl,   // ioctl
+    libnl,   // netlink (libnl-3)
+    netlink, // netlink (native rtnetlink)
+}
+
+// NetDrivers enumerator implementation
+impl NetDrivers {
+    // driver() method
+    /// return the implementation of the network driver
+    pub fn driver(&self) -> Arc<dyn NetDriver> {
+        // --- Linux specific network drivers
+        #[cfg(target_os = "linux")]
+        match self {
+            NetDrivers::ioctl => Arc::new(os::linux::netdev::Netdev),
+            NetDrivers::libnl => Arc::new(os::linux::libnl::Libnl),
+            NetDrivers::netlink => Arc::new(os::linux::rtnetlink::Netlink),
+        }
+        // END Linux specific network drivers
+
+        // --- FreeBSD specific network drivers
+        #[cfg(target_os = "freebsd")]
+        Arc::new(os::freebsd::netinet::Netinet)
+        // END FreeBSD specific network drivers
+    }
+}
+
+/// Network Driver Trait
+/// The operations a virtual router performs on the host network stack,
+/// the finite-state machine never calls the operating system directly.
+pub trait NetDriver: fmt::Debug + Send + Sync {
+    // kind() method
+    /// return the network driver variant
+    fn kind(&self) -> NetDrivers;
+
+    // set_ip_address() method
+    /// add or remove an IPv4 address on an interface
+    /// (ioctls replace the interface's primary address instead of adding one)
+    fn set_ip_address(
+        &self,
+        fd: i32,
+        ifindex: i32,
+        ifname: &str,
+        ip: [u8; 4],
+        netmask: [u8; 4],
+        op: &Operation,
+        debug: &Verbose,
+    ) -> io::Result<()>;
+
+    // get_mac_address() method
+    /// get the Ethernet MAC address of an interface
+    fn get_mac_address(&self, fd: i32, ifname: &str, debug: &Verbose) -> io::Result<[u8; 6]>;
+
+    // set_mac_address() method
+    /// set the Ethernet MAC address of an interface
+    fn set_mac_address(
+        &self,
+        fd: i32,
+        ifname: &str,
+        mac: [u8; 6],
+        debug: &Verbose,
+    ) -> io::Result<()>;
+
+    // set_ip_route() method
+    /// add or remove a static route
+    fn set_ip_route(
+        &self,
+        fd: i32,
+        ifname: &str,
+        route: &Static,
+        op: &Operation,
+        debug: &Verbose,
+    ) -> io::Result<()>;
+
+    // setup_macvlan_link() method
+    /// create a macvlan interface on top of the master interface,
+    /// or delete it by its ifindex
+    fn setup_macvlan_link(
+        &self,
+        master: i32,
+        vifidx: i32,
+        vifname: &str,
+        mac: [u8; 6],
+        mode: MacvlanMode,
+        op: &Operation,
+        debug: &Verbose,
+    ) -> io::Result<()>;
+
+    // setup_ipvlan_link() method
+    /// create an ipvlan interface (in L2 mode) on top of the master interface,
+    /// or delete it by its ifindex
+    fn setup_ipvlan_link(
+        &self,
+        master: i32,
+        vifidx: i32,
+        vifname: &str,
+        op: &Operation,
+        debug: &Verbose,
+    ) -> io::Result<()>;
+
+    // setup_vlan_link() method
+    /// create an 802.1Q VLAN sub-interface on top of the master interface,
+    /// or delete it by its ifindex
+    fn setup_vlan_link(
+        &self,
+        master: i32,
+        vlanidx: i32,
+        vlanname: &str,
+        vid: u16,
+        op: &Operation,
+        debug: &Verbose,
+    ) -> io::Result<()>;
+
+    // set_secondary_mac() method
+    /// add or remove a secondary unicast MAC address on an interface,
+    /// keeping its primary MAC address
+    fn set_secondary_mac(
+        &self,
+        ifindex: i32,
+        mac: [u8; 6],
+        op: &Operation,
+        debug: &Verbose,
+    ) -> io::Result<()>;
+
+    // set_promiscuous() method
+    /// set or clear the promiscuous mode flag of an interface
+    fn set_promiscuous(&self, fd: i32, ifname: &str, on: bool) -> io::Result<()>;
+
+    // link_index() method
+    /// return the ifindex of an interface
+    fn link_index(&self, ifname: &str) -> io::Result<i32>;
+
+    // send_frame() method
+    /// send a raw Ethernet frame out of an interface
+    fn send_frame(
+        &self,
+        fd: i32,
+        ifindex: i32,
+        frame: &mut Vec<u8>,
+        debug: &Verbose,
+    ) -> io::Result<()>;
+
+    // send_arp() method
+    /// send a raw ARP frame out of an interface
+    fn send_arp(
+        &self,
+        fd: i32,
+        ifindex: i32,
+        frame: &mut Vec<u8>,
+        debug: &Verbose,
+    ) -> io::Result<()>;
 }
 
 // network interfaces type enumerator
 #[allow(non_camel_case_types)]
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum IfTypes {
     ether,   // default ethernet
     macvlan, // macvlan
+    ipvlan,  // ipvlan (sharing the master's MAC address)
+}
+
+// macvlan modes enumerator
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MacvlanMode {
+    private,  // no communication with the other macvlan interfaces
+    vepa,     // traffic between macvlan interfaces goes through the switch
+    bridge,   // macvlan interfaces are bridged together
+    passthru, // single macvlan interface owning the master
+}
+
+// MacvlanMode enumerator implementation
+impl MacvlanMode {
+    // name() method
+    // return the name of the mode
+    pub fn name(&self) -> &'static str {
+        match self {
+            MacvlanMode::private => "private",
+            MacvlanMode::vepa => "vepa",
+            MacvlanMode::bridge => "bridge",
+            MacvlanMode::passthru => "passthru",
+        }
+    }
+    // value() method
+    // return the kernel value of the mode (linux/if_link.h)
+    pub fn value(&self) -> u32 {
+        match self {
+            MacvlanMode::private => 1,
+            MacvlanMode::vepa => 2,
+            MacvlanMode::bridge => 4,
+            MacvlanMode::passthru => 8,
+        }
+    }
+}
+
+// virtual router MAC address modes enumerator
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VmacModes {
+    primary,   // the interface's MAC address is replaced
+    secondary, // added to the interface's unicast filter
+    none,      // the interface's MAC address is kept
 }
 
 // pflag operation Enumerator
@@ -24,8 +226,20 @@ pub enum PflagOp {
 }
 
 // Operation enumerator
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Operation {
     Add, // Add IP address
     Rem, // Remove IP Address
 }
+
+// Operation implementation
+impl Operation {
+    // action() method
+    // return the name of the operation
+    pub fn action(&self) -> &'static str {
+        match self {
+            Operation::Add => "add",
+            Operation::Rem => "remove",
+        }
+    }
+}
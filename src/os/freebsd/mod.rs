@@ -7,9 +7,6 @@ pub mod constants;
 // FreeBSD standard C library support
 pub mod libc;
 
-// FreeBSD ARP support
-pub mod arp;
-
 // FreeBSD BPF support
 pub mod bpf;
 
@@ -11,7 +11,16 @@ use std::ffi::CString;
 use crate::os::freebsd::constants::*;
 
 // operating systems drivers
-use crate::os::drivers::Operation;
+use crate::os::drivers::{MacvlanMode, NetDriver, NetDrivers, Operation};
+
+// FreeBSD standard C library support
+use crate::os::freebsd::libc::raw_sendto;
+
+// debugging
+use crate::debug::Verbose;
+
+// protocols
+use crate::protocols::Static;
 
 // IfAliasReq Structure
 #[repr(C)]
@@ -119,4 +128,68 @@ pub fn set_ip_address(_fd: i32, ifname: &CString, ip: [u8; 4], netmask: [u8; 4],
     }
 
     Ok(())
-} 
+}
+
+/// Netinet Network Driver Structure
+/// IPv4 addresses are added and removed using ioctls,
+/// frames are written on the BPF device
+#[derive(Debug)]
+pub struct Netinet;
+
+// NetDriver trait implementation for the netinet (ioctl) driver
+impl NetDriver for Netinet {
+    // kind() method
+    fn kind(&self) -> NetDrivers {
+        NetDrivers::ioctl
+    }
+    // set_ip_address() method
+    fn set_ip_address(&self, fd: i32, _ifindex: i32, ifname: &str, ip: [u8; 4], netmask: [u8; 4], op: &Operation, _debug: &Verbose) -> io::Result<()> {
+        set_ip_address(fd, &CString::new(ifname).unwrap(), ip, netmask, *op)
+    }
+    // get_mac_address() method
+    fn get_mac_address(&self, _fd: i32, _ifname: &str, _debug: &Verbose) -> io::Result<[u8; 6]> {
+        Err(io::Error::new(io::ErrorKind::Other, "MAC addresses are not supported on FreeBSD"))
+    }
+    // set_mac_address() method
+    fn set_mac_address(&self, _fd: i32, _ifname: &str, _mac: [u8; 6], _debug: &Verbose) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Other, "MAC addresses are not supported on FreeBSD"))
+    }
+    // set_ip_route() method
+    fn set_ip_route(&self, _fd: i32, _ifname: &str, _route: &Static, _op: &Operation, _debug: &Verbose) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Other, "static routes are not supported on FreeBSD"))
+    }
+    // setup_macvlan_link() method
+    fn setup_macvlan_link(&self, _master: i32, _vifidx: i32, _vifname: &str, _mac: [u8; 6], _mode: MacvlanMode, _op: &Operation, _debug: &Verbose) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Other, "macvlan interfaces are not supported on FreeBSD"))
+    }
+    // setup_ipvlan_link() method
+    fn setup_ipvlan_link(&self, _master: i32, _vifidx: i32, _vifname: &str, _op: &Operation, _debug: &Verbose) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Other, "ipvlan interfaces are not supported on FreeBSD"))
+    }
+    // setup_vlan_link() method
+    fn setup_vlan_link(&self, _master: i32, _vlanidx: i32, _vlanname: &str, _vid: u16, _op: &Operation, _debug: &Verbose) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Other, "VLAN interfaces are not supported on FreeBSD"))
+    }
+    // set_secondary_mac() method
+    fn set_secondary_mac(&self, _ifindex: i32, _mac: [u8; 6], _op: &Operation, _debug: &Verbose) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Other, "secondary MAC addresses are not supported on FreeBSD"))
+    }
+    // set_promiscuous() method
+    // the promiscuous mode is set on the BPF device
+    fn set_promiscuous(&self, _fd: i32, _ifname: &str, _on: bool) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Other, "use the BPF device to set the promiscuous mode on FreeBSD"))
+    }
+    // link_index() method
+    fn link_index(&self, _ifname: &str) -> io::Result<i32> {
+        Err(io::Error::new(io::ErrorKind::Other, "interfaces index are not supported on FreeBSD"))
+    }
+    // send_frame() method
+    fn send_frame(&self, fd: i32, ifindex: i32, frame: &mut Vec<u8>, debug: &Verbose) -> io::Result<()> {
+        raw_sendto(fd, ifindex, frame, debug)
+    }
+    // send_arp() method
+    // ARP frames are written on the BPF device as well
+    fn send_arp(&self, fd: i32, ifindex: i32, frame: &mut Vec<u8>, debug: &Verbose) -> io::Result<()> {
+        raw_sendto(fd, ifindex, frame, debug)
+    }
+}
@@ -3,6 +3,13 @@
 // libc
 use libc::c_void;
 
+// ancillary data offsets (linux/filter.h)
+const SKF_AD_OFF: u32 = 0xfffff000;
+const SKF_AD_PKTTYPE: u32 = 4;
+const SKF_AD_VLAN_TAG: u32 = 44;
+const SKF_AD_VLAN_TAG_PRESENT: u32 = 48;
+const PACKET_OTHERHOST: u32 = 3;
+
 // SockFilter structure
 #[repr(C)]
 pub struct SockFilter {
@@ -17,6 +24,10 @@ impl SockFilter {
     // new_vrrpv2_gid() method
     //
     // BPF Filter - VRRPv2 Advertisement Packets:
+    // ld       vlan_avail
+    // jneq     #0x0, drop
+    // ld       pkttype
+    // jeq      #0x3, drop
     // ldh      [12]
     // jne      #0x800, drop
     // ldb      [23]
@@ -28,7 +39,23 @@ impl SockFilter {
     // ret      #-1
     // drop:    ret #0
     //
+    // The kernel removes the VLAN tags before the frames reach the socket,
+    // the frames of a VLAN without sub-interface are then received on the
+    // parent interface as if they were untagged, but for another host.
+    // When listening for a VLAN on its parent interface (with a socket
+    // receiving all the frames), its tagged frames are accepted instead:
+    // ld       vlan_avail
+    // jneq     #0x1, drop
+    // ld       vlan_tci
+    // and      #0xfff
+    // jneq     #0x7b, drop
+    // ...
+    //
     // BPF Bytecode:
+    // { 0x20,  0,  0, 0xfffff030 },
+    // { 0x15,  0, 11, 0000000000 },
+    // { 0x20,  0,  0, 0xfffff004 },
+    // { 0x15,  9,  0, 0x00000003 },
     // { 0x28,  0,  0, 0x0000000c },
     // { 0x15,  0,  7, 0x00000800 },
     // { 0x30,  0,  0, 0x00000017 },
@@ -40,8 +67,69 @@ impl SockFilter {
     // { 0x06,  0,  0, 0xffffffff },
     // { 0x06,  0,  0, 0000000000 },
     //
-    pub fn new_vrrpv2_gid(gid: u8) -> [SockFilter; 10] {
-        let filter: [SockFilter; 10] = [
+    pub fn new_vrrpv2_gid(gid: u8, vlan: Option<u16>) -> Vec<SockFilter> {
+        // VLAN tag check, jumping to the last instruction on mismatch
+        let mut filter = match vlan {
+            None => vec![
+                SockFilter {
+                    code: 0x20,
+                    jt: 0x0,
+                    jf: 0x0,
+                    k: SKF_AD_OFF + SKF_AD_VLAN_TAG_PRESENT,
+                },
+                SockFilter {
+                    code: 0x15,
+                    jt: 0x0,
+                    jf: 0xb,
+                    k: 0x00000000,
+                },
+                SockFilter {
+                    code: 0x20,
+                    jt: 0x0,
+                    jf: 0x0,
+                    k: SKF_AD_OFF + SKF_AD_PKTTYPE,
+                },
+                SockFilter {
+                    code: 0x15,
+                    jt: 0x9,
+                    jf: 0x0,
+                    k: PACKET_OTHERHOST,
+                },
+            ],
+            Some(vid) => vec![
+                SockFilter {
+                    code: 0x20,
+                    jt: 0x0,
+                    jf: 0x0,
+                    k: SKF_AD_OFF + SKF_AD_VLAN_TAG_PRESENT,
+                },
+                SockFilter {
+                    code: 0x15,
+                    jt: 0x0,
+                    jf: 0xc,
+                    k: 0x00000001,
+                },
+                SockFilter {
+                    code: 0x20,
+                    jt: 0x0,
+                    jf: 0x0,
+                    k: SKF_AD_OFF + SKF_AD_VLAN_TAG,
+                },
+                SockFilter {
+                    code: 0x54,
+                    jt: 0x0,
+                    jf: 0x0,
+                    k: 0x00000fff,
+                },
+                SockFilter {
+                    code: 0x15,
+                    jt: 0x0,
+                    jf: 0x9,
+                    k: vid as u32, // replace by the VLAN id
+                },
+            ],
+        };
+        filter.extend(vec![
             SockFilter {
                 // 001
                 code: 0x28,
@@ -81,7 +169,7 @@ impl SockFilter {
                 // 006
                 code: 0x15,
                 jt: 0x0,
-                jf: 0x0,
+                jf: 0x3,
                 k: 0x00000021,
             },
             SockFilter {
@@ -112,7 +200,7 @@ impl SockFilter {
                 jf: 0x0,
                 k: 0000000000,
             },
-        ];
+        ]);
         filter
     }
 }
@@ -127,7 +215,7 @@ pub struct SockFprog {
 // SockFprog implementation
 impl SockFprog {
     // build_fprog_vrrpv2_gid() method
-    pub fn build_fprog_vrrpv2_gid(filter: &[SockFilter; 10]) -> SockFprog {
+    pub fn build_fprog_vrrpv2_gid(filter: &[SockFilter]) -> SockFprog {
         let fprog = SockFprog {
             filter: filter.as_ptr() as *const c_void,
             len: filter.len() as u16,
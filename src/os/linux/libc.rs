@@ -2,7 +2,10 @@
 use crate::*;
 
 // std, libc, ffi
-use libc::{setsockopt, socket, AF_PACKET, SOCK_RAW, SOL_SOCKET, SO_ATTACH_FILTER};
+use libc::{
+    close, setsockopt, socket, timeval, AF_PACKET, SOCK_RAW, SOL_SOCKET, SO_ATTACH_FILTER,
+    SO_RCVTIMEO,
+};
 use std::ffi::CString;
 use std::io;
 use std::mem;
@@ -23,6 +26,18 @@ pub fn open_raw_socket_fd() -> io::Result<i32> {
     }
 }
 
+// open_raw_socket_all() function
+/// Open a raw AF_PACKET socket receiving all the frames, the VLAN tags
+/// removed by the kernel are only visible to such sockets
+pub fn open_raw_socket_all() -> io::Result<i32> {
+    unsafe {
+        match socket(AF_PACKET, SOCK_RAW, ETHER_P_ALL.to_be() as i32) {
+            -1 => Err(io::Error::last_os_error()),
+            fd => Ok(fd),
+        }
+    }
+}
+
 // set_sock_filter function
 /// Set a BPF filter on a socket
 pub fn set_sock_filter(sockfd: i32, bpf: &SockFprog) -> io::Result<i32> {
@@ -41,6 +56,40 @@ pub fn set_sock_filter(sockfd: i32, bpf: &SockFprog) -> io::Result<i32> {
     }
 }
 
+// set_sock_rcvtimeo() function
+/// Set the receive timeout of a socket, so blocking reads can be interrupted
+pub fn set_sock_rcvtimeo(sockfd: i32, secs: i64) -> io::Result<i32> {
+    let tv = timeval {
+        tv_sec: secs as libc::time_t,
+        tv_usec: 0,
+    };
+    unsafe {
+        // man 7 socket
+        match setsockopt(
+            sockfd,
+            SOL_SOCKET,
+            SO_RCVTIMEO,
+            &tv as *const _ as *mut c_void,
+            mem::size_of::<timeval>() as u32,
+        ) {
+            -1 => Err(io::Error::last_os_error()),
+            ret => Ok(ret),
+        }
+    }
+}
+
+// close_fd() function
+/// Close a file descriptor
+pub fn close_fd(fd: i32) -> io::Result<()> {
+    unsafe {
+        // man 2 close
+        match close(fd) {
+            -1 => Err(io::Error::last_os_error()),
+            _ => Ok(()),
+        }
+    }
+}
+
 // recv_ip_pkts() function
 /// Receive IP packets
 pub fn recv_ip_pkts(sockfd: i32, sockaddr: &mut sockaddr_ll, buf: &mut [u8]) -> io::Result<usize> {
@@ -90,13 +139,14 @@ pub fn c_ifnametoindex(ifname: &String) -> io::Result<u32> {
 pub fn raw_sendto(
     sockfd: i32,
     ifindex: i32,
+    protocol: u16,
     frame: &mut Vec<u8>,
     _debug: &Verbose,
 ) -> io::Result<()> {
     // sockaddr_ll (man 7 packet)
     let mut sa = libc::sockaddr_ll {
         sll_family: libc::AF_PACKET as u16,
-        sll_protocol: ETHER_P_IP.to_be(),
+        sll_protocol: protocol.to_be(),
         sll_ifindex: ifindex,
         sll_hatype: 0,
         sll_pkttype: 0,
@@ -14,7 +14,9 @@ const INT_RTAX_MAX: usize = 8; // to verify __RTAX_MAX enum value
 const INT_NLM_F_CREATE: i32 = 0x400; // include/linux/netlink.h
 
 // operating system drivers
-use crate::os::drivers::Operation;
+use crate::os::drivers::{MacvlanMode, NetDriver, Operation};
+use crate::os::linux::netdev::Netdev;
+use crate::os::linux::rtnetlink;
 
 // custom libnl types
 // nl_list_head type
@@ -569,7 +571,14 @@ pub fn set_ip_route(
 // setup_macvlan_link() function
 //
 /// Create new or delete existing macvlan interface
-pub fn setup_macvlan_link(vr: &VirtualRouter, mac: [u8; 6], op: &Operation) -> io::Result<()> {
+pub fn setup_macvlan_link(
+    master: i32,
+    vifidx: i32,
+    vifname: &str,
+    mac: [u8; 6],
+    mode: MacvlanMode,
+    op: &Operation,
+) -> io::Result<()> {
     // call to external nlsock() function
     let nlsock = unsafe { nl_socket_alloc() };
     if nlsock.is_null() {
@@ -596,10 +605,11 @@ pub fn setup_macvlan_link(vr: &VirtualRouter, mac: [u8; 6], op: &Operation) -> i
     }
 
     // set modes on macvlan interface
+    let mode = format!("{}\0", mode.name());
     let _r = unsafe {
         rtnl_link_macvlan_set_mode(
             link,
-            rtnl_link_macvlan_str2mode("bridge\0".as_bytes().as_ptr() as *const c_void),
+            rtnl_link_macvlan_str2mode(mode.as_bytes().as_ptr() as *const c_void),
         )
     };
 
@@ -609,13 +619,13 @@ pub fn setup_macvlan_link(vr: &VirtualRouter, mac: [u8; 6], op: &Operation) -> i
     match op {
         Operation::Add => {
             // set interface name
-            let mut ifname = vr.parameters.vifname();
+            let mut ifname = vifname.to_string();
             ifname.push_str("\0");
 
             unsafe { rtnl_link_set_name(link, ifname.as_bytes().as_ptr() as *const c_char) };
 
             // set macvlan master interface to our vr's interface
-            unsafe { rtnl_link_set_link(link, vr.parameters.ifindex()) };
+            unsafe { rtnl_link_set_link(link, master) };
 
             // add macvlan link
             let res = unsafe { rtnl_link_add(nlsock, link, INT_NLM_F_CREATE) };
@@ -634,7 +644,7 @@ pub fn setup_macvlan_link(vr: &VirtualRouter, mac: [u8; 6], op: &Operation) -> i
         }
         Operation::Rem => {
             // set macvlan ifindex
-            unsafe { rtnl_link_set_ifindex(link, vr.parameters.vifidx() as i32) };
+            unsafe { rtnl_link_set_ifindex(link, vifidx) };
 
             // delete macvlan link
             let res = unsafe { rtnl_link_delete(nlsock, link) };
@@ -652,3 +662,147 @@ pub fn setup_macvlan_link(vr: &VirtualRouter, mac: [u8; 6], op: &Operation) -> i
 
     Ok(())
 }
+
+/// Libnl Network Driver Structure
+/// IPv4 addresses, routes and macvlan interfaces are set using netlink
+/// (libnl-3), MAC addresses and raw frames are handled as with netdev
+#[derive(Debug)]
+pub struct Libnl;
+
+// NetDriver trait implementation for the netlink (libnl) driver
+impl NetDriver for Libnl {
+    // kind() method
+    fn kind(&self) -> NetDrivers {
+        NetDrivers::libnl
+    }
+    // set_ip_address() method
+    fn set_ip_address(
+        &self,
+        _fd: i32,
+        ifindex: i32,
+        ifname: &str,
+        ip: [u8; 4],
+        netmask: [u8; 4],
+        op: &Operation,
+        debug: &Verbose,
+    ) -> io::Result<()> {
+        set_ip_address(
+            ifindex,
+            &CString::new(ifname).unwrap(),
+            ip,
+            netmask,
+            *op,
+            debug,
+        )
+    }
+    // get_mac_address() method
+    fn get_mac_address(&self, fd: i32, ifname: &str, debug: &Verbose) -> io::Result<[u8; 6]> {
+        Netdev.get_mac_address(fd, ifname, debug)
+    }
+    // set_mac_address() method
+    fn set_mac_address(
+        &self,
+        fd: i32,
+        ifname: &str,
+        mac: [u8; 6],
+        debug: &Verbose,
+    ) -> io::Result<()> {
+        Netdev.set_mac_address(fd, ifname, mac, debug)
+    }
+    // set_ip_route() method
+    fn set_ip_route(
+        &self,
+        fd: i32,
+        ifname: &str,
+        route: &Static,
+        op: &Operation,
+        debug: &Verbose,
+    ) -> io::Result<()> {
+        set_ip_route(
+            fd,
+            &ifname.to_string(),
+            route.route(),
+            route.mask(),
+            route.nh(),
+            route.metric(),
+            route.mtu(),
+            op,
+            debug,
+        )
+    }
+    // setup_macvlan_link() method
+    fn setup_macvlan_link(
+        &self,
+        master: i32,
+        vifidx: i32,
+        vifname: &str,
+        mac: [u8; 6],
+        mode: MacvlanMode,
+        op: &Operation,
+        _debug: &Verbose,
+    ) -> io::Result<()> {
+        setup_macvlan_link(master, vifidx, vifname, mac, mode, op)
+    }
+    // setup_ipvlan_link() method
+    // (ipvlan links are only supported by recent libnl versions)
+    fn setup_ipvlan_link(
+        &self,
+        master: i32,
+        vifidx: i32,
+        vifname: &str,
+        op: &Operation,
+        _debug: &Verbose,
+    ) -> io::Result<()> {
+        rtnetlink::setup_ipvlan_link(master, vifidx, vifname, op)
+    }
+    // setup_vlan_link() method
+    fn setup_vlan_link(
+        &self,
+        master: i32,
+        vlanidx: i32,
+        vlanname: &str,
+        vid: u16,
+        op: &Operation,
+        _debug: &Verbose,
+    ) -> io::Result<()> {
+        rtnetlink::setup_vlan_link(master, vlanidx, vlanname, vid, op)
+    }
+    // set_secondary_mac() method
+    fn set_secondary_mac(
+        &self,
+        ifindex: i32,
+        mac: [u8; 6],
+        op: &Operation,
+        _debug: &Verbose,
+    ) -> io::Result<()> {
+        rtnetlink::set_secondary_mac(ifindex, mac, op)
+    }
+    // set_promiscuous() method
+    fn set_promiscuous(&self, fd: i32, ifname: &str, on: bool) -> io::Result<()> {
+        Netdev.set_promiscuous(fd, ifname, on)
+    }
+    // link_index() method
+    fn link_index(&self, ifname: &str) -> io::Result<i32> {
+        Netdev.link_index(ifname)
+    }
+    // send_frame() method
+    fn send_frame(
+        &self,
+        fd: i32,
+        ifindex: i32,
+        frame: &mut Vec<u8>,
+        debug: &Verbose,
+    ) -> io::Result<()> {
+        Netdev.send_frame(fd, ifindex, frame, debug)
+    }
+    // send_arp() method
+    fn send_arp(
+        &self,
+        fd: i32,
+        ifindex: i32,
+        frame: &mut Vec<u8>,
+        debug: &Verbose,
+    ) -> io::Result<()> {
+        Netdev.send_arp(fd, ifindex, frame, debug)
+    }
+}
@@ -6,6 +6,8 @@ pub mod libc;
 pub mod netdev;
 // libnl netlink support
 pub mod libnl;
+// native rtnetlink support
+pub mod rtnetlink;
 // Linux ARP support
 pub mod arp;
 // Linux Socket Filter support
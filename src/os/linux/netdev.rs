@@ -11,7 +11,11 @@ use std::ffi::CString;
 use std::io;
 
 // operating system drivers
-use crate::os::drivers::Operation;
+use crate::os::drivers::{MacvlanMode, NetDriver, Operation};
+
+// standard C library and ARP support
+use crate::os::linux::arp::open_raw_socket_arp;
+use crate::os::linux::libc::{c_ifnametoindex, close_fd, raw_sendto};
 
 /// ioctl_flags Structure
 #[repr(C)]
@@ -482,3 +486,163 @@ pub fn set_ip_route(
 
     Ok(())
 }
+
+/// Netdev Network Driver Structure
+/// IPv4 addresses, MAC addresses and routes are set using ioctls
+#[derive(Debug)]
+pub struct Netdev;
+
+// NetDriver trait implementation for the netdev (ioctl) driver
+impl NetDriver for Netdev {
+    // kind() method
+    fn kind(&self) -> NetDrivers {
+        NetDrivers::ioctl
+    }
+    // set_ip_address() method
+    // the address replaces the interface's primary address
+    fn set_ip_address(
+        &self,
+        fd: i32,
+        _ifindex: i32,
+        ifname: &str,
+        ip: [u8; 4],
+        netmask: [u8; 4],
+        op: &Operation,
+        _debug: &Verbose,
+    ) -> io::Result<()> {
+        match op {
+            Operation::Add => set_ip_address(fd, &CString::new(ifname).unwrap(), ip, netmask),
+            // the primary address must be set back instead
+            Operation::Rem => Err(io::Error::new(
+                io::ErrorKind::Other,
+                "ioctls cannot remove an IP address",
+            )),
+        }
+    }
+    // get_mac_address() method
+    fn get_mac_address(&self, fd: i32, ifname: &str, debug: &Verbose) -> io::Result<[u8; 6]> {
+        get_mac_addr(fd, &CString::new(ifname).unwrap(), debug)
+    }
+    // set_mac_address() method
+    fn set_mac_address(
+        &self,
+        fd: i32,
+        ifname: &str,
+        mac: [u8; 6],
+        debug: &Verbose,
+    ) -> io::Result<()> {
+        set_mac_addr(fd, &CString::new(ifname).unwrap(), mac, debug)
+    }
+    // set_ip_route() method
+    fn set_ip_route(
+        &self,
+        fd: i32,
+        ifname: &str,
+        route: &Static,
+        op: &Operation,
+        debug: &Verbose,
+    ) -> io::Result<()> {
+        set_ip_route(
+            fd,
+            &ifname.to_string(),
+            route.route(),
+            route.mask(),
+            route.nh(),
+            route.metric(),
+            route.mtu(),
+            op,
+            debug,
+        )
+    }
+    // setup_macvlan_link() method
+    fn setup_macvlan_link(
+        &self,
+        _master: i32,
+        _vifidx: i32,
+        _vifname: &str,
+        _mac: [u8; 6],
+        _mode: MacvlanMode,
+        _op: &Operation,
+        _debug: &Verbose,
+    ) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "macvlan interfaces require the libnl network driver",
+        ))
+    }
+    // setup_ipvlan_link() method
+    fn setup_ipvlan_link(
+        &self,
+        _master: i32,
+        _vifidx: i32,
+        _vifname: &str,
+        _op: &Operation,
+        _debug: &Verbose,
+    ) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "ipvlan interfaces require the libnl network driver",
+        ))
+    }
+    // setup_vlan_link() method
+    fn setup_vlan_link(
+        &self,
+        _master: i32,
+        _vlanidx: i32,
+        _vlanname: &str,
+        _vid: u16,
+        _op: &Operation,
+        _debug: &Verbose,
+    ) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "VLAN interfaces require the libnl network driver",
+        ))
+    }
+    // set_secondary_mac() method
+    fn set_secondary_mac(
+        &self,
+        _ifindex: i32,
+        _mac: [u8; 6],
+        _op: &Operation,
+        _debug: &Verbose,
+    ) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "secondary MAC addresses require the libnl network driver",
+        ))
+    }
+    // set_promiscuous() method
+    fn set_promiscuous(&self, fd: i32, ifname: &str, on: bool) -> io::Result<()> {
+        let op = if on { PflagOp::Set } else { PflagOp::Unset };
+        set_if_promiscuous(fd, &CString::new(ifname).unwrap(), op)
+    }
+    // link_index() method
+    fn link_index(&self, ifname: &str) -> io::Result<i32> {
+        c_ifnametoindex(&ifname.to_string()).map(|i| i as i32)
+    }
+    // send_frame() method
+    fn send_frame(
+        &self,
+        fd: i32,
+        ifindex: i32,
+        frame: &mut Vec<u8>,
+        debug: &Verbose,
+    ) -> io::Result<()> {
+        raw_sendto(fd, ifindex, ETHER_P_IP, frame, debug)
+    }
+    // send_arp() method
+    // ARP frames are sent on their own raw socket
+    fn send_arp(
+        &self,
+        _fd: i32,
+        ifindex: i32,
+        frame: &mut Vec<u8>,
+        debug: &Verbose,
+    ) -> io::Result<()> {
+        let arp_fd = open_raw_socket_arp()?;
+        let r = raw_sendto(arp_fd, ifindex, ETHER_P_ARP, frame, debug);
+        close_fd(arp_fd)?;
+        r
+    }
+}
@@ -0,0 +1,1395 @@
+//! Linux rtnetlink support
+//! This module talks to the kernel routing netlink (rtnetlink) interface
+//! directly, without going through the libnl libraries.
+use crate::*;
+
+// libc
+use libc::{
+    bind, c_void, close, recv, send, setsockopt, sockaddr, sockaddr_nl, socket, AF_INET,
+    AF_NETLINK, AF_UNSPEC, IFF_PROMISC, IFF_RUNNING, IFF_UP, NETLINK_ROUTE, SOCK_CLOEXEC, SOCK_RAW,
+};
+
+// std
+use std::io;
+
+// operating system drivers
+use crate::os::drivers::{MacvlanMode, NetDriver, Operation};
+use crate::os::linux::libc::c_ifnametoindex;
+use crate::os::linux::netdev::Netdev;
+
+// netlink constants (linux/netlink.h)
+const NLMSG_HDRLEN: usize = 16;
+const NLMSG_ERROR: u16 = 2;
+const NLMSG_DONE: u16 = 3;
+const NLM_F_REQUEST: u16 = 0x1;
+const NLM_F_ACK: u16 = 0x4;
+const NLM_F_DUMP: u16 = 0x300;
+const NLM_F_EXCL: u16 = 0x200;
+const NLM_F_CREATE: u16 = 0x400;
+const NLA_F_NESTED: u16 = 0x8000;
+const NLA_TYPE_MASK: u16 = 0x3fff;
+const SOL_NETLINK: i32 = 270;
+const NETLINK_ADD_MEMBERSHIP: i32 = 1;
+
+// rtnetlink constants (linux/rtnetlink.h)
+const RTM_NEWLINK: u16 = 16;
+const RTM_DELLINK: u16 = 17;
+const RTM_GETLINK: u16 = 18;
+const RTM_NEWADDR: u16 = 20;
+const RTM_DELADDR: u16 = 21;
+const RTM_GETADDR: u16 = 22;
+const RTM_NEWROUTE: u16 = 24;
+const RTM_DELROUTE: u16 = 25;
+const RTM_NEWNEIGH: u16 = 28;
+const RTM_DELNEIGH: u16 = 29;
+const RT_TABLE_MAIN: u8 = 254;
+const RTPROT_STATIC: u8 = 4;
+const RT_SCOPE_UNIVERSE: u8 = 0;
+const RT_SCOPE_NOWHERE: u8 = 255;
+const RTN_UNICAST: u8 = 1;
+const RTNLGRP_LINK: u32 = 1;
+const RTNLGRP_IPV4_IFADDR: u32 = 5;
+
+// address attributes (linux/if_addr.h)
+const IFA_ADDRESS: u16 = 1;
+const IFA_LOCAL: u16 = 2;
+const IFA_LABEL: u16 = 3;
+
+// route attributes (linux/rtnetlink.h)
+const RTA_DST: u16 = 1;
+const RTA_GATEWAY: u16 = 5;
+const RTA_PRIORITY: u16 = 6;
+const RTA_METRICS: u16 = 8;
+const RTAX_MTU: u16 = 2;
+
+// link attributes (linux/if_link.h)
+const IFLA_ADDRESS: u16 = 1;
+const IFLA_IFNAME: u16 = 3;
+const IFLA_LINK: u16 = 5;
+const IFLA_LINKINFO: u16 = 18;
+const IFLA_INFO_KIND: u16 = 1;
+const IFLA_INFO_DATA: u16 = 2;
+const IFLA_MACVLAN_MODE: u16 = 1;
+const IFLA_IPVLAN_MODE: u16 = 1;
+const IPVLAN_MODE_L2: u16 = 0;
+const IFLA_VLAN_ID: u16 = 1;
+
+// neighbour attributes (linux/neighbour.h)
+const NDA_LLADDR: u16 = 2;
+const NUD_PERMANENT: u16 = 0x80;
+const NTF_SELF: u8 = 0x02;
+const AF_BRIDGE: u8 = 7;
+
+// receive buffers size
+const NL_RECV_BUF_SIZE: usize = 8192;
+const NL_MONITOR_BUF_SIZE: usize = 32768;
+
+// message headers length
+const IFINFOMSG_LEN: usize = 16;
+const IFADDRMSG_LEN: usize = 8;
+
+/// Netlink Message Structure
+/// A netlink request being built, the family specific header
+/// is followed by the (possibly nested) attributes
+struct NlMsg {
+    buf: Vec<u8>,
+    nests: Vec<usize>,
+}
+
+// NlMsg Type Implementation
+impl NlMsg {
+    // new() method
+    // start a request with the netlink message header, the length
+    // and sequence number are filled in when sending it
+    fn new(msg_type: u16, flags: u16) -> NlMsg {
+        let mut buf = Vec::with_capacity(128);
+        buf.extend_from_slice(&0u32.to_ne_bytes());
+        buf.extend_from_slice(&msg_type.to_ne_bytes());
+        buf.extend_from_slice(&(flags | NLM_F_REQUEST | NLM_F_ACK).to_ne_bytes());
+        buf.extend_from_slice(&0u32.to_ne_bytes());
+        buf.extend_from_slice(&0u32.to_ne_bytes());
+        NlMsg {
+            buf,
+            nests: Vec::new(),
+        }
+    }
+    // push() method
+    // append a family specific header, aligned on 4 bytes
+    fn push(&mut self, data: &[u8]) {
+        self.buf.extend_from_slice(data);
+        self.align();
+    }
+    // align() method
+    fn align(&mut self) {
+        while self.buf.len() % 4 != 0 {
+            self.buf.push(0);
+        }
+    }
+    // attr() method
+    // append a type-length-value attribute
+    fn attr(&mut self, attr_type: u16, data: &[u8]) {
+        self.buf
+            .extend_from_slice(&(4 + data.len() as u16).to_ne_bytes());
+        self.buf.extend_from_slice(&attr_type.to_ne_bytes());
+        self.push(data);
+    }
+    // attr_u32() method
+    fn attr_u32(&mut self, attr_type: u16, value: u32) {
+        self.attr(attr_type, &value.to_ne_bytes());
+    }
+    // attr_str() method
+    // append a nul terminated string attribute
+    fn attr_str(&mut self, attr_type: u16, value: &str) {
+        let mut data = value.as_bytes().to_vec();
+        data.push(0);
+        self.attr(attr_type, &data);
+    }
+    // nest() method
+    // open a nested attribute, closed by end()
+    fn nest(&mut self, attr_type: u16) {
+        self.nests.push(self.buf.len());
+        self.buf.extend_from_slice(&0u16.to_ne_bytes());
+        self.buf
+            .extend_from_slice(&(attr_type | NLA_F_NESTED).to_ne_bytes());
+    }
+    // end() method
+    fn end(&mut self) {
+        let start = self.nests.pop().expect("no nested attribute opened");
+        let len = (self.buf.len() - start) as u16;
+        self.buf[start..start + 2].copy_from_slice(&len.to_ne_bytes());
+    }
+    // finish() method
+    // set the message length and sequence number
+    fn finish(mut self, seq: u32) -> Vec<u8> {
+        let len = self.buf.len() as u32;
+        self.buf[0..4].copy_from_slice(&len.to_ne_bytes());
+        self.buf[8..12].copy_from_slice(&seq.to_ne_bytes());
+        self.buf
+    }
+}
+
+/// Netlink Socket Structure
+/// A NETLINK_ROUTE socket, closed when dropped
+struct NlSocket {
+    fd: i32,
+    seq: u32,
+}
+
+// NlSocket Type Implementation
+impl NlSocket {
+    // open() method
+    fn open() -> io::Result<NlSocket> {
+        let fd = unsafe { socket(AF_NETLINK, SOCK_RAW | SOCK_CLOEXEC, NETLINK_ROUTE) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let sock = NlSocket { fd, seq: 0 };
+
+        // let the kernel assign the port id
+        let mut sa: sockaddr_nl = unsafe { std::mem::zeroed() };
+        sa.nl_family = AF_NETLINK as u16;
+        let r = unsafe {
+            bind(
+                fd,
+                &sa as *const sockaddr_nl as *const sockaddr,
+                std::mem::size_of::<sockaddr_nl>() as u32,
+            )
+        };
+        if r < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(sock)
+    }
+    // request() method
+    // send a request to the kernel and wait for its acknowledgement,
+    // a negative error code is returned as a raw os error
+    fn request(&mut self, msg: NlMsg) -> Result<(), i32> {
+        self.seq = self.seq.wrapping_add(1);
+        let seq = self.seq;
+        let msg = msg.finish(seq);
+
+        let r = unsafe { send(self.fd, msg.as_ptr() as *const c_void, msg.len(), 0) };
+        if r < 0 {
+            return Err(io::Error::last_os_error().raw_os_error().unwrap_or(0));
+        }
+
+        let mut buf = [0u8; NL_RECV_BUF_SIZE];
+        loop {
+            let len = unsafe { recv(self.fd, buf.as_mut_ptr() as *mut c_void, buf.len(), 0) };
+            if len < 0 {
+                return Err(io::Error::last_os_error().raw_os_error().unwrap_or(0));
+            }
+            let mut pkt = &buf[..len as usize];
+            // walk through the received messages
+            while pkt.len() >= NLMSG_HDRLEN {
+                let msg_len = read_u32(pkt, 0) as usize;
+                if msg_len < NLMSG_HDRLEN || msg_len > pkt.len() {
+                    break;
+                }
+                let msg_type = u16::from_ne_bytes([pkt[4], pkt[5]]);
+                if msg_type == NLMSG_ERROR && read_u32(pkt, 8) == seq {
+                    if msg_len < NLMSG_HDRLEN + 4 {
+                        return Err(libc::EBADMSG);
+                    }
+                    // a zero error code acknowledges the request
+                    return match read_u32(pkt, NLMSG_HDRLEN) as i32 {
+                        0 => Ok(()),
+                        e => Err(-e),
+                    };
+                }
+                let next = (msg_len + 3) & !3;
+                pkt = &pkt[next.min(pkt.len())..];
+            }
+        }
+    }
+    // dump() method
+    // send a dump request to the kernel and return the links and
+    // addresses of its multipart answer
+    fn dump(&mut self, msg: NlMsg) -> io::Result<Vec<LinkEvent>> {
+        self.seq = self.seq.wrapping_add(1);
+        let seq = self.seq;
+        let msg = msg.finish(seq);
+
+        let r = unsafe { send(self.fd, msg.as_ptr() as *const c_void, msg.len(), 0) };
+        if r < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let mut events = Vec::new();
+        let mut buf = vec![0u8; NL_MONITOR_BUF_SIZE];
+        loop {
+            let len = unsafe { recv(self.fd, buf.as_mut_ptr() as *mut c_void, buf.len(), 0) };
+            if len < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let pkt = &buf[..len as usize];
+            events.extend(parse_events(pkt));
+            match dump_end(pkt, seq) {
+                Some(0) => return Ok(events),
+                Some(e) => return Err(io::Error::from_raw_os_error(e)),
+                None => {}
+            }
+        }
+    }
+}
+
+// dump_end() function
+/// look for the end of a dump answer in a netlink datagram: 0 when it is
+/// done, the error code when the kernel refused it
+fn dump_end(mut pkt: &[u8], seq: u32) -> Option<i32> {
+    while pkt.len() >= NLMSG_HDRLEN {
+        let msg_len = read_u32(pkt, 0) as usize;
+        if msg_len < NLMSG_HDRLEN || msg_len > pkt.len() {
+            break;
+        }
+        let msg_type = u16::from_ne_bytes([pkt[4], pkt[5]]);
+        if read_u32(pkt, 8) == seq {
+            match msg_type {
+                NLMSG_DONE => return Some(0),
+                NLMSG_ERROR if msg_len >= NLMSG_HDRLEN + 4 => {
+                    match read_u32(pkt, NLMSG_HDRLEN) as i32 {
+                        0 => {}
+                        e => return Some(-e),
+                    }
+                }
+                NLMSG_ERROR => return Some(libc::EBADMSG),
+                _ => {}
+            }
+        }
+        let next = (msg_len + 3) & !3;
+        pkt = &pkt[next.min(pkt.len())..];
+    }
+    None
+}
+
+// dump() function
+/// return the current links and IPv4 addresses of the host, as if they
+/// had all just been notified
+pub fn dump() -> io::Result<Vec<LinkEvent>> {
+    // a socket of its own, not to mix the answer with the notifications
+    let mut sock = NlSocket::open()?;
+
+    let mut links = NlMsg::new(RTM_GETLINK, NLM_F_DUMP);
+    links.push(&ifinfomsg(0, 0, 0));
+    let mut events = sock.dump(links)?;
+
+    // ifaddrmsg
+    let mut addrs = NlMsg::new(RTM_GETADDR, NLM_F_DUMP);
+    addrs.push(&[AF_INET as u8, 0, 0, 0, 0, 0, 0, 0]);
+    events.extend(sock.dump(addrs)?);
+
+    Ok(events)
+}
+
+// NlSocket Drop trait implementation
+impl Drop for NlSocket {
+    fn drop(&mut self) {
+        unsafe { close(self.fd) };
+    }
+}
+
+/// Link Event Enumerator
+/// A link or IPv4 address change notified by the kernel
+#[derive(Debug, Clone, PartialEq)]
+pub enum LinkEvent {
+    NewLink {
+        ifindex: i32,
+        ifname: String,
+        up: bool, // administratively up and running
+    },
+    DelLink {
+        ifindex: i32,
+        ifname: String,
+    },
+    NewAddr {
+        ifindex: i32,
+        ip: [u8; 4],
+    },
+    DelAddr {
+        ifindex: i32,
+        ip: [u8; 4],
+    },
+}
+
+/// Netlink Monitor Structure
+/// A NETLINK_ROUTE socket subscribed to the links and IPv4 addresses changes
+pub struct Monitor {
+    sock: NlSocket,
+}
+
+// Monitor Type Implementation
+impl Monitor {
+    // open() method
+    // open the socket and join the RTNLGRP_LINK and RTNLGRP_IPV4_IFADDR groups
+    pub fn open() -> io::Result<Monitor> {
+        let sock = NlSocket::open()?;
+        for group in &[RTNLGRP_LINK, RTNLGRP_IPV4_IFADDR] {
+            let r = unsafe {
+                setsockopt(
+                    sock.fd,
+                    SOL_NETLINK,
+                    NETLINK_ADD_MEMBERSHIP,
+                    group as *const u32 as *const c_void,
+                    std::mem::size_of::<u32>() as u32,
+                )
+            };
+            if r < 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(Monitor { sock })
+    }
+    // recv() method
+    // block until the kernel sends notifications, and return the ones
+    // about links and IPv4 addresses
+    pub fn recv(&self) -> io::Result<Vec<LinkEvent>> {
+        let mut buf = vec![0u8; NL_MONITOR_BUF_SIZE];
+        let len = unsafe { recv(self.sock.fd, buf.as_mut_ptr() as *mut c_void, buf.len(), 0) };
+        if len < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(parse_events(&buf[..len as usize]))
+    }
+}
+
+// parse_events() function
+/// decode the link and address notifications of a netlink datagram,
+/// malformed or unrelated messages are skipped
+fn parse_events(mut pkt: &[u8]) -> Vec<LinkEvent> {
+    let mut events = Vec::new();
+    while pkt.len() >= NLMSG_HDRLEN {
+        let msg_len = read_u32(pkt, 0) as usize;
+        if msg_len < NLMSG_HDRLEN || msg_len > pkt.len() {
+            break;
+        }
+        let msg_type = u16::from_ne_bytes([pkt[4], pkt[5]]);
+        let body = &pkt[NLMSG_HDRLEN..msg_len];
+        match msg_type {
+            RTM_NEWLINK | RTM_DELLINK if body.len() >= IFINFOMSG_LEN => {
+                let ifindex = read_u32(body, 4) as i32;
+                let flags = read_u32(body, 8);
+                let ifname = attrs(&body[IFINFOMSG_LEN..])
+                    .find(|(t, _)| *t == IFLA_IFNAME)
+                    .map(|(_, v)| {
+                        let v = v.split(|b| *b == 0).next().unwrap_or(&[]);
+                        String::from_utf8_lossy(v).into_owned()
+                    })
+                    .unwrap_or_default();
+                events.push(if msg_type == RTM_NEWLINK {
+                    let up = (IFF_UP | IFF_RUNNING) as u32;
+                    LinkEvent::NewLink {
+                        ifindex,
+                        ifname,
+                        up: flags & up == up,
+                    }
+                } else {
+                    LinkEvent::DelLink { ifindex, ifname }
+                });
+            }
+            RTM_NEWADDR | RTM_DELADDR if body.len() >= IFADDRMSG_LEN => {
+                if body[0] == AF_INET as u8 {
+                    let ifindex = read_u32(body, 4) as i32;
+                    // the local address, or the address of a broadcast interface
+                    let mut ip = None;
+                    for (t, v) in attrs(&body[IFADDRMSG_LEN..]) {
+                        if v.len() == 4 && (t == IFA_LOCAL || (t == IFA_ADDRESS && ip.is_none())) {
+                            ip = Some([v[0], v[1], v[2], v[3]]);
+                        }
+                    }
+                    if let Some(ip) = ip {
+                        events.push(if msg_type == RTM_NEWADDR {
+                            LinkEvent::NewAddr { ifindex, ip }
+                        } else {
+                            LinkEvent::DelAddr { ifindex, ip }
+                        });
+                    }
+                }
+            }
+            _ => {}
+        }
+        let next = (msg_len + 3) & !3;
+        pkt = &pkt[next.min(pkt.len())..];
+    }
+    events
+}
+
+// attrs() function
+/// iterate over the type-length-value attributes of a message
+fn attrs(mut buf: &[u8]) -> impl Iterator<Item = (u16, &[u8])> {
+    std::iter::from_fn(move || {
+        if buf.len() < 4 {
+            return None;
+        }
+        let len = u16::from_ne_bytes([buf[0], buf[1]]) as usize;
+        if len < 4 || len > buf.len() {
+            return None;
+        }
+        let attr_type = u16::from_ne_bytes([buf[2], buf[3]]) & NLA_TYPE_MASK;
+        let value = &buf[4..len];
+        buf = &buf[((len + 3) & !3).min(buf.len())..];
+        Some((attr_type, value))
+    })
+}
+
+// read_u32() function
+fn read_u32(buf: &[u8], pos: usize) -> u32 {
+    u32::from_ne_bytes([buf[pos], buf[pos + 1], buf[pos + 2], buf[pos + 3]])
+}
+
+// prefix_len() function
+/// convert a netmask to a prefix length
+fn prefix_len(netmask: [u8; 4]) -> u8 {
+    u32::from_be_bytes(netmask).count_ones() as u8
+}
+
+// nl_error() function
+/// map a netlink error code to an io::Error describing the failed request
+fn nl_error(errno: i32, what: String) -> io::Error {
+    let err = io::Error::from_raw_os_error(errno);
+    let kind = match errno {
+        libc::EEXIST => io::ErrorKind::AlreadyExists,
+        libc::ENODEV | libc::ENOENT | libc::ESRCH => io::ErrorKind::NotFound,
+        libc::EADDRNOTAVAIL => io::ErrorKind::AddrNotAvailable,
+        libc::EPERM | libc::EACCES => io::ErrorKind::PermissionDenied,
+        libc::EINVAL | libc::ERANGE => io::ErrorKind::InvalidInput,
+        _ => err.kind(),
+    };
+    io::Error::new(kind, format!("{}: {}", what, err))
+}
+
+// request() function
+/// send a single request on a new netlink socket
+fn request(msg: NlMsg, what: impl FnOnce() -> String) -> io::Result<()> {
+    let mut sock = NlSocket::open()?;
+    sock.request(msg).map_err(|e| nl_error(e, what()))
+}
+
+// ifinfomsg() function
+/// build the link message header
+fn ifinfomsg(ifindex: i32, flags: u32, change: u32) -> Vec<u8> {
+    let mut hdr = vec![AF_UNSPEC as u8, 0, 0, 0];
+    hdr.extend_from_slice(&ifindex.to_ne_bytes());
+    hdr.extend_from_slice(&flags.to_ne_bytes());
+    hdr.extend_from_slice(&change.to_ne_bytes());
+    hdr
+}
+
+// set_ip_address() function
+/// Add or remove an IPv4 address on an interface
+pub fn set_ip_address(
+    ifindex: i32,
+    ifname: &str,
+    ip: [u8; 4],
+    netmask: [u8; 4],
+    op: &Operation,
+) -> io::Result<()> {
+    let mut msg = match op {
+        Operation::Add => NlMsg::new(RTM_NEWADDR, NLM_F_CREATE | NLM_F_EXCL),
+        Operation::Rem => NlMsg::new(RTM_DELADDR, 0),
+    };
+    // ifaddrmsg
+    let mut hdr = vec![AF_INET as u8, prefix_len(netmask), 0, RT_SCOPE_UNIVERSE];
+    hdr.extend_from_slice(&ifindex.to_ne_bytes());
+    msg.push(&hdr);
+    msg.attr(IFA_LOCAL, &ip);
+    msg.attr(IFA_ADDRESS, &ip);
+    msg.attr_str(IFA_LABEL, ifname);
+
+    request(msg, || {
+        format!(
+            "cannot {} IP address {}/{} on interface {}",
+            op.action(),
+            std::net::Ipv4Addr::from(ip),
+            prefix_len(netmask),
+            ifname
+        )
+    })
+}
+
+// set_ip_route() function
+/// Add or delete a route in the main routing table
+pub fn set_ip_route(
+    route: [u8; 4],
+    rtmask: [u8; 4],
+    gw: [u8; 4],
+    metric: i16,
+    mtu: u64,
+    op: &Operation,
+) -> io::Result<()> {
+    let (mut msg, protocol, scope) = match op {
+        Operation::Add => (
+            NlMsg::new(RTM_NEWROUTE, NLM_F_CREATE | NLM_F_EXCL),
+            RTPROT_STATIC,
+            RT_SCOPE_UNIVERSE,
+        ),
+        // match any protocol and scope
+        Operation::Rem => (NlMsg::new(RTM_DELROUTE, 0), 0, RT_SCOPE_NOWHERE),
+    };
+    // rtmsg
+    let dst_len = prefix_len(rtmask);
+    let mut hdr = vec![
+        AF_INET as u8,
+        dst_len,
+        0,
+        0,
+        RT_TABLE_MAIN,
+        protocol,
+        scope,
+        RTN_UNICAST,
+    ];
+    hdr.extend_from_slice(&0u32.to_ne_bytes());
+    msg.push(&hdr);
+    if dst_len > 0 {
+        msg.attr(RTA_DST, &route);
+    }
+    msg.attr(RTA_GATEWAY, &gw);
+    if metric > 0 {
+        msg.attr_u32(RTA_PRIORITY, metric as u32);
+    }
+    if mtu > 0 {
+        msg.nest(RTA_METRICS);
+        msg.attr_u32(RTAX_MTU, mtu as u32);
+        msg.end();
+    }
+
+    request(msg, || {
+        format!(
+            "cannot {} route {}/{} via {}",
+            op.action(),
+            std::net::Ipv4Addr::from(route),
+            dst_len,
+            std::net::Ipv4Addr::from(gw)
+        )
+    })
+}
+
+// setup_macvlan_link() function
+/// Create a macvlan interface on top of its master interface and bring
+/// it up, or delete it
+pub fn setup_macvlan_link(
+    master: i32,
+    vifidx: i32,
+    vifname: &str,
+    mac: [u8; 6],
+    mode: MacvlanMode,
+    op: &Operation,
+) -> io::Result<()> {
+    let msg = match op {
+        Operation::Add => {
+            let mut msg = new_link(master, vifname);
+            msg.attr(IFLA_ADDRESS, &mac);
+            msg.nest(IFLA_LINKINFO);
+            msg.attr(IFLA_INFO_KIND, b"macvlan");
+            msg.nest(IFLA_INFO_DATA);
+            msg.attr_u32(IFLA_MACVLAN_MODE, mode.value());
+            msg.end();
+            msg.end();
+            msg
+        }
+        Operation::Rem => del_link(vifidx),
+    };
+
+    request(msg, || {
+        format!(
+            "cannot {} macvlan interface {} (master ifindex {})",
+            op.action(),
+            vifname,
+            master
+        )
+    })
+}
+
+// setup_ipvlan_link() function
+/// Create an ipvlan interface (in L2 mode, so ARP is handled by the
+/// interface itself) on top of its master interface and bring it up,
+/// or delete it
+pub fn setup_ipvlan_link(
+    master: i32,
+    vifidx: i32,
+    vifname: &str,
+    op: &Operation,
+) -> io::Result<()> {
+    let msg = match op {
+        Operation::Add => {
+            let mut msg = new_link(master, vifname);
+            msg.nest(IFLA_LINKINFO);
+            msg.attr(IFLA_INFO_KIND, b"ipvlan");
+            msg.nest(IFLA_INFO_DATA);
+            msg.attr(IFLA_IPVLAN_MODE, &IPVLAN_MODE_L2.to_ne_bytes());
+            msg.end();
+            msg.end();
+            msg
+        }
+        Operation::Rem => del_link(vifidx),
+    };
+
+    request(msg, || {
+        format!(
+            "cannot {} ipvlan interface {} (master ifindex {})",
+            op.action(),
+            vifname,
+            master
+        )
+    })
+}
+
+// setup_vlan_link() function
+/// Create an 802.1Q VLAN sub-interface on top of the master interface,
+/// or delete it
+pub fn setup_vlan_link(
+    master: i32,
+    vlanidx: i32,
+    vlanname: &str,
+    vid: u16,
+    op: &Operation,
+) -> io::Result<()> {
+    let msg = match op {
+        Operation::Add => {
+            let mut msg = new_link(master, vlanname);
+            msg.nest(IFLA_LINKINFO);
+            msg.attr(IFLA_INFO_KIND, b"vlan");
+            msg.nest(IFLA_INFO_DATA);
+            msg.attr(IFLA_VLAN_ID, &vid.to_ne_bytes());
+            msg.end();
+            msg.end();
+            msg
+        }
+        Operation::Rem => del_link(vlanidx),
+    };
+
+    request(msg, || {
+        format!(
+            "cannot {} VLAN {} interface {} (master ifindex {})",
+            op.action(),
+            vid,
+            vlanname,
+            master
+        )
+    })
+}
+
+// new_link() function
+/// start the creation request of a virtual interface, which is up
+fn new_link(master: i32, vifname: &str) -> NlMsg {
+    let mut msg = NlMsg::new(RTM_NEWLINK, NLM_F_CREATE | NLM_F_EXCL);
+    msg.push(&ifinfomsg(0, IFF_UP as u32, IFF_UP as u32));
+    msg.attr_str(IFLA_IFNAME, vifname);
+    msg.attr_u32(IFLA_LINK, master as u32);
+    msg
+}
+
+// del_link() function
+/// build the deletion request of an interface
+fn del_link(ifindex: i32) -> NlMsg {
+    let mut msg = NlMsg::new(RTM_DELLINK, 0);
+    msg.push(&ifinfomsg(ifindex, 0, 0));
+    msg
+}
+
+// set_secondary_mac() function
+/// Add or remove a secondary unicast MAC address on an interface,
+/// the address is added to the interface's unicast filter (as with
+/// 'bridge fdb add <mac> dev <ifname> self permanent')
+pub fn set_secondary_mac(ifindex: i32, mac: [u8; 6], op: &Operation) -> io::Result<()> {
+    let mut msg = match op {
+        Operation::Add => NlMsg::new(RTM_NEWNEIGH, NLM_F_CREATE | NLM_F_EXCL),
+        Operation::Rem => NlMsg::new(RTM_DELNEIGH, 0),
+    };
+    // ndmsg
+    let mut hdr = vec![AF_BRIDGE, 0, 0, 0];
+    hdr.extend_from_slice(&ifindex.to_ne_bytes());
+    hdr.extend_from_slice(&NUD_PERMANENT.to_ne_bytes());
+    hdr.extend_from_slice(&[NTF_SELF, 0]);
+    msg.push(&hdr);
+    msg.attr(NDA_LLADDR, &mac);
+
+    request(msg, || {
+        format!(
+            "cannot {} secondary MAC address {:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x} on interface index {}",
+            op.action(),
+            mac[0],
+            mac[1],
+            mac[2],
+            mac[3],
+            mac[4],
+            mac[5],
+            ifindex
+        )
+    })
+}
+
+// set_link_flags() function
+/// Set or clear the flags of an interface, only the flags in 'change'
+/// are modified
+pub fn set_link_flags(ifindex: i32, flags: u32, change: u32) -> io::Result<()> {
+    let mut msg = NlMsg::new(RTM_NEWLINK, 0);
+    msg.push(&ifinfomsg(ifindex, flags, change));
+
+    request(msg, || {
+        format!("cannot change flags of interface index {}", ifindex)
+    })
+}
+
+/// Netlink Network Driver Structure
+/// IPv4 addresses, routes, virtual interfaces and link flags are set using
+/// rtnetlink messages, MAC addresses and raw frames are handled as with netdev
+#[derive(Debug)]
+pub struct Netlink;
+
+// NetDriver trait implementation for the native netlink driver
+impl NetDriver for Netlink {
+    // kind() method
+    fn kind(&self) -> NetDrivers {
+        NetDrivers::netlink
+    }
+    // set_ip_address() method
+    fn set_ip_address(
+        &self,
+        _fd: i32,
+        ifindex: i32,
+        ifname: &str,
+        ip: [u8; 4],
+        netmask: [u8; 4],
+        op: &Operation,
+        _debug: &Verbose,
+    ) -> io::Result<()> {
+        set_ip_address(ifindex, ifname, ip, netmask, op)
+    }
+    // get_mac_address() method
+    fn get_mac_address(&self, fd: i32, ifname: &str, debug: &Verbose) -> io::Result<[u8; 6]> {
+        Netdev.get_mac_address(fd, ifname, debug)
+    }
+    // set_mac_address() method
+    fn set_mac_address(
+        &self,
+        fd: i32,
+        ifname: &str,
+        mac: [u8; 6],
+        debug: &Verbose,
+    ) -> io::Result<()> {
+        Netdev.set_mac_address(fd, ifname, mac, debug)
+    }
+    // set_ip_route() method
+    fn set_ip_route(
+        &self,
+        _fd: i32,
+        _ifname: &str,
+        route: &Static,
+        op: &Operation,
+        _debug: &Verbose,
+    ) -> io::Result<()> {
+        set_ip_route(
+            route.route(),
+            route.mask(),
+            route.nh(),
+            route.metric(),
+            route.mtu(),
+            op,
+        )
+    }
+    // setup_macvlan_link() method
+    fn setup_macvlan_link(
+        &self,
+        master: i32,
+        vifidx: i32,
+        vifname: &str,
+        mac: [u8; 6],
+        mode: MacvlanMode,
+        op: &Operation,
+        _debug: &Verbose,
+    ) -> io::Result<()> {
+        setup_macvlan_link(master, vifidx, vifname, mac, mode, op)
+    }
+    // setup_ipvlan_link() method
+    fn setup_ipvlan_link(
+        &self,
+        master: i32,
+        vifidx: i32,
+        vifname: &str,
+        op: &Operation,
+        _debug: &Verbose,
+    ) -> io::Result<()> {
+        setup_ipvlan_link(master, vifidx, vifname, op)
+    }
+    // setup_vlan_link() method
+    fn setup_vlan_link(
+        &self,
+        master: i32,
+        vlanidx: i32,
+        vlanname: &str,
+        vid: u16,
+        op: &Operation,
+        _debug: &Verbose,
+    ) -> io::Result<()> {
+        setup_vlan_link(master, vlanidx, vlanname, vid, op)
+    }
+    // set_secondary_mac() method
+    fn set_secondary_mac(
+        &self,
+        ifindex: i32,
+        mac: [u8; 6],
+        op: &Operation,
+        _debug: &Verbose,
+    ) -> io::Result<()> {
+        set_secondary_mac(ifindex, mac, op)
+    }
+    // set_promiscuous() method
+    fn set_promiscuous(&self, _fd: i32, ifname: &str, on: bool) -> io::Result<()> {
+        let ifindex = self.link_index(ifname)?;
+        let flags = if on { IFF_PROMISC as u32 } else { 0 };
+        set_link_flags(ifindex, flags, IFF_PROMISC as u32)
+    }
+    // link_index() method
+    fn link_index(&self, ifname: &str) -> io::Result<i32> {
+        c_ifnametoindex(&ifname.to_string()).map(|i| i as i32)
+    }
+    // send_frame() method
+    fn send_frame(
+        &self,
+        fd: i32,
+        ifindex: i32,
+        frame: &mut Vec<u8>,
+        debug: &Verbose,
+    ) -> io::Result<()> {
+        Netdev.send_frame(fd, ifindex, frame, debug)
+    }
+    // send_arp() method
+    fn send_arp(
+        &self,
+        fd: i32,
+        ifindex: i32,
+        frame: &mut Vec<u8>,
+        debug: &Verbose,
+    ) -> io::Result<()> {
+        Netdev.send_arp(fd, ifindex, frame, debug)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::os::linux::filter::{SockFilter, SockFprog};
+    use crate::os::linux::libc::{
+        close_fd, open_raw_socket_all, open_raw_socket_fd, recv_ip_pkts, set_sock_filter,
+        set_sock_rcvtimeo,
+    };
+    use libc::sockaddr_ll;
+    use std::env;
+    use std::process::Command;
+
+    const VMAC: [u8; 6] = [0x00, 0x00, 0x5e, 0x00, 0x01, 0x07];
+
+    // netns() function
+    // run the test again in a new unprivileged user and network namespace,
+    // return true when running inside of it, the test is skipped when such
+    // namespaces are not available
+    fn netns(test: &str) -> bool {
+        if env::var_os("RVRRPD_TEST_NETNS").is_some() {
+            return true;
+        }
+        match Command::new("unshare")
+            .args(&["-rn", "ip", "link"])
+            .output()
+        {
+            Ok(ref o) if o.status.success() => {}
+            _ => {
+                eprintln!("skipping {}: no unprivileged network namespace", test);
+                return false;
+            }
+        }
+        let status = Command::new("unshare")
+            .arg("-rn")
+            .arg(env::current_exe().unwrap())
+            .arg("--exact")
+            .arg(format!("os::linux::rtnetlink::tests::{}", test))
+            .args(&["--nocapture", "--test-threads=1"])
+            .env("RVRRPD_TEST_NETNS", "1")
+            .status()
+            .unwrap();
+        assert!(status.success(), "{} failed in the network namespace", test);
+        false
+    }
+
+    // ip() function
+    // return the output of an iproute2 command
+    fn ip(args: &str) -> String {
+        cmd("ip", args)
+    }
+
+    // cmd() function
+    // return the output of a command, which must succeed
+    fn cmd(prog: &str, args: &str) -> String {
+        let out = Command::new(prog)
+            .args(args.split_whitespace())
+            .output()
+            .unwrap();
+        assert!(
+            out.status.success(),
+            "{} {}: {}",
+            prog,
+            args,
+            String::from_utf8_lossy(&out.stderr)
+        );
+        String::from_utf8(out.stdout).unwrap()
+    }
+
+    // veth() function
+    // create a veth pair and return the ifindex of its first end
+    fn veth() -> i32 {
+        ip("link add v0 type veth peer name v1");
+        c_ifnametoindex(&"v0".to_string()).unwrap() as i32
+    }
+
+    #[test]
+    fn link_flags() {
+        if !netns("link_flags") {
+            return;
+        }
+        let idx = veth();
+        set_link_flags(idx, IFF_UP as u32, IFF_UP as u32).unwrap();
+        assert!(ip("link show v0").contains(",UP"));
+
+        Netlink.set_promiscuous(-1, "v0", true).unwrap();
+        assert!(ip("link show v0").contains("PROMISC"));
+        // the other flags are left untouched
+        Netlink.set_promiscuous(-1, "v0", false).unwrap();
+        let link = ip("link show v0");
+        assert!(!link.contains("PROMISC") && link.contains(",UP"));
+
+        let e = set_link_flags(9999, IFF_UP as u32, IFF_UP as u32).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn address_add_delete() {
+        if !netns("address_add_delete") {
+            return;
+        }
+        let idx = veth();
+        let (ip4, mask) = ([10, 1, 0, 1], [255, 255, 255, 0]);
+        set_ip_address(idx, "v0", ip4, mask, &Operation::Add).unwrap();
+        assert!(ip("-4 addr show dev v0").contains("inet 10.1.0.1/24"));
+
+        let e = set_ip_address(idx, "v0", ip4, mask, &Operation::Add).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::AlreadyExists);
+        assert!(e.to_string().contains("10.1.0.1/24"), "{}", e);
+
+        set_ip_address(idx, "v0", ip4, mask, &Operation::Rem).unwrap();
+        assert!(!ip("-4 addr show dev v0").contains("10.1.0.1"));
+
+        let e = set_ip_address(idx, "v0", ip4, mask, &Operation::Rem).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::AddrNotAvailable);
+    }
+
+    #[test]
+    fn route_add_delete() {
+        if !netns("route_add_delete") {
+            return;
+        }
+        let idx = veth();
+        set_ip_address(
+            idx,
+            "v0",
+            [10, 1, 0, 1],
+            [255, 255, 255, 0],
+            &Operation::Add,
+        )
+        .unwrap();
+        set_link_flags(idx, IFF_UP as u32, IFF_UP as u32).unwrap();
+
+        let (route, mask, gw) = ([10, 9, 0, 0], [255, 255, 0, 0], [10, 1, 0, 254]);
+        set_ip_route(route, mask, gw, 10, 1400, &Operation::Add).unwrap();
+        let table = ip("-4 route show 10.9.0.0/16");
+        assert!(table.contains("via 10.1.0.254 dev v0"), "{}", table);
+        assert!(table.contains("metric 10") && table.contains("mtu 1400"));
+
+        let e = set_ip_route(route, mask, gw, 10, 1400, &Operation::Add).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::AlreadyExists);
+
+        set_ip_route(route, mask, gw, 10, 1400, &Operation::Rem).unwrap();
+        assert!(ip("-4 route show 10.9.0.0/16").is_empty());
+
+        let e = set_ip_route(route, mask, gw, 10, 1400, &Operation::Rem).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::NotFound);
+
+        // the gateway must be reachable
+        let e = set_ip_route(route, mask, [10, 2, 0, 1], 0, 0, &Operation::Add).unwrap_err();
+        assert!(e.to_string().contains("via 10.2.0.1"), "{}", e);
+    }
+
+    #[test]
+    fn macvlan_create_delete() {
+        if !netns("macvlan_create_delete") {
+            return;
+        }
+        let idx = veth();
+        let mode = MacvlanMode::bridge;
+        setup_macvlan_link(idx, 0, "vrrp.7", VMAC, mode, &Operation::Add).unwrap();
+        let link = ip("-d link show vrrp.7");
+        assert!(
+            link.contains("vrrp.7@v0") && link.contains(",UP"),
+            "{}",
+            link
+        );
+        assert!(link.contains("link/ether 00:00:5e:00:01:07"), "{}", link);
+        assert!(link.contains("macvlan mode bridge"), "{}", link);
+
+        let e = setup_macvlan_link(idx, 0, "vrrp.7", VMAC, mode, &Operation::Add).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::AlreadyExists);
+
+        let vifidx = Netlink.link_index("vrrp.7").unwrap();
+        setup_macvlan_link(idx, vifidx, "vrrp.7", VMAC, mode, &Operation::Rem).unwrap();
+        assert!(Netlink.link_index("vrrp.7").is_err());
+
+        let e = setup_macvlan_link(9999, 0, "vrrp.8", VMAC, mode, &Operation::Add).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::NotFound);
+
+        // the other modes
+        for mode in &[
+            MacvlanMode::private,
+            MacvlanMode::vepa,
+            MacvlanMode::passthru,
+        ] {
+            setup_macvlan_link(idx, 0, "vrrp.7", VMAC, *mode, &Operation::Add).unwrap();
+            let link = ip("-d link show vrrp.7");
+            assert!(
+                link.contains(&format!("macvlan mode {}", mode.name())),
+                "{}",
+                link
+            );
+            let vifidx = Netlink.link_index("vrrp.7").unwrap();
+            setup_macvlan_link(idx, vifidx, "vrrp.7", VMAC, *mode, &Operation::Rem).unwrap();
+        }
+    }
+
+    #[test]
+    fn ipvlan_create_delete() {
+        if !netns("ipvlan_create_delete") {
+            return;
+        }
+        let idx = veth();
+        match setup_ipvlan_link(idx, 0, "vrrp.7", &Operation::Add) {
+            // the kernel may be built without ipvlan support
+            Err(ref e) if e.kind() == io::ErrorKind::Unsupported => return,
+            r => r.unwrap(),
+        }
+        let link = ip("-d link show vrrp.7");
+        assert!(
+            link.contains("vrrp.7@v0") && link.contains(",UP"),
+            "{}",
+            link
+        );
+        assert!(
+            link.contains("ipvlan") && link.contains("mode l2"),
+            "{}",
+            link
+        );
+        // the ipvlan interface shares the MAC address of its master
+        let mac = |ifname: &str| {
+            let link = ip(&format!("link show {}", ifname));
+            let pos = link.find("link/ether ").unwrap() + 11;
+            link[pos..pos + 17].to_string()
+        };
+        assert_eq!(mac("vrrp.7"), mac("v0"));
+
+        let e = setup_ipvlan_link(idx, 0, "vrrp.7", &Operation::Add).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::AlreadyExists);
+
+        let vifidx = Netlink.link_index("vrrp.7").unwrap();
+        setup_ipvlan_link(idx, vifidx, "vrrp.7", &Operation::Rem).unwrap();
+        assert!(Netlink.link_index("vrrp.7").is_err());
+    }
+
+    #[test]
+    fn vlan_create_delete() {
+        if !netns("vlan_create_delete") {
+            return;
+        }
+        let idx = veth();
+        match setup_vlan_link(idx, 0, "v0.123", 123, &Operation::Add) {
+            // the kernel may be built without 802.1Q support
+            Err(ref e) if e.kind() == io::ErrorKind::Unsupported => return,
+            r => r.unwrap(),
+        }
+        let link = ip("-d link show v0.123");
+        assert!(
+            link.contains("v0.123@v0") && link.contains(",UP"),
+            "{}",
+            link
+        );
+        assert!(link.contains("vlan protocol 802.1Q id 123"), "{}", link);
+
+        let e = setup_vlan_link(idx, 0, "v0.123", 123, &Operation::Add).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::AlreadyExists);
+
+        let vlanidx = Netlink.link_index("v0.123").unwrap();
+        setup_vlan_link(idx, vlanidx, "v0.123", 123, &Operation::Rem).unwrap();
+        assert!(Netlink.link_index("v0.123").is_err());
+    }
+
+    #[test]
+    fn vlan_socket_filter() {
+        if !netns("vlan_socket_filter") {
+            return;
+        }
+        let idx = veth();
+        ip("link set v0 up");
+        ip("link set v1 up");
+        let v1 = Netlink.link_index("v1").unwrap();
+
+        // sockets listening on the parent interface, untagged or for VLAN 123
+        let open = |vlan: Option<u16>| {
+            let fd = match vlan {
+                Some(_) => open_raw_socket_all().unwrap(),
+                None => open_raw_socket_fd().unwrap(),
+            };
+            let filter = SockFilter::new_vrrpv2_gid(7, vlan);
+            set_sock_filter(fd, &SockFprog::build_fprog_vrrpv2_gid(&filter)).unwrap();
+            set_sock_rcvtimeo(fd, 1).unwrap();
+            fd
+        };
+        let untagged = open(None);
+        let tagged = open(Some(123));
+
+        // advertisements sourced from 10.0.0.<src>, sent on the other end
+        let fd = open_raw_socket_fd().unwrap();
+        for (src, vlan) in &[(1, None), (2, Some(123)), (3, Some(124))] {
+            let mut frame = Vec::new();
+            EthernetHeader {
+                dst_mac: ETHER_VRRP_V2_DST_MAC,
+                src_mac: VMAC,
+                vlans: vlan
+                    .iter()
+                    .map(|&tci| VlanTag {
+                        tpid: ETHER_P_8021Q,
+                        tci,
+                    })
+                    .collect(),
+                ethertype: ETHER_P_IP,
+            }
+            .encode(&mut frame);
+            frame.extend_from_slice(&[0x45, 0, 0, 40, 0, 0, 0, 0, 255, 112, 0, 0]);
+            frame.extend_from_slice(&[10, 0, 0, *src, 224, 0, 0, 18]);
+            frame.extend_from_slice(&[0x21, 7, 100, 1, 0, 1, 0, 0, 10, 0, 0, 1]);
+            frame.extend_from_slice(&[0; 8]);
+            Netdev
+                .send_frame(fd, v1, &mut frame, &Verbose::new(0, 0, 0))
+                .unwrap();
+        }
+        close_fd(fd).unwrap();
+
+        // the tags are removed by the kernel before the frames are received
+        let received = |fd: i32| {
+            let mut sources = Vec::new();
+            let mut sockaddr: sockaddr_ll = unsafe { std::mem::zeroed() };
+            let mut buf = [0u8; 1024];
+            while let Ok(len) = recv_ip_pkts(fd, &mut sockaddr, &mut buf) {
+                if sockaddr.sll_ifindex == idx && len > 29 {
+                    sources.push(buf[29]);
+                }
+            }
+            close_fd(fd).unwrap();
+            sources
+        };
+        assert_eq!(received(untagged), vec![1]);
+        assert_eq!(received(tagged), vec![2]);
+    }
+
+    #[test]
+    fn secondary_mac_add_delete() {
+        if !netns("secondary_mac_add_delete") {
+            return;
+        }
+        let idx = veth();
+        set_secondary_mac(idx, VMAC, &Operation::Add).unwrap();
+        let fdb = cmd("bridge", "fdb show dev v0");
+        assert!(fdb.contains("00:00:5e:00:01:07 self permanent"), "{}", fdb);
+
+        let e = set_secondary_mac(idx, VMAC, &Operation::Add).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::AlreadyExists);
+
+        set_secondary_mac(idx, VMAC, &Operation::Rem).unwrap();
+        let fdb = cmd("bridge", "fdb show dev v0");
+        assert!(!fdb.contains("00:00:5e:00:01:07"), "{}", fdb);
+
+        let e = set_secondary_mac(idx, VMAC, &Operation::Rem).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn parse_link_and_address_events() {
+        // RTM_NEWLINK of an interface up and running
+        let mut link = NlMsg::new(RTM_NEWLINK, 0);
+        link.push(&ifinfomsg(3, (IFF_UP | IFF_RUNNING) as u32, 0));
+        link.attr_u32(IFLA_LINK, 3);
+        link.attr_str(IFLA_IFNAME, "eth0");
+        let mut pkt = link.finish(0);
+
+        // RTM_DELADDR of an IPv4 address
+        let mut addr = NlMsg::new(RTM_DELADDR, 0);
+        let mut hdr = vec![AF_INET as u8, 24, 0, 0];
+        hdr.extend_from_slice(&3i32.to_ne_bytes());
+        addr.push(&hdr);
+        addr.attr(IFA_ADDRESS, &[10, 1, 0, 255]);
+        addr.attr(IFA_LOCAL, &[10, 1, 0, 1]);
+        pkt.extend_from_slice(&addr.finish(0));
+
+        // RTM_NEWLINK of an interface administratively down
+        let mut down = NlMsg::new(RTM_NEWLINK, 0);
+        down.push(&ifinfomsg(4, IFF_RUNNING as u32, 0));
+        down.attr_str(IFLA_IFNAME, "eth1");
+        pkt.extend_from_slice(&down.finish(0));
+
+        assert_eq!(
+            parse_events(&pkt),
+            vec![
+                LinkEvent::NewLink {
+                    ifindex: 3,
+                    ifname: "eth0".to_string(),
+                    up: true
+                },
+                LinkEvent::DelAddr {
+                    ifindex: 3,
+                    ip: [10, 1, 0, 1]
+                },
+                LinkEvent::NewLink {
+                    ifindex: 4,
+                    ifname: "eth1".to_string(),
+                    up: false
+                },
+            ]
+        );
+
+        // a truncated datagram is ignored from the broken message on
+        assert_eq!(parse_events(&pkt[..pkt.len() - 1]).len(), 2);
+        assert!(parse_events(&pkt[..10]).is_empty());
+    }
+
+    #[test]
+    fn dump_answer_end() {
+        let mut link = NlMsg::new(RTM_NEWLINK, 0);
+        link.push(&ifinfomsg(3, 0, 0));
+        link.attr_str(IFLA_IFNAME, "eth0");
+        let mut pkt = link.finish(7);
+        assert_eq!(dump_end(&pkt, 7), None);
+
+        // the end of another request is not this one's
+        let done = NlMsg::new(NLMSG_DONE, 0);
+        let mut other = pkt.clone();
+        other.extend_from_slice(&done.finish(6));
+        assert_eq!(dump_end(&other, 7), None);
+
+        let mut done = NlMsg::new(NLMSG_DONE, 0);
+        done.push(&0i32.to_ne_bytes());
+        pkt.extend_from_slice(&done.finish(7));
+        assert_eq!(dump_end(&pkt, 7), Some(0));
+        assert_eq!(parse_events(&pkt).len(), 1);
+
+        // an acknowledgement does not end the dump, an error does
+        let mut ack = NlMsg::new(NLMSG_ERROR, 0);
+        ack.push(&0i32.to_ne_bytes());
+        assert_eq!(dump_end(&ack.finish(7), 7), None);
+        let mut err = NlMsg::new(NLMSG_ERROR, 0);
+        err.push(&(-libc::EPERM).to_ne_bytes());
+        assert_eq!(dump_end(&err.finish(7), 7), Some(libc::EPERM));
+    }
+
+    #[test]
+    fn monitor_events() {
+        if !netns("monitor_events") {
+            return;
+        }
+        let monitor = Monitor::open().unwrap();
+        crate::os::linux::libc::set_sock_rcvtimeo(monitor.sock.fd, 5).unwrap();
+        // wait_for() closure
+        // receive notifications until the expected one
+        let wait_for = |expected: LinkEvent| loop {
+            let events = monitor.recv().expect("no notification received");
+            if events.contains(&expected) {
+                break;
+            }
+        };
+
+        let idx = veth();
+        set_link_flags(idx, IFF_UP as u32, IFF_UP as u32).unwrap();
+        ip("link set v1 up");
+        wait_for(LinkEvent::NewLink {
+            ifindex: idx,
+            ifname: "v0".to_string(),
+            up: true,
+        });
+
+        // the dump holds the current state
+        assert!(dump().unwrap().contains(&LinkEvent::NewLink {
+            ifindex: idx,
+            ifname: "v0".to_string(),
+            up: true,
+        }));
+
+        let (ip4, mask) = ([10, 1, 0, 1], [255, 255, 255, 0]);
+        set_ip_address(idx, "v0", ip4, mask, &Operation::Add).unwrap();
+        wait_for(LinkEvent::NewAddr {
+            ifindex: idx,
+            ip: ip4,
+        });
+        set_ip_address(idx, "v0", ip4, mask, &Operation::Rem).unwrap();
+        wait_for(LinkEvent::DelAddr {
+            ifindex: idx,
+            ip: ip4,
+        });
+
+        // the carrier is lost when the peer goes down
+        ip("link set v1 down");
+        wait_for(LinkEvent::NewLink {
+            ifindex: idx,
+            ifname: "v0".to_string(),
+            up: false,
+        });
+
+        ip("link del v0");
+        wait_for(LinkEvent::DelLink {
+            ifindex: idx,
+            ifname: "v0".to_string(),
+        });
+    }
+}
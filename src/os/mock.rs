@@ -0,0 +1,298 @@
+//! in-memory network driver module
+//! This module provides a network driver recording the operations of the
+//! virtual routers instead of performing them, for testing purposes.
+use crate::*;
+
+// debugging
+use crate::debug::Verbose;
+
+// operating system drivers
+use crate::os::drivers::{MacvlanMode, NetDriver, Operation};
+
+// protocols
+use crate::protocols::Static;
+
+/// Driver Call Enumerator
+#[derive(Debug, Clone, PartialEq)]
+pub enum Call {
+    SetIpAddress {
+        ifindex: i32,
+        ifname: String,
+        ip: [u8; 4],
+        netmask: [u8; 4],
+        op: Operation,
+    },
+    GetMacAddress {
+        ifname: String,
+    },
+    SetMacAddress {
+        ifname: String,
+        mac: [u8; 6],
+    },
+    SetIpRoute {
+        ifname: String,
+        route: [u8; 4],
+        nh: [u8; 4],
+        op: Operation,
+    },
+    SetupMacvlanLink {
+        master: i32,
+        vifname: String,
+        mac: [u8; 6],
+        mode: MacvlanMode,
+        op: Operation,
+    },
+    SetupIpvlanLink {
+        master: i32,
+        vifname: String,
+        op: Operation,
+    },
+    SetupVlanLink {
+        master: i32,
+        vlanname: String,
+        vid: u16,
+        op: Operation,
+    },
+    SetSecondaryMac {
+        ifindex: i32,
+        mac: [u8; 6],
+        op: Operation,
+    },
+    SetPromiscuous {
+        ifname: String,
+        on: bool,
+    },
+    LinkIndex {
+        ifname: String,
+    },
+    SendFrame {
+        ifindex: i32,
+        frame: Vec<u8>,
+    },
+    SendArp {
+        ifindex: i32,
+        frame: Vec<u8>,
+    },
+}
+
+/// Mock Network Driver Structure
+#[derive(Debug)]
+pub struct MockDriver {
+    kind: NetDrivers,        // Network driver being mimicked
+    mac: [u8; 6],            // MAC address of every interface
+    ifindex: i32,            // ifindex of every interface
+    calls: Mutex<Vec<Call>>, // Recorded calls
+}
+
+// MockDriver Type Implementation
+impl MockDriver {
+    // new() method
+    pub fn new(kind: NetDrivers) -> MockDriver {
+        MockDriver {
+            kind,
+            mac: [0x52, 0x54, 0x00, 0x12, 0x34, 0x56],
+            ifindex: 42,
+            calls: Mutex::new(Vec::new()),
+        }
+    }
+    // mac() getter
+    pub fn mac(&self) -> [u8; 6] {
+        self.mac
+    }
+    // ifindex() getter
+    pub fn ifindex(&self) -> i32 {
+        self.ifindex
+    }
+    // calls() method
+    /// return the recorded calls, in order
+    pub fn calls(&self) -> Vec<Call> {
+        self.calls.lock().unwrap().clone()
+    }
+    // clear() method
+    /// forget the recorded calls
+    pub fn clear(&self) {
+        self.calls.lock().unwrap().clear();
+    }
+    // record() method
+    fn record(&self, call: Call) {
+        self.calls.lock().unwrap().push(call);
+    }
+}
+
+// NetDriver trait implementation for the mock driver
+impl NetDriver for MockDriver {
+    // kind() method
+    fn kind(&self) -> NetDrivers {
+        self.kind
+    }
+    // set_ip_address() method
+    fn set_ip_address(
+        &self,
+        _fd: i32,
+        ifindex: i32,
+        ifname: &str,
+        ip: [u8; 4],
+        netmask: [u8; 4],
+        op: &Operation,
+        _debug: &Verbose,
+    ) -> io::Result<()> {
+        self.record(Call::SetIpAddress {
+            ifindex,
+            ifname: ifname.to_string(),
+            ip,
+            netmask,
+            op: *op,
+        });
+        Ok(())
+    }
+    // get_mac_address() method
+    fn get_mac_address(&self, _fd: i32, ifname: &str, _debug: &Verbose) -> io::Result<[u8; 6]> {
+        self.record(Call::GetMacAddress {
+            ifname: ifname.to_string(),
+        });
+        Ok(self.mac)
+    }
+    // set_mac_address() method
+    fn set_mac_address(
+        &self,
+        _fd: i32,
+        ifname: &str,
+        mac: [u8; 6],
+        _debug: &Verbose,
+    ) -> io::Result<()> {
+        self.record(Call::SetMacAddress {
+            ifname: ifname.to_string(),
+            mac,
+        });
+        Ok(())
+    }
+    // set_ip_route() method
+    fn set_ip_route(
+        &self,
+        _fd: i32,
+        ifname: &str,
+        route: &Static,
+        op: &Operation,
+        _debug: &Verbose,
+    ) -> io::Result<()> {
+        self.record(Call::SetIpRoute {
+            ifname: ifname.to_string(),
+            route: route.route(),
+            nh: route.nh(),
+            op: *op,
+        });
+        Ok(())
+    }
+    // setup_macvlan_link() method
+    fn setup_macvlan_link(
+        &self,
+        master: i32,
+        _vifidx: i32,
+        vifname: &str,
+        mac: [u8; 6],
+        mode: MacvlanMode,
+        op: &Operation,
+        _debug: &Verbose,
+    ) -> io::Result<()> {
+        self.record(Call::SetupMacvlanLink {
+            master,
+            vifname: vifname.to_string(),
+            mac,
+            mode,
+            op: *op,
+        });
+        Ok(())
+    }
+    // setup_ipvlan_link() method
+    fn setup_ipvlan_link(
+        &self,
+        master: i32,
+        _vifidx: i32,
+        vifname: &str,
+        op: &Operation,
+        _debug: &Verbose,
+    ) -> io::Result<()> {
+        self.record(Call::SetupIpvlanLink {
+            master,
+            vifname: vifname.to_string(),
+            op: *op,
+        });
+        Ok(())
+    }
+    // setup_vlan_link() method
+    fn setup_vlan_link(
+        &self,
+        master: i32,
+        _vlanidx: i32,
+        vlanname: &str,
+        vid: u16,
+        op: &Operation,
+        _debug: &Verbose,
+    ) -> io::Result<()> {
+        self.record(Call::SetupVlanLink {
+            master,
+            vlanname: vlanname.to_string(),
+            vid,
+            op: *op,
+        });
+        Ok(())
+    }
+    // set_secondary_mac() method
+    fn set_secondary_mac(
+        &self,
+        ifindex: i32,
+        mac: [u8; 6],
+        op: &Operation,
+        _debug: &Verbose,
+    ) -> io::Result<()> {
+        self.record(Call::SetSecondaryMac {
+            ifindex,
+            mac,
+            op: *op,
+        });
+        Ok(())
+    }
+    // set_promiscuous() method
+    fn set_promiscuous(&self, _fd: i32, ifname: &str, on: bool) -> io::Result<()> {
+        self.record(Call::SetPromiscuous {
+            ifname: ifname.to_string(),
+            on,
+        });
+        Ok(())
+    }
+    // link_index() method
+    fn link_index(&self, ifname: &str) -> io::Result<i32> {
+        self.record(Call::LinkIndex {
+            ifname: ifname.to_string(),
+        });
+        Ok(self.ifindex)
+    }
+    // send_frame() method
+    fn send_frame(
+        &self,
+        _fd: i32,
+        ifindex: i32,
+        frame: &mut Vec<u8>,
+        _debug: &Verbose,
+    ) -> io::Result<()> {
+        self.record(Call::SendFrame {
+            ifindex,
+            frame: frame.clone(),
+        });
+        Ok(())
+    }
+    // send_arp() method
+    fn send_arp(
+        &self,
+        _fd: i32,
+        ifindex: i32,
+        frame: &mut Vec<u8>,
+        _debug: &Verbose,
+    ) -> io::Result<()> {
+        self.record(Call::SendArp {
+            ifindex,
+            frame: frame.clone(),
+        });
+        Ok(())
+    }
+}
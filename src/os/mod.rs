@@ -3,6 +3,10 @@
 // drivers
 pub mod drivers;
 
+// in-memory network driver
+#[cfg(test)]
+pub mod mock;
+
 // Linux Operating System support
 #[cfg(target_os = "linux")]
 pub mod linux;
@@ -0,0 +1,209 @@
+//! peers module
+//! This module keeps track of the routers seen advertising for a virtual router.
+
+// std
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// maximum number of peers kept per virtual router
+const PEERS_MAX_ENTRIES: usize = 64;
+
+// a peer is forgotten after this many master down intervals without ADVERTISEMENT
+const PEERS_EXPIRY_FACTOR: u64 = 3;
+
+/// Peer Structure
+/// a router seen advertising for the virtual router
+#[derive(Debug, Clone)]
+pub struct Peer {
+    pub ipsrc: [u8; 4],        // source IP address
+    pub macsrc: [u8; 6],       // source MAC address
+    pub prio: u8,              // advertised priority
+    pub adverint: u8,          // advertised interval
+    pub authtype: u8,          // authentication type
+    pub ipaddrs: Vec<[u8; 4]>, // advertised IP addresses
+    pub first_seen: u64,       // first ADVERTISEMENT (seconds since epoch)
+    pub last_seen: u64,        // last ADVERTISEMENT (seconds since epoch)
+}
+
+// Peer Implementation
+impl Peer {
+    // expired() method
+    // return true if the peer has not advertised for a few master down
+    // intervals (computed from its own advertisement interval)
+    fn expired(&self, now: u64) -> bool {
+        // the skew time is always less than a second
+        let master_down = 3 * u64::from(self.adverint) + 1;
+        now.saturating_sub(self.last_seen) > PEERS_EXPIRY_FACTOR * master_down
+    }
+    // is_master() method
+    // return true if the peer is the master of the virtual router
+    pub fn is_master(&self, master: Option<[u8; 4]>) -> bool {
+        master == Some(self.ipsrc)
+    }
+}
+
+/// PeerTable Structure
+#[derive(Debug, Default)]
+pub struct PeerTable {
+    peers: Vec<Peer>,
+}
+
+// PeerTable Implementation
+impl PeerTable {
+    // new() method
+    pub fn new() -> PeerTable {
+        PeerTable { peers: Vec::new() }
+    }
+    // seen() method
+    // record an ADVERTISEMENT from a peer, the peers are identified
+    // by their source IP address
+    pub fn seen(
+        &mut self,
+        ipsrc: [u8; 4],
+        macsrc: [u8; 6],
+        prio: u8,
+        adverint: u8,
+        authtype: u8,
+        ipaddrs: Vec<[u8; 4]>,
+    ) {
+        let now = now();
+        self.record(Peer {
+            ipsrc,
+            macsrc,
+            prio,
+            adverint,
+            authtype,
+            ipaddrs,
+            first_seen: now,
+            last_seen: now,
+        });
+    }
+    // record() method
+    // add or update a peer, as last seen at its 'last_seen' time
+    fn record(&mut self, peer: Peer) {
+        self.expire(peer.last_seen);
+        match self.peers.iter_mut().find(|p| p.ipsrc == peer.ipsrc) {
+            Some(p) => {
+                *p = Peer {
+                    first_seen: p.first_seen,
+                    ..peer
+                };
+            }
+            None => {
+                // forget the peer which has not been seen for the longest time
+                if self.peers.len() >= PEERS_MAX_ENTRIES {
+                    if let Some(i) = (0..self.peers.len()).min_by_key(|&i| self.peers[i].last_seen)
+                    {
+                        self.peers.remove(i);
+                    }
+                }
+                self.peers.push(peer);
+            }
+        }
+    }
+    // expire() method
+    // forget the peers which stopped advertising
+    fn expire(&mut self, now: u64) {
+        self.peers.retain(|p| !p.expired(now));
+    }
+    // peers() method
+    // return the peers seen recently
+    pub fn peers(&mut self) -> &Vec<Peer> {
+        self.expire(now());
+        &self.peers
+    }
+}
+
+// now() function
+// return the current time (seconds since epoch)
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // peer() function
+    // return a peer seen at the given time
+    fn peer(i: usize, now: u64, prio: u8, adverint: u8) -> Peer {
+        Peer {
+            ipsrc: [10, 0, (i / 256) as u8, (i % 256) as u8],
+            macsrc: [0x00, 0x50, 0x56, 0x8a, 0x01, 0x02],
+            prio,
+            adverint,
+            authtype: 0,
+            ipaddrs: vec![[10, 0, 0, 1]],
+            first_seen: now,
+            last_seen: now,
+        }
+    }
+
+    #[test]
+    fn update_in_place() {
+        let mut table = PeerTable::new();
+        table.record(peer(2, 100, 100, 1));
+        table.record(peer(3, 102, 90, 1));
+        table.record(Peer {
+            authtype: 1,
+            ipaddrs: vec![[10, 0, 0, 254]],
+            ..peer(2, 104, 200, 2)
+        });
+
+        assert_eq!(table.peers.len(), 2);
+        let p = &table.peers[0];
+        assert_eq!(p.ipsrc, [10, 0, 0, 2]);
+        assert_eq!((p.prio, p.adverint, p.authtype), (200, 2, 1));
+        assert_eq!(p.ipaddrs, vec![[10, 0, 0, 254]]);
+        assert_eq!((p.first_seen, p.last_seen), (100, 104));
+    }
+
+    #[test]
+    fn eviction_at_max_entries() {
+        let mut table = PeerTable::new();
+        for i in 0..PEERS_MAX_ENTRIES {
+            table.record(peer(i, 100 + i as u64, 100, 255));
+        }
+        // the first peer advertises again, the second one is the oldest
+        let now = 100 + PEERS_MAX_ENTRIES as u64;
+        table.record(peer(0, now, 100, 255));
+        table.record(peer(PEERS_MAX_ENTRIES, now, 100, 255));
+
+        let ips: Vec<[u8; 4]> = table.peers.iter().map(|p| p.ipsrc).collect();
+        assert_eq!(ips.len(), PEERS_MAX_ENTRIES);
+        assert!(ips.contains(&peer(0, now, 100, 1).ipsrc));
+        assert!(!ips.contains(&peer(1, now, 100, 1).ipsrc));
+        assert!(ips.contains(&peer(PEERS_MAX_ENTRIES, now, 100, 1).ipsrc));
+    }
+
+    #[test]
+    fn stale_peers_expire() {
+        let mut table = PeerTable::new();
+        table.record(peer(2, 100, 100, 1));
+        table.record(peer(3, 100, 100, 10));
+
+        // after 3 master down intervals of 4s, then of 31s
+        table.expire(112);
+        assert_eq!(table.peers.len(), 2);
+        table.expire(113);
+        assert_eq!(table.peers.len(), 1);
+        assert_eq!(table.peers[0].ipsrc, [10, 0, 0, 3]);
+        table.expire(194);
+        assert!(table.peers.is_empty());
+    }
+
+    #[test]
+    fn master_flag() {
+        let mut table = PeerTable::new();
+        table.record(peer(2, 100, 100, 1));
+        table.record(peer(3, 100, 200, 1));
+
+        let master = Some([10, 0, 0, 3]);
+        let flags: Vec<bool> = table.peers.iter().map(|p| p.is_master(master)).collect();
+        assert_eq!(flags, vec![false, true]);
+        // the local router is master
+        assert!(!table.peers.iter().any(|p| p.is_master(None)));
+    }
+}
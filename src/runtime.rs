@@ -0,0 +1,321 @@
+//! runtime module
+//! This module keeps track of the running virtual routers and their threads,
+//! so virtual routers can be added or removed while the daemon is running.
+use super::*;
+
+// config
+use config::VRConfig;
+
+// finite state machine
+use fsm::Event;
+
+// std
+use std::time::Duration;
+
+/// Runtime Structure
+///
+/// Shared between the main thread and the client API thread.
+#[derive(Clone)]
+pub struct Runtime {
+    vrouters: Arc<RwLock<Vec<Arc<RwLock<VirtualRouter>>>>>,
+    threads: Arc<Mutex<Option<ThreadPool>>>,
+    protocols: Arc<Mutex<Protocols>>,
+    capi: Option<(Sender<FSMQueryResult>, Receiver<FSMQueryResult>)>,
+    maintenance: Arc<Mutex<Vec<(u8, String)>>>,
+    dry_run: bool,
+    debug: Verbose,
+}
+
+// Runtime Implementation
+impl Runtime {
+    // new() method
+    pub fn new(
+        vrouters: &Vec<Arc<RwLock<VirtualRouter>>>,
+        protocols: Arc<Mutex<Protocols>>,
+        capi: Option<(Sender<FSMQueryResult>, Receiver<FSMQueryResult>)>,
+        dry_run: bool,
+        debug: &Verbose,
+    ) -> Runtime {
+        // virtual routers started in maintenance mode
+        let maintenance = vrouters
+            .iter()
+            .map(|vr| vr.read().unwrap())
+            .filter(|vr| vr.parameters.maintenance())
+            .map(|vr| (vr.parameters.vrid(), phys_interface(&vr)))
+            .collect();
+
+        Runtime {
+            vrouters: Arc::new(RwLock::new(vrouters.clone())),
+            threads: Arc::new(Mutex::new(None)),
+            protocols,
+            capi,
+            maintenance: Arc::new(Mutex::new(maintenance)),
+            dry_run,
+            debug: debug.clone(),
+        }
+    }
+    // debug() method
+    pub fn debug(&self) -> &Verbose {
+        &self.debug
+    }
+    // vrouters() method
+    // return the currently running virtual routers
+    pub fn vrouters(&self) -> Vec<Arc<RwLock<VirtualRouter>>> {
+        self.vrouters.read().unwrap().clone()
+    }
+    // find() method
+    // find a running virtual router by group id and interface
+    pub fn find(&self, vrid: u8, intf: &str) -> Option<Arc<RwLock<VirtualRouter>>> {
+        self.vrouters
+            .read()
+            .unwrap()
+            .iter()
+            .find(|vr| {
+                let vr = vr.read().unwrap();
+                // when a macvlan or ipvlan is in use, the interfaces are swapped
+                vr.parameters.vrid() == vrid
+                    && (vr.parameters.interface() == intf || vr.parameters.vifname() == intf)
+            })
+            .cloned()
+    }
+    // start() method
+    // create the threads pool, then start the workers and listeners threads
+    #[cfg(target_os = "linux")]
+    pub fn start(&self) {
+        let vrouters = self.vrouters();
+        let mut threads = ThreadPool::new(&vrouters, &self.debug);
+
+        // send Startup event to worker threads
+        threads.startup(&vrouters, &self.debug);
+
+        // spawn a listener thread per virtual router
+        for vr in &vrouters {
+            threads.listen(vr, &self.debug);
+        }
+
+        *self.threads.lock().unwrap() = Some(threads);
+
+        // watch the interfaces of the virtual routers
+        if let Err(e) = monitor::start(self.clone()) {
+            eprintln!(
+                "error(runtime): cannot monitor the interfaces changes: {}",
+                e
+            );
+        }
+    }
+    // add() method
+    // create a virtual router from its configuration and start it
+    #[cfg(target_os = "linux")]
+    pub fn add(&self, vrc: &VRConfig) -> io::Result<Arc<RwLock<VirtualRouter>>> {
+        // the threads pool must be running
+        let mut threads = self.threads.lock().unwrap();
+        let threads = match threads.as_mut() {
+            Some(t) => t,
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    "virtual routers are not started yet",
+                ))
+            }
+        };
+
+        // create the virtual router and setup its socket
+        let mut vr = new_vrouter(vrc, &self.protocols, &self.debug, self.dry_run)?;
+        // keep the maintenance mode of a previously removed virtual router
+        if self.in_maintenance(vrc.group(), &vrc.ifname()) {
+            vr.parameters.set_maintenance(true);
+        }
+        if let Err(e) = setup_vr_socket(&mut vr, &self.capi, &self.debug) {
+            // do not leave a created VLAN sub-interface behind
+            let _ = release_vlan_link(&vr, &self.debug);
+            return Err(e);
+        }
+        let vr = Arc::new(RwLock::new(vr));
+
+        // print debugging information
+        print_debug(
+            &self.debug,
+            DEBUG_LEVEL_MEDIUM,
+            DEBUG_SRC_VR,
+            format!(
+                "adding virtual router for group {} on interface {}",
+                vrc.group(),
+                vrc.ifname()
+            ),
+        );
+
+        // start its threads
+        threads.add(&vr, &self.debug);
+        self.vrouters.write().unwrap().push(Arc::clone(&vr));
+
+        Ok(vr)
+    }
+    // add() method
+    #[cfg(not(target_os = "linux"))]
+    pub fn add(&self, _vrc: &VRConfig) -> io::Result<Arc<RwLock<VirtualRouter>>> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "adding virtual routers at runtime is not supported on this platform",
+        ))
+    }
+    // remove() method
+    // shut down a virtual router and stop its threads
+    #[cfg(target_os = "linux")]
+    pub fn remove(&self, vr: &Arc<RwLock<VirtualRouter>>) -> io::Result<()> {
+        let mut threads = self.threads.lock().unwrap();
+        let threads = match threads.as_mut() {
+            Some(t) => t,
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    "virtual routers are not started yet",
+                ))
+            }
+        };
+
+        // print debugging information
+        {
+            let vro = vr.read().unwrap();
+            print_debug(
+                &self.debug,
+                DEBUG_LEVEL_MEDIUM,
+                DEBUG_SRC_VR,
+                format!(
+                    "removing virtual router for group {} on interface {}",
+                    vro.parameters.vrid(),
+                    vro.parameters.interface()
+                ),
+            );
+        }
+
+        // sends priority 0 and release the virtual router's resources
+        threads.remove(vr, &self.debug);
+        self.vrouters
+            .write()
+            .unwrap()
+            .retain(|v| !Arc::ptr_eq(v, vr));
+
+        // keep the interface in promiscuous mode if it is still in use
+        let vro = vr.read().unwrap();
+        let vrouters = self.vrouters.read().unwrap();
+        let shared = vrouters.iter().find(|v| {
+            let v = v.read().unwrap();
+            v.parameters.interface() == vro.parameters.interface()
+                || v.parameters.vifname() == vro.parameters.interface()
+        });
+        release_vr_socket(&vro, shared.is_none(), &self.debug)?;
+
+        // hand a created VLAN sub-interface over to a virtual router still using it
+        match shared {
+            Some(v) if vro.parameters.vlan_owned() => {
+                v.write().unwrap().parameters.set_vlan_owned(true);
+                Ok(())
+            }
+            _ => release_vlan_link(&vro, &self.debug),
+        }
+    }
+    // remove() method
+    #[cfg(not(target_os = "linux"))]
+    pub fn remove(&self, _vr: &Arc<RwLock<VirtualRouter>>) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "removing virtual routers at runtime is not supported on this platform",
+        ))
+    }
+    // request() method
+    // send an event to a virtual router's worker thread and wait for it to be applied
+    pub fn request<F>(&self, vr: &Arc<RwLock<VirtualRouter>>, event: F) -> io::Result<()>
+    where
+        F: FnOnce(mpsc::Sender<()>) -> Event,
+    {
+        let (ack_tx, ack_rx) = mpsc::channel();
+        match vr.read().unwrap().parameters.notification() {
+            Some(tx) => tx.lock().unwrap().send(event(ack_tx)).map_err(|_| {
+                io::Error::new(io::ErrorKind::Other, "the worker thread has exited")
+            })?,
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    "notification channel does not exist",
+                ))
+            }
+        }
+        // the lock must not be held here, the worker needs write access
+        ack_rx.recv_timeout(Duration::from_secs(5)).map_err(|_| {
+            io::Error::new(io::ErrorKind::TimedOut, "the worker thread did not answer")
+        })
+    }
+    // set_maintenance() method
+    // put a virtual router in or out of maintenance mode, and remember it
+    // so it is kept if the virtual router is removed and added back
+    pub fn set_maintenance(&self, vr: &Arc<RwLock<VirtualRouter>>, on: bool) -> io::Result<()> {
+        self.request(vr, |ack| Event::Maintenance(on, ack))?;
+
+        let key = {
+            let vr = vr.read().unwrap();
+            (vr.parameters.vrid(), phys_interface(&vr))
+        };
+        let mut maintenance = self.maintenance.lock().unwrap();
+        maintenance.retain(|k| *k != key);
+        if on {
+            maintenance.push(key);
+        }
+        Ok(())
+    }
+    // in_maintenance() method
+    // return true if the group on this interface has been put in maintenance
+    pub fn in_maintenance(&self, vrid: u8, intf: &str) -> bool {
+        self.maintenance
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|(g, i)| *g == vrid && i == intf)
+    }
+    // shutdown() method
+    // shut down all the virtual routers and release their sockets
+    #[cfg(target_os = "linux")]
+    pub fn shutdown(&self) -> io::Result<()> {
+        let vrouters = self.vrouters();
+
+        // manually calling the threads pool destructor
+        if let Some(threads) = self.threads.lock().unwrap().as_mut() {
+            threads.drop(&vrouters, &self.debug);
+        }
+
+        for vr in &vrouters {
+            // acquire read lock
+            let vr = vr.read().unwrap();
+            // remove promiscuous mode off interface
+            release_vr_socket(&vr, true, &self.debug)?;
+            // delete its VLAN sub-interface
+            release_vlan_link(&vr, &self.debug)?;
+        }
+
+        Ok(())
+    }
+}
+
+// config_interface() function
+/// return the interface of a virtual router as named in its configuration,
+/// its VLAN sub-interface when it listens on the parent interface
+pub fn config_interface(vr: &VirtualRouter) -> String {
+    match vr.parameters.vlan_tag() {
+        Some(vid) => format!("{}.{}", phys_interface(vr), vid),
+        None => phys_interface(vr),
+    }
+}
+
+// phys_interface() function
+/// return the interface a virtual router has been configured on, as a master
+/// using a macvlan or ipvlan runs on its virtual interface (except in dry-run mode)
+pub fn phys_interface(vr: &VirtualRouter) -> String {
+    #[cfg(target_os = "linux")]
+    {
+        if let fsm::States::Master = vr.get_states() {
+            if vr.parameters.iftype() != IfTypes::ether && !vr.parameters.dry_run() {
+                return vr.parameters.vifname();
+            }
+        }
+    }
+    vr.parameters.interface()
+}
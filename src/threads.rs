@@ -10,15 +10,22 @@ use std::thread;
 // channels
 use std::sync::mpsc;
 
+// atomics
+#[cfg(target_os = "linux")]
+use std::sync::atomic::{AtomicBool, Ordering};
+
 // debugging
 use crate::debug::Verbose;
 
 // finite state machine
-use fsm::{fsm_run, Event};
+use fsm::{fsm_run, register_tx, Event};
 
 /// ThreadPool Structure
 pub struct ThreadPool {
     workers: Vec<Worker>,
+    #[cfg(target_os = "linux")]
+    listeners: Vec<Listener>,
+    next_id: usize,
 }
 
 // ThreadPool Implementation
@@ -34,13 +41,93 @@ impl ThreadPool {
 
         // creating individual workers for every virtual routers
         for (id, vr) in vrouters.iter().enumerate() {
-            // acquire read lock on vr
-            let vro = vr.read().unwrap();
+            // read vr's socket (the worker needs write access to register its channel)
+            let fd = vr.read().unwrap().parameters.fd();
             // create new worker
-            workers.push(Worker::new(id, Arc::clone(&vr), vro.parameters.fd(), debug));
+            workers.push(Worker::new(id, Arc::clone(&vr), fd, debug));
+        }
+
+        ThreadPool {
+            workers,
+            #[cfg(target_os = "linux")]
+            listeners: Vec::new(),
+            next_id: vrouters.len(),
+        }
+    }
+    // add() method
+    // Create a worker and a listener thread for a new virtual router, and start it up
+    #[cfg(target_os = "linux")]
+    pub fn add(&mut self, vr: &Arc<RwLock<VirtualRouter>>, debug: &Verbose) {
+        // create new worker
+        let id = self.next_id;
+        self.next_id += 1;
+        let fd = vr.read().unwrap().parameters.fd();
+        self.workers
+            .push(Worker::new(id, Arc::clone(vr), fd, debug));
+
+        // send Startup event to the worker thread
+        match vr.read().unwrap().parameters.notification() {
+            Some(tx) => tx.lock().unwrap().send(Event::Startup).unwrap(),
+            None => eprintln!(
+                "error(thread): cannot send Startup event for thread {}, channel does not exist",
+                id
+            ),
         }
 
-        ThreadPool { workers }
+        // start receiving VRRP packets
+        self.listen(vr, debug);
+    }
+    // listen() method
+    // Spawn a listener thread for a virtual router
+    #[cfg(target_os = "linux")]
+    pub fn listen(&mut self, vr: &Arc<RwLock<VirtualRouter>>, debug: &Verbose) {
+        self.listeners.push(Listener::new(Arc::clone(vr), debug));
+    }
+    // remove() method
+    // Shut down a virtual router, then stop and remove its threads
+    #[cfg(target_os = "linux")]
+    pub fn remove(&mut self, vr: &Arc<RwLock<VirtualRouter>>, debug: &Verbose) {
+        // stop receiving VRRP packets first
+        if let Some(pos) = self.listeners.iter().position(|l| Arc::ptr_eq(&l.vr, vr)) {
+            let mut listener = self.listeners.remove(pos);
+            listener.stop.store(true, Ordering::Relaxed);
+            if let Some(thread) = listener.thread.take() {
+                if thread.join().is_err() {
+                    eprintln!("error(thread): listener thread panicked");
+                }
+            }
+        }
+
+        // send Shutdown/Terminate events to the worker (which may have exited already)
+        match vr.read().unwrap().parameters.notification() {
+            Some(tx) => {
+                let tx = tx.lock().unwrap();
+                if tx
+                    .send(Event::Shutdown)
+                    .and_then(|_| tx.send(Event::Terminate))
+                    .is_err()
+                {
+                    eprintln!("error(thread): cannot send Terminate event, worker has exited");
+                }
+            }
+            None => eprintln!("error(thread): cannot send Terminate event, channel does not exist"),
+        }
+
+        // waiting for the worker thread
+        if let Some(pos) = self.workers.iter().position(|w| Arc::ptr_eq(&w.vr, vr)) {
+            let mut worker = self.workers.remove(pos);
+            print_debug(
+                debug,
+                DEBUG_LEVEL_HIGH,
+                DEBUG_SRC_THREAD,
+                format!("waiting for thread {} to exit...", worker.id),
+            );
+            if let Some(thread) = worker.thread.take() {
+                if thread.join().is_err() {
+                    eprintln!("error(thread): worker thread {} panicked", worker.id);
+                }
+            }
+        }
     }
     // startup() method
     // Send startup event to every worker threads
@@ -122,6 +209,7 @@ impl<F: FnOnce()> FnBox for F {
 /// Worker Structure
 pub struct Worker {
     id: usize,
+    vr: Arc<RwLock<VirtualRouter>>,
     // we wrap thread::JoinHandle in a Option<T> so we can
     // consume the thread later when calling .join().
     thread: Option<thread::JoinHandle<()>>,
@@ -136,6 +224,17 @@ impl Worker {
         let receiver = Arc::new(Mutex::new(receiver));
         let sender = Arc::new(Mutex::new(sender));
 
+        // print debugging information
+        print_debug(
+            debug,
+            DEBUG_LEVEL_HIGH,
+            DEBUG_SRC_FSM,
+            format!("registering notification sender channel for thread {}", id),
+        );
+        // register notification sender channel before the thread starts,
+        // so events can be sent to the worker right away
+        register_tx(&vr, &sender, id, debug);
+
         // clone VR for worker thread
         let worker_vr = Arc::clone(&vr);
 
@@ -160,7 +259,43 @@ impl Worker {
 
         Worker {
             id,
+            vr,
             thread: Some(worker_thread),
         }
     }
 }
+
+/// Listener Structure
+#[cfg(target_os = "linux")]
+struct Listener {
+    vr: Arc<RwLock<VirtualRouter>>,
+    stop: Arc<AtomicBool>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+// Listener Implementation
+#[cfg(target_os = "linux")]
+impl Listener {
+    // new() method
+    fn new(vr: Arc<RwLock<VirtualRouter>>, debug: &Verbose) -> Listener {
+        let stop = Arc::new(AtomicBool::new(false));
+
+        // clone VR and stop flag for listener thread
+        let listener_vr = Arc::clone(&vr);
+        let listener_stop = Arc::clone(&stop);
+
+        // clone debug
+        let debug = debug.clone();
+
+        // create listener thread
+        let listener_thread = thread::spawn(move || {
+            recv_vrrp_pkts(listener_vr, listener_stop, &debug);
+        });
+
+        Listener {
+            vr,
+            stop,
+            thread: Some(listener_thread),
+        }
+    }
+}
@@ -37,9 +37,11 @@ pub fn start_timers(
     // clone debug
     let debug = debug.clone();
 
-    // set duration from vr's timer
-    let master_down = Duration::from_secs(vr0.timers.master_down() as u64);
-    let advert = Duration::from_secs(vr0.timers.advert() as u64);
+    // set duration from vr's parameters, as timers may be cancelled
+    let master_down = Duration::from_millis((vr0.parameters.master_down() * 1000.0) as u64);
+    let advert = Duration::from_secs(vr0.parameters.adverint() as u64);
+    // timers generation these intervals belong to
+    let epoch = vr0.timers.epoch();
 
     // drop the lock as we don't need read access to vr anymore
     drop(vr0);
@@ -56,7 +58,7 @@ pub fn start_timers(
     // 0x1, no ADVERTISEMENT has been received (since) and the master is signaled
     // down to the approriate vr's thread, this timer share the 'tx' channel with.
     let master_down_int = Interval::new_interval(master_down)
-        .take_while(move |_| future::ok(is_master_down_disabled(&vr1, &debug)))
+        .take_while(move |_| future::ok(is_master_down_disabled(&vr1, epoch, &debug)))
         .for_each(move |_| {
             print_debug(
                 &debug,
@@ -116,7 +118,7 @@ pub fn start_timers(
     // which then trigger an ADVERTISEMENT message in some finite state machine states.
     let advert_int = Interval::new(Instant::now() + advert, advert)
         // must return true to activate the interval timer
-        .take_while(move |_| future::ok(is_advert_disabled(&vr3, &debug)))
+        .take_while(move |_| future::ok(is_advert_disabled(&vr3, epoch, &debug)))
         .for_each(move |_| {
             // print debugging information
             print_debug(
@@ -150,10 +152,13 @@ pub fn start_timers(
 }
 
 // is_master_down_disabled() function
-/// return boolean false is the master_down interval is zero or lower
-fn is_master_down_disabled(vr: &Arc<RwLock<VirtualRouter>>, debug: &Verbose) -> bool {
+/// return boolean false is the master_down interval is zero or lower,
+/// or if the timers have been restarted since
+fn is_master_down_disabled(vr: &Arc<RwLock<VirtualRouter>>, epoch: u32, debug: &Verbose) -> bool {
     let vr = vr.read().unwrap();
-    if vr.timers.master_down() > 0.0 {
+    if vr.timers.epoch() != epoch {
+        false
+    } else if vr.timers.master_down() > 0.0 {
         true
     } else {
         // print debugging information
@@ -169,10 +174,12 @@ fn is_master_down_disabled(vr: &Arc<RwLock<VirtualRouter>>, debug: &Verbose) ->
 
 // is_advert_disabled() function
 /// return boolean true is the advertisement interval vr's timer
-/// is higher than zero
-fn is_advert_disabled(vr: &Arc<RwLock<VirtualRouter>>, debug: &Verbose) -> bool {
+/// is higher than zero and the timers have not been restarted since
+fn is_advert_disabled(vr: &Arc<RwLock<VirtualRouter>>, epoch: u32, debug: &Verbose) -> bool {
     let vr = vr.read().unwrap();
-    if vr.timers.advert() > 0 {
+    if vr.timers.epoch() != epoch {
+        false
+    } else if vr.timers.advert() > 0 {
         true
     } else if vr.timers.advert() == 255 {
         // special non-zero value to disable timer
@@ -2,30 +2,23 @@
 //!
 use crate::*;
 
-// libc
-#[cfg(target_os = "freebsd")]
-use crate::os::freebsd::libc::raw_sendto;
-#[cfg(target_os = "linux")]
-use crate::os::linux::libc::raw_sendto;
-#[cfg(target_os = "freebsd")]
-use libc::{c_void, write};
-#[cfg(target_os = "linux")]
-use libc::{sendto, AF_PACKET};
-
 // debugging
 use crate::debug::Verbose;
 
-// packets related function
-use crate::packets::as_u8_slice;
+// VRRP packets codec
+use crate::codec::{AdvertFrame, EthernetHeader, Ipv4Header, VrrpAdvert};
 
 // operating system drivers
-use crate::os::drivers::Operation;
+use crate::os::drivers::{MacvlanMode, NetDriver, Operation, VmacModes};
+
+// peers
+use crate::peers::PeerTable;
+
+// transitions history
+use crate::history::{self, History};
 
-// address resolution protocol
-#[cfg(target_os = "freebsd")]
-use crate::os::freebsd::arp;
-#[cfg(target_os = "linux")]
-use crate::os::linux::arp;
+// std
+use std::net::Ipv4Addr;
 
 /// Virtual Router Structure
 #[derive(Debug)]
@@ -52,7 +45,7 @@ impl VirtualRouter {
         auth_secret: Option<String>,
         protocols: Arc<Mutex<Protocols>>,
         debug: &Verbose,
-        netdrv: NetDrivers,
+        driver: Arc<dyn NetDriver>,
         iftype: IfTypes,
         vif_name: String,
         fd: i32,
@@ -145,7 +138,7 @@ impl VirtualRouter {
                 [0; 8],
                 auth_secret,
                 protocols,
-                netdrv,
+                driver,
                 iftype,
                 vif_name,
                 0,
@@ -186,37 +179,83 @@ impl VirtualRouter {
             fsm::States::Init => "Init",
         }
     }
-    // send_advertisement() method
-    /// Send a VRRP ADVERTISEMENT message
-    pub fn send_advertisement(&self, fd: i32, debug: &Verbose) -> io::Result<()> {
-        // generate initial VRRP ADVERTISEMENT frame/packet
-        let advert = VRRPpkt::gen_advert(self);
-
-        // build static frame slice
-        let static_frame = unsafe { as_u8_slice(&advert) };
-
-        // initialize frame_vec vector and push static frame into it
-        let mut frame: Vec<u8> = Vec::new();
-        for b in static_frame {
-            frame.push(*b);
+    // src_mac() method
+    /// return the source MAC address of the frames sent by the virtual router,
+    /// an ipvlan interface keeps the MAC address of its master interface
+    pub fn src_mac(&self) -> [u8; 6] {
+        match (self.parameters.iftype(), self.parameters.vmac_mode()) {
+            (IfTypes::ipvlan, _) | (IfTypes::ether, VmacModes::none) => self.parameters.ifmac(),
+            _ => {
+                let mut vmac = ETHER_VRRP_V2_SRC_MAC;
+                vmac[5] = self.parameters.vrid();
+                vmac
+            }
         }
-
-        // set and push the VIP to the ipaddrs
-        let vip = self.parameters.vip();
-        for i in 0..4 {
-            frame.push(vip[i]);
+    }
+    // arp_mac() method
+    /// return the MAC address announced by the gratuitous ARP requests,
+    /// the kernel only accepts IP packets sent to the interface's primary
+    /// MAC address, so a secondary virtual MAC address is never announced
+    pub fn arp_mac(&self) -> [u8; 6] {
+        match (self.parameters.iftype(), self.parameters.vmac_mode()) {
+            (IfTypes::ether, VmacModes::secondary) => self.parameters.ifmac(),
+            _ => self.src_mac(),
         }
+    }
+    // advert() method
+    /// build the VRRPv2 ADVERTISEMENT frame of the virtual router
+    pub fn advert(&self) -> AdvertFrame {
+        // generate source MAC address from VID
+        let src_mac = self.src_mac();
 
-        // check if rfc3768 compatibility flag is true
+        // the VIP, and the local IP addresses unless in rfc3768 compatibility mode
+        let mut ipaddrs = vec![self.parameters.vip()];
         if !self.parameters.rfc3768() {
-            // extend the frame with the variable-length list of local IP addresses
-            for addr in self.parameters.ipaddrs() {
-                for i in 0..4 {
-                    frame.push(addr[i]);
-                }
-            }
+            ipaddrs.extend_from_slice(self.parameters.ipaddrs());
         }
 
+        let mut advert = AdvertFrame {
+            // dst multicast MAC address for 224.0.0.18
+            eth: EthernetHeader {
+                dst_mac: ETHER_VRRP_V2_DST_MAC,
+                src_mac,
+                vlans: Vec::new(),
+                ethertype: ETHER_P_IP,
+            },
+            // sourced from the interface 'primary' ip address, with a TTL of 255
+            ip: Ipv4Header {
+                tos: IP_DSCP_CS6,
+                ident: 0,
+                flags: 0,
+                ttl: IP_TTL_VRRP_MINTTL,
+                proto: IP_UPPER_PROTO_VRRP,
+                checksum: 0,
+                src: self.parameters.primary_ip(),
+                dst: VRRP_V2_IP_MCAST_DST,
+                options: Vec::new(),
+            },
+            vrrp: VrrpAdvert {
+                version: VRRP_V2_ADVERT_VERSION_TYPE,
+                vrid: self.parameters.vrid(),
+                prio: self.parameters.prio(),
+                authtype: self.parameters.authtype(),
+                adverint: self.parameters.adverint(),
+                checksum: 0,
+                ipaddrs,
+                authdata: [0; 8],
+            },
+        };
+        // add authentication data
+        advert.vrrp.sign(self.parameters.authsecret());
+        advert
+    }
+
+    // send_advertisement() method
+    /// Send a VRRP ADVERTISEMENT message
+    pub fn send_advertisement(&self, fd: i32, debug: &Verbose) -> io::Result<()> {
+        // encode the ADVERTISEMENT frame, the lengths and checksums are computed
+        let mut frame = self.advert().encode();
+
         // print debugging information
         print_debug(
             debug,
@@ -229,171 +268,77 @@ impl VirtualRouter {
             ),
         );
 
-        // add authentication data
-        match self.parameters.authtype() {
-            // AUTH_TYPE_P0 (PROPRIETARY-TRUNCATED-8B-SHA256)
-            // AUTH_TYPE_P1 (PROPRIETARY-XOF-8B-SHAKE256)
-            AUTH_TYPE_P0 | AUTH_TYPE_P1 => {
-                for b in gen_auth_data(
-                    self.parameters.authtype(),
-                    self.parameters.authsecret(),
-                    Option::Some(&frame[VRRP_V2_FRAME_OFFSET..]),
-                ) {
-                    frame.push(b);
-                }
-            }
-            // all remaining types
-            _ => {
-                for b in gen_auth_data(
-                    self.parameters.authtype(),
-                    self.parameters.authsecret(),
-                    Option::None,
-                ) {
-                    frame.push(b);
-                }
-            }
+        // in dry-run mode, the ADVERTISEMENT is never sent
+        if self.parameters.dry_run() {
+            print_debug(
+                debug,
+                DEBUG_LEVEL_MEDIUM,
+                DEBUG_SRC_DRYRUN,
+                format!(
+                    "not sending ADVERTISEMENT out if {} ({} bytes)",
+                    self.parameters.interface(),
+                    frame.len() - ETHER_FRAME_SIZE
+                ),
+            );
+            return Ok(());
         }
 
-        // generate VRRP checksum (vrrp checksum is at offset 34+6 bytes)
-        let vrrp_checksum =
-            checksums::one_complement_sum(&frame[VRRP_V2_FRAME_OFFSET..], Option::Some(6));
-        // print debugging information
-        print_debug(
-            debug,
-            DEBUG_LEVEL_EXTENSIVE,
-            DEBUG_SRC_PACKET,
-            format!("VRRP checksum is {:#X}", vrrp_checksum),
-        );
-        // set vrrp's checksum field
-        frame[VRRP_V2_FRAME_OFFSET + 6] = vrrp_checksum.to_be() as u8;
-        frame[VRRP_V2_FRAME_OFFSET + 6 + 1] = vrrp_checksum as u8;
-
-        // generate IP checksum (ip checksum is at offset 14+10 bytes)
-        let ip_checksum =
-            checksums::one_complement_sum(&frame[IP_FRAME_OFFSET..], Option::Some(10));
-        // print debugging information
-        print_debug(
-            debug,
-            DEBUG_LEVEL_EXTENSIVE,
-            DEBUG_SRC_PACKET,
-            format!("IP checksum is {:#X}", ip_checksum),
-        );
-
-        // set ip checksum field (offset 34)
-        frame[IP_FRAME_OFFSET + 10] = ip_checksum.to_be() as u8;
-        frame[IP_FRAME_OFFSET + 10 + 1] = ip_checksum as u8;
-
-        // print debugging information
-        print_debug(
-            debug,
-            DEBUG_LEVEL_EXTENSIVE,
-            DEBUG_SRC_PACKET,
-            format!(
-                "final ADVERTISEMENT frame is {} bytes long",
-                frame.len() - ETHER_FRAME_SIZE
-            ),
-        );
-        // set length of ip packet (offset 16)
-        // the length of ip header + data = frame size - ethernet frame
-        let frame_size = frame.len() - ETHER_FRAME_SIZE;
-        frame[IP_FRAME_OFFSET + 2] = frame_size.to_be() as u8;
-        frame[IP_FRAME_OFFSET + 2 + 1] = frame_size as u8;
-
         // sending raw ethernet frame
         let ifindex = self.parameters.ifindex();
-        let res = raw_sendto(fd, ifindex, &mut frame, &debug);
-
-        // return above call result
-        return res;
+        self.parameters
+            .driver()
+            .send_frame(fd, ifindex, &mut frame, debug)
     }
 
     // broadcast_gratuitious_arp() function
     /// Broadcast Gratuitious ARP requests
     pub fn broadcast_gratuitious_arp(&self, fd: i32, debug: &Verbose) -> io::Result<()> {
-        // suppress warnings about usued variables on linux
-        #[cfg(target_os = "linux")]
-        let _d = debug;
+        if self.parameters.dry_run() {
+            self.skip_op(
+                "arp",
+                "broadcast",
+                format!(
+                    "gratuitous ARP for {} on {}",
+                    Ipv4Addr::from(self.parameters.vip()),
+                    self.parameters.interface()
+                ),
+                debug,
+            );
+            return Ok(());
+        }
 
-        // build gratuitious ARP request
-        let mut arpframe = arp::ARPframe {
+        // build gratuitious ARP request, sourced from the virtual router MAC address
+        let vmac = self.arp_mac();
+        let mut frame = Vec::with_capacity(ETHER_FRAME_SIZE + 28);
+        EthernetHeader {
             dst_mac: ETHER_ARP_DST_MAC,
-            src_mac: ETHER_VRRP_V2_SRC_MAC,
-            ethertype: ETHER_P_ARP.to_be(),
-
-            hardware_type: ARP_HW_TYPE.to_be(),
-            protocol_type: ETHER_P_IP.to_be(),
-            hw_addr_len: 6,
-            proto_addr_len: 4,
-            opcode: ARP_OP_REQUEST.to_be(),
-            sender_hw_addr: ETHER_VRRP_V2_SRC_MAC,
-            sender_proto_addr: self.parameters.vip(),
-            target_hw_addr: [0xff, 0xff, 0xff, 0xff, 0xff, 0xff],
-            target_proto_addr: [255, 255, 255, 255],
-        };
-
-        // --- Linux specific handling
-        #[cfg(target_os = "linux")]
-        {
-            // set VRID on source MAC addresses
-            arpframe.src_mac[5] = self.parameters.vrid();
-            arpframe.sender_hw_addr[5] = self.parameters.vrid();
-
-            // sockaddr_ll (man 7 packet)
-            let mut sa = sockaddr_ll {
-                sll_family: AF_PACKET as u16,
-                sll_protocol: ETHER_P_ARP.to_be(),
-                sll_ifindex: self.parameters.ifindex(),
-                sll_hatype: 0,
-                sll_pkttype: 0,
-                sll_halen: 0,
-                sll_addr: [0; 8],
-            };
-
-            unsafe {
-                let ptr_sockaddr = mem::transmute::<*mut sockaddr_ll, *mut sockaddr>(&mut sa);
-                match sendto(
-                    fd,
-                    &mut arpframe as *mut _ as *const c_void,
-                    mem::size_of_val(&arpframe),
-                    0,
-                    ptr_sockaddr,
-                    mem::size_of_val(&sa) as u32,
-                ) {
-                    -1 => Err(io::Error::last_os_error()),
-                    _ => Ok(()),
-                }
-            }
+            src_mac: vmac,
+            vlans: Vec::new(),
+            ethertype: ETHER_P_ARP,
         }
-        // END Linux specific handling
+        .encode(&mut frame);
+        frame.extend_from_slice(&ARP_HW_TYPE.to_be_bytes());
+        frame.extend_from_slice(&ETHER_P_IP.to_be_bytes());
+        frame.extend_from_slice(&[6, 4]);
+        frame.extend_from_slice(&ARP_OP_REQUEST.to_be_bytes());
+        frame.extend_from_slice(&vmac);
+        frame.extend_from_slice(&self.parameters.vip());
+        frame.extend_from_slice(&[0xff; 6]);
+        frame.extend_from_slice(&[0xff; 4]);
 
-        // --- FreeBSD specific handling
-        #[cfg(target_os = "freebsd")]
-        {
-            // set VRID on source MAC addresses
-            arpframe.src_mac[5] = self.parameters.vrid();
-            arpframe.sender_hw_addr[5] = self.parameters.vrid();
-
-            unsafe {
-                // unsafe call to write()
-                match write(
-                    fd,
-                    &mut arpframe as *mut _ as *const c_void,
-                    mem::size_of_val(&arpframe),
-                ) {
-                    -1 => Err(io::Error::last_os_error()),
-                    _ => {
-                        print_debug(
-                            debug,
-                            DEBUG_LEVEL_MEDIUM,
-                            DEBUG_SRC_ARP,
-                            format!("VRRPv2 frame successfully sent on BPF device, fd {}", fd),
-                        );
-                        Ok(())
-                    }
-                }
-            }
-        }
-        // END FreeBSD specific handling
+        // print debugging information
+        print_debug(
+            debug,
+            DEBUG_LEVEL_MEDIUM,
+            DEBUG_SRC_ARP,
+            format!(
+                "broadcasting gratuitous ARP request out if {}",
+                self.parameters.interface()
+            ),
+        );
+        self.parameters
+            .driver()
+            .send_arp(fd, self.parameters.ifindex(), &mut frame, debug)
     }
 
     // set_ip_addresses() method
@@ -426,7 +371,7 @@ impl VirtualRouter {
         }
 
         // construct interface name
-        let ifname = CString::new(self.parameters.interface().as_bytes() as &[u8]).unwrap();
+        let ifname = self.parameters.interface();
 
         // set the last ip address from vector
         let idx = addrs.len() - 1;
@@ -437,7 +382,7 @@ impl VirtualRouter {
             DEBUG_LEVEL_HIGH,
             DEBUG_SRC_IP,
             format!(
-                "setting IP address {}.{}.{}.{} netmask {}.{}.{}.{} on {:?}",
+                "setting IP address {}.{}.{}.{} netmask {}.{}.{}.{} on {}",
                 addrs[idx][0],
                 addrs[idx][1],
                 addrs[idx][2],
@@ -450,78 +395,56 @@ impl VirtualRouter {
             ),
         );
 
-        // --- Linux specific interface tyoe handling
-        #[cfg(target_os = "linux")]
-        {
-            let ifindex = match self.parameters.iftype() {
-                IfTypes::macvlan => self.parameters.vifidx(),
-                _ => self.parameters.ifindex(),
-            };
-
-            // set ifindex on physical or macvlan interface
-            // set virtual ip address according to the network driver in use
-            match self.parameters.netdrv() {
-                NetDrivers::ioctl => {
-                    if let Err(e) =
-                        os::linux::netdev::set_ip_address(fd, &ifname, addrs[idx], netmasks[idx])
-                    {
-                        eprintln!(
-                            "error(ip): error while assigning IP address on interface {:?}: {}",
-                            &ifname, e
-                        );
-                    }
-                }
-                NetDrivers::libnl => {
-                    print_debug(
-                        debug,
-                        DEBUG_LEVEL_HIGH,
-                        DEBUG_SRC_IP,
-                        format!(
-                    "setting up IP address on interface {:?} (ifindex: {}) using netlink (libnl)",
-                    &ifname, ifindex
+        if self.parameters.dry_run() {
+            self.skip_op(
+                "vip",
+                op.action(),
+                format!(
+                    "set {}/{} on {}",
+                    Ipv4Addr::from(addrs[idx]),
+                    Ipv4Addr::from(netmasks[idx]),
+                    self.parameters.interface()
                 ),
-                    );
-                    if let Err(e) = os::linux::libnl::set_ip_address(
-                        ifindex,
-                        &ifname,
-                        addrs[idx],
-                        netmasks[idx],
-                        Operation::Add,
-                        debug,
-                    ) {
-                        eprintln!(
-                            "error(ip): error while assigning IP address on interface {:?}: {}",
-                            &ifname, e
-                        );
-                    }
-                }
-            }
+                debug,
+            );
+            return;
         }
-        // END Linux specific interface type handling
 
-        // FreeBSD specific interface type handling
-        #[cfg(target_os = "freebsd")]
-        {
-            print_debug(
-                debug,
-                DEBUG_LEVEL_HIGH,
-                DEBUG_SRC_IP,
-                format!("setting ip addresss on interface {:?}, fd {}", ifname, fd),
+        // set ifindex on physical or virtual interface
+        let ifindex = match self.parameters.iftype() {
+            IfTypes::macvlan | IfTypes::ipvlan => self.parameters.vifidx(),
+            _ => self.parameters.ifindex(),
+        };
+
+        // print debugging information
+        print_debug(
+            debug,
+            DEBUG_LEVEL_HIGH,
+            DEBUG_SRC_IP,
+            format!(
+                "setting up IP address on interface {} (ifindex: {}) using {:?}",
+                ifname,
+                ifindex,
+                self.parameters.netdrv()
+            ),
+        );
+        // set virtual ip address according to the network driver in use
+        let r = self.parameters.driver().set_ip_address(
+            fd,
+            ifindex,
+            &ifname,
+            addrs[idx],
+            netmasks[idx],
+            &Operation::Add,
+            debug,
+        );
+        self.record_op("vip", op.action(), &r);
+        if let Err(e) = r {
+            eprintln!(
+                "error(ip): error while assigning IP address on interface {}: {}",
+                ifname, e
             );
-            if let Err(e) = os::freebsd::netinet::set_ip_address(
-                fd,
-                &ifname,
-                addrs[idx],
-                netmasks[idx],
-                Operation::Add,
-            ) {
-                eprintln!(
-                    "error(ip): error while setting IP address on interface {:?}: {}",
-                    ifname, e
-                );
-            }
         }
-        // END FreeBSD specific interface type handling
     }
 
     // delete_ip_addresses() method
@@ -535,7 +458,7 @@ impl VirtualRouter {
         netmasks.push(self.parameters.ipmasks()[0]);
 
         // construct interface name
-        let ifname = CString::new(self.parameters.interface().as_bytes() as &[u8]).unwrap();
+        let ifname = self.parameters.interface();
 
         // print debugging information
         let vip = self.parameters.vip();
@@ -544,7 +467,7 @@ impl VirtualRouter {
             DEBUG_LEVEL_HIGH,
             DEBUG_SRC_IP,
             format!(
-                "removing IP address {}.{}.{}.{} netmask {}.{}.{}.{} on {:?}",
+                "removing IP address {}.{}.{}.{} netmask {}.{}.{}.{} on {}",
                 vip[0],
                 vip[1],
                 vip[2],
@@ -557,109 +480,170 @@ impl VirtualRouter {
             ),
         );
 
-        // --- Linux specific interface tyoe handling
-        #[cfg(target_os = "linux")]
-        {
-            // workaround compilation warning
-            let _fd = fd;
-            // delete virtual ip address according to the network driver in use
-            match self.parameters.netdrv() {
-                NetDrivers::libnl => {
-                    print_debug(
-                        debug,
-                        DEBUG_LEVEL_HIGH,
-                        DEBUG_SRC_IP,
-                        format!(
-                        "removing IP address on interface {:?} (ifindex: {}) using netlink (libnl)",
-                        &ifname,
-                        self.parameters.ifindex()
-                    ),
-                    );
-                    if let Err(e) = os::linux::libnl::set_ip_address(
-                        self.parameters.ifindex(),
-                        &ifname,
-                        self.parameters.vip(),
-                        netmasks[0],
-                        Operation::Rem,
-                        debug,
-                    ) {
-                        eprintln!(
-                            "error(ip): error while removing IP address on interface {:?}: {}",
-                            &ifname, e
-                        );
-                    }
-                }
-                _ => {}
-            }
+        if self.parameters.dry_run() {
+            self.skip_op(
+                "vip",
+                "remove",
+                format!(
+                    "remove {}/{} from {}",
+                    Ipv4Addr::from(vip),
+                    Ipv4Addr::from(netmasks[0]),
+                    self.parameters.interface()
+                ),
+                debug,
+            );
+            return;
         }
-        // END Linux specific interface type handling
 
-        // FreeBSD specific interface type handling
-        #[cfg(target_os = "freebsd")]
-        {
-            print_debug(
-                debug,
-                DEBUG_LEVEL_HIGH,
-                DEBUG_SRC_IP,
-                format!("setting ip addresss on interface {:?}, fd {}", ifname, fd),
+        // delete virtual ip address according to the network driver in use
+        let r = self.parameters.driver().set_ip_address(
+            fd,
+            self.parameters.ifindex(),
+            &ifname,
+            vip,
+            netmasks[0],
+            &Operation::Rem,
+            debug,
+        );
+        self.record_op("vip", "remove", &r);
+        if let Err(e) = r {
+            eprintln!(
+                "error(ip): error while removing IP address on interface {}: {}",
+                ifname, e
             );
-            if let Err(e) = os::freebsd::netinet::set_ip_address(
-                fd,
-                &ifname,
-                self.parameters.vip,
-                netmasks[0],
-                Operation::Rem,
-            ) {
-                eprintln!(
-                    "error(ip): error while setting IP address on interface {:?}: {}",
-                    ifname, e
-                );
-            }
         }
-        // END FreeBSD specific interface type handling
     }
 
     // get_mac_addresses() method
     /// get Ethernet MAC address from vr's interface
     #[cfg(target_os = "linux")]
     pub fn get_mac_addresses(&self, fd: i32, debug: &Verbose) -> [u8; 6] {
-        // --- Linux specific interface tyoe handling
-        #[cfg(target_os = "linux")]
-        {
-            // construct interface name
-            let ifname = CString::new(self.parameters.interface().as_bytes() as &[u8]).unwrap();
-            // get mac address of interface
-            match os::linux::netdev::get_mac_addr(fd, &ifname, debug) {
-                Ok(mac) => mac,
-                Err(e) => {
-                    eprintln!(
-                        "error(mac): error while getting MAC address on interface {:?}: {}",
-                        ifname, e
-                    );
-                    [0, 0, 0, 0, 0, 0]
-                }
+        // get mac address of interface
+        let ifname = self.parameters.interface();
+        match self.parameters.driver().get_mac_address(fd, &ifname, debug) {
+            Ok(mac) => mac,
+            Err(e) => {
+                eprintln!(
+                    "error(mac): error while getting MAC address on interface {}: {}",
+                    ifname, e
+                );
+                [0, 0, 0, 0, 0, 0]
             }
         }
-        // END Linux specific interface type handling
     }
 
     // set_mac_addresses() method
     /// Set Ethernet MAC address on vr's interface
     #[cfg(target_os = "linux")]
     pub fn set_mac_addresses(&self, fd: i32, mac: [u8; 6], debug: &Verbose) {
-        // construct interface name
-        let ifname = CString::new(self.parameters.interface().as_bytes() as &[u8]).unwrap();
+        if self.parameters.dry_run() {
+            self.skip_op(
+                "mac",
+                "set",
+                format!(
+                    "set {:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x} on {}",
+                    mac[0],
+                    mac[1],
+                    mac[2],
+                    mac[3],
+                    mac[4],
+                    mac[5],
+                    self.parameters.interface()
+                ),
+                debug,
+            );
+            return;
+        }
 
-        // --- Linux specific interface tyoe handling
-        #[cfg(target_os = "linux")]
-        {
-            // set mac address
-            match os::linux::netdev::set_mac_addr(fd, &ifname, mac, debug) {
-                Err(e) => eprintln!("error(mac): error while setting mac address: {}", e),
-                _ => {}
+        // set mac address
+        let r =
+            self.parameters
+                .driver()
+                .set_mac_address(fd, &self.parameters.interface(), mac, debug);
+        self.record_op("mac", "set", &r);
+        match r {
+            Err(e) => eprintln!("error(mac): error while setting mac address: {}", e),
+            _ => {}
+        }
+    }
+
+    // set_promiscuous() method
+    /// set an interface of the virtual router in or out of promiscuous mode
+    pub fn set_promiscuous(
+        &self,
+        fd: i32,
+        ifname: &str,
+        on: bool,
+        debug: &Verbose,
+    ) -> io::Result<()> {
+        let action = if on { "set" } else { "unset" };
+
+        // the packet socket receives the VRRP multicast frames anyway
+        if self.parameters.dry_run() {
+            self.skip_op(
+                "promisc",
+                action,
+                format!("{} promisc on {}", action, ifname),
+                debug,
+            );
+            return Ok(());
+        }
+
+        let r = self.parameters.driver().set_promiscuous(fd, ifname, on);
+        self.record_op("promisc", action, &r);
+        r
+    }
+
+    // set_vmac() method
+    /// set the virtual router MAC address on vr's interface, either replacing
+    /// its MAC address or as a secondary unicast address, or restore it
+    #[cfg(target_os = "linux")]
+    pub fn set_vmac(&self, fd: i32, op: Operation, debug: &Verbose) {
+        let mut vmac = ETHER_VRRP_V2_SRC_MAC;
+        vmac[5] = self.parameters.vrid();
+
+        match self.parameters.vmac_mode() {
+            VmacModes::primary => match op {
+                Operation::Add => self.set_mac_addresses(fd, vmac, debug),
+                Operation::Rem => self.set_mac_addresses(fd, self.parameters.ifmac(), debug),
+            },
+            VmacModes::secondary => {
+                if self.parameters.dry_run() {
+                    self.skip_op(
+                        "mac",
+                        op.action(),
+                        format!(
+                            "{} secondary {:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x} on {}",
+                            op.action(),
+                            vmac[0],
+                            vmac[1],
+                            vmac[2],
+                            vmac[3],
+                            vmac[4],
+                            vmac[5],
+                            self.parameters.interface()
+                        ),
+                        debug,
+                    );
+                    return;
+                }
+                let r = self.parameters.driver().set_secondary_mac(
+                    self.parameters.ifindex(),
+                    vmac,
+                    &op,
+                    debug,
+                );
+                self.record_op("mac", op.action(), &r);
+                if let Err(e) = r {
+                    eprintln!(
+                        "error(mac): error while setting secondary mac address: {}",
+                        e
+                    );
+                }
             }
+            // the interface keeps its MAC address
+            VmacModes::none => {}
         }
-        // END Linux specific interface type handling
     }
 
     // set_ip_routes() method
@@ -671,7 +655,7 @@ impl VirtualRouter {
         let protocols = protocols.lock().unwrap();
 
         // construct interface name
-        let ifname = CString::new(self.parameters.interface().as_bytes() as &[u8]).unwrap();
+        let ifname = self.parameters.interface();
 
         // ensure routes are added or deleted only once per virtual router
         // routes may be added or removed multiple times across virtual routers
@@ -693,70 +677,47 @@ impl VirtualRouter {
             Some(r) => {
                 // for every static routes
                 for st in r {
-                    // --- Linux specific interface tyoe handling
-                    #[cfg(target_os = "linux")]
-                    {
-                        // add route acccording to the network driver in use
-                        match self.parameters.netdrv() {
-                            NetDrivers::ioctl => {
-                                print_debug(
-                                debug,
-                                DEBUG_LEVEL_HIGH,
-                                DEBUG_SRC_IP,
-                                format!(
-                                "setting up route on interface {:?} (ifindex: {}) using netlink (ioctl)",
-                                &ifname, self.parameters.ifindex()
-                            ),
-                            );
-                                if let Err(e) = os::linux::netdev::set_ip_route(
-                                    fd,
-                                    &self.parameters.interface(),
-                                    st.route(),
-                                    st.mask(),
-                                    st.nh(),
-                                    st.metric(),
-                                    st.mtu(),
-                                    &op,
-                                    debug,
-                                ) {
-                                    eprintln!(
-                                        "error(route): cannot add or delete route {:?}: {}",
-                                        st.route(),
-                                        e
-                                    );
-                                }
-                            }
-                            NetDrivers::libnl => {
-                                print_debug(
-                                debug,
-                                DEBUG_LEVEL_HIGH,
-                                DEBUG_SRC_IP,
-                                format!(
-                                "setting up route on interface {:?} (ifindex: {}) using netlink (libnl)",
-                                &ifname, self.parameters.ifindex()
+                    if self.parameters.dry_run() {
+                        self.skip_op(
+                            "route",
+                            op.action(),
+                            format!(
+                                "{} {}/{} via {} on {}",
+                                op.action(),
+                                Ipv4Addr::from(st.route()),
+                                Ipv4Addr::from(st.mask()),
+                                Ipv4Addr::from(st.nh()),
+                                self.parameters.interface()
                             ),
-                            );
-                                if let Err(e) = os::linux::libnl::set_ip_route(
-                                    fd,
-                                    &self.parameters.interface(),
-                                    st.route(),
-                                    st.mask(),
-                                    st.nh(),
-                                    st.metric(),
-                                    st.mtu(),
-                                    &op,
-                                    debug,
-                                ) {
-                                    eprintln!(
-                                        "error(route): cannot add or delete route {:?}: {}",
-                                        st.route(),
-                                        e
-                                    );
-                                }
-                            }
-                        }
+                            debug,
+                        );
+                        continue;
+                    }
+                    // print debugging information
+                    print_debug(
+                        debug,
+                        DEBUG_LEVEL_HIGH,
+                        DEBUG_SRC_IP,
+                        format!(
+                            "setting up route on interface {} (ifindex: {}) using {:?}",
+                            ifname,
+                            self.parameters.ifindex(),
+                            self.parameters.netdrv()
+                        ),
+                    );
+                    // add route acccording to the network driver in use
+                    let r = self
+                        .parameters
+                        .driver()
+                        .set_ip_route(fd, &ifname, st, &op, debug);
+                    self.record_op("route", op.action(), &r);
+                    if let Err(e) = r {
+                        eprintln!(
+                            "error(route): cannot add or delete route {:?}: {}",
+                            st.route(),
+                            e
+                        );
                     }
-                    // END Linux specific interface type handling
                 }
             }
             None => {}
@@ -769,36 +730,116 @@ impl VirtualRouter {
         }
     }
 
-    // setup_mac_vlan_link() method (Linux specific)
+    // record_op() method
+    /// record the outcome of an operation in the virtual router's history
+    fn record_op<E: std::fmt::Display>(
+        &self,
+        op: &'static str,
+        action: &'static str,
+        r: &Result<(), E>,
+    ) {
+        let r = r.as_ref().map(|_| ()).map_err(|e| e.to_string());
+        self.parameters.history().lock().unwrap().op(op, action, r);
+    }
+
+    // skip_op() method
+    /// log and record an operation which is not performed in dry-run mode
+    fn skip_op(&self, op: &'static str, action: &'static str, detail: String, debug: &Verbose) {
+        print_debug(
+            debug,
+            DEBUG_LEVEL_INFO,
+            DEBUG_SRC_DRYRUN,
+            format!(
+                "vrid {} on interface {}: skipping {} {} ({})",
+                self.parameters.vrid(),
+                self.parameters.interface(),
+                op,
+                action,
+                detail
+            ),
+        );
+        self.parameters
+            .history()
+            .lock()
+            .unwrap()
+            .skipped(op, action, detail);
+    }
+
+    // setup_virtual_link() method (Linux specific)
+    /// create or delete the macvlan or ipvlan interface of the virtual router,
+    /// return the ifindex and name of a created interface
     #[cfg(target_os = "linux")]
-    pub fn setup_macvlan_link(
+    pub fn setup_virtual_link(
         &self,
         vmac: [u8; 6],
         op: Operation,
         debug: &Verbose,
     ) -> Option<(i32, String)> {
+        let kind = match self.parameters.iftype() {
+            IfTypes::ipvlan => "ipvlan",
+            _ => "macvlan",
+        };
+
+        // the virtual router keeps running on the physical interface
+        if self.parameters.dry_run() {
+            self.skip_op(
+                kind,
+                op.action(),
+                format!(
+                    "{} {} on master {}",
+                    op.action(),
+                    self.parameters.vifname(),
+                    self.parameters.interface()
+                ),
+                debug,
+            );
+            return None;
+        }
+
         // print debugging information
         print_debug(
             debug,
             DEBUG_LEVEL_HIGH,
             DEBUG_SRC_MACVLAN,
             format!(
-                "setting up macvlan interface on master {:?} using libnl",
-                self.parameters.interface()
+                "setting up {} interface on master {:?} using {:?}",
+                kind,
+                self.parameters.interface(),
+                self.parameters.netdrv()
             ),
         );
 
-        // call to libnl setup_macvlan_link()
-        match os::linux::libnl::setup_macvlan_link(self, vmac, &op) {
-            // the macvlan interface has been added or deleted successfully
+        // create or delete the virtual interface
+        let driver = self.parameters.driver();
+        let r = match self.parameters.iftype() {
+            IfTypes::ipvlan => driver.setup_ipvlan_link(
+                self.parameters.ifindex(),
+                self.parameters.vifidx(),
+                &self.parameters.vifname(),
+                &op,
+                debug,
+            ),
+            _ => driver.setup_macvlan_link(
+                self.parameters.ifindex(),
+                self.parameters.vifidx(),
+                &self.parameters.vifname(),
+                vmac,
+                self.parameters.macvlan_mode(),
+                &op,
+                debug,
+            ),
+        };
+        self.record_op(kind, op.action(), &r);
+        match r {
+            // the virtual interface has been added or deleted successfully
             Ok(()) => {
                 // If added, return the ifindex and name of the virtual interface
                 match op {
                     Operation::Add => {
-                        // find new macvlan ifindex
-                        match os::linux::libc::c_ifnametoindex(&self.parameters.vifname()) {
+                        // find new virtual interface ifindex
+                        match driver.link_index(&self.parameters.vifname()) {
                             Ok(i) => {
-                                return Some((i as i32, self.parameters.vifname().clone()));
+                                return Some((i, self.parameters.vifname()));
                             }
                             Err(_e) => return None,
                         }
@@ -808,11 +849,11 @@ impl VirtualRouter {
                     }
                 }
             }
-            // catched an error while setting up the macvlan interface
+            // catched an error while setting up the virtual interface
             Err(e) => {
                 eprintln!(
-                "error(macvlan): cannot perform operation {:?} on macvlan interface (master if: {:?}): {}",
-                op, self.parameters.interface(), e
+                "error({}): cannot perform operation {:?} on {} interface (master if: {:?}): {}",
+                kind, op, kind, self.parameters.interface(), e
             );
                 None
             }
@@ -823,26 +864,36 @@ impl VirtualRouter {
 /// Virtual Router Parameters Structure
 #[derive(Debug)]
 pub struct Parameters {
-    vrid: u8,                    // Virtual Router Identifier (1-255)
-    interface: String,           // Interface where the virtual router is running
-    ifindex: i32,                // Interface ifindex
-    prio: u8,                    // Priority (0-255)
-    vip: [u8; 4],                // Virtual IP (not in RFC parameters list)
+    vrid: u8,                     // Virtual Router Identifier (1-255)
+    interface: String,            // Interface where the virtual router is running
+    ifindex: i32,                 // Interface ifindex
+    prio: u8,                     // Priority (0-255)
+    vip: [u8; 4],                 // Virtual IP (not in RFC parameters list)
     ipaddrs: Vec<[u8; 4]>, // One or more local IPv4 Addresse(s) associated with the virtual router
     ipmasks: Vec<[u8; 4]>, // IPv4 Netmask(s) of above IP addresses
     adverint: u8,          // Advertisement interval
     skew_time: f32,        // Time to skew Master_Down interval (second)
     master_down: f32,      // Time interval for Backup to declare Master Down
     preempt_mode: bool, // Control whether a higher-priority Backup router can preempt a lower-priority Master
+    maintenance: bool,  // Maintenance mode, the virtual router must not become Master
+    dry_run: bool,      // Observer mode, the host is never modified and no ADVERTISEMENT is sent
+    master: Option<[u8; 4]>, // Primary address of the master router (as last seen)
+    peers: Mutex<PeerTable>, // Routers seen advertising for the virtual router
+    history: Arc<Mutex<History>>, // State transitions history
     rfc3768: bool,      // RFC2338 compatibility flag
+    rfc3768_cfg: bool,  // Configured RFC2338 compatibility flag
     auth_type: u8,      // Authentication type being used
     auth_data: [u8; 8], // Autentication data (type specific)
     auth_secret: Option<String>, // Authentication secret
     notification: Option<Arc<Mutex<mpsc::Sender<fsm::Event>>>>, // Notification channel
     protocols: Arc<Mutex<Protocols>>, // Internal protocols information
     ifmac: [u8; 6],     // Interface Ethernet MAC address
-    netdrv: NetDrivers, // Network driver
+    driver: Arc<dyn NetDriver>, // Network driver
     iftype: IfTypes,    // Interfaces type
+    macvlan_mode: MacvlanMode, // Mode of the macvlan interface
+    vmac_mode: VmacModes, // How the virtual router MAC address is set
+    vlan_tag: Option<u16>, // VLAN id of the frames received on the parent interface
+    vlan_owned: bool,   // VLAN sub-interface created by the virtual router
     vif_name: String,   // Virtual interface name (or physical when saved)
     vif_idx: i32,       // Virtual interface ifindex
     fd: i32,            // Raw socket or BPF file descriptor
@@ -871,7 +922,7 @@ impl Parameters {
         auth_data: [u8; 8],
         auth_secret: Option<String>,
         protocols: Arc<Mutex<Protocols>>,
-        netdrv: NetDrivers,
+        driver: Arc<dyn NetDriver>,
         iftype: IfTypes,
         vif_name: String,
         vif_idx: i32,
@@ -880,6 +931,8 @@ impl Parameters {
         capi_tx: Option<Sender<FSMQueryResult>>,
         capi_rx: Option<Receiver<FSMQueryResult>>,
     ) -> Parameters {
+        // the transitions history is kept for the crash dump
+        let history = history::register(vrid, &interface);
         Parameters {
             vrid,
             interface,
@@ -892,15 +945,25 @@ impl Parameters {
             skew_time,
             master_down,
             preempt_mode,
-            rfc3768,
+            maintenance: false,
+            dry_run: false,
+            master: None,
+            peers: Mutex::new(PeerTable::new()),
+            history,
+            rfc3768: rfc3768 || forces_rfc3768(auth_type),
+            rfc3768_cfg: rfc3768,
             auth_type,
             auth_data,
             auth_secret,
             notification: Option::None,
             protocols,
             ifmac: [0, 0, 0, 0, 0, 0],
-            netdrv,
+            driver,
             iftype,
+            macvlan_mode: MacvlanMode::bridge,
+            vmac_mode: VmacModes::primary,
+            vlan_tag: None,
+            vlan_owned: false,
             vif_name,
             vif_idx,
             fd,
@@ -926,6 +989,11 @@ impl Parameters {
     pub fn ifindex(&self) -> i32 {
         self.ifindex
     }
+    // set_ifindex() setter
+    #[cfg(target_os = "linux")]
+    pub fn set_ifindex(&mut self, ifindex: i32) {
+        self.ifindex = ifindex;
+    }
     // prio() getter
     pub fn prio(&self) -> u8 {
         self.prio
@@ -950,6 +1018,17 @@ impl Parameters {
     pub fn adverint(&self) -> u8 {
         self.adverint
     }
+    // set_adverint() setter
+    pub fn set_adverint(&mut self, adverint: u8) {
+        self.adverint = adverint;
+    }
+    // update_master_down() method
+    // recompute skew_time and master_down after a priority or interval change
+    pub fn update_master_down(&mut self) {
+        // calculate skew_time according to RFC3768 6.1
+        self.skew_time = (256.0 - self.prio as f32) / 256.0;
+        self.master_down = (3.0 * self.adverint as f32) + self.skew_time;
+    }
     // skewtime() getter
     pub fn skewtime(&self) -> f32 {
         self.skew_time
@@ -962,6 +1041,42 @@ impl Parameters {
     pub fn preempt(&self) -> bool {
         self.preempt_mode
     }
+    // set_preempt() setter
+    pub fn set_preempt(&mut self, preempt: bool) {
+        self.preempt_mode = preempt;
+    }
+    // maintenance() getter
+    pub fn maintenance(&self) -> bool {
+        self.maintenance
+    }
+    // set_maintenance() setter
+    pub fn set_maintenance(&mut self, maintenance: bool) {
+        self.maintenance = maintenance;
+    }
+    // dry_run() getter
+    pub fn dry_run(&self) -> bool {
+        self.dry_run
+    }
+    // set_dry_run() setter
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        self.dry_run = dry_run;
+    }
+    // master() getter
+    pub fn master(&self) -> Option<[u8; 4]> {
+        self.master
+    }
+    // set_master() setter
+    pub fn set_master(&mut self, master: Option<[u8; 4]>) {
+        self.master = master;
+    }
+    // peers() getter
+    pub fn peers(&self) -> &Mutex<PeerTable> {
+        &self.peers
+    }
+    // history() getter
+    pub fn history(&self) -> &Arc<Mutex<History>> {
+        &self.history
+    }
     // rfc3768() getter
     pub fn rfc3768(&self) -> bool {
         self.rfc3768
@@ -974,16 +1089,13 @@ impl Parameters {
     pub fn authsecret(&self) -> &Option<String> {
         &self.auth_secret
     }
-    // addrcount() method
-    pub fn addrcount(&self) -> u8 {
-        // calculate the number of addresses (or arrays) in ipaddrs vector
-        let num = *&self.ipaddrs.len() as u8;
-        // if rfc3768 compatibility flag is false, add one to account for the VIP
-        if !self.rfc3768 {
-            num + 1
-        } else {
-            num
-        }
+    // set_auth() setter
+    pub fn set_auth(&mut self, auth_type: u8, auth_secret: Option<String>) {
+        self.auth_type = auth_type;
+        self.auth_secret = auth_secret;
+        // proprietary authentication types force the rfc3768 compatibility flag,
+        // otherwise the configured flag applies
+        self.rfc3768 = self.rfc3768_cfg || forces_rfc3768(auth_type);
     }
     // primary_ip() method
     pub fn primary_ip(&self) -> [u8; 4] {
@@ -1015,15 +1127,49 @@ impl Parameters {
     pub fn set_ifmac(&mut self, mac: [u8; 6]) {
         self.ifmac = mac;
     }
+    // driver() getter
+    pub fn driver(&self) -> &dyn NetDriver {
+        self.driver.as_ref()
+    }
     // netdrv() getter
-    #[cfg(target_os = "linux")]
-    pub fn netdrv(&self) -> &NetDrivers {
-        &self.netdrv
+    pub fn netdrv(&self) -> NetDrivers {
+        self.driver.kind()
     }
     // iftype() getter
-    #[cfg(target_os = "linux")]
-    pub fn iftype(&self) -> &IfTypes {
-        &self.iftype
+    pub fn iftype(&self) -> IfTypes {
+        self.iftype
+    }
+    // macvlan_mode() getter
+    pub fn macvlan_mode(&self) -> MacvlanMode {
+        self.macvlan_mode
+    }
+    // set_macvlan_mode() setter
+    pub fn set_macvlan_mode(&mut self, mode: MacvlanMode) {
+        self.macvlan_mode = mode;
+    }
+    // vmac_mode() getter
+    pub fn vmac_mode(&self) -> VmacModes {
+        self.vmac_mode
+    }
+    // set_vmac_mode() setter
+    pub fn set_vmac_mode(&mut self, mode: VmacModes) {
+        self.vmac_mode = mode;
+    }
+    // vlan_tag() getter
+    pub fn vlan_tag(&self) -> Option<u16> {
+        self.vlan_tag
+    }
+    // set_vlan_tag() setter
+    pub fn set_vlan_tag(&mut self, vid: Option<u16>) {
+        self.vlan_tag = vid;
+    }
+    // vlan_owned() getter
+    pub fn vlan_owned(&self) -> bool {
+        self.vlan_owned
+    }
+    // set_vlan_owned() setter
+    pub fn set_vlan_owned(&mut self, owned: bool) {
+        self.vlan_owned = owned;
     }
     // vif_name() getter
     pub fn vifname(&self) -> String {
@@ -1039,7 +1185,6 @@ impl Parameters {
         self.vif_name = vif;
     }
     // vifidx() getter
-    #[cfg(target_os = "linux")]
     pub fn vifidx(&self) -> i32 {
         self.vif_idx
     }
@@ -1085,3 +1230,13 @@ impl Parameters {
         )
     }
 }
+
+// forces_rfc3768() function
+/// return true if the authentication type requires the rfc3768 compatibility,
+/// the proprietary authentication types do not support the local addresses
+fn forces_rfc3768(auth_type: u8) -> bool {
+    match auth_type {
+        AUTH_TYPE_P0 | AUTH_TYPE_P1 => true,
+        _ => false,
+    }
+}
@@ -80,16 +80,37 @@ fn main() {
                 .help("hashing algorithm (default: sha256)")
                 .index(3),
         )
+        .arg(
+            Arg::with_name("level")
+                .short("l")
+                .long("level")
+                .takes_value(true)
+                .help("API access level (default: 0)"),
+        )
         .after_help("HASHING ALGS:\n\
         sha256\t\tSHA2 (256 bits)\n\
-        scrypt\t\tscrypt (interactive)\n")
+        scrypt\t\tscrypt (interactive)\n\n\
+        ACCESS LEVELS:\n\
+        0\t\tread-only running information\n\
+        1\t\tread-only running information and configuration\n\
+        2\t\tfull access (modifications)\n")
         .get_matches();
 
     let user = matches.value_of("user").unwrap();
     let passwd = matches.value_of("password").unwrap();
     let alg = matches.value_of("alg").unwrap_or("sha256");
+    let level = match matches.value_of("level").unwrap_or("0").parse::<u8>() {
+        Ok(l) if l <= 9 => l,
+        _ => {
+            eprintln!(
+                "{}",
+                MyError::new("the access level must be between 0 and 9")
+            );
+            std::process::exit(1);
+        }
+    };
 
-    match gen_hashed_pw(user, passwd, alg) {
+    match gen_hashed_pw(user, passwd, alg, level) {
         Err(e) => {
             eprintln!("{}", e);
             std::process::exit(1);
@@ -100,7 +121,7 @@ fn main() {
 
 /// gen_hashed_pw() function
 /// Print new user account information
-fn gen_hashed_pw(user: &str, passwd: &str, alg: &str) -> Result<(), MyError> {
+fn gen_hashed_pw(user: &str, passwd: &str, alg: &str, level: u8) -> Result<(), MyError> {
     match alg {
         "sha256" => {
             let mut rng = rand::thread_rng();
@@ -108,7 +129,7 @@ fn gen_hashed_pw(user: &str, passwd: &str, alg: &str) -> Result<(), MyError> {
             let salt = format!("{:x}", r);
             match gen_sha256_hash(&passwd, &salt) {
                 Some(h) => {
-                    display_userpw_line(alg, user, Some(salt), h);
+                    display_userpw_line(alg, user, level, Some(salt), h);
                 }
                 None => {
                     let err = format!("the {} hashing function failed", alg);
@@ -118,7 +139,7 @@ fn gen_hashed_pw(user: &str, passwd: &str, alg: &str) -> Result<(), MyError> {
         }
         "scrypt" => match gen_scrypt_hash(passwd) {
             Some(h) => {
-                display_userpw_line(alg, user, None, h);
+                display_userpw_line(alg, user, level, None, h);
             }
             None => {
                 let err = format!("the {} hashing function failed", alg);
@@ -164,9 +185,7 @@ fn gen_scrypt_hash(passwd: &str) -> Option<String> {
 
 /// display_userpw_line() function
 /// Display the user password line for inclusion in rVRRPd configuration
-fn display_userpw_line(alg: &str, user: &str, salt: Option<String>, hash: String) {
-    // not implemented yet
-    let level = 0;
+fn display_userpw_line(alg: &str, user: &str, level: u8, salt: Option<String>, hash: String) {
     println!(
         "{{{{{}}}}}{}:{}:{}:{}",
         alg.to_uppercase(),